@@ -20,9 +20,10 @@ use crate::{
     external::{
         copy_to_clipboard, exec_user_command, exec_user_command_suspend, ExternalCommandParameters,
     },
-    git::{Commit, FileChange, Head, Ref, Repository},
-    graph::{CellWidthType, Graph, GraphImageManager},
+    git::{self, Commit, CommitHash, FileChange, FileDiff, Head, Ref, Repository},
+    graph::{Graph, GraphImageCache, GraphImageManager},
     keybind::KeyBind,
+    loader::{self, CommitLoader},
     protocol::ImageProtocol,
     view::{RefreshViewContext, View},
     widget::commit_list::{CommitInfo, CommitListState},
@@ -48,10 +49,35 @@ pub enum InitialSelection {
 pub enum Ret {
     Quit,
     Refresh(RefreshRequest),
+    // rebuild the app with the commits loaded in the background
+    Append(RefreshRequest),
+    // reload the repository with only the commits that touch the paths
+    FilterPaths {
+        request: RefreshRequest,
+        paths: Vec<String>,
+    },
+    // hide the commits that do not match the query
+    FilterCommits {
+        request: RefreshRequest,
+        query: String,
+    },
+    ToggleFirstParent(RefreshRequest),
+    ToggleSimplifyByDecoration(RefreshRequest),
+    // show the commits collapsed into the row
+    ExpandCommits {
+        request: RefreshRequest,
+        commit_hash: CommitHash,
+    },
+    // fold the side branch of the merge commit into a row, or unfold it
+    ToggleFold {
+        request: RefreshRequest,
+        commit_hash: CommitHash,
+    },
 }
 
 pub struct RefreshRequest {
     pub context: RefreshViewContext,
+    pub graph_image_cache: GraphImageCache,
 }
 
 #[derive(Debug)]
@@ -61,6 +87,8 @@ pub struct AppContext {
     pub ui_config: UiConfig,
     pub color_theme: ColorTheme,
     pub image_protocol: ImageProtocol,
+    // to resolve the paths given in the prompt relative to the directory serie was started in
+    pub repo_path: git::RepositoryPath,
 }
 
 #[derive(Debug, Default)]
@@ -73,6 +101,9 @@ struct AppStatus {
 #[derive(Debug)]
 pub struct App<'a> {
     repository: &'a Repository,
+    loader: &'a CommitLoader,
+    // the repository has been changed outside and the view has not been refreshed yet
+    repository_changed: bool,
     view: View<'a>,
     app_status: AppStatus,
     ctx: Rc<AppContext>,
@@ -82,10 +113,10 @@ pub struct App<'a> {
 impl<'a> App<'a> {
     pub fn new(
         repository: &'a Repository,
+        loader: &'a CommitLoader,
         graph_image_manager: GraphImageManager<'a>,
         graph: &'a Graph,
         graph_color_set: &'a GraphColorSet,
-        cell_width_type: CellWidthType,
         initial_selection: InitialSelection,
         ctx: Rc<AppContext>,
         ec: &'a EventController,
@@ -101,22 +132,18 @@ impl<'a> App<'a> {
                 for r in &refs {
                     ref_name_to_commit_index_map.insert(r.name(), i);
                 }
-                let (pos_x, _) = graph.commit_pos_map[&commit.commit_hash];
-                let graph_color = graph_color_set.get(pos_x).to_ratatui_color();
-                CommitInfo::new(commit, refs, graph_color)
+                let color_index = graph.commit_colors[&commit.commit_hash];
+                let graph_color = graph_color_set.get(color_index).to_ratatui_color();
+                let collapsed = repository.collapsed_commits(&commit.commit_hash).is_some();
+                CommitInfo::new(commit, refs, graph_color, collapsed)
             })
             .collect();
-        let graph_cell_width = match cell_width_type {
-            CellWidthType::Double => (graph.max_pos_x + 1) as u16 * 2,
-            CellWidthType::Single => (graph.max_pos_x + 1) as u16,
-        };
-        let head = repository.head();
         let mut commit_list_state = CommitListState::new(
             commits,
             graph_image_manager,
-            graph_cell_width,
-            head,
+            repository,
             ref_name_to_commit_index_map,
+            ctx.ui_config.list.subject_min_width,
             ctx.core_config.search.ignore_case,
             ctx.core_config.search.fuzzy,
         );
@@ -131,6 +158,8 @@ impl<'a> App<'a> {
 
         let mut app = Self {
             repository,
+            loader,
+            repository_changed: false,
             view,
             app_status: AppStatus::default(),
             ctx,
@@ -152,6 +181,11 @@ impl App<'_> {
         terminal.clear()?;
 
         loop {
+            if let Some(request) = self.append_request() {
+                return Ok(Ret::Append(request));
+            }
+            self.refresh_if_repository_changed();
+
             self.prepare_render(terminal)?;
             self.flush_pending_graph_uploads()?;
             terminal.draw(|f| self.render(f))?;
@@ -230,6 +264,17 @@ impl App<'_> {
                     terminal.clear()?;
                     self.close_detail();
                 }
+                AppEvent::OpenDiff => {
+                    self.clear_image(Some(terminal))?;
+                    self.open_diff();
+                }
+                AppEvent::CloseDiff => {
+                    terminal.clear()?;
+                    self.close_diff();
+                }
+                AppEvent::OpenFileDiff => {
+                    self.open_file_diff();
+                }
                 AppEvent::OpenUserCommand(n) => {
                     self.clear_image(Some(terminal))?;
                     self.open_user_command(n, Some(terminal));
@@ -244,6 +289,28 @@ impl App<'_> {
                 AppEvent::CloseRefs => {
                     self.close_refs();
                 }
+                AppEvent::OpenFileTree => {
+                    self.clear_image(Some(terminal))?;
+                    self.open_file_tree();
+                }
+                AppEvent::CloseFileTree => {
+                    terminal.clear()?;
+                    self.close_file_tree();
+                }
+                AppEvent::OpenFilePreview => {
+                    self.open_file_preview();
+                }
+                AppEvent::OpenBlame(commit_hash, path) => {
+                    self.clear_image(Some(terminal))?;
+                    self.open_blame(commit_hash, path);
+                }
+                AppEvent::CloseBlame => {
+                    terminal.clear()?;
+                    self.close_blame();
+                }
+                AppEvent::BlameParent => {
+                    self.blame_parent();
+                }
                 AppEvent::OpenHelp => {
                     self.clear_image(None)?;
                     self.open_help();
@@ -265,10 +332,91 @@ impl App<'_> {
                     self.copy_to_clipboard(name, value);
                 }
                 AppEvent::Refresh(context) => {
-                    self.cleanup_graph_images()?;
-                    let request = RefreshRequest { context };
+                    // the images are deleted after rebuilding the graph, only if their rows have changed
+                    let graph_image_cache = self.view.take_graph_image_cache();
+                    let request = RefreshRequest {
+                        context,
+                        graph_image_cache,
+                    };
                     return Ok(Ret::Refresh(request));
                 }
+                AppEvent::FilterPaths(paths, context) => {
+                    let graph_image_cache = self.view.take_graph_image_cache();
+                    let request = RefreshRequest {
+                        context,
+                        graph_image_cache,
+                    };
+                    return Ok(Ret::FilterPaths { request, paths });
+                }
+                AppEvent::FilterCommits(query, context) => {
+                    let graph_image_cache = self.view.take_graph_image_cache();
+                    let request = RefreshRequest {
+                        context,
+                        graph_image_cache,
+                    };
+                    return Ok(Ret::FilterCommits { request, query });
+                }
+                AppEvent::ToggleFirstParent(context) => {
+                    let graph_image_cache = self.view.take_graph_image_cache();
+                    let request = RefreshRequest {
+                        context,
+                        graph_image_cache,
+                    };
+                    return Ok(Ret::ToggleFirstParent(request));
+                }
+                AppEvent::ToggleSimplifyByDecoration(context) => {
+                    let graph_image_cache = self.view.take_graph_image_cache();
+                    let request = RefreshRequest {
+                        context,
+                        graph_image_cache,
+                    };
+                    return Ok(Ret::ToggleSimplifyByDecoration(request));
+                }
+                AppEvent::ExpandCommits(commit_hash, context) => {
+                    let graph_image_cache = self.view.take_graph_image_cache();
+                    let request = RefreshRequest {
+                        context,
+                        graph_image_cache,
+                    };
+                    return Ok(Ret::ExpandCommits {
+                        request,
+                        commit_hash,
+                    });
+                }
+                AppEvent::ToggleFold(commit_hash, context) => {
+                    // the row a side branch is folded into unfolds it
+                    if self.repository.folded_merge(&commit_hash).is_none() {
+                        if self.repository.parents_hash(&commit_hash).len() <= 1 {
+                            self.warn_notification("Not a merge commit".into());
+                            continue;
+                        }
+                        if !self.repository.has_side_branch_commits(&commit_hash) {
+                            let msg = "No commits are merged only by the merge commit".into();
+                            self.warn_notification(msg);
+                            continue;
+                        }
+                    }
+                    let graph_image_cache = self.view.take_graph_image_cache();
+                    let request = RefreshRequest {
+                        context,
+                        graph_image_cache,
+                    };
+                    return Ok(Ret::ToggleFold {
+                        request,
+                        commit_hash,
+                    });
+                }
+                AppEvent::CommitsLoaded => {
+                    if let Some(e) = self.loader.take_error() {
+                        self.error_notification(format!("Failed to load commits: {e}"));
+                    }
+                }
+                AppEvent::CommitDiffLoaded(commit_hash, result) => {
+                    self.commit_diff_loaded(commit_hash, result);
+                }
+                AppEvent::RepositoryChanged => {
+                    self.repository_changed = true;
+                }
                 AppEvent::ClearStatusLine => {
                     self.clear_status_line();
                 }
@@ -291,6 +439,47 @@ impl App<'_> {
         }
     }
 
+    fn append_request(&mut self) -> Option<RefreshRequest> {
+        // the graph is rebuilt only in the list view, to keep the state of other views simple
+        let View::List(ref view) = self.view else {
+            return None;
+        };
+        if let StatusLine::Input(..) = self.app_status.status_line {
+            return None;
+        }
+        let status = self.loader.status();
+        if status.pending == 0 {
+            return None;
+        }
+        // the graph is rebuilt each time the number of commits doubles, to keep the total cost linear
+        // (the loaded commits are counted rather than the shown ones, which may be filtered)
+        if !status.finished && status.pending < status.loaded - status.pending {
+            return None;
+        }
+        let context = view.refresh_context();
+        let graph_image_cache = self.view.take_graph_image_cache();
+        Some(RefreshRequest {
+            context,
+            graph_image_cache,
+        })
+    }
+
+    fn refresh_if_repository_changed(&mut self) {
+        if !self.repository_changed {
+            return;
+        }
+        // postpone the refresh while the help is shown or any input is being typed
+        if let View::Help(_) = self.view {
+            return;
+        }
+        if let StatusLine::Input(..) = self.app_status.status_line {
+            return;
+        }
+        self.repository_changed = false;
+        // the refresh event is sent with the current view context, so the selection and search are kept
+        self.view.refresh();
+    }
+
     fn prepare_render(&mut self, terminal: &mut DefaultTerminal) -> Result<(), std::io::Error> {
         let area: Rect = terminal.size()?.into();
         let [view_area, _] = split_app_areas(area);
@@ -337,11 +526,15 @@ impl App<'_> {
     fn render_status_line(&self, f: &mut Frame, area: Rect) {
         let text: Line = match &self.app_status.status_line {
             StatusLine::None => {
-                if self.app_status.numeric_prefix.is_empty() {
-                    Line::raw("")
-                } else {
+                let status = self.loader.status();
+                if !self.app_status.numeric_prefix.is_empty() {
                     Line::raw(self.app_status.numeric_prefix.as_str())
                         .fg(self.ctx.color_theme.status_input_transient_fg)
+                } else if !status.finished {
+                    Line::raw(format!("Loading commits... {}", status.loaded))
+                        .fg(self.ctx.color_theme.status_info_fg)
+                } else {
+                    Line::raw("")
                 }
             }
             StatusLine::Input(msg, _, transient_msg) => {
@@ -419,16 +612,36 @@ impl App<'_> {
     }
 
     fn open_detail(&mut self) {
+        let commit_list_state = match self.view {
+            View::List(ref mut view) => view.as_list_state(),
+            View::Diff(ref mut view) => view.as_list_state(),
+            View::UserCommand(ref mut view) => view.as_list_state(),
+            _ => return,
+        };
+        let (commit, changes, refs) =
+            match selected_commit_details(self.repository, commit_list_state) {
+                Ok(details) => details,
+                Err(err) => {
+                    self.ec.send(AppEvent::NotifyError(err));
+                    return;
+                }
+            };
+        let filtered_changes = changes
+            .iter()
+            .map(|change| self.repository.is_filtered_change(&commit, change))
+            .collect();
+        // take list state only when the details are loaded, to avoid losing the state when loading fails
         let commit_list_state = match self.view {
             View::List(ref mut view) => view.take_list_state(),
+            View::Diff(ref mut view) => view.take_list_state(),
             View::UserCommand(ref mut view) => view.take_list_state(),
             _ => return,
         };
-        let (commit, changes, refs) = selected_commit_details(self.repository, &commit_list_state);
         self.view = View::of_detail(
             commit_list_state,
             commit,
             changes,
+            filtered_changes,
             refs,
             self.ctx.clone(),
             self.ec.sender(),
@@ -442,6 +655,58 @@ impl App<'_> {
         }
     }
 
+    fn open_diff(&mut self) {
+        let commit_list_state = match self.view {
+            View::List(ref mut view) => view.as_list_state(),
+            View::Detail(ref mut view) => view.as_list_state(),
+            View::UserCommand(ref mut view) => view.as_list_state(),
+            _ => return,
+        };
+        let selected = commit_list_state.selected_commit_hash();
+        let reader = match self.repository.commit_diff_reader(selected) {
+            Ok(reader) => reader,
+            Err(err) => {
+                self.ec.send(AppEvent::NotifyError(err.to_string()));
+                return;
+            }
+        };
+        // take list state only when the diff can be loaded, to avoid losing the state when loading fails
+        let commit_list_state = match self.view {
+            View::List(ref mut view) => view.take_list_state(),
+            View::Detail(ref mut view) => view.take_list_state(),
+            View::UserCommand(ref mut view) => view.take_list_state(),
+            _ => return,
+        };
+        self.view = View::of_diff(
+            commit_list_state,
+            self.ctx.ui_config.diff.layout,
+            self.ctx.clone(),
+            self.ec.sender(),
+        );
+        loader::load_commit_diff(reader, self.ec.sender());
+    }
+
+    fn close_diff(&mut self) {
+        if let View::Diff(ref mut view) = self.view {
+            let commit_list_state = view.take_list_state();
+            self.view = View::of_list(commit_list_state, self.ctx.clone(), self.ec.sender());
+        }
+    }
+
+    fn open_file_diff(&mut self) {
+        if let View::Detail(ref mut view) = self.view {
+            view.open_file_diff(self.repository);
+        }
+    }
+
+    fn commit_diff_loaded(&mut self, commit_hash: CommitHash, result: git::Result<Vec<FileDiff>>) {
+        if let View::Detail(ref mut view) = self.view {
+            view.commit_diff_loaded(&commit_hash, result);
+        } else if let View::Diff(ref mut view) = self.view {
+            view.commit_diff_loaded(&commit_hash, result);
+        }
+    }
+
     fn open_user_command(
         &mut self,
         user_command_number: usize,
@@ -481,11 +746,19 @@ impl App<'_> {
         let commit_list_state = match self.view {
             View::List(ref mut view) => view.as_list_state(),
             View::Detail(ref mut view) => view.as_list_state(),
+            View::Diff(ref mut view) => view.as_list_state(),
             View::UserCommand(ref mut view) => view.as_list_state(),
             _ => return,
         };
-        let (commit, _, refs) = selected_commit_details(self.repository, commit_list_state);
+        let (commit, _, refs) = match selected_commit_details(self.repository, commit_list_state) {
+            Ok(details) => details,
+            Err(err) => {
+                self.ec.send(AppEvent::NotifyError(err));
+                return;
+            }
+        };
         let result = build_external_command_parameters_and_exec_command(
+            self.repository,
             &commit,
             &refs,
             user_command_number,
@@ -498,6 +771,7 @@ impl App<'_> {
                 let commit_list_state = match self.view {
                     View::List(ref mut view) => view.take_list_state(),
                     View::Detail(ref mut view) => view.take_list_state(),
+                    View::Diff(ref mut view) => view.take_list_state(),
                     View::UserCommand(ref mut view) => view.take_list_state(),
                     _ => return,
                 };
@@ -519,11 +793,19 @@ impl App<'_> {
         let commit_list_state = match self.view {
             View::List(ref mut view) => view.as_list_state(),
             View::Detail(ref mut view) => view.as_list_state(),
+            View::Diff(ref mut view) => view.as_list_state(),
             View::UserCommand(ref mut view) => view.as_list_state(),
             _ => return,
         };
-        let (commit, _, refs) = selected_commit_details(self.repository, commit_list_state);
+        let (commit, _, refs) = match selected_commit_details(self.repository, commit_list_state) {
+            Ok(details) => details,
+            Err(err) => {
+                self.ec.send(AppEvent::NotifyError(err));
+                return;
+            }
+        };
         let result = build_external_command_parameters_and_exec_command(
+            self.repository,
             &commit,
             &refs,
             user_command_number,
@@ -546,11 +828,19 @@ impl App<'_> {
         let commit_list_state = match self.view {
             View::List(ref mut view) => view.as_list_state(),
             View::Detail(ref mut view) => view.as_list_state(),
+            View::Diff(ref mut view) => view.as_list_state(),
             View::UserCommand(ref mut view) => view.as_list_state(),
             _ => return,
         };
-        let (commit, _, refs) = selected_commit_details(self.repository, commit_list_state);
+        let (commit, _, refs) = match selected_commit_details(self.repository, commit_list_state) {
+            Ok(details) => details,
+            Err(err) => {
+                self.ec.send(AppEvent::NotifyError(err));
+                return;
+            }
+        };
         match build_external_command_parameters(
+            self.repository,
             &commit,
             &refs,
             user_command_number,
@@ -599,6 +889,87 @@ impl App<'_> {
         }
     }
 
+    fn open_file_tree(&mut self) {
+        let commit_list_state = match self.view {
+            View::List(ref mut view) => view.as_list_state(),
+            View::Detail(ref mut view) => view.as_list_state(),
+            View::Diff(ref mut view) => view.as_list_state(),
+            _ => return,
+        };
+        let selected = commit_list_state.selected_commit_hash();
+        let entries = match self.repository.tree_entries(selected) {
+            Ok(entries) => entries,
+            Err(err) => {
+                self.ec.send(AppEvent::NotifyError(err.to_string()));
+                return;
+            }
+        };
+        // take list state only when the tree is loaded, to avoid losing the state when loading fails
+        let commit_list_state = match self.view {
+            View::List(ref mut view) => view.take_list_state(),
+            View::Detail(ref mut view) => view.take_list_state(),
+            View::Diff(ref mut view) => view.take_list_state(),
+            _ => return,
+        };
+        self.view = View::of_file_tree(
+            commit_list_state,
+            entries,
+            self.ctx.clone(),
+            self.ec.sender(),
+        );
+    }
+
+    fn close_file_tree(&mut self) {
+        if let View::FileTree(ref mut view) = self.view {
+            let commit_list_state = view.take_list_state();
+            self.view = View::of_list(commit_list_state, self.ctx.clone(), self.ec.sender());
+        }
+    }
+
+    fn open_file_preview(&mut self) {
+        if let View::FileTree(ref mut view) = self.view {
+            view.open_file_preview(self.repository);
+        }
+    }
+
+    fn open_blame(&mut self, commit_hash: CommitHash, path: String) {
+        let blame = match self.repository.blame(&commit_hash, &path) {
+            Ok(blame) => blame,
+            Err(err) => {
+                self.ec.send(AppEvent::NotifyError(err.to_string()));
+                return;
+            }
+        };
+        // take list state only when the blame is loaded, to avoid losing the state when loading fails
+        let commit_list_state = match self.view {
+            View::List(ref mut view) => view.take_list_state(),
+            View::Detail(ref mut view) => view.take_list_state(),
+            View::FileTree(ref mut view) => view.take_list_state(),
+            _ => return,
+        };
+        self.view = View::of_blame(
+            commit_list_state,
+            commit_hash,
+            path,
+            blame,
+            self.ctx.clone(),
+            self.ec.sender(),
+        );
+    }
+
+    fn close_blame(&mut self) {
+        if let View::Blame(ref mut view) = self.view {
+            let commit_list_state = view.take_list_state();
+            self.view = View::of_list(commit_list_state, self.ctx.clone(), self.ec.sender());
+        }
+    }
+
+    fn blame_parent(&mut self) {
+        if let View::Blame(ref mut view) = self.view {
+            view.blame_parent(self.repository);
+        }
+    }
+
     fn open_help(&mut self) {
         let before_view = std::mem::take(&mut self.view);
         self.view = View::of_help(before_view, self.ctx.clone(), self.ec.sender());
@@ -613,6 +984,10 @@ impl App<'_> {
     fn select_older_commit(&mut self) {
         if let View::Detail(ref mut view) = self.view {
             view.select_older_commit(self.repository);
+        } else if let View::Diff(ref mut view) = self.view {
+            view.select_older_commit(self.repository);
+        } else if let View::FileTree(ref mut view) = self.view {
+            view.select_older_commit(self.repository);
         } else if let View::UserCommand(ref mut view) = self.view {
             view.select_older_commit(
                 self.repository,
@@ -625,6 +1000,10 @@ impl App<'_> {
     fn select_newer_commit(&mut self) {
         if let View::Detail(ref mut view) = self.view {
             view.select_newer_commit(self.repository);
+        } else if let View::Diff(ref mut view) = self.view {
+            view.select_newer_commit(self.repository);
+        } else if let View::FileTree(ref mut view) = self.view {
+            view.select_newer_commit(self.repository);
         } else if let View::UserCommand(ref mut view) = self.view {
             view.select_newer_commit(
                 self.repository,
@@ -637,6 +1016,10 @@ impl App<'_> {
     fn select_parent_commit(&mut self) {
         if let View::Detail(ref mut view) = self.view {
             view.select_parent_commit(self.repository);
+        } else if let View::Diff(ref mut view) = self.view {
+            view.select_parent_commit(self.repository);
+        } else if let View::FileTree(ref mut view) = self.view {
+            view.select_parent_commit(self.repository);
         } else if let View::UserCommand(ref mut view) = self.view {
             view.select_parent_commit(
                 self.repository,
@@ -655,6 +1038,12 @@ impl App<'_> {
             RefreshViewContext::Detail { .. } => {
                 self.open_detail();
             }
+            RefreshViewContext::Diff { diff_context, .. } => {
+                self.open_diff();
+                if let View::Diff(ref mut view) = self.view {
+                    view.set_layout(diff_context.layout);
+                }
+            }
             RefreshViewContext::UserCommand {
                 user_command_context,
                 ..
@@ -667,6 +1056,23 @@ impl App<'_> {
                     view.reset_refs_with(refs_context);
                 }
             }
+            RefreshViewContext::FileTree {
+                file_tree_context, ..
+            } => {
+                self.open_file_tree();
+                if let View::FileTree(ref mut view) = self.view {
+                    view.reset_file_tree_with(file_tree_context);
+                }
+            }
+            RefreshViewContext::Blame { blame_context, .. } => {
+                self.open_blame(
+                    blame_context.commit_hash.clone(),
+                    blame_context.path.clone(),
+                );
+                if let View::Blame(ref mut view) = self.view {
+                    view.reset_blame_with(blame_context);
+                }
+            }
         }
     }
 
@@ -715,11 +1121,13 @@ impl App<'_> {
 fn selected_commit_details(
     repository: &Repository,
     commit_list_state: &CommitListState,
-) -> (Commit, Vec<FileChange>, Vec<Ref>) {
+) -> Result<(Commit, Vec<FileChange>, Vec<Ref>), String> {
     let selected = commit_list_state.selected_commit_hash().clone();
-    let (commit, changes) = repository.commit_detail(&selected);
+    let (commit, changes) = repository
+        .commit_detail(&selected)
+        .map_err(|err| err.to_string())?;
     let refs: Vec<Ref> = repository.refs(&selected).into_iter().cloned().collect();
-    (commit, changes, refs)
+    Ok((commit, changes, refs))
 }
 
 fn process_numeric_prefix(
@@ -757,17 +1165,26 @@ fn extract_user_command_refresh_by_number(user_command_number: usize, ctx: &AppC
 }
 
 fn build_external_command_parameters_and_exec_command(
+    repository: &Repository,
     commit: &Commit,
     refs: &[Ref],
     user_command_number: usize,
     view_area: Rect,
     ctx: &AppContext,
 ) -> Result<String, String> {
-    build_external_command_parameters(commit, refs, user_command_number, view_area, ctx)
-        .and_then(exec_user_command)
+    build_external_command_parameters(
+        repository,
+        commit,
+        refs,
+        user_command_number,
+        view_area,
+        ctx,
+    )
+    .and_then(exec_user_command)
 }
 
 fn build_external_command_parameters<'a>(
+    repository: &'a Repository,
     commit: &'a Commit,
     refs: &'a [Ref],
     user_command_number: usize,
@@ -806,6 +1223,7 @@ fn build_external_command_parameters<'a>(
         .saturating_sub(1); // minus the top border
     Ok(ExternalCommandParameters {
         command,
+        repository_path: repository.path(),
         target_hash,
         parent_hashes,
         all_refs,
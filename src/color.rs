@@ -1,9 +1,11 @@
+use std::hash::{Hash, Hasher};
+
 use ratatui::style::Color as RatatuiColor;
 use serde::Deserialize;
 use smart_default::SmartDefault;
 use umbra::optional;
 
-use crate::config::GraphColorConfig;
+use crate::{config::GraphColorConfig, git::Ref};
 
 #[optional(derives = [Deserialize], visibility = pub)]
 #[derive(Debug, Clone, PartialEq, Eq, SmartDefault)]
@@ -66,12 +68,59 @@ pub struct ColorTheme {
     pub detail_file_change_delete_fg: RatatuiColor,
     #[default(RatatuiColor::Magenta)]
     pub detail_file_change_move_fg: RatatuiColor,
+    #[default(RatatuiColor::Black)]
+    pub detail_file_change_match_fg: RatatuiColor,
+    #[default(RatatuiColor::Yellow)]
+    pub detail_file_change_match_bg: RatatuiColor,
+    #[default(RatatuiColor::White)]
+    pub detail_file_change_selected_fg: RatatuiColor,
+    #[default(RatatuiColor::DarkGray)]
+    pub detail_file_change_selected_bg: RatatuiColor,
 
     #[default(RatatuiColor::White)]
     pub ref_selected_fg: RatatuiColor,
     #[default(RatatuiColor::DarkGray)]
     pub ref_selected_bg: RatatuiColor,
 
+    #[default(RatatuiColor::Yellow)]
+    pub diff_file_fg: RatatuiColor,
+    #[default(RatatuiColor::DarkGray)]
+    pub diff_header_fg: RatatuiColor,
+    #[default(RatatuiColor::Cyan)]
+    pub diff_hunk_fg: RatatuiColor,
+    #[default(RatatuiColor::DarkGray)]
+    pub diff_line_number_fg: RatatuiColor,
+    #[default(RatatuiColor::Reset)]
+    pub diff_context_fg: RatatuiColor,
+    #[default(RatatuiColor::Green)]
+    pub diff_added_fg: RatatuiColor,
+    #[default(RatatuiColor::Red)]
+    pub diff_removed_fg: RatatuiColor,
+    #[default(RatatuiColor::Rgb(0x1f, 0x35, 0x24))]
+    pub diff_added_bg: RatatuiColor,
+    #[default(RatatuiColor::Rgb(0x3f, 0x1f, 0x24))]
+    pub diff_removed_bg: RatatuiColor,
+    #[default(RatatuiColor::Black)]
+    pub diff_added_word_fg: RatatuiColor,
+    #[default(RatatuiColor::Green)]
+    pub diff_added_word_bg: RatatuiColor,
+    #[default(RatatuiColor::Black)]
+    pub diff_removed_word_fg: RatatuiColor,
+    #[default(RatatuiColor::Red)]
+    pub diff_removed_word_bg: RatatuiColor,
+
+    #[default(RatatuiColor::Blue)]
+    pub file_tree_dir_fg: RatatuiColor,
+    #[default(RatatuiColor::White)]
+    pub file_tree_selected_fg: RatatuiColor,
+    #[default(RatatuiColor::DarkGray)]
+    pub file_tree_selected_bg: RatatuiColor,
+
+    #[default(RatatuiColor::White)]
+    pub blame_selected_fg: RatatuiColor,
+    #[default(RatatuiColor::DarkGray)]
+    pub blame_selected_bg: RatatuiColor,
+
     #[default(RatatuiColor::Green)]
     pub help_block_title_fg: RatatuiColor,
     #[default(RatatuiColor::Yellow)]
@@ -126,34 +175,88 @@ impl GraphColor {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphColorMode {
+    // colored by the lane, which changes when the lanes are assigned differently
+    #[default]
+    Lane,
+    // colored by the branch whose first-parent history the commit belongs to
+    Branch,
+}
+
 #[derive(Debug, Clone)]
 pub struct GraphColorSet {
+    // the colors of the rules follow the palette in the branch mode
     pub colors: Vec<GraphColor>,
     pub edge_color: GraphColor,
     pub background_color: GraphColor,
+    pub mode: GraphColorMode,
+    palette_len: usize,
+    rule_patterns: Vec<String>,
 }
 
 impl GraphColorSet {
     pub fn new(config: &GraphColorConfig) -> Self {
-        let colors = config
+        let mut colors: Vec<GraphColor> = config
             .branches
             .iter()
             .filter_map(|s| parse_rgba_color(s))
             .collect();
+        let palette_len = colors.len();
         let edge_color = parse_rgba_color(&config.edge).unwrap_or(GraphColor::transparent());
         let background_color =
             parse_rgba_color(&config.background).unwrap_or(GraphColor::transparent());
 
+        let mut rule_patterns = Vec::new();
+        if config.mode == GraphColorMode::Branch {
+            for rule in &config.rules {
+                colors.push(parse_rgba_color(&rule.color).unwrap_or(GraphColor::transparent()));
+                rule_patterns.push(rule.pattern.clone());
+            }
+        }
+
         Self {
             colors,
             edge_color,
             background_color,
+            mode: config.mode,
+            palette_len,
+            rule_patterns,
         }
     }
 
     pub fn get(&self, index: usize) -> GraphColor {
         self.colors[index % self.colors.len()]
     }
+
+    /// Returns the index of the first rule matching the branch.
+    pub fn rule_index(&self, branch: &Ref) -> Option<usize> {
+        self.rule_patterns
+            .iter()
+            .position(|pattern| branch.matches_pattern(pattern))
+    }
+
+    /// Returns the color index of the branch, which is the color of the matching rule,
+    /// or the color of the palette decided by the name.
+    pub fn branch_color_index(&self, branch: &Ref) -> usize {
+        match self.rule_index(branch) {
+            Some(i) => self.palette_len + i,
+            None => self.hash_color_index(branch.name()),
+        }
+    }
+
+    /// Returns the color index of the palette decided by the key, which is the same on every run.
+    pub fn hash_color_index(&self, key: impl Hash) -> usize {
+        let mut hasher = rustc_hash::FxHasher::default();
+        key.hash(&mut hasher);
+        hasher.finish() as usize % self.palette_len
+    }
+
+    /// Returns the color index of the palette for the lane.
+    pub fn lane_color_index(&self, pos_x: usize) -> usize {
+        pos_x % self.palette_len
+    }
 }
 
 fn parse_rgba_color(s: &str) -> Option<GraphColor> {
@@ -10,9 +10,12 @@ use smart_default::SmartDefault;
 use umbra::optional;
 
 use crate::{
-    color::{ColorTheme, OptionalColorTheme},
+    color::{ColorTheme, GraphColorMode, OptionalColorTheme},
+    git::GitBackendType,
     graph::GraphImageWidthMode,
     keybind::KeyBind,
+    syntax,
+    widget::commit_diff::DiffLayout,
     CommitOrderType, GraphStyle, GraphWidthType, ImageProtocolType, InitialSelection, Result,
 };
 
@@ -137,6 +140,10 @@ pub struct CoreOptionConfig {
 pub struct CoreGitConfig {
     #[default = false]
     pub mailmap: bool,
+    #[default(GitBackendType::Subprocess)]
+    pub backend: GitBackendType,
+    #[default = false]
+    pub watch: bool,
 }
 
 #[optional(derives = [Deserialize])]
@@ -283,6 +290,18 @@ pub struct UiConfig {
     pub detail: UiDetailConfig,
     #[garde(dive)]
     #[nested]
+    pub diff: UiDiffConfig,
+    #[garde(dive)]
+    #[nested]
+    pub syntax: UiSyntaxConfig,
+    #[garde(dive)]
+    #[nested]
+    pub file_tree: UiFileTreeConfig,
+    #[garde(dive)]
+    #[nested]
+    pub blame: UiBlameConfig,
+    #[garde(dive)]
+    #[nested]
     pub user_command: UiUserCommandConfig,
     #[garde(dive)]
     #[nested]
@@ -383,6 +402,59 @@ pub struct UiDetailConfig {
     pub date_local: bool,
 }
 
+#[optional(derives = [Deserialize])]
+#[derive(Debug, Clone, PartialEq, Eq, SmartDefault, Validate)]
+pub struct UiDiffConfig {
+    #[garde(range(min = 1))]
+    #[default = 30]
+    pub height: u16,
+    #[garde(skip)]
+    #[default(DiffLayout::Unified)]
+    pub layout: DiffLayout,
+    #[garde(skip)]
+    #[default = 4]
+    pub tab_width: u16,
+}
+
+#[optional(derives = [Deserialize])]
+#[derive(Debug, Clone, PartialEq, Eq, SmartDefault, Validate)]
+pub struct UiSyntaxConfig {
+    #[garde(skip)]
+    #[default = true]
+    pub enabled: bool,
+    #[garde(custom(validate_syntax_theme))]
+    #[default = "base16-ocean.dark"]
+    pub theme: String,
+}
+
+#[optional(derives = [Deserialize])]
+#[derive(Debug, Clone, PartialEq, Eq, SmartDefault, Validate)]
+pub struct UiFileTreeConfig {
+    #[garde(range(min = 1))]
+    #[default = 30]
+    pub height: u16,
+    #[garde(range(min = 1))]
+    #[default = 40]
+    pub width: u16,
+}
+
+#[optional(derives = [Deserialize])]
+#[derive(Debug, Clone, PartialEq, Eq, SmartDefault, Validate)]
+pub struct UiBlameConfig {
+    #[garde(range(min = 1))]
+    #[default = 30]
+    pub height: u16,
+}
+
+fn validate_syntax_theme(theme: &str, _: &()) -> garde::Result {
+    let names = syntax::theme_names();
+    if !names.contains(&theme) {
+        let msg = format!("unknown theme, expected one of: {}", names.join(", "));
+        return Err(garde::Error::new(msg));
+    }
+    Ok(())
+}
+
 #[optional(derives = [Deserialize])]
 #[derive(Debug, Clone, PartialEq, Eq, SmartDefault, Validate)]
 pub struct UiUserCommandConfig {
@@ -405,6 +477,15 @@ pub struct GraphConfig {
     #[garde(skip)]
     #[default(GraphImageWidthMode::Compact)]
     pub row_image_width: GraphImageWidthMode,
+    #[garde(inner(length(min = 1)))]
+    #[default(Vec::new())]
+    pub pinned_branches: Vec<String>,
+    #[garde(skip)]
+    #[default = false]
+    pub compact_lanes: bool,
+    #[garde(range(min = 1))]
+    #[default(None)]
+    pub max_lanes: Option<usize>,
     #[garde(dive)]
     #[nested]
     pub color: GraphColorConfig,
@@ -413,6 +494,9 @@ pub struct GraphConfig {
 #[optional(derives = [Deserialize])]
 #[derive(Debug, Clone, PartialEq, Eq, SmartDefault, Validate)]
 pub struct GraphColorConfig {
+    #[garde(skip)]
+    #[default(GraphColorMode::Lane)]
+    pub mode: GraphColorMode,
     #[garde(length(min = 1), inner(pattern(r"^#([0-9a-fA-F]{6}|[0-9a-fA-F]{8})$")))]
     #[default(vec![
         "#E06C76".into(),
@@ -429,6 +513,26 @@ pub struct GraphColorConfig {
     #[garde(pattern(r"^#([0-9a-fA-F]{6}|[0-9a-fA-F]{8})$"))]
     #[default = "#00000000"]
     pub background: String,
+    #[garde(dive)]
+    #[default(vec![
+        GraphColorRule {
+            pattern: "main".into(),
+            color: "#61AFEF".into(),
+        },
+        GraphColorRule {
+            pattern: "master".into(),
+            color: "#61AFEF".into(),
+        },
+    ])]
+    pub rules: Vec<GraphColorRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Validate)]
+pub struct GraphColorRule {
+    #[garde(length(min = 1))]
+    pub pattern: String,
+    #[garde(pattern(r"^#([0-9a-fA-F]{6}|[0-9a-fA-F]{8})$"))]
+    pub color: String,
 }
 
 #[cfg(test)]
@@ -447,7 +551,11 @@ mod tests {
                     graph_style: None,
                     initial_selection: None,
                 },
-                git: CoreGitConfig { mailmap: false },
+                git: CoreGitConfig {
+                    mailmap: false,
+                    backend: GitBackendType::Subprocess,
+                    watch: false,
+                },
                 search: CoreSearchConfig {
                     ignore_case: false,
                     fuzzy: false,
@@ -499,12 +607,30 @@ mod tests {
                     date_format: "%Y-%m-%d %H:%M:%S %z".into(),
                     date_local: true,
                 },
+                diff: UiDiffConfig {
+                    height: 30,
+                    layout: DiffLayout::Unified,
+                    tab_width: 4,
+                },
+                syntax: UiSyntaxConfig {
+                    enabled: true,
+                    theme: "base16-ocean.dark".into(),
+                },
+                file_tree: UiFileTreeConfig {
+                    height: 30,
+                    width: 40,
+                },
+                blame: UiBlameConfig { height: 30 },
                 user_command: UiUserCommandConfig { height: 20 },
                 refs: UiRefsConfig { width: 26 },
             },
             graph: GraphConfig {
                 row_image_width: GraphImageWidthMode::Compact,
+                pinned_branches: Vec::new(),
+                compact_lanes: false,
+                max_lanes: None,
                 color: GraphColorConfig {
+                    mode: GraphColorMode::Lane,
                     branches: vec![
                         "#E06C76".into(),
                         "#98C379".into(),
@@ -515,6 +641,16 @@ mod tests {
                     ],
                     edge: "#00000000".into(),
                     background: "#00000000".into(),
+                    rules: vec![
+                        GraphColorRule {
+                            pattern: "main".into(),
+                            color: "#61AFEF".into(),
+                        },
+                        GraphColorRule {
+                            pattern: "master".into(),
+                            color: "#61AFEF".into(),
+                        },
+                    ],
                 },
             },
             color: ColorTheme::default(),
@@ -534,6 +670,8 @@ mod tests {
             initial_selection = "head"
             [core.git]
             mailmap = true
+            backend = "gix"
+            watch = true
             [core.search]
             ignore_case = true
             fuzzy = true
@@ -556,16 +694,33 @@ mod tests {
             height = 30
             date_format = "%Y/%m/%d %H:%M:%S"
             date_local = false
+            [ui.diff]
+            height = 40
+            layout = "side-by-side"
+            tab_width = 8
+            [ui.syntax]
+            enabled = false
+            theme = "Solarized (dark)"
+            [ui.file_tree]
+            height = 20
+            width = 50
+            [ui.blame]
+            height = 40
             [ui.user_command]
             height = 30
             [ui.refs]
             width = 40
             [graph]
             row_image_width = "fixed"
+            pinned_branches = ["main", "release/*"]
+            compact_lanes = true
+            max_lanes = 10
             [graph.color]
+            mode = "branch"
             branches = ["#ff0000", "#00ff00", "#0000ff"]
             edge = "#000000"
             background = "#ffffff"
+            rules = [{ pattern = "release/*", color = "#ffa500" }]
         "##;
         let actual: Config = toml::from_str::<OptionalConfig>(toml).unwrap().into();
         let expected = Config {
@@ -577,7 +732,11 @@ mod tests {
                     graph_style: Some(GraphStyle::Angular),
                     initial_selection: Some(InitialSelection::Head),
                 },
-                git: CoreGitConfig { mailmap: true },
+                git: CoreGitConfig {
+                    mailmap: true,
+                    backend: GitBackendType::Gix,
+                    watch: true,
+                },
                 search: CoreSearchConfig {
                     ignore_case: true,
                     fuzzy: true,
@@ -654,15 +813,37 @@ mod tests {
                     date_format: "%Y/%m/%d %H:%M:%S".into(),
                     date_local: false,
                 },
+                diff: UiDiffConfig {
+                    height: 40,
+                    layout: DiffLayout::SideBySide,
+                    tab_width: 8,
+                },
+                syntax: UiSyntaxConfig {
+                    enabled: false,
+                    theme: "Solarized (dark)".into(),
+                },
+                file_tree: UiFileTreeConfig {
+                    height: 20,
+                    width: 50,
+                },
+                blame: UiBlameConfig { height: 40 },
                 user_command: UiUserCommandConfig { height: 30 },
                 refs: UiRefsConfig { width: 40 },
             },
             graph: GraphConfig {
                 row_image_width: GraphImageWidthMode::Fixed,
+                pinned_branches: vec!["main".into(), "release/*".into()],
+                compact_lanes: true,
+                max_lanes: Some(10),
                 color: GraphColorConfig {
+                    mode: GraphColorMode::Branch,
                     branches: vec!["#ff0000".into(), "#00ff00".into(), "#0000ff".into()],
                     edge: "#000000".into(),
                     background: "#ffffff".into(),
+                    rules: vec![GraphColorRule {
+                        pattern: "release/*".into(),
+                        color: "#ffa500".into(),
+                    }],
                 },
             },
             color: ColorTheme::default(),
@@ -689,7 +870,11 @@ mod tests {
                     graph_style: None,
                     initial_selection: None,
                 },
-                git: CoreGitConfig { mailmap: false },
+                git: CoreGitConfig {
+                    mailmap: false,
+                    backend: GitBackendType::Subprocess,
+                    watch: false,
+                },
                 search: CoreSearchConfig {
                     ignore_case: false,
                     fuzzy: false,
@@ -741,12 +926,30 @@ mod tests {
                     date_format: "%Y-%m-%d %H:%M:%S %z".into(),
                     date_local: true,
                 },
+                diff: UiDiffConfig {
+                    height: 30,
+                    layout: DiffLayout::Unified,
+                    tab_width: 4,
+                },
+                syntax: UiSyntaxConfig {
+                    enabled: true,
+                    theme: "base16-ocean.dark".into(),
+                },
+                file_tree: UiFileTreeConfig {
+                    height: 30,
+                    width: 40,
+                },
+                blame: UiBlameConfig { height: 30 },
                 user_command: UiUserCommandConfig { height: 20 },
                 refs: UiRefsConfig { width: 26 },
             },
             graph: GraphConfig {
                 row_image_width: GraphImageWidthMode::Compact,
+                pinned_branches: Vec::new(),
+                compact_lanes: false,
+                max_lanes: None,
                 color: GraphColorConfig {
+                    mode: GraphColorMode::Lane,
                     branches: vec![
                         "#E06C76".into(),
                         "#98C379".into(),
@@ -757,6 +960,16 @@ mod tests {
                     ],
                     edge: "#00000000".into(),
                     background: "#00000000".into(),
+                    rules: vec![
+                        GraphColorRule {
+                            pattern: "main".into(),
+                            color: "#61AFEF".into(),
+                        },
+                        GraphColorRule {
+                            pattern: "master".into(),
+                            color: "#61AFEF".into(),
+                        },
+                    ],
                 },
             },
             color: ColorTheme::default(),
@@ -804,4 +1017,21 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_config_syntax_theme() {
+        let toml = r#"
+            [ui.syntax]
+            theme = "InspiredGitHub"
+        "#;
+        let config: Config = toml::from_str::<OptionalConfig>(toml).unwrap().into();
+        assert!(config.validate().is_ok());
+
+        let toml = r#"
+            [ui.syntax]
+            theme = "unknown"
+        "#;
+        let config: Config = toml::from_str::<OptionalConfig>(toml).unwrap().into();
+        assert!(config.validate().is_err());
+    }
 }
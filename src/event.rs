@@ -13,7 +13,10 @@ use serde::{
     Deserialize,
 };
 
-use crate::view::RefreshViewContext;
+use crate::{
+    git::{CommitHash, FileDiff, GitError},
+    view::RefreshViewContext,
+};
 
 #[derive(Debug)]
 pub enum AppEvent {
@@ -22,6 +25,15 @@ pub enum AppEvent {
     Quit,
     OpenDetail,
     CloseDetail,
+    OpenDiff,
+    CloseDiff,
+    OpenFileDiff,
+    OpenFileTree,
+    CloseFileTree,
+    OpenFilePreview,
+    OpenBlame(CommitHash, String),
+    CloseBlame,
+    BlameParent,
     OpenUserCommand(usize),
     CloseUserCommand,
     OpenRefs,
@@ -33,6 +45,15 @@ pub enum AppEvent {
     SelectParentCommit,
     CopyToClipboard { name: String, value: String },
     Refresh(RefreshViewContext),
+    FilterPaths(Vec<String>, RefreshViewContext),
+    FilterCommits(String, RefreshViewContext),
+    ToggleFirstParent(RefreshViewContext),
+    ToggleSimplifyByDecoration(RefreshViewContext),
+    ExpandCommits(CommitHash, RefreshViewContext),
+    ToggleFold(CommitHash, RefreshViewContext),
+    CommitsLoaded,
+    CommitDiffLoaded(CommitHash, Result<Vec<FileDiff>, GitError>),
+    RepositoryChanged,
     ClearStatusLine,
     UpdateStatusInput(String, Option<u16>, Option<String>),
     NotifyInfo(String),
@@ -50,6 +71,11 @@ impl Sender {
     pub fn send(&self, event: AppEvent) {
         self.tx.send(event).unwrap();
     }
+
+    /// Sends the event unless the receiver has been dropped, for threads that may outlive the application.
+    pub fn try_send(&self, event: AppEvent) {
+        let _ = self.tx.send(event);
+    }
 }
 
 impl Debug for Sender {
@@ -213,6 +239,18 @@ pub enum UserEvent {
     Confirm,
     RefList,
     Search,
+    PathFilter,
+    CommitFilter,
+    FirstParentToggle,
+    DecorationToggle,
+    FoldToggle,
+    HighlightToggle,
+    DiffToggle,
+    DiffLayoutToggle,
+    GoToNextFile,
+    GoToPreviousFile,
+    FileTreeToggle,
+    Blame,
     UserCommand(usize),
     IgnoreCaseToggle,
     FuzzyToggle,
@@ -277,6 +315,18 @@ impl<'de> Deserialize<'de> for UserEvent {
                         "confirm" => Ok(UserEvent::Confirm),
                         "ref_list" | "ref_list_toggle" => Ok(UserEvent::RefList),
                         "search" => Ok(UserEvent::Search),
+                        "path_filter" => Ok(UserEvent::PathFilter),
+                        "commit_filter" => Ok(UserEvent::CommitFilter),
+                        "first_parent_toggle" => Ok(UserEvent::FirstParentToggle),
+                        "decoration_toggle" => Ok(UserEvent::DecorationToggle),
+                        "fold_toggle" => Ok(UserEvent::FoldToggle),
+                        "highlight_toggle" => Ok(UserEvent::HighlightToggle),
+                        "diff_toggle" => Ok(UserEvent::DiffToggle),
+                        "diff_layout_toggle" => Ok(UserEvent::DiffLayoutToggle),
+                        "go_to_next_file" => Ok(UserEvent::GoToNextFile),
+                        "go_to_previous_file" => Ok(UserEvent::GoToPreviousFile),
+                        "file_tree_toggle" => Ok(UserEvent::FileTreeToggle),
+                        "blame" => Ok(UserEvent::Blame),
                         "ignore_case_toggle" => Ok(UserEvent::IgnoreCaseToggle),
                         "fuzzy_toggle" => Ok(UserEvent::FuzzyToggle),
                         "refresh" => Ok(UserEvent::Refresh),
@@ -1,4 +1,4 @@
-use std::{cell::RefCell, process::Command};
+use std::{cell::RefCell, path::Path, process::Command};
 
 use arboard::Clipboard;
 
@@ -73,6 +73,7 @@ fn copy_to_clipboard_auto(value: String) -> Result<(), String> {
 
 pub struct ExternalCommandParameters<'a> {
     pub command: &'a [String],
+    pub repository_path: &'a Path,
     pub target_hash: &'a str,
     pub parent_hashes: Vec<&'a str>,
     pub all_refs: Vec<&'a str>,
@@ -89,6 +90,7 @@ pub fn exec_user_command(params: ExternalCommandParameters) -> Result<String, St
 
     let output = Command::new(&command[0])
         .args(&command[1..])
+        .current_dir(params.repository_path)
         .output()
         .map_err(|e| format!("Failed to execute command: {e:?}"))?;
 
@@ -109,6 +111,7 @@ pub fn exec_user_command_suspend(params: ExternalCommandParameters) -> Result<()
 
     let output = Command::new(&command[0])
         .args(&command[1..])
+        .current_dir(params.repository_path)
         .status()
         .map_err(|e| format!("Failed to execute command: {e:?}"))?;
 
@@ -1,14 +1,133 @@
+mod diff;
+mod filter;
+mod gitoxide;
+mod subprocess;
+
 use std::{
+    collections::BTreeSet,
+    ffi::OsString,
+    fmt,
     hash::Hash,
-    io::{BufRead, BufReader},
+    io::{self, BufReader, Read},
     path::{Path, PathBuf},
-    process::{Command, Stdio},
+    process::{Child, ChildStdout, Command, ExitStatus, Stdio},
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 use chrono::{DateTime, FixedOffset};
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Deserialize;
+
+use gitoxide::GixBackend;
+use subprocess::SubprocessBackend;
+
+pub use diff::{parse_unified_diff, DiffLine, DiffLineKind, FileDiff};
+pub use filter::{CommitFilter, CommitFilterQuery};
+
+pub type Result<T> = std::result::Result<T, GitError>;
 
-use crate::Result;
+const COMMIT_BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub enum GitError {
+    NotRepository,
+    InvalidPath {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Spawn {
+        command: String,
+        source: io::Error,
+    },
+    Io {
+        command: String,
+        source: io::Error,
+    },
+    CommandFailed {
+        command: String,
+        status: ExitStatus,
+        stderr: String,
+    },
+    UnexpectedOutput {
+        command: String,
+        output: String,
+    },
+    Gix {
+        operation: &'static str,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    InvalidPattern {
+        pattern: String,
+        source: regex::Error,
+    },
+    NoCommits,
+    NoMatchingCommits,
+    CommitNotFound(CommitHash),
+    CollapsedCommits,
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitError::NotRepository => {
+                write!(f, "not a git repository (or any of the parent directories)")
+            }
+            GitError::InvalidPath { path, source } => {
+                write!(f, "cannot change to '{}': {source}", path.display())
+            }
+            GitError::Spawn { command, source } => {
+                write!(f, "failed to execute `{command}`: {source}")
+            }
+            GitError::Io { command, source } => {
+                write!(f, "failed to read the output of `{command}`: {source}")
+            }
+            GitError::CommandFailed {
+                command,
+                status,
+                stderr,
+            } => {
+                write!(f, "`{command}` failed ({status})")?;
+                if !stderr.is_empty() {
+                    write!(f, ": {stderr}")?;
+                }
+                Ok(())
+            }
+            GitError::UnexpectedOutput { command, output } => {
+                write!(f, "unexpected output from `{command}`: {output}")
+            }
+            GitError::Gix { operation, source } => write!(f, "failed to {operation}: {source}"),
+            GitError::InvalidPattern { pattern, source } => {
+                write!(f, "invalid pattern '{pattern}': {source}")
+            }
+            GitError::NoCommits => write!(f, "no commits in the repository"),
+            GitError::NoMatchingCommits => {
+                write!(f, "no commits match the given revisions and paths")
+            }
+            GitError::CommitNotFound(hash) => write!(f, "commit not found: {}", hash.as_str()),
+            GitError::CollapsedCommits => {
+                write!(
+                    f,
+                    "the row of collapsed commits must be expanded to show a commit"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for GitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GitError::InvalidPath { source, .. }
+            | GitError::Spawn { source, .. }
+            | GitError::Io { source, .. } => Some(source),
+            GitError::Gix { source, .. } => Some(source.as_ref()),
+            GitError::InvalidPattern { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct CommitHash(String);
@@ -41,6 +160,8 @@ pub struct Commit {
     pub subject: String,
     pub body: String,
     pub parent_commit_hashes: Vec<CommitHash>,
+    // the path of the followed file in this commit, which differs from the given one before a rename
+    pub followed_path: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -82,6 +203,19 @@ impl Ref {
             Ref::Stash { target, .. } => target,
         }
     }
+
+    /// Returns whether the name matches the glob pattern.
+    ///
+    /// The remote branches also match the patterns without the remote name, e.g. `origin/main` matches `main`.
+    pub fn matches_pattern(&self, pattern: &str) -> bool {
+        let name = self.name();
+        let local_name = match self {
+            Ref::RemoteBranch { .. } => name.split_once('/').map(|(_, n)| n),
+            _ => None,
+        };
+        filter::glob_matches(pattern, name)
+            || local_name.is_some_and(|n| filter::glob_matches(pattern, n))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -91,12 +225,114 @@ pub enum Head {
     None,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy)]
 pub enum SortCommit {
+    #[default]
     Chronological,
     Topological,
 }
 
+#[derive(Debug, Default, Clone)]
+pub struct RevisionFilter {
+    revisions: Vec<String>,
+    paths: Vec<String>,
+    // follow the history of the single file beyond renames, i.e. `--follow`
+    follow: bool,
+}
+
+impl RevisionFilter {
+    pub fn new(revisions: Vec<String>, paths: Vec<String>) -> Self {
+        Self {
+            revisions,
+            paths,
+            follow: false,
+        }
+    }
+
+    /// Follows the renames of the file, which must be the only path.
+    pub fn with_follow(mut self, follow: bool) -> Self {
+        self.follow = follow;
+        self
+    }
+
+    /// Returns the filter with the same revisions and the given paths.
+    pub fn with_paths(&self, paths: Vec<String>) -> Self {
+        Self::new(self.revisions.clone(), paths)
+    }
+
+    fn is_default_revisions(&self) -> bool {
+        self.revisions.is_empty()
+    }
+}
+
+// A simplified pathspec match, the path is matched if it is the given path or under the given directory,
+// or if it matches the wildcards of the pathspec unless the pathspec is literal.
+fn pathspec_matches(pathspec: &str, path: &str) -> bool {
+    let (magic, pathspec) = split_pathspec_magic(pathspec);
+    let pathspec = pathspec.trim_start_matches("./").trim_end_matches('/');
+    if pathspec.is_empty() || pathspec == "." {
+        return true;
+    }
+    let is_prefix = path
+        .strip_prefix(pathspec)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'));
+    let literal = magic.contains("literal");
+    is_prefix || (!literal && pathspec.contains(['*', '?']) && filter::glob_matches(pathspec, path))
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitBackendType {
+    #[default]
+    Subprocess,
+    Gix,
+}
+
+/// The operations to read a repository, implemented by running `git` commands or by reading the object database directly.
+trait GitBackend: fmt::Debug + Send + Sync {
+    fn load_refs(&self) -> Result<(RefMap, Head)>;
+
+    fn load_stashes(&self, mailmap: bool) -> Result<Vec<Commit>>;
+
+    fn load_stashes_as_refs(&self) -> Result<RefMap>;
+
+    /// Passes the commits to `f` in the `git log` order until `f` returns `false`.
+    fn read_commits(&self, options: &LogOptions, f: &mut dyn FnMut(Commit) -> bool) -> Result<()>;
+
+    fn diff_summary(&self, commit_hash: &CommitHash) -> Result<Vec<FileChange>>;
+
+    fn initial_commit_additions(&self, commit_hash: &CommitHash) -> Result<Vec<FileChange>>;
+
+    /// Returns the patch of the commit against its first parent, or against the empty tree for the root commit.
+    fn diff_patch(&self, commit_hash: &CommitHash) -> Result<Vec<FileDiff>>;
+
+    /// Returns all files in the tree of the commit, without the directories.
+    fn tree_entries(&self, commit_hash: &CommitHash) -> Result<Vec<TreeEntry>>;
+
+    /// Returns the content of the blob, up to `limit` bytes from the beginning.
+    fn blob_content(&self, object_id: &str, limit: usize) -> Result<Vec<u8>>;
+
+    /// Returns whether the path is a file in the tree of the commit.
+    fn is_file(&self, commit_hash: &CommitHash, path: &str) -> Result<bool>;
+
+    /// Returns the last commit that changed the path in the history of the commit.
+    fn last_modified_commit(
+        &self,
+        commit_hash: &CommitHash,
+        path: &str,
+    ) -> Result<Option<CommitHash>>;
+
+    /// Returns the lines of the file at the commit, with the commits that last changed them.
+    fn blame(&self, commit_hash: &CommitHash, path: &str) -> Result<Blame>;
+}
+
+fn open_backend(backend: GitBackendType, path: &RepositoryPath) -> Result<Arc<dyn GitBackend>> {
+    Ok(match backend {
+        GitBackendType::Subprocess => Arc::new(SubprocessBackend::open(path)?),
+        GitBackendType::Gix => Arc::new(GixBackend::open(path)?),
+    })
+}
+
 type CommitMap = FxHashMap<CommitHash, Commit>;
 type CommitsMap = FxHashMap<CommitHash, Vec<CommitHash>>;
 
@@ -105,6 +341,7 @@ type RefMap = FxHashMap<CommitHash, Vec<Ref>>;
 #[derive(Debug)]
 pub struct Repository {
     path: PathBuf,
+    backend: Arc<dyn GitBackend>,
     commit_map: CommitMap,
 
     parents_map: CommitsMap,
@@ -114,47 +351,232 @@ pub struct Repository {
     head: Head,
     // to preserve order of the original commits from `git log`, we store the commit hashes
     commit_hashes: Vec<CommitHash>,
+    // stashes whose base commits have not been loaded yet, keyed by the base commit hash
+    pending_stashes: FxHashMap<CommitHash, Vec<Commit>>,
+    // the options the commits were loaded with, to load only the new commits on refresh
+    options: LogOptions,
+    // pairs of a commit and its parent between which some commits are hidden by `filter_commits`
+    hidden_parents: FxHashSet<(CommitHash, CommitHash)>,
+    // the hidden commits of each row collapsed by `simplify_by_decoration`, keyed by the newest one
+    collapsed_map: CommitsMap,
+    // the merge commits folded by `fold_merges`, keyed by the rows their side branches are folded into
+    folded_rows: FxHashMap<CommitHash, CommitHash>,
 }
 
 impl Repository {
-    pub fn load(
-        path: &Path,
+    /// Loads the refs and stashes of the repository.
+    /// The commits are read from the returned stream and added by `append_commits`.
+    pub fn load_without_commits(
+        path: &RepositoryPath,
+        backend: GitBackendType,
         sort: SortCommit,
         max_count: Option<usize>,
         mailmap: bool,
-    ) -> Result<Self> {
-        check_git_repository(path)?;
+        filter: &RevisionFilter,
+    ) -> Result<(Self, CommitStream)> {
+        let backend = open_backend(backend, path)?;
 
-        let (mut ref_map, head) = load_refs(path);
+        let (mut ref_map, head) = backend.load_refs()?;
 
-        let stashes = load_all_stashes(path, mailmap);
-        let commits = load_all_commits(path, sort, &head, &stashes, max_count, mailmap);
-        if commits.is_empty() {
-            return Err("no commits in the repository".into());
-        }
+        // stashes are only shown along with the default revisions (all refs)
+        let stashes = if filter.is_default_revisions() {
+            backend.load_stashes(mailmap)?
+        } else {
+            Vec::new()
+        };
+
+        let stash_ref_map = backend.load_stashes_as_refs()?;
+        merge_ref_maps(&mut ref_map, stash_ref_map);
+
+        let options = LogOptions {
+            sort,
+            stash_base_hashes: stashes
+                .iter()
+                .filter_map(|stash| stash.parent_commit_hashes.first().cloned())
+                .collect(),
+            has_head: !matches!(head, Head::None),
+            max_count,
+            mailmap,
+            filter: filter.clone(),
+            exclude_hashes: Vec::new(),
+        };
+        let stream = CommitStream {
+            backend: backend.clone(),
+            options: options.clone(),
+        };
+
+        let mut repository = Self::new(
+            path.path.clone(),
+            backend,
+            CommitMap::default(),
+            CommitsMap::default(),
+            CommitsMap::default(),
+            ref_map,
+            head,
+            Vec::new(),
+        );
+        repository.pending_stashes = group_stashes_by_base(stashes);
+        repository.options = options;
+
+        Ok((repository, stream))
+    }
 
-        let commits = merge_stashes_to_commits(commits, stashes);
-        let commit_hashes = commits.iter().map(|c| c.commit_hash.clone()).collect();
+    /// Adds the commits that follow the already loaded ones in the `git log` order.
+    pub fn append_commits(&mut self, commits: Vec<Commit>) {
+        let commits = merge_stashes_to_commits(commits, &mut self.pending_stashes);
+        for commit in commits {
+            let hash = &commit.commit_hash;
+            for parent_hash in &commit.parent_commit_hashes {
+                self.parents_map
+                    .entry(hash.clone())
+                    .or_default()
+                    .push(parent_hash.clone());
+                self.children_map
+                    .entry(parent_hash.clone())
+                    .or_default()
+                    .push(hash.clone());
+            }
+            self.commit_hashes.push(hash.clone());
+            self.commit_map.insert(hash.clone(), commit);
+        }
+    }
 
-        let (parents_map, children_map) = build_commits_maps(&commits);
-        let commit_map = to_commit_map(commits);
+    /// Reloads the refs and loads only the commits that are not loaded yet.
+    ///
+    /// Returns `false` without changing the repository if it has to be loaded again from scratch,
+    /// i.e. when some of the loaded commits are no longer reachable or the new commits cannot be placed on top of them.
+    pub fn refresh(&mut self) -> Result<bool> {
+        let filter = &self.options.filter;
+        if self.options.max_count.is_some()
+            || !filter.is_default_revisions()
+            || !filter.paths.is_empty()
+        {
+            // the loaded commits are not decided only by the refs
+            return Ok(false);
+        }
 
-        let stash_ref_map = load_stashes_as_refs(path);
+        let (mut ref_map, head) = self.backend.load_refs()?;
+        let stash_ref_map = self.backend.load_stashes_as_refs()?;
+        if stash_hashes(&stash_ref_map) != stash_hashes(&self.ref_map) {
+            // stashes are placed next to their base commits, not on top
+            return Ok(false);
+        }
         merge_ref_maps(&mut ref_map, stash_ref_map);
 
-        Ok(Self::new(
-            path.to_path_buf(),
+        let loaded_tips: Vec<CommitHash> = tip_hashes(&self.ref_map, &self.head, &self.options)
+            .into_iter()
+            .filter(|hash| self.commit_map.contains_key(hash))
+            .collect();
+        let options = LogOptions {
+            has_head: !matches!(head, Head::None),
+            exclude_hashes: loaded_tips,
+            ..self.options.clone()
+        };
+        let mut commits = Vec::new();
+        self.backend.read_commits(&options, &mut |commit| {
+            commits.push(commit);
+            true
+        })?;
+
+        if let SortCommit::Chronological = options.sort {
+            let newest_loaded_date = self.commit_map.values().map(|c| c.committer_date).max();
+            if let Some(date) = newest_loaded_date {
+                if commits.iter().any(|c| c.committer_date < date) {
+                    // `git log --date-order` would place the new commits among the loaded ones
+                    return Ok(false);
+                }
+            }
+        }
+
+        let mut reachable_roots: Vec<&CommitHash> = tip_hashes(&ref_map, &head, &options)
+            .iter()
+            .filter_map(|hash| self.commit_map.get_key_value(hash).map(|(k, _)| k))
+            .collect();
+        reachable_roots.extend(commits.iter().flat_map(|c| &c.parent_commit_hashes));
+        reachable_roots.extend(self.ref_map.values().flatten().filter_map(|r| match r {
+            Ref::Stash { target, .. } => Some(target),
+            _ => None,
+        }));
+        if self.count_reachable_commits(reachable_roots) < self.commit_map.len() {
+            // some refs have been deleted or rewritten
+            return Ok(false);
+        }
+
+        self.prepend_commits(commits);
+        self.ref_map = ref_map;
+        self.head = head;
+        self.options.has_head = options.has_head;
+        Ok(true)
+    }
+
+    fn count_reachable_commits<'a>(&'a self, roots: Vec<&'a CommitHash>) -> usize {
+        let mut visited = FxHashSet::default();
+        let mut stack = roots;
+        while let Some(hash) = stack.pop() {
+            if !self.commit_map.contains_key(hash) || !visited.insert(hash) {
+                continue;
+            }
+            stack.extend(self.parents_map.get(hash).into_iter().flatten());
+        }
+        visited.len()
+    }
+
+    fn prepend_commits(&mut self, commits: Vec<Commit>) {
+        let commits = merge_stashes_to_commits(commits, &mut self.pending_stashes);
+
+        // the new children come before the loaded ones, in the same order as loading all at once
+        let mut new_children_map = CommitsMap::default();
+        let mut commit_hashes = Vec::with_capacity(commits.len() + self.commit_hashes.len());
+        for commit in commits {
+            let hash = &commit.commit_hash;
+            for parent_hash in &commit.parent_commit_hashes {
+                self.parents_map
+                    .entry(hash.clone())
+                    .or_default()
+                    .push(parent_hash.clone());
+                new_children_map
+                    .entry(parent_hash.clone())
+                    .or_default()
+                    .push(hash.clone());
+            }
+            commit_hashes.push(hash.clone());
+            self.commit_map.insert(hash.clone(), commit);
+        }
+        for (parent_hash, mut children) in new_children_map {
+            let loaded_children = self.children_map.entry(parent_hash).or_default();
+            children.append(loaded_children);
+            *loaded_children = children;
+        }
+        commit_hashes.append(&mut self.commit_hashes);
+        self.commit_hashes = commit_hashes;
+    }
+
+    /// Builds the repository from the given commits, for the tests which do not read a repository.
+    #[cfg(test)]
+    pub fn from_commits(
+        path: &RepositoryPath,
+        commit_map: CommitMap,
+        parents_map: CommitsMap,
+        children_map: CommitsMap,
+        ref_map: RefMap,
+        head: Head,
+        commit_hashes: Vec<CommitHash>,
+    ) -> Self {
+        Self::new(
+            path.path.clone(),
+            Arc::new(SubprocessBackend::new(path)),
             commit_map,
             parents_map,
             children_map,
             ref_map,
             head,
             commit_hashes,
-        ))
+        )
     }
 
-    pub fn new(
+    fn new(
         path: PathBuf,
+        backend: Arc<dyn GitBackend>,
         commit_map: CommitMap,
         parents_map: CommitsMap,
         children_map: CommitsMap,
@@ -164,12 +586,352 @@ impl Repository {
     ) -> Self {
         Self {
             path,
+            backend,
             commit_map,
             parents_map,
             children_map,
             ref_map,
             head,
             commit_hashes,
+            pending_stashes: FxHashMap::default(),
+            options: LogOptions::default(),
+            hidden_parents: FxHashSet::default(),
+            collapsed_map: CommitsMap::default(),
+            folded_rows: FxHashMap::default(),
+        }
+    }
+
+    /// Returns the repository with only the commits that match the filter.
+    ///
+    /// The parents are rewritten to the nearest matching ancestors to keep the graph connected,
+    /// and `has_hidden_commits_between` tells where the commits are hidden.
+    pub fn filter_commits(&self, filter: &CommitFilter) -> Repository {
+        // parents come before their children in the reversed `git log` order
+        let history = self.commit_hashes.iter().rev().map(|hash| {
+            let parents = self.parents_map.get(hash).map_or(&[][..], Vec::as_slice);
+            (hash, parents)
+        });
+        let parents_map =
+            simplify_history(history, |hash, _| filter.matches(&self.commit_map[hash]));
+        self.with_parents(parents_map, CommitsMap::default())
+    }
+
+    /// Returns the repository with only the commits on the first-parent history of the refs,
+    /// as `git log --first-parent` does.
+    ///
+    /// The merge commits keep their original parents in `Commit::parent_commit_hashes`.
+    pub fn first_parent_only(&self) -> Repository {
+        let mut first_parents = FxHashSet::default();
+        let mut parents_map = CommitsMap::default();
+        // children come before their parents in the `git log` order
+        for hash in &self.commit_hashes {
+            let is_tip = self.ref_map.contains_key(hash) || !self.children_map.contains_key(hash);
+            if !is_tip && !first_parents.contains(hash) {
+                continue;
+            }
+            let parents: Vec<CommitHash> = self
+                .parents_hash(hash)
+                .into_iter()
+                .take(1)
+                .cloned()
+                .collect();
+            first_parents.extend(parents.iter().cloned());
+            parents_map.insert(hash.clone(), parents);
+        }
+        self.with_parents(parents_map, CommitsMap::default())
+    }
+
+    /// Returns the repository with only the commits that have refs and the merge and fork points that connect them,
+    /// like `git log --simplify-by-decoration`.
+    ///
+    /// The hidden commits below each shown commit are collapsed into a row of the newest one,
+    /// whose subject is replaced with the number of the commits and `collapsed_commits` tells the hidden ones.
+    /// The commits in `expanded` are shown even if they have no refs.
+    pub fn simplify_by_decoration(&self, expanded: &FxHashSet<CommitHash>) -> Repository {
+        let mut kept: FxHashSet<CommitHash> = self
+            .commit_hashes
+            .iter()
+            .filter(|hash| {
+                self.ref_map.contains_key(*hash)
+                    || !self.children_map.contains_key(*hash)
+                    || expanded.contains(*hash)
+                    || matches!(&self.head, Head::Detached { target } if target == *hash)
+            })
+            .cloned()
+            .collect();
+
+        // the commits where the histories of two or more kept commits join are kept as merge points,
+        // and where they diverge as fork points, until no more commits are needed
+        // keeping more commits in one direction does not make more commits needed in the same direction,
+        // so the passes alternate until one of them keeps no more commits
+        let mut forks_checked = false;
+        let parents_map = loop {
+            let kept_count = kept.len();
+            let history = self.commit_hashes.iter().rev().map(|hash| {
+                let parents = self.parents_map.get(hash).map_or(&[][..], Vec::as_slice);
+                (hash, parents)
+            });
+            let parents_map = simplify_history(history, |hash, nearest| {
+                nearest.len() > 1 || kept.contains(hash)
+            });
+            for hash in parents_map.keys() {
+                kept.insert(hash.clone());
+            }
+            if forks_checked && kept.len() == kept_count {
+                break parents_map;
+            }
+
+            let kept_count = kept.len();
+            let history = self.commit_hashes.iter().map(|hash| {
+                let children = self.children_map.get(hash).map_or(&[][..], Vec::as_slice);
+                (hash, children)
+            });
+            let children_map = simplify_history(history, |hash, nearest| {
+                nearest.len() > 1 || kept.contains(hash)
+            });
+            for hash in children_map.into_keys() {
+                kept.insert(hash);
+            }
+            if kept.len() == kept_count {
+                break parents_map;
+            }
+            forks_checked = true;
+        };
+
+        let positions: FxHashMap<&CommitHash, usize> = self
+            .commit_hashes
+            .iter()
+            .enumerate()
+            .map(|(i, hash)| (hash, i))
+            .collect();
+        let mut shown_parents_map = CommitsMap::default();
+        let mut collapsed_map = CommitsMap::default();
+        let mut claimed = FxHashSet::default();
+        // the newer commits claim the hidden commits first
+        for hash in &self.commit_hashes {
+            let Some(parents) = parents_map.get(hash) else {
+                continue;
+            };
+            let mut direct_parents = Vec::new();
+            let mut collapsed = Vec::new();
+            let mut collapsed_parents = Vec::new();
+            let mut visited = FxHashSet::default();
+            let mut stack: Vec<&CommitHash> = Vec::new();
+            for parent_hash in self.parents_hash(hash) {
+                if kept.contains(parent_hash) || !positions.contains_key(parent_hash) {
+                    direct_parents.push(parent_hash.clone());
+                } else {
+                    stack.push(parent_hash);
+                }
+            }
+            while let Some(h) = stack.pop() {
+                if !visited.insert(h) {
+                    continue;
+                }
+                if kept.contains(h) || !positions.contains_key(h) {
+                    if !collapsed_parents.contains(h) {
+                        collapsed_parents.push(h.clone());
+                    }
+                    continue;
+                }
+                if claimed.insert(h) {
+                    collapsed.push(h.clone());
+                }
+                stack.extend(self.parents_hash(h));
+            }
+
+            match collapsed.len() {
+                0 => {
+                    shown_parents_map.insert(hash.clone(), parents.clone());
+                }
+                n => {
+                    collapsed.sort_by_key(|h| positions[h]);
+                    let row_hash = collapsed[0].clone();
+                    direct_parents.push(row_hash.clone());
+                    shown_parents_map.insert(hash.clone(), direct_parents);
+                    shown_parents_map.insert(row_hash.clone(), collapsed_parents);
+                    // a single hidden commit is shown as it is
+                    if n > 1 {
+                        collapsed_map.insert(row_hash, collapsed);
+                    }
+                }
+            }
+        }
+        self.with_parents(shown_parents_map, collapsed_map)
+    }
+
+    /// Returns the repository with the side branches of the merge commits folded into a row each,
+    /// whose subject is replaced with the number of the commits and `collapsed_commits` tells the hidden ones.
+    ///
+    /// Only the commits that are merged by the merge commit alone are folded,
+    /// so the commits also reachable from the other commits are kept shown.
+    pub fn fold_merges(&self, merges: &FxHashSet<CommitHash>) -> Repository {
+        let positions: FxHashMap<&CommitHash, usize> = self
+            .commit_hashes
+            .iter()
+            .enumerate()
+            .map(|(i, hash)| (hash, i))
+            .collect();
+        // the rows collapsed by the previous modification are kept as they are
+        let mut collapsed_map = self.collapsed_map.clone();
+        let mut folded_rows = FxHashMap::default();
+        let mut row_hashes: FxHashMap<&CommitHash, CommitHash> = FxHashMap::default();
+        // the rows of this repository folded into each row
+        let mut folded_row_members: FxHashMap<CommitHash, Vec<&CommitHash>> = FxHashMap::default();
+        // the newer merge commits are folded first, so the merge commits folded by them are ignored
+        for hash in &self.commit_hashes {
+            if !merges.contains(hash) || row_hashes.contains_key(hash) {
+                continue;
+            }
+            let folded = self.side_branch_commits(hash, &positions);
+            let Some(row_hash) = folded.first().map(|h| (*h).clone()) else {
+                continue;
+            };
+            // the rows collapsed before are folded with the commits hidden in them
+            let mut members = Vec::new();
+            for h in &folded {
+                match collapsed_map.remove(*h) {
+                    Some(hidden) => members.extend(hidden),
+                    None => members.push((*h).clone()),
+                }
+                row_hashes.insert(h, row_hash.clone());
+            }
+            collapsed_map.insert(row_hash.clone(), members);
+            folded_rows.insert(row_hash.clone(), hash.clone());
+            folded_row_members.insert(row_hash, folded);
+        }
+
+        let mut parents_map = CommitsMap::default();
+        for hash in &self.commit_hashes {
+            let members = match row_hashes.get(hash) {
+                Some(row_hash) if row_hash == hash => &folded_row_members[hash][..],
+                Some(_) => continue,
+                None => std::slice::from_ref(&hash),
+            };
+            let mut parents = Vec::new();
+            for parent_hash in members.iter().flat_map(|h| self.parents_hash(h)) {
+                let parent_hash = row_hashes.get(parent_hash).unwrap_or(parent_hash);
+                if parent_hash != hash && !parents.contains(parent_hash) {
+                    parents.push(parent_hash.clone());
+                }
+            }
+            parents_map.insert(hash.clone(), parents);
+        }
+
+        let mut repository = self.with_parents(parents_map, collapsed_map);
+        repository.folded_rows = folded_rows;
+        repository
+    }
+
+    /// Returns whether some commits are merged by the merge commit alone, which `fold_merges` folds into a row.
+    pub fn has_side_branch_commits(&self, merge_hash: &CommitHash) -> bool {
+        let positions: FxHashMap<&CommitHash, usize> = self
+            .commit_hashes
+            .iter()
+            .enumerate()
+            .map(|(i, hash)| (hash, i))
+            .collect();
+        !self.side_branch_commits(merge_hash, &positions).is_empty()
+    }
+
+    // Returns the commits that are reachable only through the merge commit from its second and later parents,
+    // in the order of `commit_hashes`.
+    fn side_branch_commits<'a>(
+        &'a self,
+        merge_hash: &CommitHash,
+        positions: &FxHashMap<&CommitHash, usize>,
+    ) -> Vec<&'a CommitHash> {
+        let parents = self.parents_hash(merge_hash);
+        let Some((first_parent, merged_parents)) = parents.split_first() else {
+            return Vec::new();
+        };
+        let mut folded = Vec::new();
+        let mut folded_set = FxHashSet::default();
+        // the children come before their parents in `commit_hashes`,
+        // so whether all the children of a commit are folded is known when it is visited
+        let mut queue: BTreeSet<usize> = merged_parents
+            .iter()
+            .filter_map(|hash| positions.get(hash).copied())
+            .collect();
+        while let Some(pos) = queue.pop_first() {
+            let hash = &self.commit_hashes[pos];
+            let only_merged = hash != *first_parent
+                && self
+                    .children_hash(hash)
+                    .into_iter()
+                    .all(|child| child == merge_hash || folded_set.contains(child));
+            if !only_merged {
+                continue;
+            }
+            folded.push(hash);
+            folded_set.insert(hash);
+            queue.extend(
+                self.parents_hash(hash)
+                    .into_iter()
+                    .filter_map(|parent_hash| positions.get(parent_hash).copied()),
+            );
+        }
+        folded
+    }
+
+    // Builds the repository with only the commits in `parents_map`, whose parents are replaced with the given ones.
+    fn with_parents(&self, parents_map: CommitsMap, collapsed_map: CommitsMap) -> Repository {
+        let mut commit_map = CommitMap::default();
+        let mut children_map = CommitsMap::default();
+        let mut commit_hashes = Vec::new();
+        let mut hidden_parents = FxHashSet::default();
+        for hash in &self.commit_hashes {
+            let Some(parents) = parents_map.get(hash) else {
+                continue;
+            };
+            let original_parents = self.parents_hash(hash);
+            for parent_hash in parents {
+                // the edges of the collapsed rows are not dotted, as the rows tell the hidden commits
+                let hidden = !original_parents.contains(&parent_hash)
+                    || self.has_hidden_commits_between(hash, parent_hash);
+                if hidden
+                    && !collapsed_map.contains_key(hash)
+                    && !collapsed_map.contains_key(parent_hash)
+                {
+                    hidden_parents.insert((hash.clone(), parent_hash.clone()));
+                }
+                children_map
+                    .entry(parent_hash.clone())
+                    .or_default()
+                    .push(hash.clone());
+            }
+            let mut commit = self.commit_map[hash].clone();
+            if let Some(collapsed) = collapsed_map.get(hash) {
+                commit.subject = match collapsed.len() {
+                    1 => "1 commit".into(),
+                    n => format!("{n} commits"),
+                };
+                commit.body = String::new();
+                // the row is not the newest hidden commit, which only lends its hash to the row
+                commit.author_name = String::new();
+                commit.author_email = String::new();
+                commit.committer_name = String::new();
+                commit.committer_email = String::new();
+                commit.parent_commit_hashes = parents.clone();
+            }
+            commit_hashes.push(hash.clone());
+            commit_map.insert(hash.clone(), commit);
+        }
+
+        Repository {
+            path: self.path.clone(),
+            backend: self.backend.clone(),
+            commit_map,
+            parents_map,
+            children_map,
+            ref_map: self.ref_map.clone(),
+            head: self.head.clone(),
+            commit_hashes,
+            pending_stashes: FxHashMap::default(),
+            options: self.options.clone(),
+            hidden_parents,
+            collapsed_map,
+            folded_rows: FxHashMap::default(),
         }
     }
 
@@ -191,6 +953,26 @@ impl Repository {
             .unwrap_or_default()
     }
 
+    /// Returns whether some commits between the commit and its parent are hidden by `filter_commits`.
+    pub fn has_hidden_commits_between(
+        &self,
+        commit_hash: &CommitHash,
+        parent_hash: &CommitHash,
+    ) -> bool {
+        self.hidden_parents
+            .contains(&(commit_hash.clone(), parent_hash.clone()))
+    }
+
+    /// Returns the hidden commits if the commit is a row collapsed by `simplify_by_decoration` or `fold_merges`.
+    pub fn collapsed_commits(&self, commit_hash: &CommitHash) -> Option<&[CommitHash]> {
+        self.collapsed_map.get(commit_hash).map(Vec::as_slice)
+    }
+
+    /// Returns the merge commit if the commit is a row its side branch is folded into by `fold_merges`.
+    pub fn folded_merge(&self, commit_hash: &CommitHash) -> Option<&CommitHash> {
+        self.folded_rows.get(commit_hash)
+    }
+
     pub fn children_hash(&self, commit_hash: &CommitHash) -> Vec<&CommitHash> {
         self.children_map
             .get(commit_hash)
@@ -213,237 +995,481 @@ impl Repository {
         &self.head
     }
 
-    pub fn commit_detail(&self, commit_hash: &CommitHash) -> (Commit, Vec<FileChange>) {
-        let commit = self.commit(commit_hash).unwrap().clone();
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns whether the commits are loaded from the given revisions instead of all refs.
+    pub fn is_revision_limited(&self) -> bool {
+        !self.options.filter.is_default_revisions()
+    }
+
+    // The collapsed rows only lend the hashes of their newest hidden commits, which must not be read for them.
+    fn check_not_collapsed(&self, commit_hash: &CommitHash) -> Result<()> {
+        if self.collapsed_map.contains_key(commit_hash) {
+            return Err(GitError::CollapsedCommits);
+        }
+        Ok(())
+    }
+
+    pub fn commit_detail(&self, commit_hash: &CommitHash) -> Result<(Commit, Vec<FileChange>)> {
+        self.check_not_collapsed(commit_hash)?;
+        let commit = self
+            .commit(commit_hash)
+            .ok_or_else(|| GitError::CommitNotFound(commit_hash.clone()))?
+            .clone();
         let changes = if commit.parent_commit_hashes.is_empty() {
-            get_initial_commit_additions(&self.path, commit_hash)
+            self.backend.initial_commit_additions(commit_hash)?
         } else {
-            get_diff_summary(&self.path, commit_hash)
+            self.backend.diff_summary(commit_hash)?
         };
-        (commit, changes)
+        Ok((commit, changes))
     }
-}
 
-fn check_git_repository(path: &Path) -> Result<()> {
-    if !is_inside_work_tree(path) && !is_bare_repository(path) {
-        let msg = "not a git repository (or any of the parent directories)";
-        return Err(msg.into());
+    pub fn commit_diff_reader(&self, commit_hash: &CommitHash) -> Result<CommitDiffReader> {
+        self.check_not_collapsed(commit_hash)?;
+        if self.commit(commit_hash).is_none() {
+            return Err(GitError::CommitNotFound(commit_hash.clone()));
+        }
+        Ok(CommitDiffReader {
+            backend: self.backend.clone(),
+            commit_hash: commit_hash.clone(),
+        })
     }
-    Ok(())
-}
 
-fn is_inside_work_tree(path: &Path) -> bool {
-    let output = Command::new("git")
-        .arg("rev-parse")
-        .arg("--is-inside-work-tree")
-        .current_dir(path)
-        .output()
-        .unwrap();
-    output.status.success() && output.stdout == b"true\n"
-}
+    pub fn tree_entries(&self, commit_hash: &CommitHash) -> Result<Vec<TreeEntry>> {
+        self.check_not_collapsed(commit_hash)?;
+        self.backend.tree_entries(commit_hash)
+    }
 
-fn is_bare_repository(path: &Path) -> bool {
-    let output = Command::new("git")
-        .arg("rev-parse")
-        .arg("--is-bare-repository")
-        .current_dir(path)
-        .output()
-        .unwrap();
-    output.status.success() && output.stdout == b"true\n"
-}
+    pub fn blob_content(&self, entry: &TreeEntry, limit: usize) -> Result<Vec<u8>> {
+        self.backend.blob_content(&entry.object_id, limit)
+    }
 
-fn load_all_commits(
-    path: &Path,
-    sort: SortCommit,
-    head: &Head,
-    stashes: &[Commit],
-    max_count: Option<usize>,
-    mailmap: bool,
-) -> Vec<Commit> {
-    let mut cmd = Command::new("git");
-    cmd.arg("log");
+    pub fn is_file(&self, commit_hash: &CommitHash, path: &str) -> Result<bool> {
+        self.backend.is_file(commit_hash, path)
+    }
 
-    cmd.arg(match sort {
-        SortCommit::Chronological => "--date-order",
-        SortCommit::Topological => "--topo-order",
-    })
-    .arg(format!("--pretty={}", load_commits_format(mailmap)))
-    .arg("--date=iso-strict")
-    .arg("-z"); // use NUL as a delimiter
+    pub fn last_modified_commit(
+        &self,
+        commit_hash: &CommitHash,
+        path: &str,
+    ) -> Result<Option<CommitHash>> {
+        self.backend.last_modified_commit(commit_hash, path)
+    }
 
-    // exclude stashes and other refs
-    cmd.arg("--branches").arg("--remotes").arg("--tags");
+    pub fn blame(&self, commit_hash: &CommitHash, path: &str) -> Result<Blame> {
+        self.backend.blame(commit_hash, path)
+    }
 
-    // commits that are reachable from the stashes
-    stashes.iter().for_each(|stash| {
-        cmd.arg(stash.parent_commit_hashes[0].as_str());
-    });
+    /// Returns whether the change is to the paths that the commits are filtered by.
+    pub fn is_filtered_change(&self, commit: &Commit, change: &FileChange) -> bool {
+        let pathspecs = match &commit.followed_path {
+            Some(path) => std::slice::from_ref(path),
+            None => self.options.filter.paths.as_slice(),
+        };
+        let changed_paths: &[&String] = match change {
+            FileChange::Add { path }
+            | FileChange::Modify { path }
+            | FileChange::Delete { path } => &[path],
+            FileChange::Move { from, to } => &[from, to],
+        };
+        changed_paths.iter().any(|path| {
+            pathspecs
+                .iter()
+                .any(|pathspec| pathspec_matches(pathspec, path))
+        })
+    }
+}
+
+/// The directory in which git commands are executed, with the environment variables passed to them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepositoryPath {
+    path: PathBuf,
+    env: Vec<(&'static str, OsString)>,
+    // the directory in the working tree the repository was resolved from, e.g. `src/`, to resolve the pathspecs
+    prefix: String,
+}
 
-    if !matches!(head, Head::None) {
-        cmd.arg("HEAD");
+impl RepositoryPath {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            env: Vec::new(),
+            prefix: String::new(),
+        }
     }
 
-    if let Some(n) = max_count {
-        cmd.arg("--max-count").arg(n.to_string());
+    /// Passes the environment variable to the git processes instead of the inherited one.
+    pub fn with_env(mut self, key: &'static str, value: impl Into<OsString>) -> Self {
+        self.env.retain(|(k, _)| *k != key);
+        self.env.push((key, value.into()));
+        self
     }
 
-    cmd.current_dir(path).stdout(Stdio::piped());
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
 
-    let mut process = cmd.spawn().unwrap();
+    /// Resolves the pathspecs given relative to the directory the repository was resolved from,
+    /// into the ones relative to the top-level directory where git commands are executed.
+    pub fn resolve_pathspecs(&self, pathspecs: Vec<String>) -> Vec<String> {
+        pathspecs
+            .into_iter()
+            .map(|pathspec| resolve_pathspec(&self.path, &self.prefix, &pathspec))
+            .collect()
+    }
+}
 
-    let stdout = process.stdout.take().expect("failed to open stdout");
+/// Resolves the directory in which git commands are executed.
+///
+/// Returns the top-level directory of the working tree, or the git directory for bare repositories.
+/// `GIT_DIR` and `GIT_WORK_TREE` are respected in the same way as `git -C <path>`.
+pub fn resolve_repository_path(path: &Path) -> Result<RepositoryPath> {
+    let path = path
+        .canonicalize()
+        .map_err(|source| GitError::InvalidPath {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+    let mut repository_path = RepositoryPath::new(&path);
+    for (key, value) in absolute_git_path_env(&path, |key| std::env::var_os(key)) {
+        repository_path = repository_path.with_env(key, value);
+    }
+    let prefix = rev_parse_path(&repository_path, "--show-prefix")?;
 
-    let reader = BufReader::new(stdout);
+    let resolved = match rev_parse_path(&repository_path, "--show-toplevel")? {
+        Some(toplevel) => toplevel,
+        // bare repository
+        None => rev_parse_path(&repository_path, "--absolute-git-dir")?
+            .ok_or(GitError::NotRepository)?,
+    };
+    repository_path.path = resolved;
+    repository_path.prefix = prefix
+        .map(|prefix| prefix.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(repository_path)
+}
 
-    let mut commits = Vec::new();
+/// Returns `GIT_DIR` and `GIT_WORK_TREE` with relative paths joined to the given path.
+///
+/// git commands are executed in the resolved directory instead of the given one,
+/// so relative paths must be made absolute to point to the same locations.
+pub fn absolute_git_path_env<F>(path: &Path, var_os: F) -> Vec<(&'static str, PathBuf)>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    ["GIT_DIR", "GIT_WORK_TREE"]
+        .into_iter()
+        .filter_map(|key| var_os(key).map(|value| (key, path.join(value))))
+        .collect()
+}
 
-    for bytes in reader.split(b'\0') {
-        let bytes = bytes.unwrap();
-        let s = String::from_utf8_lossy(&bytes);
+// Resolves the pathspec as git does in the subdirectory of the working tree,
+// keeping the magic signatures such as `:(glob)` and `:!`, and leaving the ones with `top` magic as they are.
+fn resolve_pathspec(toplevel: &Path, prefix: &str, pathspec: &str) -> String {
+    let (magic, path) = split_pathspec_magic(pathspec);
+    let is_top = match magic.strip_prefix(":(") {
+        Some(long) => long
+            .trim_end_matches(')')
+            .split(',')
+            .any(|m| m.trim() == "top"),
+        None => magic.contains('/'),
+    };
+    if is_top {
+        return pathspec.into();
+    }
 
-        let parts: Vec<&str> = s.split('\x1f').collect();
-        if parts.len() != 10 {
-            panic!("unexpected number of parts: {} [{}]", parts.len(), s);
+    let path = match Path::new(path).strip_prefix(toplevel) {
+        // the absolute paths in the working tree are made relative to the top
+        Ok(relative) => relative.to_string_lossy().into_owned(),
+        Err(_) if path.starts_with('/') => return pathspec.into(),
+        Err(_) => format!("{prefix}{path}"),
+    };
+    let mut components: Vec<&str> = Vec::new();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." if components.last().is_some_and(|c| *c != "..") => {
+                components.pop();
+            }
+            c => components.push(c),
         }
+    }
+    // the trailing slash tells the pathspec only matches a directory
+    let trailing_slash = if path.ends_with('/') && !components.is_empty() {
+        "/"
+    } else {
+        ""
+    };
+    let path = match components.join("/") {
+        joined if joined.is_empty() => ".".into(),
+        joined => joined,
+    };
+    format!("{magic}{path}{trailing_slash}")
+}
 
-        let commit = Commit {
-            commit_hash: parts[0].into(),
-            author_name: parts[1].into(),
-            author_email: parts[2].into(),
-            author_date: parse_iso_date(parts[3]),
-            committer_name: parts[4].into(),
-            committer_email: parts[5].into(),
-            committer_date: parse_iso_date(parts[6]),
-            subject: parts[7].into(),
-            body: parts[8].into(),
-            parent_commit_hashes: parse_parent_commit_hashes(parts[9]),
-        };
+// Splits the pathspec into the magic signature, e.g. `:(glob)` or `:!`, and the path.
+fn split_pathspec_magic(pathspec: &str) -> (&str, &str) {
+    let Some(rest) = pathspec.strip_prefix(':') else {
+        return ("", pathspec);
+    };
+    let magic_len = if rest.starts_with('(') {
+        match rest.find(')') {
+            Some(end) => end + 1,
+            None => return ("", pathspec),
+        }
+    } else {
+        let n = rest.find(|c| !"/!^".contains(c)).unwrap_or(rest.len());
+        // the short form may be terminated with `:`
+        n + usize::from(rest[n..].starts_with(':'))
+    };
+    pathspec.split_at(1 + magic_len)
+}
 
-        commits.push(commit);
-    }
+/// Resolves the git directory and the common directory of the repository, which differ in linked worktrees.
+pub fn resolve_git_dirs(path: &RepositoryPath) -> Result<(PathBuf, PathBuf)> {
+    let git_dir = rev_parse_path(path, "--absolute-git-dir")?.ok_or(GitError::NotRepository)?;
+    let common_dir = rev_parse_path(path, "--git-common-dir")?.ok_or(GitError::NotRepository)?;
+    // the common directory may be printed as a relative path
+    let common_dir = path.path.join(common_dir);
+    let common_dir = common_dir
+        .canonicalize()
+        .map_err(|source| GitError::InvalidPath {
+            path: common_dir,
+            source,
+        })?;
+    Ok((git_dir, common_dir))
+}
 
-    process.wait().unwrap();
+fn rev_parse_path(path: &RepositoryPath, option: &str) -> Result<Option<PathBuf>> {
+    let mut cmd = git_command(path);
+    cmd.arg("rev-parse").arg(option);
+    let output = GitProcess::spawn(cmd)?.output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stdout = stdout.trim_end_matches('\n');
+    if !output.status.success() || stdout.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(PathBuf::from(stdout)))
+}
 
-    commits
+fn git_command(path: &RepositoryPath) -> Command {
+    let mut cmd = Command::new("git");
+    cmd.current_dir(&path.path);
+    cmd.envs(path.env.iter().map(|(key, value)| (key, value)));
+    cmd
 }
 
-fn load_all_stashes(path: &Path, mailmap: bool) -> Vec<Commit> {
-    let mut cmd = Command::new("git")
-        .arg("stash")
-        .arg("list")
-        .arg(format!("--pretty={}", load_commits_format(mailmap)))
-        .arg("--date=iso-strict")
-        .arg("-z") // use NUL as a delimiter
-        .current_dir(path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .spawn()
-        .unwrap();
+struct GitProcess {
+    // used in error messages, e.g. `git log`
+    command: String,
+    child: Child,
+    // stderr is read on another thread so that git is not blocked by a full pipe while stdout is read
+    stderr: Option<JoinHandle<io::Result<Vec<u8>>>>,
+}
 
-    let stdout = cmd.stdout.take().expect("failed to open stdout");
+impl GitProcess {
+    fn spawn(mut cmd: Command) -> Result<Self> {
+        let command = describe_command(&cmd);
+        let mut child = cmd
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|source| GitError::Spawn {
+                command: command.clone(),
+                source,
+            })?;
+        let stderr = child.stderr.take().map(|mut pipe| {
+            thread::spawn(move || {
+                let mut buf = Vec::new();
+                pipe.read_to_end(&mut buf).map(|_| buf)
+            })
+        });
+        Ok(Self {
+            command,
+            child,
+            stderr,
+        })
+    }
 
-    let reader = BufReader::new(stdout);
+    fn stdout(&mut self) -> BufReader<ChildStdout> {
+        BufReader::new(self.child.stdout.take().expect("stdout should be piped"))
+    }
 
-    let mut commits = Vec::new();
+    fn io_error(&self, source: io::Error) -> GitError {
+        GitError::Io {
+            command: self.command.clone(),
+            source,
+        }
+    }
 
-    for bytes in reader.split(b'\0') {
-        let bytes = bytes.unwrap();
-        let s = String::from_utf8_lossy(&bytes);
+    fn unexpected_output(&self, output: &str) -> GitError {
+        GitError::UnexpectedOutput {
+            command: self.command.clone(),
+            output: output.into(),
+        }
+    }
 
-        let parts: Vec<&str> = s.split('\x1f').collect();
-        if parts.len() != 10 {
-            panic!("unexpected number of parts: {} [{}]", parts.len(), s);
+    fn read_stderr(&mut self) -> Result<Vec<u8>> {
+        match self.stderr.take() {
+            Some(handle) => handle
+                .join()
+                .expect("stderr reader should not panic")
+                .map_err(|e| self.io_error(e)),
+            None => Ok(Vec::new()),
         }
+    }
 
-        let commit = Commit {
-            commit_hash: parts[0].into(),
-            author_name: parts[1].into(),
-            author_email: parts[2].into(),
-            author_date: parse_iso_date(parts[3]),
-            committer_name: parts[4].into(),
-            committer_email: parts[5].into(),
-            committer_date: parse_iso_date(parts[6]),
-            subject: parts[7].into(),
-            body: parts[8].into(),
-            parent_commit_hashes: parse_parent_commit_hashes(parts[9]),
-        };
+    fn output(mut self) -> Result<std::process::Output> {
+        let mut stdout = Vec::new();
+        if let Some(mut pipe) = self.child.stdout.take() {
+            pipe.read_to_end(&mut stdout)
+                .map_err(|e| self.io_error(e))?;
+        }
+        let stderr = self.read_stderr()?;
+        let status = self.child.wait().map_err(|e| self.io_error(e))?;
+        Ok(std::process::Output {
+            status,
+            stdout,
+            stderr,
+        })
+    }
 
-        commits.push(commit);
+    fn kill(mut self) -> Result<()> {
+        self.child.kill().map_err(|e| self.io_error(e))?;
+        self.child.wait().map_err(|e| self.io_error(e))?;
+        Ok(())
     }
 
-    cmd.wait().unwrap();
+    fn wait(mut self) -> Result<()> {
+        let stderr = self.read_stderr()?;
+        let stderr = String::from_utf8_lossy(&stderr);
+        let status = self.child.wait().map_err(|e| self.io_error(e))?;
+        if !status.success() {
+            return Err(GitError::CommandFailed {
+                command: self.command,
+                status,
+                stderr: stderr.trim().into(),
+            });
+        }
+        Ok(())
+    }
+}
 
-    commits
+fn describe_command(cmd: &Command) -> String {
+    let subcommands = cmd
+        .get_args()
+        .map(|arg| arg.to_string_lossy())
+        .take_while(|arg| !arg.starts_with('-'))
+        .take(2);
+    std::iter::once(cmd.get_program().to_string_lossy())
+        .chain(subcommands)
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
-fn load_commits_format(mailmap: bool) -> String {
-    // The uppercase name/email placeholders (`%aN`, `%aE`, `%cN`, `%cE`) resolve
-    // identities through the repository's .mailmap, while the lowercase variants
-    // use the raw values recorded in each commit.
-    let format = if mailmap {
-        [
-            "%H", "%aN", "%aE", "%ad", "%cN", "%cE", "%cd", "%s", "%b", "%P",
-        ]
-    } else {
-        [
-            "%H", "%an", "%ae", "%ad", "%cn", "%ce", "%cd", "%s", "%b", "%P",
-        ]
-    };
-    format.join("%x1f") // use Unit Separator as a delimiter
+/// The options of reading commits, corresponding to the arguments of `git log`.
+#[derive(Debug, Default, Clone)]
+struct LogOptions {
+    sort: SortCommit,
+    stash_base_hashes: Vec<CommitHash>,
+    has_head: bool,
+    max_count: Option<usize>,
+    mailmap: bool,
+    filter: RevisionFilter,
+    // commits whose ancestors are excluded, i.e. `--not <hashes>`
+    exclude_hashes: Vec<CommitHash>,
 }
 
-fn parse_iso_date(s: &str) -> DateTime<FixedOffset> {
-    DateTime::parse_from_rfc3339(s).unwrap()
+/// Reads the patch of a commit, which can take long for large commits and is done on another thread.
+#[derive(Debug, Clone)]
+pub struct CommitDiffReader {
+    backend: Arc<dyn GitBackend>,
+    commit_hash: CommitHash,
 }
 
-fn parse_parent_commit_hashes(s: &str) -> Vec<CommitHash> {
-    if s.is_empty() {
-        return Vec::new();
+impl CommitDiffReader {
+    pub fn commit_hash(&self) -> &CommitHash {
+        &self.commit_hash
+    }
+
+    pub fn read(&self) -> Result<Vec<FileDiff>> {
+        self.backend.diff_patch(&self.commit_hash)
     }
-    s.split(' ').map(|s| s.into()).collect()
 }
 
-fn build_commits_maps(commits: &Vec<Commit>) -> (CommitsMap, CommitsMap) {
-    let mut parents_map: CommitsMap = FxHashMap::default();
-    let mut children_map: CommitsMap = FxHashMap::default();
-    for commit in commits {
-        let hash = &commit.commit_hash;
-        for parent_hash in &commit.parent_commit_hashes {
-            parents_map
-                .entry(hash.clone())
-                .or_default()
-                .push(parent_hash.clone());
-            children_map
-                .entry(parent_hash.clone())
-                .or_default()
-                .push(hash.clone());
+/// Reads the commits of the repository in the `git log` order.
+#[derive(Debug, Clone)]
+pub struct CommitStream {
+    backend: Arc<dyn GitBackend>,
+    options: LogOptions,
+}
+
+impl CommitStream {
+    /// Passes the commits to `f` in batches of at most `batch_size` commits.
+    /// A smaller batch is passed if reading a batch takes long, so that the first commits can be shown early.
+    /// Reading stops when `f` returns `false`.
+    pub fn read(&self, batch_size: usize, mut f: impl FnMut(Vec<Commit>) -> bool) -> Result<()> {
+        let mut total = 0;
+        let mut batch = Vec::new();
+        let mut batch_started = Instant::now();
+        let mut cancelled = false;
+
+        self.backend.read_commits(&self.options, &mut |commit| {
+            batch.push(commit);
+            if batch.len() >= batch_size || batch_started.elapsed() >= COMMIT_BATCH_INTERVAL {
+                total += batch.len();
+                if !f(std::mem::take(&mut batch)) {
+                    cancelled = true;
+                    return false;
+                }
+                batch_started = Instant::now();
+            }
+            true
+        })?;
+
+        if cancelled {
+            return Ok(());
+        }
+
+        if !batch.is_empty() {
+            total += batch.len();
+            f(batch);
         }
-    }
 
-    (parents_map, children_map)
+        if total == 0 {
+            let filter = &self.options.filter;
+            if filter.is_default_revisions() && filter.paths.is_empty() {
+                return Err(GitError::NoCommits);
+            }
+            return Err(GitError::NoMatchingCommits);
+        }
+        Ok(())
+    }
 }
 
-fn to_commit_map(commits: Vec<Commit>) -> CommitMap {
-    commits
+fn group_stashes_by_base(stashes: Vec<Commit>) -> FxHashMap<CommitHash, Vec<Commit>> {
+    // Stash commit has multiple parent commits, but the first parent commit is the commit that the stash was created from.
+    stashes
         .into_iter()
-        .map(|commit| (commit.commit_hash.clone(), commit))
-        .collect()
+        .fold(FxHashMap::default(), |mut acc, commit| {
+            if let Some(parent) = commit.parent_commit_hashes.first() {
+                acc.entry(parent.clone()).or_default().push(commit);
+            }
+            acc
+        })
 }
 
-fn merge_stashes_to_commits(commits: Vec<Commit>, stashes: Vec<Commit>) -> Vec<Commit> {
-    // Stash commit has multiple parent commits, but the first parent commit is the commit that the stash was created from.
+fn merge_stashes_to_commits(
+    commits: Vec<Commit>,
+    stash_map: &mut FxHashMap<CommitHash, Vec<Commit>>,
+) -> Vec<Commit> {
     // If the first parent commit is not found, the stash commit is ignored.
     let mut ret = Vec::new();
-    let mut statsh_map: FxHashMap<CommitHash, Vec<Commit>> =
-        stashes
-            .into_iter()
-            .fold(FxHashMap::default(), |mut acc, commit| {
-                let parent = commit.parent_commit_hashes[0].clone();
-                acc.entry(parent).or_default().push(commit);
-                acc
-            });
     for commit in commits {
-        if let Some(stashes) = statsh_map.remove(&commit.commit_hash) {
+        if let Some(stashes) = stash_map.remove(&commit.commit_hash) {
             for stash in stashes {
                 ret.push(stash);
             }
@@ -453,106 +1479,141 @@ fn merge_stashes_to_commits(commits: Vec<Commit>, stashes: Vec<Commit>) -> Vec<C
     ret
 }
 
-fn load_refs(path: &Path) -> (RefMap, Head) {
-    let mut cmd = Command::new("git")
-        .arg("show-ref")
-        .arg("--head")
-        .arg("--dereference")
-        .current_dir(path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .spawn()
-        .unwrap();
-
-    let stdout = cmd.stdout.take().expect("failed to open stdout");
-
-    let reader = BufReader::new(stdout);
-
-    let mut ref_map = RefMap::default();
-    let mut tag_map: FxHashMap<String, Ref> = FxHashMap::default();
-    let mut head: Head = Head::None;
-
-    for line in reader.lines() {
-        let line = line.unwrap();
-
-        let parts: Vec<&str> = line.split(' ').collect();
-        if parts.len() != 2 {
-            panic!("unexpected number of parts: {} [{}]", parts.len(), line);
-        }
+// the commits that the default revisions of `git log` start from
+fn tip_hashes(ref_map: &RefMap, head: &Head, options: &LogOptions) -> Vec<CommitHash> {
+    let mut hashes: Vec<CommitHash> = ref_map
+        .values()
+        .flatten()
+        .filter(|r| !matches!(r, Ref::Stash { .. }))
+        .map(|r| r.target().clone())
+        .collect();
+    hashes.extend(options.stash_base_hashes.iter().cloned());
+    if let Head::Detached { target } = head {
+        hashes.push(target.clone());
+    }
+    hashes.sort();
+    hashes.dedup();
+    hashes
+}
 
-        let hash = parts[0];
-        let refs = parts[1];
+fn stash_hashes(ref_map: &RefMap) -> Vec<&CommitHash> {
+    let mut hashes: Vec<&CommitHash> = ref_map
+        .values()
+        .flatten()
+        .filter_map(|r| match r {
+            Ref::Stash { target, .. } => Some(target),
+            _ => None,
+        })
+        .collect();
+    hashes.sort();
+    hashes
+}
 
-        if refs == "HEAD" {
-            head = if let Some(branch) = get_current_branch(path) {
-                Head::Branch { name: branch }
-            } else {
-                Head::Detached {
-                    target: hash.into(),
-                }
+// Rewrites the parents of the kept commits to their nearest kept ancestors, as `git log --parents` does with history simplification,
+// and drops the parents that are ancestors of the other parents, as `--simplify-merges` does.
+// The commits must be given parents first. The parents that are not given are regarded as kept.
+// Whether a commit is kept is decided with its nearest kept ancestors.
+fn simplify_history<'a>(
+    history: impl Iterator<Item = (&'a CommitHash, &'a [CommitHash])>,
+    mut is_kept: impl FnMut(&CommitHash, &[CommitHash]) -> bool,
+) -> CommitsMap {
+    // the nearest kept commits of each commit, including the commit itself
+    let mut nearest_map: CommitsMap = FxHashMap::default();
+    let mut parents_map: CommitsMap = FxHashMap::default();
+    let mut generations = Generations::default();
+    for (hash, parents) in history {
+        let mut nearest: Vec<CommitHash> = Vec::new();
+        for parent_hash in parents {
+            let ancestors = match nearest_map.get(parent_hash) {
+                Some(ancestors) => ancestors.as_slice(),
+                None => std::slice::from_ref(parent_hash),
             };
-        } else if let Some(r) = parse_branch_refs(hash, refs) {
-            ref_map.entry(hash.into()).or_default().push(r);
-        } else if let Some(r) = parse_tag_refs(hash, refs) {
-            // if annotated tag exists, it will be overwritten by the following line of the same tag
-            // this will make the tag point to the commit that the annotated tag points to
-            tag_map.insert(r.name().into(), r);
+            for ancestor in ancestors {
+                if !nearest.contains(ancestor) {
+                    nearest.push(ancestor.clone());
+                }
+            }
+        }
+        if nearest.len() > 1 {
+            // all the ancestors are already kept or hidden, so the redundant ones can be dropped here
+            let redundant = generations.redundant_commits(&parents_map, &nearest);
+            let mut redundant = redundant.into_iter();
+            nearest.retain(|_| !redundant.next().unwrap_or_default());
+        }
+        if is_kept(hash, &nearest) {
+            generations.insert(hash, &nearest);
+            parents_map.insert(hash.clone(), nearest);
+            nearest_map.insert(hash.clone(), vec![hash.clone()]);
+        } else {
+            nearest_map.insert(hash.clone(), nearest);
         }
     }
-
-    for tag in tag_map.into_values() {
-        ref_map.entry(tag.target().clone()).or_default().push(tag);
-    }
-
-    ref_map.values_mut().for_each(|refs| refs.sort());
-
-    cmd.wait().unwrap();
-
-    (ref_map, head)
+    parents_map
 }
 
-fn load_stashes_as_refs(path: &Path) -> RefMap {
-    let format = ["%gd", "%H", "%s"].join("%x1f"); // use Unit Separator as a delimiter
-    let mut cmd = Command::new("git")
-        .arg("stash")
-        .arg("list")
-        .arg(format!("--format={format}"))
-        .current_dir(path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .spawn()
-        .unwrap();
-
-    let stdout = cmd.stdout.take().expect("failed to open stdout");
-
-    let reader = BufReader::new(stdout);
-
-    let mut ref_map = RefMap::default();
-
-    for line in reader.lines() {
-        let line = line.unwrap();
+// The generation numbers of the kept commits, which bound the walks to find the ancestors.
+// A commit is greater than all its ancestors, and the commits that are not given are regarded as 0.
+#[derive(Default)]
+struct Generations {
+    generations: FxHashMap<CommitHash, u32>,
+    // the minimum generation of the kept commits whose parent is a commit that is not given
+    min_child_generations: FxHashMap<CommitHash, u32>,
+}
 
-        let parts: Vec<&str> = line.split('\x1f').collect();
-        if parts.len() != 3 {
-            panic!("unexpected number of parts: {} [{}]", parts.len(), line);
+impl Generations {
+    fn insert(&mut self, hash: &CommitHash, parents: &[CommitHash]) {
+        let generation = 1 + parents.iter().map(|p| self.get(p)).max().unwrap_or(0);
+        for parent in parents {
+            if !self.generations.contains_key(parent) {
+                let min = self
+                    .min_child_generations
+                    .entry(parent.clone())
+                    .or_insert(generation);
+                *min = (*min).min(generation);
+            }
         }
-
-        let name = parts[0];
-        let hash = parts[1];
-        let subject = parts[2];
-
-        let r = Ref::Stash {
-            name: name.into(),
-            message: subject.into(),
-            target: hash.into(),
-        };
-
-        ref_map.entry(hash.into()).or_default().push(r);
+        self.generations.insert(hash.clone(), generation);
     }
 
-    cmd.wait().unwrap();
+    fn get(&self, hash: &CommitHash) -> u32 {
+        self.generations.get(hash).copied().unwrap_or(0)
+    }
 
-    ref_map
+    // Returns whether each commit is an ancestor of the other commits, in a single walk from them.
+    // The walk stops at the commits older than any of the given commits can be the parent of.
+    fn redundant_commits(&self, parents_map: &CommitsMap, hashes: &[CommitHash]) -> Vec<bool> {
+        let min_generation = hashes
+            .iter()
+            .map(|h| match self.generations.get(h) {
+                Some(generation) => generation + 1,
+                // the commit cannot be found if no kept commit has it as a parent
+                None => self
+                    .min_child_generations
+                    .get(h)
+                    .copied()
+                    .unwrap_or(u32::MAX),
+            })
+            .min()
+            .unwrap_or(0);
+
+        let mut redundant = vec![false; hashes.len()];
+        let mut visited = FxHashSet::default();
+        let mut stack: Vec<&CommitHash> = hashes.iter().collect();
+        while let Some(hash) = stack.pop() {
+            if self.get(hash) < min_generation {
+                continue;
+            }
+            for parent in parents_map.get(hash).into_iter().flatten() {
+                if let Some(i) = hashes.iter().position(|h| h == parent) {
+                    redundant[i] = true;
+                }
+                if visited.insert(parent) {
+                    stack.push(parent);
+                }
+            }
+        }
+        redundant
+    }
 }
 
 fn merge_ref_maps(m1: &mut RefMap, m2: RefMap) {
@@ -561,62 +1622,6 @@ fn merge_ref_maps(m1: &mut RefMap, m2: RefMap) {
     }
 }
 
-fn parse_branch_refs(hash: &str, refs: &str) -> Option<Ref> {
-    if refs.starts_with("refs/heads/") {
-        let name = refs.trim_start_matches("refs/heads/");
-        Some(Ref::Branch {
-            name: name.into(),
-            target: hash.into(),
-        })
-    } else if refs.starts_with("refs/remotes/") {
-        let name = refs.trim_start_matches("refs/remotes/");
-        Some(Ref::RemoteBranch {
-            name: name.into(),
-            target: hash.into(),
-        })
-    } else {
-        None
-    }
-}
-
-fn parse_tag_refs(hash: &str, refs: &str) -> Option<Ref> {
-    if refs.starts_with("refs/tags/") {
-        let name = refs.trim_start_matches("refs/tags/");
-        let name = name.trim_end_matches("^{}");
-        Some(Ref::Tag {
-            name: name.into(),
-            target: hash.into(),
-        })
-    } else {
-        None
-    }
-}
-
-fn get_current_branch(path: &Path) -> Option<String> {
-    let mut cmd = Command::new("git")
-        .arg("branch")
-        .arg("--show-current")
-        .current_dir(path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .spawn()
-        .unwrap();
-
-    let stdout = cmd.stdout.take().expect("failed to open stdout");
-
-    let reader = BufReader::new(stdout);
-
-    let branch = if let Some(line) = reader.lines().next() {
-        line.ok()
-    } else {
-        None
-    };
-
-    cmd.wait().unwrap();
-
-    branch
-}
-
 #[derive(Debug)]
 pub enum FileChange {
     Add { path: String },
@@ -625,75 +1630,64 @@ pub enum FileChange {
     Move { from: String, to: String },
 }
 
-pub fn get_diff_summary(path: &Path, commit_hash: &CommitHash) -> Vec<FileChange> {
-    let mut cmd = Command::new("git")
-        .arg("diff")
-        .arg("--name-status")
-        .arg(format!("{}^", commit_hash.0))
-        .arg(&commit_hash.0)
-        .current_dir(path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .spawn()
-        .unwrap();
-
-    let stdout = cmd.stdout.take().expect("failed to open stdout");
-
-    let reader = BufReader::new(stdout);
-
-    let mut changes = Vec::new();
-
-    for line in reader.lines() {
-        let line = line.unwrap();
-        let parts: Vec<&str> = line.split('\t').collect();
-
-        match &parts[0][0..1] {
-            "A" => changes.push(FileChange::Add {
-                path: parts[1].into(),
-            }),
-            "M" => changes.push(FileChange::Modify {
-                path: parts[1].into(),
-            }),
-            "D" => changes.push(FileChange::Delete {
-                path: parts[1].into(),
-            }),
-            "R" => changes.push(FileChange::Move {
-                from: parts[1].into(),
-                to: parts[2].into(),
-            }),
-            _ => {}
+impl FileChange {
+    /// Returns the path of the file after the change, or before the change if the file is deleted.
+    pub fn path(&self) -> &str {
+        match self {
+            FileChange::Add { path }
+            | FileChange::Modify { path }
+            | FileChange::Delete { path } => path,
+            FileChange::Move { to, .. } => to,
         }
     }
 
-    cmd.wait().unwrap();
-
-    changes
+    /// Returns whether the diff is of the file changed by this change.
+    pub fn is_change_of(&self, diff: &FileDiff) -> bool {
+        match self {
+            FileChange::Add { path } | FileChange::Modify { path } => diff.new_path == *path,
+            FileChange::Delete { path } => diff.old_path == *path,
+            FileChange::Move { from, to } => diff.old_path == *from && diff.new_path == *to,
+        }
+    }
 }
 
-pub fn get_initial_commit_additions(path: &Path, commit_hash: &CommitHash) -> Vec<FileChange> {
-    let mut cmd = Command::new("git")
-        .arg("ls-tree")
-        .arg("--name-status")
-        .arg("-r") // the empty tree hash
-        .arg(&commit_hash.0)
-        .current_dir(path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .spawn()
-        .unwrap();
-
-    let stdout = cmd.stdout.take().expect("failed to open stdout");
-
-    let reader = BufReader::new(stdout);
-
-    let mut changes = Vec::new();
+/// A file in the tree of a commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeEntry {
+    pub path: String,
+    // the file mode in octal, e.g. `100644`
+    pub mode: String,
+    pub object_id: String,
+    // none for the commits of the submodules
+    pub size: Option<u64>,
+}
 
-    for line in reader.lines() {
-        let line = line.unwrap();
-        changes.push(FileChange::Add { path: line });
+impl TreeEntry {
+    pub fn is_submodule(&self) -> bool {
+        self.mode == "160000"
     }
+}
 
-    cmd.wait().unwrap();
+/// The lines of a file annotated with the commits that last changed them.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Blame {
+    pub lines: Vec<BlameLine>,
+    pub commits: FxHashMap<CommitHash, BlameCommit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameLine {
+    pub commit_hash: CommitHash,
+    // the line number in the file at the commit that changed the line
+    pub original_line_number: usize,
+    pub content: String,
+}
 
-    changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameCommit {
+    pub author_name: String,
+    pub author_date: DateTime<FixedOffset>,
+    pub summary: String,
+    // the parent commit and the path of the file in it, none if the lines were added in a root commit
+    pub previous: Option<(CommitHash, String)>,
 }
@@ -0,0 +1,403 @@
+use std::ops::Range;
+
+// the word diff is skipped for the lines with too many tokens, as its cost is quadratic
+const MAX_WORD_DIFF_TOKENS: usize = 200;
+
+/// The changes to a file in a unified diff.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    pub old_path: String,
+    pub new_path: String,
+    // the extended header lines, e.g. `new file mode 100644` or `Binary files ... differ`
+    pub headers: Vec<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FileDiff {
+    pub fn path(&self) -> String {
+        if self.old_path == self.new_path || self.old_path.is_empty() {
+            self.new_path.clone()
+        } else if self.new_path.is_empty() {
+            self.old_path.clone()
+        } else {
+            format!("{} -> {}", self.old_path, self.new_path)
+        }
+    }
+
+    /// Returns the numbers of the added and removed lines.
+    pub fn stats(&self) -> (usize, usize) {
+        let lines = self.hunks.iter().flat_map(|h| &h.lines);
+        lines.fold((0, 0), |(added, removed), line| match line.kind {
+            DiffLineKind::Added => (added + 1, removed),
+            DiffLineKind::Removed => (added, removed + 1),
+            _ => (added, removed),
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    // the `@@ -1,2 +1,3 @@` line, including the section heading after it
+    pub header: String,
+    pub old_start: usize,
+    pub new_start: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+    // `\ No newline at end of file`
+    NoNewline,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    // the line without the leading `+`, `-` or ` `
+    pub content: String,
+    pub old_lineno: Option<usize>,
+    pub new_lineno: Option<usize>,
+    // the byte ranges of the content that differ from the paired line, for the word-level highlight
+    pub emphasized: Vec<Range<usize>>,
+}
+
+impl DiffLine {
+    fn new(
+        kind: DiffLineKind,
+        content: &str,
+        old_lineno: Option<usize>,
+        new_lineno: Option<usize>,
+    ) -> Self {
+        DiffLine {
+            kind,
+            content: content.into(),
+            old_lineno,
+            new_lineno,
+            emphasized: Vec::new(),
+        }
+    }
+}
+
+/// Parses the output of `git diff` with the `a/` and `b/` prefixes.
+pub fn parse_unified_diff(s: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    // the line numbers of the next old and new lines in the current hunk
+    let mut old_lineno = 0;
+    let mut new_lineno = 0;
+    // the numbers of the lines remaining in the current hunk, to tell the `---` and `+++` lines from the content
+    let mut old_remaining: usize = 0;
+    let mut new_remaining: usize = 0;
+
+    for line in s.lines() {
+        // `\ No newline at end of file` comes after the last line of the hunk
+        if old_remaining > 0 || new_remaining > 0 || line.starts_with('\\') {
+            let hunk = files.last_mut().and_then(|f| f.hunks.last_mut());
+            if let (Some(hunk), Some((kind, content))) = (hunk, parse_hunk_line(line)) {
+                let (old, new) = match kind {
+                    DiffLineKind::Context => (Some(old_lineno), Some(new_lineno)),
+                    DiffLineKind::Added => (None, Some(new_lineno)),
+                    DiffLineKind::Removed => (Some(old_lineno), None),
+                    DiffLineKind::NoNewline => (None, None),
+                };
+                if old.is_some() {
+                    old_lineno += 1;
+                    old_remaining = old_remaining.saturating_sub(1);
+                }
+                if new.is_some() {
+                    new_lineno += 1;
+                    new_remaining = new_remaining.saturating_sub(1);
+                }
+                hunk.lines.push(DiffLine::new(kind, content, old, new));
+                continue;
+            }
+            // the hunk is shorter than its header says, so the line is parsed as a header
+            old_remaining = 0;
+            new_remaining = 0;
+        }
+
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            let (old_path, new_path) = parse_diff_git_paths(rest);
+            files.push(FileDiff {
+                old_path,
+                new_path,
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+
+        if line.starts_with("@@") {
+            if let Some(hunk) = parse_hunk_header(line) {
+                (old_lineno, old_remaining) = (hunk.0, hunk.1);
+                (new_lineno, new_remaining) = (hunk.2, hunk.3);
+                file.hunks.push(Hunk {
+                    header: line.into(),
+                    old_start: hunk.0,
+                    new_start: hunk.2,
+                    lines: Vec::new(),
+                });
+            }
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            if let Some(path) = strip_path_prefix(path, "a/") {
+                file.old_path = path;
+            }
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            if let Some(path) = strip_path_prefix(path, "b/") {
+                file.new_path = path;
+            }
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            file.old_path = unquote(path);
+            file.headers.push(line.into());
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            file.new_path = unquote(path);
+            file.headers.push(line.into());
+        } else if !line.starts_with("index ") {
+            file.headers.push(line.into());
+        }
+    }
+
+    for file in &mut files {
+        for hunk in &mut file.hunks {
+            highlight_words(&mut hunk.lines);
+        }
+    }
+    files
+}
+
+fn parse_hunk_line(line: &str) -> Option<(DiffLineKind, &str)> {
+    match line.as_bytes().first() {
+        Some(b'+') => Some((DiffLineKind::Added, &line[1..])),
+        Some(b'-') => Some((DiffLineKind::Removed, &line[1..])),
+        Some(b' ') => Some((DiffLineKind::Context, &line[1..])),
+        Some(b'\\') => Some((DiffLineKind::NoNewline, line)),
+        // some tools strip the trailing whitespace of the empty context lines
+        None => Some((DiffLineKind::Context, "")),
+        Some(_) => None,
+    }
+}
+
+// The paths are split in the middle, as they are the same unless the file is renamed,
+// in which case they are read from the `rename from` and `rename to` lines later.
+fn parse_diff_git_paths(s: &str) -> (String, String) {
+    let s = s.trim_end();
+    if s.starts_with('"') {
+        // the quoted paths, which are `"a/..." "b/..."`
+        if let Some(i) = s.find("\" \"") {
+            let old = unquote(&s[..=i]);
+            let new = unquote(&s[i + 2..]);
+            return (trim_prefix(old, "a/"), trim_prefix(new, "b/"));
+        }
+    }
+    let middle = s.len() / 2;
+    if s.is_char_boundary(middle) && s[middle..].starts_with(" b/") {
+        let old = trim_prefix(s[..middle].into(), "a/");
+        let new = trim_prefix(s[middle + 1..].into(), "b/");
+        return (old, new);
+    }
+    match s.rfind(" b/") {
+        Some(i) => (trim_prefix(s[..i].into(), "a/"), s[i + 3..].into()),
+        None => (s.into(), s.into()),
+    }
+}
+
+// Returns none for `/dev/null`.
+fn strip_path_prefix(s: &str, prefix: &str) -> Option<String> {
+    // git appends a tab to the paths containing spaces
+    let s = unquote(s.trim_end_matches('\t'));
+    s.strip_prefix(prefix).map(Into::into)
+}
+
+fn trim_prefix(s: String, prefix: &str) -> String {
+    match s.strip_prefix(prefix) {
+        Some(s) => s.into(),
+        None => s,
+    }
+}
+
+// Unquotes the path quoted by git because of the special characters in it.
+fn unquote(s: &str) -> String {
+    let Some(s) = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) else {
+        return s.into();
+    };
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut chars = s.bytes().peekable();
+    while let Some(b) = chars.next() {
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        match chars.next() {
+            Some(b'n') => bytes.push(b'\n'),
+            Some(b't') => bytes.push(b'\t'),
+            Some(d @ b'0'..=b'7') => {
+                // octal escape of a byte, e.g. `\343`
+                let mut n = (d - b'0') as u32;
+                for _ in 0..2 {
+                    if let Some(d @ b'0'..=b'7') = chars.peek().copied() {
+                        n = n * 8 + (d - b'0') as u32;
+                        chars.next();
+                    }
+                }
+                bytes.push(n as u8);
+            }
+            Some(c) => bytes.push(c),
+            None => {}
+        }
+    }
+    String::from_utf8_lossy(&bytes).into()
+}
+
+// Returns the old start, old count, new start and new count.
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize, usize)> {
+    let rest = line.strip_prefix("@@ -")?;
+    let (ranges, _) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let (old_start, old_count) = parse_hunk_range(old)?;
+    let (new_start, new_count) = parse_hunk_range(new)?;
+    Some((old_start, old_count, new_start, new_count))
+}
+
+fn parse_hunk_range(s: &str) -> Option<(usize, usize)> {
+    match s.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((s.parse().ok()?, 1)),
+    }
+}
+
+// Pairs the removed lines with the added lines that follow them,
+// and marks the words that differ between each pair.
+fn highlight_words(lines: &mut [DiffLine]) {
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].kind != DiffLineKind::Removed {
+            i += 1;
+            continue;
+        }
+        let removed_start = i;
+        while i < lines.len() && lines[i].kind == DiffLineKind::Removed {
+            i += 1;
+        }
+        let removed_end = i;
+        if i < lines.len() && lines[i].kind == DiffLineKind::NoNewline {
+            i += 1;
+        }
+        let added_start = i;
+        while i < lines.len() && lines[i].kind == DiffLineKind::Added {
+            i += 1;
+        }
+        let added_end = i;
+
+        // the lines are paired only if the numbers are the same, otherwise it is hard to tell which lines correspond
+        if removed_end - removed_start != added_end - added_start {
+            continue;
+        }
+        for n in 0..(removed_end - removed_start) {
+            let (old, new) = word_diff(
+                &lines[removed_start + n].content,
+                &lines[added_start + n].content,
+            );
+            lines[removed_start + n].emphasized = old;
+            lines[added_start + n].emphasized = new;
+        }
+    }
+}
+
+/// Returns the byte ranges of the words only in the old line and only in the new line.
+/// Nothing is returned if the lines have nothing in common, as the whole lines are changed.
+fn word_diff(old: &str, new: &str) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+    if old_tokens.len() > MAX_WORD_DIFF_TOKENS || new_tokens.len() > MAX_WORD_DIFF_TOKENS {
+        return (Vec::new(), Vec::new());
+    }
+
+    let old_words: Vec<&str> = old_tokens.iter().map(|r| &old[r.clone()]).collect();
+    let new_words: Vec<&str> = new_tokens.iter().map(|r| &new[r.clone()]).collect();
+    // lcs[i][j] is the length of the longest common subsequence of old_tokens[i..] and new_tokens[j..]
+    let (n, m) = (old_tokens.len(), new_tokens.len());
+    let mut lcs = vec![vec![0u16; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_words[i] == new_words[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut old_common = vec![false; n];
+    let mut new_common = vec![false; m];
+    let mut has_common_word = false;
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old_words[i] == new_words[j] {
+            old_common[i] = true;
+            new_common[j] = true;
+            has_common_word |= !old_words[i].trim().is_empty();
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    if !has_common_word {
+        return (Vec::new(), Vec::new());
+    }
+
+    (
+        uncommon_ranges(&old_tokens, &old_common),
+        uncommon_ranges(&new_tokens, &new_common),
+    )
+}
+
+// Splits the line into the runs of word characters, the runs of whitespaces, and the other characters.
+fn tokenize(s: &str) -> Vec<Range<usize>> {
+    #[derive(PartialEq)]
+    enum Class {
+        Word,
+        Space,
+        Other,
+    }
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            Class::Word
+        } else if c.is_whitespace() {
+            Class::Space
+        } else {
+            Class::Other
+        }
+    };
+
+    let mut tokens: Vec<Range<usize>> = Vec::new();
+    let mut prev: Option<Class> = None;
+    for (i, c) in s.char_indices() {
+        let cls = class(c);
+        let joined = cls != Class::Other && prev.as_ref() == Some(&cls);
+        match tokens.last_mut() {
+            Some(last) if joined => last.end = i + c.len_utf8(),
+            _ => tokens.push(i..i + c.len_utf8()),
+        }
+        prev = Some(cls);
+    }
+    tokens
+}
+
+// Joins the adjacent tokens that are not common.
+fn uncommon_ranges(tokens: &[Range<usize>], common: &[bool]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (token, _) in tokens.iter().zip(common).filter(|(_, c)| !**c) {
+        match ranges.last_mut() {
+            Some(last) if last.end == token.start => last.end = token.end,
+            _ => ranges.push(token.clone()),
+        }
+    }
+    ranges
+}
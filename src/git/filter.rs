@@ -0,0 +1,248 @@
+use regex::Regex;
+
+use super::{git_command, Commit, GitError, GitProcess, RepositoryPath, Result};
+
+/// The conditions of `CommitFilter` as they are given, corresponding to the options of `git log`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CommitFilterQuery {
+    pub author: Option<String>,
+    pub committer: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub grep: Option<String>,
+}
+
+impl CommitFilterQuery {
+    /// Parses the input of the filter prompt, e.g. `author:alice since:"2 weeks ago" fix typo`.
+    /// The words without a key are matched against the commit message.
+    pub fn parse(input: &str) -> std::result::Result<Self, String> {
+        let mut query = Self::default();
+        let mut grep_words = Vec::new();
+        for word in split_words(input)? {
+            let (key, value) = match word.split_once(':') {
+                Some((key @ ("author" | "committer" | "since" | "until" | "grep"), value)) => {
+                    (key, value.to_string())
+                }
+                _ => {
+                    grep_words.push(word);
+                    continue;
+                }
+            };
+            if value.is_empty() {
+                return Err(format!("no value is given to {key}"));
+            }
+            match key {
+                "author" => query.author = Some(value),
+                "committer" => query.committer = Some(value),
+                "since" => query.since = Some(value),
+                "until" => query.until = Some(value),
+                _ => grep_words.push(value),
+            }
+        }
+        if !grep_words.is_empty() {
+            query.grep = Some(grep_words.join(" "));
+        }
+        Ok(query)
+    }
+}
+
+// Splits the input by whitespaces, except inside double quotes.
+fn split_words(input: &str) -> std::result::Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if quoted {
+        return Err("unclosed quote".into());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Hides the loaded commits that do not match, unlike `RevisionFilter` which limits the commits to load.
+///
+/// All the given conditions must match, as `git log` does.
+#[derive(Debug, Default, Clone)]
+pub struct CommitFilter {
+    author: Option<Regex>,
+    committer: Option<Regex>,
+    // the range of the committer dates in seconds since the epoch
+    since: Option<i64>,
+    until: Option<i64>,
+    grep: Option<Regex>,
+}
+
+impl CommitFilter {
+    /// The dates are parsed by git, so any format that `git log` accepts such as `2 weeks ago` can be used.
+    pub fn new(path: &RepositoryPath, query: &CommitFilterQuery) -> Result<Self> {
+        Ok(Self {
+            author: query.author.as_deref().map(compile_pattern).transpose()?,
+            committer: query
+                .committer
+                .as_deref()
+                .map(compile_pattern)
+                .transpose()?,
+            since: query
+                .since
+                .as_deref()
+                .map(|date| parse_date(path, "since", date))
+                .transpose()?,
+            until: query
+                .until
+                .as_deref()
+                .map(|date| parse_date(path, "until", date))
+                .transpose()?,
+            grep: query.grep.as_deref().map(compile_pattern).transpose()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.author.is_none()
+            && self.committer.is_none()
+            && self.since.is_none()
+            && self.until.is_none()
+            && self.grep.is_none()
+    }
+
+    pub fn matches(&self, commit: &Commit) -> bool {
+        let author = || format!("{} <{}>", commit.author_name, commit.author_email);
+        let committer = || format!("{} <{}>", commit.committer_name, commit.committer_email);
+        let date = commit.committer_date.timestamp();
+        self.author.as_ref().is_none_or(|re| re.is_match(&author()))
+            && self
+                .committer
+                .as_ref()
+                .is_none_or(|re| re.is_match(&committer()))
+            && self.since.is_none_or(|since| since <= date)
+            && self.until.is_none_or(|until| date <= until)
+            && self
+                .grep
+                .as_ref()
+                .is_none_or(|re| re.is_match(&commit.subject) || re.is_match(&commit.body))
+    }
+}
+
+fn compile_pattern(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|source| GitError::InvalidPattern {
+        pattern: pattern.into(),
+        source,
+    })
+}
+
+// `git rev-parse --since=<date>` prints `--max-age=<timestamp>`, and `--until=<date>` prints `--min-age=<timestamp>`
+fn parse_date(path: &RepositoryPath, option: &str, date: &str) -> Result<i64> {
+    let mut cmd = git_command(path);
+    cmd.arg("rev-parse").arg(format!("--{option}={date}"));
+    let output = GitProcess::spawn(cmd)?.output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .trim_end()
+        .split_once('=')
+        .and_then(|(_, timestamp)| timestamp.parse().ok())
+        .ok_or_else(|| GitError::UnexpectedOutput {
+            command: "git rev-parse".into(),
+            output: stdout.into(),
+        })
+}
+
+/// Matches the name with the pattern, where `*` matches any characters and `?` matches one character.
+pub fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // the position of the last `*` and the position of the name it matched up to
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    backtrack = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_query() {
+        let actual = CommitFilterQuery::parse(
+            r#"author:alice since:"2 weeks ago"  fix until:2024-01-01 grep:typo committer:"Bob B""#,
+        );
+        let expected = CommitFilterQuery {
+            author: Some("alice".into()),
+            committer: Some("Bob B".into()),
+            since: Some("2 weeks ago".into()),
+            until: Some("2024-01-01".into()),
+            grep: Some("fix typo".into()),
+        };
+        assert_eq!(actual, Ok(expected));
+    }
+
+    #[test]
+    fn test_parse_query_unknown_key() {
+        let actual = CommitFilterQuery::parse("fix: typo");
+        let expected = CommitFilterQuery {
+            grep: Some("fix: typo".into()),
+            ..Default::default()
+        };
+        assert_eq!(actual, Ok(expected));
+    }
+
+    #[test]
+    fn test_parse_query_empty() {
+        assert_eq!(
+            CommitFilterQuery::parse("  "),
+            Ok(CommitFilterQuery::default())
+        );
+    }
+
+    #[test]
+    fn test_parse_query_error() {
+        assert!(CommitFilterQuery::parse("author:").is_err());
+        assert!(CommitFilterQuery::parse(r#"grep:"fix"#).is_err());
+    }
+
+    #[rstest]
+    #[case("main", "main", true)]
+    #[case("main", "maint", false)]
+    #[case("release/*", "release/1.0", true)]
+    #[case("release/*", "release/", true)]
+    #[case("release/*", "releases/1.0", false)]
+    #[case("*/fix-*", "alice/fix-typo", true)]
+    #[case("v?.*", "v1.2", true)]
+    #[case("v?.*", "v10.2", false)]
+    #[case("*", "", true)]
+    fn test_glob_matches(#[case] pattern: &str, #[case] name: &str, #[case] expected: bool) {
+        assert_eq!(glob_matches(pattern, name), expected);
+    }
+}
@@ -0,0 +1,480 @@
+use chrono::{DateTime, FixedOffset};
+use gix::{
+    bstr::{BStr, ByteSlice},
+    diff::tree_with_rewrites::Change,
+    head::Kind,
+    revision::plumbing::Spec,
+    traverse::commit::topo,
+    ObjectId, ThreadSafeRepository,
+};
+use rustc_hash::FxHashSet;
+
+use super::{
+    subprocess::SubprocessBackend, Blame, Commit, CommitHash, FileChange, FileDiff, GitBackend,
+    GitError, Head, LogOptions, Ref, RefMap, RepositoryPath, Result, SortCommit, TreeEntry,
+};
+
+/// Reads the object database directly with gitoxide, without running `git` commands.
+#[derive(Debug)]
+pub struct GixBackend {
+    repo: ThreadSafeRepository,
+    // the operations not available in gitoxide are delegated to git
+    subprocess: SubprocessBackend,
+}
+
+impl GixBackend {
+    pub fn open(path: &RepositoryPath) -> Result<Self> {
+        // `GIT_DIR` and `GIT_WORK_TREE` are respected in the same way as git
+        let repo = ThreadSafeRepository::discover_with_environment_overrides(path.path()).map_err(
+            |e| {
+                use gix::discover::{upwards, Error};
+                match e {
+                    Error::Discover(
+                        upwards::Error::NoGitRepository { .. }
+                        | upwards::Error::NoGitRepositoryWithinCeiling { .. }
+                        | upwards::Error::NoGitRepositoryWithinFs { .. },
+                    )
+                    | Error::Open(gix::open::Error::NotARepository { .. }) => {
+                        GitError::NotRepository
+                    }
+                    e => gix_error("open repository")(e),
+                }
+            },
+        )?;
+        Ok(Self {
+            repo,
+            subprocess: SubprocessBackend::new(path),
+        })
+    }
+}
+
+impl GitBackend for GixBackend {
+    fn load_refs(&self) -> Result<(RefMap, Head)> {
+        let repo = self.repo.to_thread_local();
+
+        let mut ref_map = RefMap::default();
+
+        let platform = repo.references().map_err(gix_error("read references"))?;
+        let references = platform.all().map_err(gix_error("read references"))?;
+        for reference in references {
+            let mut reference = reference.map_err(gix_error("read references"))?;
+            let name = reference.name().as_bstr().to_str_lossy().into_owned();
+            // annotated tags are peeled to the commits they point to
+            let Ok(id) = reference.peel_to_id() else {
+                continue;
+            };
+            let target: CommitHash = id.to_string().as_str().into();
+
+            let r = if let Some(name) = name.strip_prefix("refs/heads/") {
+                Ref::Branch {
+                    name: name.into(),
+                    target: target.clone(),
+                }
+            } else if let Some(name) = name.strip_prefix("refs/remotes/") {
+                Ref::RemoteBranch {
+                    name: name.into(),
+                    target: target.clone(),
+                }
+            } else if let Some(name) = name.strip_prefix("refs/tags/") {
+                Ref::Tag {
+                    name: name.into(),
+                    target: target.clone(),
+                }
+            } else {
+                continue;
+            };
+            ref_map.entry(target).or_default().push(r);
+        }
+
+        ref_map.values_mut().for_each(|refs| refs.sort());
+
+        let head = match repo.head().map_err(gix_error("read HEAD"))?.kind {
+            Kind::Symbolic(r) => Head::Branch {
+                name: r.name.shorten().to_str_lossy().into_owned(),
+            },
+            Kind::Detached { target, .. } => Head::Detached {
+                target: to_commit_hash(target),
+            },
+            Kind::Unborn(_) => Head::None,
+        };
+
+        Ok((ref_map, head))
+    }
+
+    fn load_stashes(&self, mailmap: bool) -> Result<Vec<Commit>> {
+        let repo = self.repo.to_thread_local();
+        let mailmap = mailmap.then(|| repo.open_mailmap());
+        stash_ids(&repo)?
+            .into_iter()
+            .map(|id| read_commit(&repo, id, mailmap.as_ref()))
+            .collect()
+    }
+
+    fn load_stashes_as_refs(&self) -> Result<RefMap> {
+        let repo = self.repo.to_thread_local();
+
+        let mut ref_map = RefMap::default();
+        for (i, id) in stash_ids(&repo)?.into_iter().enumerate() {
+            let commit = read_commit(&repo, id, None)?;
+            let r = Ref::Stash {
+                name: format!("stash@{{{i}}}"),
+                message: commit.subject,
+                target: commit.commit_hash.clone(),
+            };
+            ref_map.entry(commit.commit_hash).or_default().push(r);
+        }
+        Ok(ref_map)
+    }
+
+    fn read_commits(&self, options: &LogOptions, f: &mut dyn FnMut(Commit) -> bool) -> Result<()> {
+        if !options.filter.paths.is_empty() {
+            // history simplification by paths is not available in gitoxide, so `git log` is used instead
+            return self.subprocess.read_commits(options, f);
+        }
+
+        let repo = self.repo.to_thread_local();
+        let mailmap = options.mailmap.then(|| repo.open_mailmap());
+
+        let (mut tips, mut ends) = if options.filter.is_default_revisions() {
+            (default_tips(&repo, options)?, Vec::new())
+        } else {
+            parse_revisions(&repo, &options.filter.revisions)?
+        };
+        // the walk yields a commit once per tip, so the same tips must be given only once
+        let mut seen = FxHashSet::default();
+        tips.retain(|id| seen.insert(*id));
+
+        if !options.exclude_hashes.is_empty() {
+            let hidden = options
+                .exclude_hashes
+                .iter()
+                .map(to_object_id)
+                .collect::<Result<Vec<_>>>()?;
+            // the topological walk yields the tips even if they are reachable from the ends,
+            // so the commits to be yielded are decided first and the walk is bounded by their parents
+            let mut ids = FxHashSet::default();
+            let mut boundary = Vec::new();
+            let walk = repo
+                .rev_walk(tips.iter().copied())
+                .with_hidden(hidden)
+                .all()
+                .map_err(gix_error("walk commits"))?;
+            for info in walk {
+                let info = info.map_err(gix_error("walk commits"))?;
+                ids.insert(info.id);
+                boundary.extend(info.parent_ids);
+            }
+            boundary.retain(|id| !ids.contains(id));
+            tips.retain(|id| ids.contains(id));
+            ends.extend(boundary);
+        }
+
+        let sorting = match options.sort {
+            SortCommit::Chronological => topo::Sorting::DateOrder,
+            SortCommit::Topological => topo::Sorting::TopoOrder,
+        };
+        let walk = topo::Builder::from_iters(&repo.objects, tips, Some(ends))
+            .sorting(sorting)
+            .with_commit_graph(repo.commit_graph_if_enabled().ok().flatten())
+            .build()
+            .map_err(gix_error("walk commits"))?;
+
+        for info in walk.take(options.max_count.unwrap_or(usize::MAX)) {
+            let info = info.map_err(gix_error("walk commits"))?;
+            let commit = read_commit(&repo, info.id, mailmap.as_ref())?;
+            if !f(commit) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn diff_summary(&self, commit_hash: &CommitHash) -> Result<Vec<FileChange>> {
+        let repo = self.repo.to_thread_local();
+        let commit = find_commit(&repo, commit_hash)?;
+        let parent_id = commit
+            .parent_ids()
+            .next()
+            .ok_or_else(|| GitError::CommitNotFound(commit_hash.clone()))?;
+        let parent = repo
+            .find_commit(parent_id)
+            .map_err(gix_error("read commit"))?;
+
+        let old_tree = parent.tree().map_err(gix_error("read tree"))?;
+        let new_tree = commit.tree().map_err(gix_error("read tree"))?;
+        let changes = repo
+            .diff_tree_to_tree(&old_tree, &new_tree, None)
+            .map_err(gix_error("diff trees"))?;
+        Ok(to_file_changes(changes))
+    }
+
+    fn initial_commit_additions(&self, commit_hash: &CommitHash) -> Result<Vec<FileChange>> {
+        let repo = self.repo.to_thread_local();
+        let commit = find_commit(&repo, commit_hash)?;
+        let tree = commit.tree().map_err(gix_error("read tree"))?;
+        let changes = repo
+            .diff_tree_to_tree(None, &tree, None)
+            .map_err(gix_error("diff trees"))?;
+        Ok(to_file_changes(changes))
+    }
+
+    fn diff_patch(&self, commit_hash: &CommitHash) -> Result<Vec<FileDiff>> {
+        // generating the patches with the same options as `git diff` is not available in gitoxide
+        self.subprocess.diff_patch(commit_hash)
+    }
+
+    fn tree_entries(&self, commit_hash: &CommitHash) -> Result<Vec<TreeEntry>> {
+        // `git ls-tree -l` gets the sizes of all the blobs at once, instead of reading the header of each blob
+        self.subprocess.tree_entries(commit_hash)
+    }
+
+    fn blob_content(&self, object_id: &str, limit: usize) -> Result<Vec<u8>> {
+        // gitoxide reads the whole blob into memory, even if only the beginning is needed
+        self.subprocess.blob_content(object_id, limit)
+    }
+
+    fn is_file(&self, commit_hash: &CommitHash, path: &str) -> Result<bool> {
+        let repo = self.repo.to_thread_local();
+        let commit = find_commit(&repo, commit_hash)?;
+        let tree = commit.tree().map_err(gix_error("read tree"))?;
+        let entry = tree
+            .lookup_entry_by_path(path)
+            .map_err(gix_error("read tree"))?;
+        Ok(entry.is_some_and(|e| e.mode().is_blob_or_symlink()))
+    }
+
+    fn last_modified_commit(
+        &self,
+        commit_hash: &CommitHash,
+        path: &str,
+    ) -> Result<Option<CommitHash>> {
+        // history simplification by paths is not available in gitoxide
+        self.subprocess.last_modified_commit(commit_hash, path)
+    }
+
+    fn blame(&self, commit_hash: &CommitHash, path: &str) -> Result<Blame> {
+        // blame is not enabled in the gitoxide features
+        self.subprocess.blame(commit_hash, path)
+    }
+}
+
+fn gix_error<E>(operation: &'static str) -> impl FnOnce(E) -> GitError
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    move |e| GitError::Gix {
+        operation,
+        source: e.into(),
+    }
+}
+
+fn to_commit_hash(id: impl std::fmt::Display) -> CommitHash {
+    id.to_string().as_str().into()
+}
+
+fn to_object_id(commit_hash: &CommitHash) -> Result<ObjectId> {
+    ObjectId::from_hex(commit_hash.as_str().as_bytes())
+        .map_err(|_| GitError::CommitNotFound(commit_hash.clone()))
+}
+
+fn find_commit<'r>(repo: &'r gix::Repository, commit_hash: &CommitHash) -> Result<gix::Commit<'r>> {
+    repo.find_commit(to_object_id(commit_hash)?)
+        .map_err(|_| GitError::CommitNotFound(commit_hash.clone()))
+}
+
+// Returns the stash commits from the newest one, as `stash@{0}` is the newest.
+fn stash_ids(repo: &gix::Repository) -> Result<Vec<ObjectId>> {
+    let Some(reference) = repo
+        .try_find_reference("refs/stash")
+        .map_err(gix_error("read stashes"))?
+    else {
+        return Ok(Vec::new());
+    };
+    let mut platform = reference.log_iter();
+    let Some(log) = platform.all().map_err(gix_error("read stashes"))? else {
+        return Ok(Vec::new());
+    };
+    let mut ids = Vec::new();
+    for line in log {
+        let line = line.map_err(gix_error("read stashes"))?;
+        ids.push(ObjectId::from_hex(line.new_oid).map_err(gix_error("read stashes"))?);
+    }
+    ids.reverse();
+    Ok(ids)
+}
+
+// The starting points of `git log --branches --remotes --tags <stash bases> HEAD`.
+fn default_tips(repo: &gix::Repository, options: &LogOptions) -> Result<Vec<ObjectId>> {
+    let mut tips = Vec::new();
+
+    let platform = repo.references().map_err(gix_error("read references"))?;
+    for prefix in ["refs/heads/", "refs/remotes/", "refs/tags/"] {
+        let references = platform
+            .prefixed(prefix)
+            .map_err(gix_error("read references"))?;
+        for reference in references {
+            let mut reference = reference.map_err(gix_error("read references"))?;
+            // refs that do not point to commits, such as tags of trees, are ignored like `git log`
+            if let Ok(commit) = reference.peel_to_commit() {
+                tips.push(commit.id);
+            }
+        }
+    }
+
+    for hash in &options.stash_base_hashes {
+        tips.push(to_object_id(hash)?);
+    }
+
+    if options.has_head {
+        if let Ok(commit) = repo.head_commit() {
+            tips.push(commit.id);
+        }
+    }
+
+    Ok(tips)
+}
+
+// Converts the revisions into the tips and the ends of the walk, like `git rev-list <revisions>`.
+fn parse_revisions(
+    repo: &gix::Repository,
+    revisions: &[String],
+) -> Result<(Vec<ObjectId>, Vec<ObjectId>)> {
+    let mut tips = Vec::new();
+    let mut ends = Vec::new();
+
+    for revision in revisions {
+        let spec = repo
+            .rev_parse(revision.as_str())
+            .map_err(gix_error("parse revision"))?
+            .detach();
+        match spec {
+            Spec::Include(id) => tips.push(id),
+            Spec::Exclude(id) => ends.push(id),
+            Spec::Range { from, to } => {
+                tips.push(to);
+                ends.push(from);
+            }
+            Spec::Merge { theirs, ours } => {
+                tips.push(theirs);
+                tips.push(ours);
+                let bases = repo
+                    .merge_bases_many(theirs, &[ours])
+                    .map_err(gix_error("find merge bases"))?;
+                ends.extend(bases.into_iter().map(|id| id.detach()));
+            }
+            Spec::IncludeOnlyParents(id) => {
+                let commit = repo.find_commit(id).map_err(gix_error("read commit"))?;
+                tips.extend(commit.parent_ids().map(|id| id.detach()));
+            }
+            Spec::ExcludeParents(id) => {
+                tips.push(id);
+                let commit = repo.find_commit(id).map_err(gix_error("read commit"))?;
+                ends.extend(commit.parent_ids().map(|id| id.detach()));
+            }
+        }
+    }
+
+    Ok((tips, ends))
+}
+
+fn read_commit(
+    repo: &gix::Repository,
+    id: ObjectId,
+    mailmap: Option<&gix::mailmap::Snapshot>,
+) -> Result<Commit> {
+    let commit = repo.find_commit(id).map_err(gix_error("read commit"))?;
+    let commit = commit.decode().map_err(gix_error("decode commit"))?;
+
+    let (author_name, author_email, author_date) = read_signature(commit.author, mailmap)?;
+    let (committer_name, committer_email, committer_date) =
+        read_signature(commit.committer, mailmap)?;
+    let (subject, body) = split_message(&commit.message.to_str_lossy());
+
+    Ok(Commit {
+        commit_hash: to_commit_hash(id),
+        author_name,
+        author_email,
+        author_date,
+        committer_name,
+        committer_email,
+        committer_date,
+        subject,
+        body,
+        parent_commit_hashes: commit.parents().map(to_commit_hash).collect(),
+        followed_path: None,
+    })
+}
+
+fn read_signature(
+    signature: gix::actor::SignatureRef,
+    mailmap: Option<&gix::mailmap::Snapshot>,
+) -> Result<(String, String, DateTime<FixedOffset>)> {
+    let signature = signature.trim();
+    // identities are resolved like the `%aN` and `%aE` placeholders of `git log`
+    let (name, email) = match mailmap {
+        Some(mailmap) => {
+            let resolved = mailmap.resolve_cow(signature);
+            (resolved.name, resolved.email)
+        }
+        None => (signature.name.into(), signature.email.into()),
+    };
+    let name = name.to_str_lossy().into_owned();
+    let email = email.to_str_lossy().into_owned();
+
+    let time = signature.time().map_err(gix_error("parse date"))?;
+    let date = FixedOffset::east_opt(time.offset)
+        .and_then(|offset| {
+            DateTime::from_timestamp(time.seconds, 0).map(|d| d.with_timezone(&offset))
+        })
+        .ok_or_else(|| GitError::Gix {
+            operation: "parse date",
+            source: format!("invalid date: {}", signature.time).into(),
+        })?;
+
+    Ok((name, email, date))
+}
+
+// Splits the commit message into the subject and the body, like `%s` and `%b` of `git log`.
+fn split_message(message: &str) -> (String, String) {
+    let mut lines = message.lines().skip_while(|line| line.trim().is_empty());
+    let subject = lines
+        .by_ref()
+        .take_while(|line| !line.trim().is_empty())
+        .map(|line| line.trim())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let body = lines
+        .skip_while(|line| line.trim().is_empty())
+        .map(|line| format!("{line}\n"))
+        .collect();
+    (subject, body)
+}
+
+fn to_file_changes(changes: Vec<Change>) -> Vec<FileChange> {
+    let path = |location: &BStr| location.to_str_lossy().into_owned();
+    changes
+        .into_iter()
+        .filter(|change| !change.entry_mode().is_tree())
+        .filter_map(|change| match change {
+            Change::Addition { location, .. } => Some(FileChange::Add {
+                path: path(location.as_ref()),
+            }),
+            Change::Modification { location, .. } => Some(FileChange::Modify {
+                path: path(location.as_ref()),
+            }),
+            Change::Deletion { location, .. } => Some(FileChange::Delete {
+                path: path(location.as_ref()),
+            }),
+            // copies are not shown, like the subprocess backend
+            Change::Rewrite { copy: true, .. } => None,
+            Change::Rewrite {
+                source_location,
+                location,
+                ..
+            } => Some(FileChange::Move {
+                from: path(source_location.as_ref()),
+                to: path(location.as_ref()),
+            }),
+        })
+        .collect()
+}
@@ -0,0 +1,787 @@
+use std::{
+    io::{BufRead, Read},
+    process::Command,
+};
+
+use chrono::{DateTime, FixedOffset};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::{
+    git_command, parse_unified_diff, simplify_history, Blame, BlameCommit, BlameLine, Commit,
+    CommitHash, FileChange, FileDiff, GitBackend, GitError, GitProcess, Head, LogOptions, Ref,
+    RefMap, RepositoryPath, Result, SortCommit, TreeEntry,
+};
+
+/// Runs `git` commands in the repository.
+#[derive(Debug)]
+pub struct SubprocessBackend {
+    path: RepositoryPath,
+}
+
+impl SubprocessBackend {
+    pub fn open(path: &RepositoryPath) -> Result<Self> {
+        check_git_repository(path)?;
+        Ok(Self::new(path))
+    }
+
+    pub fn new(path: &RepositoryPath) -> Self {
+        Self { path: path.clone() }
+    }
+}
+
+impl GitBackend for SubprocessBackend {
+    fn load_refs(&self) -> Result<(RefMap, Head)> {
+        load_refs(&self.path)
+    }
+
+    fn load_stashes(&self, mailmap: bool) -> Result<Vec<Commit>> {
+        load_all_stashes(&self.path, mailmap)
+    }
+
+    fn load_stashes_as_refs(&self) -> Result<RefMap> {
+        load_stashes_as_refs(&self.path)
+    }
+
+    fn read_commits(&self, options: &LogOptions, f: &mut dyn FnMut(Commit) -> bool) -> Result<()> {
+        if options.filter.follow {
+            return read_followed_commits(&self.path, options, f);
+        }
+
+        let mut process = GitProcess::spawn(log_command(&self.path, options))?;
+        let reader = process.stdout();
+
+        for bytes in reader.split(b'\0') {
+            let bytes = bytes.map_err(|e| process.io_error(e))?;
+            let s = String::from_utf8_lossy(&bytes);
+
+            let commit = parse_commit(&s).ok_or_else(|| process.unexpected_output(&s))?;
+            if !f(commit) {
+                return process.kill();
+            }
+        }
+
+        process.wait()
+    }
+
+    fn diff_summary(&self, commit_hash: &CommitHash) -> Result<Vec<FileChange>> {
+        get_diff_summary(&self.path, commit_hash)
+    }
+
+    fn initial_commit_additions(&self, commit_hash: &CommitHash) -> Result<Vec<FileChange>> {
+        get_initial_commit_additions(&self.path, commit_hash)
+    }
+
+    fn diff_patch(&self, commit_hash: &CommitHash) -> Result<Vec<FileDiff>> {
+        get_diff_patch(&self.path, commit_hash)
+    }
+
+    fn tree_entries(&self, commit_hash: &CommitHash) -> Result<Vec<TreeEntry>> {
+        get_tree_entries(&self.path, commit_hash)
+    }
+
+    fn blob_content(&self, object_id: &str, limit: usize) -> Result<Vec<u8>> {
+        get_blob_content(&self.path, object_id, limit)
+    }
+
+    fn is_file(&self, commit_hash: &CommitHash, path: &str) -> Result<bool> {
+        is_file_in_tree(&self.path, commit_hash, path)
+    }
+
+    fn last_modified_commit(
+        &self,
+        commit_hash: &CommitHash,
+        path: &str,
+    ) -> Result<Option<CommitHash>> {
+        get_last_modified_commit(&self.path, commit_hash, path)
+    }
+
+    fn blame(&self, commit_hash: &CommitHash, path: &str) -> Result<Blame> {
+        get_blame(&self.path, commit_hash, path)
+    }
+}
+
+fn check_git_repository(path: &RepositoryPath) -> Result<()> {
+    if !is_inside_work_tree(path)? && !is_bare_repository(path)? {
+        return Err(GitError::NotRepository);
+    }
+    Ok(())
+}
+
+fn is_inside_work_tree(path: &RepositoryPath) -> Result<bool> {
+    let mut cmd = git_command(path);
+    cmd.arg("rev-parse").arg("--is-inside-work-tree");
+    let output = GitProcess::spawn(cmd)?.output()?;
+    Ok(output.status.success() && output.stdout == b"true\n")
+}
+
+fn is_bare_repository(path: &RepositoryPath) -> Result<bool> {
+    let mut cmd = git_command(path);
+    cmd.arg("rev-parse").arg("--is-bare-repository");
+    let output = GitProcess::spawn(cmd)?.output()?;
+    Ok(output.status.success() && output.stdout == b"true\n")
+}
+
+fn log_command(path: &RepositoryPath, options: &LogOptions) -> Command {
+    let mut cmd = git_command(path);
+    cmd.arg("log");
+
+    cmd.arg(match options.sort {
+        SortCommit::Chronological => "--date-order",
+        SortCommit::Topological => "--topo-order",
+    })
+    .arg(format!("--pretty={}", load_commits_format(options.mailmap)))
+    .arg("--date=iso-strict")
+    .arg("-z"); // use NUL as a delimiter
+
+    if let Some(n) = options.max_count {
+        cmd.arg("--max-count").arg(n.to_string());
+    }
+
+    let filter = &options.filter;
+    if filter.follow {
+        // `--follow` does not rewrite parents, so they are rewritten after reading all commits.
+        // the path of the file in each commit is printed after the commit
+        cmd.arg("--follow").arg("--name-only");
+    } else if !filter.paths.is_empty() {
+        // rewrite parents to the nearest commits that touch the paths to keep the graph connected
+        cmd.arg("--parents");
+    }
+
+    add_revision_args(&mut cmd, options);
+
+    cmd.arg("--").args(&filter.paths);
+    cmd
+}
+
+fn add_revision_args(cmd: &mut Command, options: &LogOptions) {
+    let filter = &options.filter;
+    if filter.is_default_revisions() {
+        // exclude stashes and other refs
+        cmd.arg("--branches").arg("--remotes").arg("--tags");
+
+        // commits that are reachable from the stashes
+        cmd.args(options.stash_base_hashes.iter().map(|hash| hash.as_str()));
+
+        if options.has_head {
+            cmd.arg("HEAD");
+        }
+    } else {
+        cmd.args(&filter.revisions);
+    }
+
+    if !options.exclude_hashes.is_empty() {
+        cmd.arg("--not")
+            .args(options.exclude_hashes.iter().map(|hash| hash.as_str()));
+    }
+}
+
+fn read_followed_commits(
+    path: &RepositoryPath,
+    options: &LogOptions,
+    f: &mut dyn FnMut(Commit) -> bool,
+) -> Result<()> {
+    let mut process = GitProcess::spawn(log_command(path, options))?;
+    let reader = process.stdout();
+
+    let mut commits: Vec<Commit> = Vec::new();
+    for bytes in reader.split(b'\0') {
+        let bytes = bytes.map_err(|e| process.io_error(e))?;
+        let s = String::from_utf8_lossy(&bytes);
+
+        // the path of the file follows the commit on a new line
+        if let Some(name) = s.strip_prefix('\n').filter(|s| !s.contains('\x1f')) {
+            if let Some(commit) = commits.last_mut() {
+                commit.followed_path = Some(name.into());
+            }
+            continue;
+        }
+
+        let s = s.trim_start_matches('\n');
+        let commit = parse_commit(s).ok_or_else(|| process.unexpected_output(s))?;
+        commits.push(commit);
+    }
+    process.wait()?;
+
+    let history = load_history_parents(path, options)?;
+    rewrite_parents(&mut commits, history);
+
+    for commit in commits {
+        if !f(commit) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// Loads the parents of all commits in the revisions, children first.
+fn load_history_parents(
+    path: &RepositoryPath,
+    options: &LogOptions,
+) -> Result<Vec<(CommitHash, Vec<CommitHash>)>> {
+    let mut cmd = git_command(path);
+    cmd.arg("rev-list").arg("--parents").arg("--topo-order");
+    add_revision_args(&mut cmd, options);
+
+    let mut process = GitProcess::spawn(cmd)?;
+    let reader = process.stdout();
+
+    let mut history = Vec::new();
+    for line in reader.lines() {
+        let line = line.map_err(|e| process.io_error(e))?;
+        let mut hashes = line.split(' ').map(CommitHash::from);
+        let hash = hashes
+            .next()
+            .ok_or_else(|| process.unexpected_output(&line))?;
+        history.push((hash, hashes.collect()));
+    }
+    process.wait()?;
+
+    Ok(history)
+}
+
+// Rewrites the parents of the commits to their nearest ancestors among the commits, as `--parents` does with paths,
+// so that the graph of the followed file stays connected.
+fn rewrite_parents(commits: &mut [Commit], history: Vec<(CommitHash, Vec<CommitHash>)>) {
+    let followed: FxHashSet<&CommitHash> = commits.iter().map(|c| &c.commit_hash).collect();
+    // parents come before their children in the reversed topological order
+    let history = history
+        .iter()
+        .rev()
+        .map(|(hash, parents)| (hash, parents.as_slice()));
+    let mut parents_map = simplify_history(history, |hash, _| followed.contains(hash));
+
+    for commit in commits.iter_mut() {
+        if let Some(parents) = parents_map.remove(&commit.commit_hash) {
+            commit.parent_commit_hashes = parents;
+        }
+    }
+}
+
+fn load_all_stashes(path: &RepositoryPath, mailmap: bool) -> Result<Vec<Commit>> {
+    let mut cmd = git_command(path);
+    cmd.arg("stash")
+        .arg("list")
+        .arg(format!("--pretty={}", load_commits_format(mailmap)))
+        .arg("--date=iso-strict")
+        .arg("-z"); // use NUL as a delimiter
+
+    let mut process = GitProcess::spawn(cmd)?;
+    let commits = read_commits(&mut process)?;
+    // the exit status is not checked because stashes are not available in bare repositories
+    process.output()?;
+
+    Ok(commits)
+}
+
+fn read_commits(process: &mut GitProcess) -> Result<Vec<Commit>> {
+    let reader = process.stdout();
+
+    let mut commits = Vec::new();
+
+    for bytes in reader.split(b'\0') {
+        let bytes = bytes.map_err(|e| process.io_error(e))?;
+        let s = String::from_utf8_lossy(&bytes);
+
+        let commit = parse_commit(&s).ok_or_else(|| process.unexpected_output(&s))?;
+
+        commits.push(commit);
+    }
+
+    Ok(commits)
+}
+
+fn parse_commit(s: &str) -> Option<Commit> {
+    let parts: Vec<&str> = s.split('\x1f').collect();
+    if parts.len() != 10 {
+        return None;
+    }
+
+    let commit = Commit {
+        commit_hash: parts[0].into(),
+        author_name: parts[1].into(),
+        author_email: parts[2].into(),
+        author_date: parse_iso_date(parts[3])?,
+        committer_name: parts[4].into(),
+        committer_email: parts[5].into(),
+        committer_date: parse_iso_date(parts[6])?,
+        subject: parts[7].into(),
+        body: parts[8].into(),
+        parent_commit_hashes: parse_parent_commit_hashes(parts[9]),
+        followed_path: None,
+    };
+    Some(commit)
+}
+
+fn load_commits_format(mailmap: bool) -> String {
+    // The uppercase name/email placeholders (`%aN`, `%aE`, `%cN`, `%cE`) resolve
+    // identities through the repository's .mailmap, while the lowercase variants
+    // use the raw values recorded in each commit.
+    let format = if mailmap {
+        [
+            "%H", "%aN", "%aE", "%ad", "%cN", "%cE", "%cd", "%s", "%b", "%P",
+        ]
+    } else {
+        [
+            "%H", "%an", "%ae", "%ad", "%cn", "%ce", "%cd", "%s", "%b", "%P",
+        ]
+    };
+    format.join("%x1f") // use Unit Separator as a delimiter
+}
+
+fn parse_iso_date(s: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(s).ok()
+}
+
+fn parse_parent_commit_hashes(s: &str) -> Vec<CommitHash> {
+    if s.is_empty() {
+        return Vec::new();
+    }
+    s.split(' ').map(|s| s.into()).collect()
+}
+
+fn load_refs(path: &RepositoryPath) -> Result<(RefMap, Head)> {
+    let mut cmd = git_command(path);
+    cmd.arg("show-ref").arg("--head").arg("--dereference");
+
+    let mut process = GitProcess::spawn(cmd)?;
+    let reader = process.stdout();
+
+    let mut ref_map = RefMap::default();
+    let mut tag_map: FxHashMap<String, Ref> = FxHashMap::default();
+    let mut head: Head = Head::None;
+
+    for line in reader.lines() {
+        let line = line.map_err(|e| process.io_error(e))?;
+
+        let parts: Vec<&str> = line.split(' ').collect();
+        if parts.len() != 2 {
+            return Err(process.unexpected_output(&line));
+        }
+
+        let hash = parts[0];
+        let refs = parts[1];
+
+        if refs == "HEAD" {
+            head = if let Some(branch) = get_current_branch(path)? {
+                Head::Branch { name: branch }
+            } else {
+                Head::Detached {
+                    target: hash.into(),
+                }
+            };
+        } else if let Some(r) = parse_branch_refs(hash, refs) {
+            ref_map.entry(hash.into()).or_default().push(r);
+        } else if let Some(r) = parse_tag_refs(hash, refs) {
+            // if annotated tag exists, it will be overwritten by the following line of the same tag
+            // this will make the tag point to the commit that the annotated tag points to
+            tag_map.insert(r.name().into(), r);
+        }
+    }
+
+    for tag in tag_map.into_values() {
+        ref_map.entry(tag.target().clone()).or_default().push(tag);
+    }
+
+    ref_map.values_mut().for_each(|refs| refs.sort());
+
+    // the exit status is not checked because `git show-ref` fails if there are no refs
+    process.output()?;
+
+    Ok((ref_map, head))
+}
+
+fn load_stashes_as_refs(path: &RepositoryPath) -> Result<RefMap> {
+    let format = ["%gd", "%H", "%s"].join("%x1f"); // use Unit Separator as a delimiter
+    let mut cmd = git_command(path);
+    cmd.arg("stash")
+        .arg("list")
+        .arg(format!("--format={format}"));
+
+    let mut process = GitProcess::spawn(cmd)?;
+    let reader = process.stdout();
+
+    let mut ref_map = RefMap::default();
+
+    for line in reader.lines() {
+        let line = line.map_err(|e| process.io_error(e))?;
+
+        let parts: Vec<&str> = line.split('\x1f').collect();
+        if parts.len() != 3 {
+            return Err(process.unexpected_output(&line));
+        }
+
+        let name = parts[0];
+        let hash = parts[1];
+        let subject = parts[2];
+
+        let r = Ref::Stash {
+            name: name.into(),
+            message: subject.into(),
+            target: hash.into(),
+        };
+
+        ref_map.entry(hash.into()).or_default().push(r);
+    }
+
+    // the exit status is not checked because stashes are not available in bare repositories
+    process.output()?;
+
+    Ok(ref_map)
+}
+
+fn parse_branch_refs(hash: &str, refs: &str) -> Option<Ref> {
+    if refs.starts_with("refs/heads/") {
+        let name = refs.trim_start_matches("refs/heads/");
+        Some(Ref::Branch {
+            name: name.into(),
+            target: hash.into(),
+        })
+    } else if refs.starts_with("refs/remotes/") {
+        let name = refs.trim_start_matches("refs/remotes/");
+        Some(Ref::RemoteBranch {
+            name: name.into(),
+            target: hash.into(),
+        })
+    } else {
+        None
+    }
+}
+
+fn parse_tag_refs(hash: &str, refs: &str) -> Option<Ref> {
+    if refs.starts_with("refs/tags/") {
+        let name = refs.trim_start_matches("refs/tags/");
+        let name = name.trim_end_matches("^{}");
+        Some(Ref::Tag {
+            name: name.into(),
+            target: hash.into(),
+        })
+    } else {
+        None
+    }
+}
+
+fn get_current_branch(path: &RepositoryPath) -> Result<Option<String>> {
+    let mut cmd = git_command(path);
+    cmd.arg("branch").arg("--show-current");
+
+    let mut process = GitProcess::spawn(cmd)?;
+    let reader = process.stdout();
+
+    let branch = match reader.lines().next() {
+        Some(line) => Some(line.map_err(|e| process.io_error(e))?),
+        None => None,
+    };
+
+    // the exit status is not checked because it has nothing to show when HEAD is detached
+    process.output()?;
+
+    Ok(branch.filter(|b| !b.is_empty()))
+}
+
+fn get_diff_summary(path: &RepositoryPath, commit_hash: &CommitHash) -> Result<Vec<FileChange>> {
+    let mut cmd = git_command(path);
+    cmd.arg("diff")
+        .arg("--name-status")
+        .arg("-z") // use NUL as a delimiter, and keep the paths unquoted
+        .arg(format!("{}^", commit_hash.0))
+        .arg(&commit_hash.0);
+
+    let mut process = GitProcess::spawn(cmd)?;
+    let reader = process.stdout();
+
+    let mut fields = Vec::new();
+    for bytes in reader.split(b'\0') {
+        let bytes = bytes.map_err(|e| process.io_error(e))?;
+        fields.push(String::from_utf8_lossy(&bytes).into_owned());
+    }
+
+    let mut changes = Vec::new();
+
+    // each change is `<status>\0<path>\0`, or `<status>\0<from>\0<to>\0` for renames and copies
+    let mut fields = fields.into_iter();
+    while let Some(status) = fields.next() {
+        let path_count = if status.starts_with(['R', 'C']) { 2 } else { 1 };
+        let paths: Vec<String> = fields.by_ref().take(path_count).collect();
+
+        match (status.get(0..1), &paths[..]) {
+            (Some("A"), [path]) => changes.push(FileChange::Add { path: path.clone() }),
+            (Some("M"), [path]) => changes.push(FileChange::Modify { path: path.clone() }),
+            (Some("D"), [path]) => changes.push(FileChange::Delete { path: path.clone() }),
+            (Some("R"), [from, to]) => changes.push(FileChange::Move {
+                from: from.clone(),
+                to: to.clone(),
+            }),
+            (Some("A" | "M" | "D" | "R") | None, _) => {
+                return Err(process.unexpected_output(&status));
+            }
+            _ => {}
+        }
+    }
+
+    process.wait()?;
+
+    Ok(changes)
+}
+
+fn get_initial_commit_additions(
+    path: &RepositoryPath,
+    commit_hash: &CommitHash,
+) -> Result<Vec<FileChange>> {
+    let mut cmd = git_command(path);
+    cmd.arg("ls-tree")
+        .arg("--name-status")
+        .arg("-r") // the empty tree hash
+        .arg("-z") // use NUL as a delimiter, and keep the paths unquoted
+        .arg(&commit_hash.0);
+
+    let mut process = GitProcess::spawn(cmd)?;
+    let reader = process.stdout();
+
+    let mut changes = Vec::new();
+
+    for bytes in reader.split(b'\0') {
+        let bytes = bytes.map_err(|e| process.io_error(e))?;
+        let path = String::from_utf8_lossy(&bytes).into_owned();
+        changes.push(FileChange::Add { path });
+    }
+
+    process.wait()?;
+
+    Ok(changes)
+}
+
+fn get_diff_patch(path: &RepositoryPath, commit_hash: &CommitHash) -> Result<Vec<FileDiff>> {
+    let mut cmd = git_command(path);
+    // the parents are read from the commit itself, since those in the repository may be rewritten by the filters
+    cmd.arg("log")
+        .arg("-1")
+        .arg("-p")
+        .arg("-m") // compare merges with their first parents, and root commits with the empty tree
+        .arg("--first-parent")
+        .arg("--format=")
+        .arg("--no-show-signature")
+        .arg("--no-color")
+        .arg("--no-ext-diff")
+        .arg("--src-prefix=a/") // the prefixes can be changed by the user config
+        .arg("--dst-prefix=b/")
+        .arg("-M")
+        .arg(&commit_hash.0);
+
+    let mut process = GitProcess::spawn(cmd)?;
+    let mut bytes = Vec::new();
+    process
+        .stdout()
+        .read_to_end(&mut bytes)
+        .map_err(|e| process.io_error(e))?;
+    process.wait()?;
+
+    Ok(parse_unified_diff(&String::from_utf8_lossy(&bytes)))
+}
+
+fn get_tree_entries(path: &RepositoryPath, commit_hash: &CommitHash) -> Result<Vec<TreeEntry>> {
+    let mut cmd = git_command(path);
+    cmd.arg("ls-tree")
+        .arg("-r")
+        .arg("-l") // with the sizes of the blobs
+        .arg("-z")
+        .arg("--full-tree")
+        .arg(&commit_hash.0);
+
+    let mut process = GitProcess::spawn(cmd)?;
+    let reader = process.stdout();
+
+    let mut entries = Vec::new();
+
+    // each entry is `<mode> <type> <object> <size>\t<path>`, and the size is `-` for submodules
+    for bytes in reader.split(b'\0') {
+        let bytes = bytes.map_err(|e| process.io_error(e))?;
+        let s = String::from_utf8_lossy(&bytes);
+        let entry = s.split_once('\t').and_then(|(meta, path)| {
+            let parts: Vec<&str> = meta.split_whitespace().collect();
+            match parts[..] {
+                [mode, _, object_id, size] => Some(TreeEntry {
+                    path: path.into(),
+                    mode: mode.into(),
+                    object_id: object_id.into(),
+                    size: size.parse().ok(),
+                }),
+                _ => None,
+            }
+        });
+        entries.push(entry.ok_or_else(|| process.unexpected_output(&s))?);
+    }
+
+    process.wait()?;
+
+    Ok(entries)
+}
+
+fn is_file_in_tree(
+    path: &RepositoryPath,
+    commit_hash: &CommitHash,
+    file_path: &str,
+) -> Result<bool> {
+    let mut cmd = git_command(path);
+    cmd.arg("ls-tree")
+        .arg("-z")
+        .arg("--full-tree")
+        .arg(&commit_hash.0)
+        .arg("--")
+        .arg(file_path);
+
+    let mut process = GitProcess::spawn(cmd)?;
+    let reader = process.stdout();
+
+    let mut is_file = false;
+    // the entry is `<mode> <type> <object>\t<path>`, and nothing is printed if the path does not exist
+    for bytes in reader.split(b'\0') {
+        let bytes = bytes.map_err(|e| process.io_error(e))?;
+        let s = String::from_utf8_lossy(&bytes);
+        let (meta, entry_path) = s
+            .split_once('\t')
+            .ok_or_else(|| process.unexpected_output(&s))?;
+        is_file |= entry_path == file_path && meta.split_whitespace().nth(1) == Some("blob");
+    }
+
+    process.wait()?;
+
+    Ok(is_file)
+}
+
+fn get_blob_content(path: &RepositoryPath, object_id: &str, limit: usize) -> Result<Vec<u8>> {
+    let mut cmd = git_command(path);
+    cmd.arg("cat-file").arg("blob").arg(object_id);
+
+    let mut process = GitProcess::spawn(cmd)?;
+    let mut bytes = Vec::new();
+    process
+        .stdout()
+        .take(limit as u64 + 1) // one more byte to know whether the rest is left
+        .read_to_end(&mut bytes)
+        .map_err(|e| process.io_error(e))?;
+    if bytes.len() > limit {
+        bytes.truncate(limit);
+        process.kill()?;
+    } else {
+        process.wait()?;
+    }
+
+    Ok(bytes)
+}
+
+fn get_last_modified_commit(
+    path: &RepositoryPath,
+    commit_hash: &CommitHash,
+    file_path: &str,
+) -> Result<Option<CommitHash>> {
+    let mut cmd = git_command(path);
+    cmd.arg("--literal-pathspecs") // the path may contain glob characters
+        .arg("log")
+        .arg("-1")
+        .arg("--format=%H")
+        .arg(&commit_hash.0)
+        .arg("--")
+        .arg(file_path);
+
+    let mut process = GitProcess::spawn(cmd)?;
+    let reader = process.stdout();
+
+    let hash = match reader.lines().next() {
+        Some(line) => Some(line.map_err(|e| process.io_error(e))?),
+        None => None,
+    };
+
+    process.wait()?;
+
+    Ok(hash.filter(|h| !h.is_empty()).map(|h| h.as_str().into()))
+}
+
+fn get_blame(path: &RepositoryPath, commit_hash: &CommitHash, file_path: &str) -> Result<Blame> {
+    let mut cmd = git_command(path);
+    cmd.arg("-c")
+        .arg("core.quotePath=false") // keep the non-ASCII paths of `previous` as is
+        .arg("blame")
+        .arg("--porcelain")
+        .arg(&commit_hash.0)
+        .arg("--")
+        .arg(file_path);
+
+    let mut process = GitProcess::spawn(cmd)?;
+    let reader = process.stdout();
+
+    let mut blame = Blame::default();
+    // the commit of the following lines, and the commit headers which are output only for its first line
+    let mut current: Option<(CommitHash, usize)> = None;
+    let mut headers: FxHashMap<&'static str, String> = FxHashMap::default();
+
+    for bytes in reader.split(b'\n') {
+        let bytes = bytes.map_err(|e| process.io_error(e))?;
+        let s = String::from_utf8_lossy(&bytes);
+
+        if let Some(content) = s.strip_prefix('\t') {
+            let (commit_hash, original_line_number) = current
+                .take()
+                .ok_or_else(|| process.unexpected_output(&s))?;
+            if !blame.commits.contains_key(&commit_hash) {
+                let commit = parse_blame_commit(&mut headers)
+                    .ok_or_else(|| process.unexpected_output(&s))?;
+                blame.commits.insert(commit_hash.clone(), commit);
+            }
+            blame.lines.push(BlameLine {
+                commit_hash,
+                original_line_number,
+                content: content.into(),
+            });
+        } else if current.is_none() {
+            // `<hash> <original line> <final line> [<number of lines>]`
+            let parts: Vec<&str> = s.split(' ').collect();
+            let line = match parts[..] {
+                [hash, original, _] | [hash, original, _, _] => original
+                    .parse()
+                    .ok()
+                    .map(|original| (CommitHash::from(hash), original)),
+                _ => None,
+            };
+            current = Some(line.ok_or_else(|| process.unexpected_output(&s))?);
+        } else if let Some((key, value)) = s.split_once(' ') {
+            if let Some(key) = ["author", "author-time", "author-tz", "summary", "previous"]
+                .into_iter()
+                .find(|k| *k == key)
+            {
+                headers.insert(key, value.into());
+            }
+        }
+    }
+
+    process.wait()?;
+
+    Ok(blame)
+}
+
+fn parse_blame_commit(headers: &mut FxHashMap<&'static str, String>) -> Option<BlameCommit> {
+    let timestamp = headers.remove("author-time")?.parse().ok()?;
+    let offset = parse_blame_timezone(&headers.remove("author-tz")?)?;
+    let author_date = DateTime::from_timestamp(timestamp, 0)?.with_timezone(&offset);
+    let previous = headers.remove("previous").and_then(|s| {
+        s.split_once(' ')
+            .map(|(hash, path)| (hash.into(), path.into()))
+    });
+    Some(BlameCommit {
+        author_name: headers.remove("author")?,
+        author_date,
+        summary: headers.remove("summary").unwrap_or_default(),
+        previous,
+    })
+}
+
+// e.g. `+0900`
+fn parse_blame_timezone(s: &str) -> Option<FixedOffset> {
+    let (sign, hhmm) = match s.split_at_checked(1)? {
+        ("+", hhmm) => (1, hhmm),
+        ("-", hhmm) => (-1, hhmm),
+        _ => return None,
+    };
+    let hours: i32 = hhmm.get(0..2)?.parse().ok()?;
+    let minutes: i32 = hhmm.get(2..4)?.parse().ok()?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
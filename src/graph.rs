@@ -1,6 +1,10 @@
 mod calc;
 mod geometry;
+mod highlight;
 mod image;
+mod text;
 
 pub use calc::*;
+pub use highlight::*;
 pub use image::*;
+pub use text::*;
@@ -1,6 +1,15 @@
-use rustc_hash::FxHashMap;
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    ops::Range,
+};
 
-use crate::git::{Commit, CommitHash, Repository};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    color::{GraphColorMode, GraphColorSet},
+    git::{Commit, CommitHash, Ref, Repository},
+};
 
 type CommitPosMap<'a> = FxHashMap<&'a CommitHash, (usize, usize)>;
 
@@ -10,6 +19,40 @@ pub struct Graph<'a> {
     pub commit_pos_map: CommitPosMap<'a>,
     pub edges: Vec<Vec<Edge>>,
     pub max_pos_x: usize,
+    // merge commits some of whose parents are not drawn, such as in the first-parent history
+    pub collapsed_merges: FxHashSet<&'a CommitHash>,
+    // the indices of `GraphColorSet` the commits are drawn with
+    pub commit_colors: FxHashMap<&'a CommitHash, usize>,
+    // the commits drawn in the overflow column beyond the maximum number of lanes
+    pub overflow_commits: FxHashSet<&'a CommitHash>,
+}
+
+impl Graph<'_> {
+    /// Returns the lanes of the commits to keep them in the graph rebuilt after refreshing.
+    pub fn lane_hint(&self) -> LaneHint {
+        let lanes = self
+            .commit_pos_map
+            .iter()
+            .filter(|(hash, _)| !self.overflow_commits.contains(*hash))
+            .map(|(hash, (pos_x, _))| ((*hash).clone(), *pos_x))
+            .collect();
+        LaneHint { lanes }
+    }
+}
+
+/// The lanes of the commits in the previous graph.
+///
+/// The commits are placed in the same lanes as far as possible,
+/// and the new commits are placed in the lanes which are not used by them.
+#[derive(Debug, Default, Clone)]
+pub struct LaneHint {
+    lanes: FxHashMap<CommitHash, usize>,
+}
+
+impl LaneHint {
+    fn lane(&self, commit_hash: &CommitHash) -> Option<usize> {
+        self.lanes.get(commit_hash).copied()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -17,6 +60,12 @@ pub struct Edge {
     pub edge_type: EdgeType,
     pub pos_x: usize,
     pub associated_line_pos_x: usize,
+    // drawn with dots because some commits are hidden between the commit and its parent
+    pub dotted: bool,
+    // the index of `GraphColorSet`, which is the same as the line position unless colored by the branches
+    pub color_index: usize,
+    // identifies the line between a commit and its parent which the edge is a part of, see `line_id`
+    pub line_id: u64,
 }
 
 impl Edge {
@@ -25,10 +74,21 @@ impl Edge {
             edge_type,
             pos_x,
             associated_line_pos_x: line_pos_x,
+            dotted: false,
+            color_index: line_pos_x,
+            line_id: 0,
         }
     }
 }
 
+/// Returns the id of the line from the commit to its parent, which is kept across rebuilding the graph.
+pub fn line_id(commit_hash: &CommitHash, parent_hash: &CommitHash) -> u64 {
+    let mut hasher = rustc_hash::FxHasher::default();
+    commit_hash.hash(&mut hasher);
+    parent_hash.hash(&mut hasher);
+    hasher.finish()
+}
+
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub enum EdgeType {
     Vertical,    // │
@@ -49,40 +109,949 @@ impl EdgeType {
     }
 }
 
-pub fn calc_graph(repository: &Repository) -> Graph<'_> {
+/// The options of how the commits are laid out in the lanes.
+#[derive(Debug, Default, Clone)]
+pub struct GraphLayout {
+    // the patterns of the branches whose first-parent histories occupy the leftmost lanes in this order
+    pub pinned_branches: Vec<String>,
+    // whether to pack the branches into the leftmost lanes they fit in after laying out all the commits,
+    // and to draw the detours of the merges and the lines to the missing parents in the nearest vacant lanes
+    pub compact_lanes: bool,
+    // the number of the lanes drawn, beyond which the lanes are elided into an overflow column
+    pub max_lanes: Option<usize>,
+}
+
+/// Calculates the graph continuing from the layout of the commits calculated before,
+/// so that only the commits appended or prepended to the repository since then are laid out.
+/// The layout is calculated from scratch following the lane hint if the previous commits are changed.
+pub fn calc_graph<'a>(
+    repository: &'a Repository,
+    color_set: &GraphColorSet,
+    layout: &GraphLayout,
+    lane_hint: &LaneHint,
+    incremental: &mut IncrementalLayout,
+) -> Graph<'a> {
     let commits = repository.all_commits();
 
-    let commit_pos_map = calc_commit_positions(&commits, repository);
-    let (graph_edges, max_pos_x) = calc_edges(&commit_pos_map, &commits, repository);
+    let (pinned_lanes, pinned_lane_count) = calc_pinned_lanes(repository, &layout.pinned_branches);
+    let branch_colors = match color_set.mode {
+        GraphColorMode::Lane => None,
+        GraphColorMode::Branch => Some(calc_branch_colors(&commits, repository, color_set)),
+    };
+    // the branches are packed over all the rows, so the layout is not continued when they are packed
+    let continued_positions = match layout.compact_lanes {
+        true => None,
+        false => incremental.continued_positions(
+            &commits,
+            &pinned_lanes,
+            pinned_lane_count,
+            branch_colors.as_ref(),
+        ),
+    };
+    let mut commit_pos_map = continued_positions.unwrap_or_else(|| {
+        *incremental = IncrementalLayout::new(pinned_lane_count);
+        FxHashMap::default()
+    });
+    if incremental.prepended > 0
+        && !calc_prepended_positions(
+            &commits[..incremental.prepended],
+            &mut commit_pos_map,
+            repository,
+            &pinned_lanes,
+            pinned_lane_count,
+            &incremental.lane_tops(),
+        )
+    {
+        *incremental = IncrementalLayout::new(pinned_lane_count);
+        commit_pos_map.clear();
+    }
+    calc_commit_positions(
+        &commits,
+        &mut commit_pos_map,
+        &mut incremental.commit_line_state,
+        repository,
+        &pinned_lanes,
+        pinned_lane_count,
+        lane_hint,
+    );
+    if layout.compact_lanes {
+        pack_lanes(&commits, &mut commit_pos_map, repository, pinned_lane_count);
+    }
+    let commit_colors = match branch_colors {
+        Some(branch_colors) => branch_colors,
+        None => commit_pos_map
+            .iter()
+            .map(|(hash, (pos_x, _))| (*hash, *pos_x))
+            .collect(),
+    };
+    let (mut graph_edges, mut max_pos_x) = calc_edges(
+        &commit_pos_map,
+        &commit_colors,
+        &commits,
+        repository,
+        color_set,
+        layout.compact_lanes,
+        incremental,
+    );
+    incremental.finish(&commits, &commit_pos_map, &commit_colors, color_set);
+
+    let mut overflow_commits = FxHashSet::default();
+    if let Some(max_lanes) = layout.max_lanes.filter(|n| *n <= max_pos_x) {
+        overflow_commits = commits
+            .iter()
+            .map(|c| &c.commit_hash)
+            .filter(|hash| commit_pos_map[hash].0 >= max_lanes)
+            .collect();
+        elide_overflow_lanes(
+            &mut commit_pos_map,
+            &mut graph_edges,
+            &commits,
+            max_lanes,
+            color_set.lane_color_index(max_lanes),
+        );
+        max_pos_x = max_lanes;
+    }
+    let collapsed_merges = commits
+        .iter()
+        .filter(|c| c.parent_commit_hashes.len() > repository.parents_hash(&c.commit_hash).len())
+        .map(|c| &c.commit_hash)
+        .collect();
 
     Graph {
         commits,
         commit_pos_map,
         edges: graph_edges,
         max_pos_x,
+        collapsed_merges,
+        commit_colors,
+        overflow_commits,
+    }
+}
+
+/// The layout of the commits calculated so far, which is continued when more commits are loaded.
+#[derive(Debug, Default)]
+pub struct IncrementalLayout {
+    // the commits laid out, in the order of the rows
+    commit_hashes: Vec<CommitHash>,
+    pinned_lane_count: usize,
+    // the lanes of the commits laid out, in the order of the rows
+    pos_xs: Vec<usize>,
+    // the colors of the commits laid out when colored by the branches, which are kept while appending
+    branch_colors: Vec<usize>,
+    // the rows of the commits whose lines go down in each lane
+    commit_line_state: Vec<Option<usize>>,
+    // the edges of the lines between the commits and to the first parents not loaded
+    edges: Vec<Vec<WrappedEdge>>,
+    max_pos_x: usize,
+    // the rows of the commits whose first parents are not loaded, whose lines are drawn to the bottom
+    open_rows: Vec<usize>,
+    // the rows of the merge commits whose merge parents are not loaded, whose lines are drawn again each time
+    missing_merge_rows: Vec<usize>,
+    // the edges of each row sorted for drawing, with the number of the edges they are made of
+    sorted_edges: Vec<Vec<Edge>>,
+    sorted_edge_counts: Vec<usize>,
+    // the first row the lines to the missing merge parents were drawn in
+    missing_merge_pos_y: usize,
+    // the rows changed by folding the side branches, keyed by the rows they are folded into
+    folded_spans: FxHashMap<CommitHash, FoldedSpan>,
+    // the number of the rows prepended by refreshing, which are laid out in the next graph
+    prepended: usize,
+}
+
+// The rows from the row a side branch is folded into down to the commit the branch forks from.
+#[derive(Debug)]
+struct FoldedSpan {
+    // the rows before folding, which are restored when the branch is unfolded
+    unfolded: SpanRows,
+    // the rows after folding, to check that they are not changed until unfolding
+    folded: SpanRows,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct SpanRows {
+    commit_hashes: Vec<CommitHash>,
+    pos_xs: Vec<usize>,
+    branch_colors: Vec<usize>,
+    edges: Vec<Vec<WrappedEdge>>,
+}
+
+impl IncrementalLayout {
+    fn new(pinned_lane_count: usize) -> Self {
+        Self {
+            pinned_lane_count,
+            commit_line_state: vec![None; pinned_lane_count],
+            ..Default::default()
+        }
+    }
+
+    /// Discards the layout, so that the next graph is calculated from scratch.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    // Returns the positions of the commits laid out before if the layout can be continued.
+    fn continued_positions<'a>(
+        &self,
+        commits: &[&'a Commit],
+        pinned_lanes: &FxHashMap<&CommitHash, usize>,
+        pinned_lane_count: usize,
+        branch_colors: Option<&FxHashMap<&CommitHash, usize>>,
+    ) -> Option<CommitPosMap<'a>> {
+        let rows = self.pos_xs.len();
+        let last_commit = commits.get(rows.checked_sub(1)?)?;
+        if self.commit_hashes.last() != Some(&last_commit.commit_hash)
+            || self.pinned_lane_count != pinned_lane_count
+        {
+            return None;
+        }
+        let laid_out = self.prepended..rows;
+        let commit_pos_map: CommitPosMap = commits[laid_out.clone()]
+            .iter()
+            .zip(&self.pos_xs[laid_out.clone()])
+            .zip(laid_out.clone())
+            .map(|((c, pos_x), pos_y)| (&c.commit_hash, (*pos_x, pos_y)))
+            .collect();
+        // the lanes and the colors of the laid out commits may be changed by the new commits, such as by the branches in them
+        let pinned_changed = pinned_lanes.iter().any(|(hash, lane)| {
+            commit_pos_map
+                .get(*hash)
+                .is_some_and(|(pos_x, _)| pos_x != lane)
+        });
+        let colors_changed = branch_colors.is_some_and(|colors| {
+            commits[laid_out.clone()]
+                .iter()
+                .zip(&self.branch_colors[laid_out.clone()])
+                .any(|(c, color)| colors[&c.commit_hash] != *color)
+        });
+        (!pinned_changed && !colors_changed).then_some(commit_pos_map)
+    }
+
+    fn finish(
+        &mut self,
+        commits: &[&Commit],
+        commit_pos_map: &CommitPosMap,
+        commit_colors: &FxHashMap<&CommitHash, usize>,
+        color_set: &GraphColorSet,
+    ) {
+        for (pos_y, commit) in commits[..self.prepended].iter().enumerate() {
+            self.pos_xs[pos_y] = commit_pos_map[&commit.commit_hash].0;
+            if let Some(color) = self.branch_colors.get_mut(pos_y) {
+                *color = commit_colors[&commit.commit_hash];
+            }
+        }
+        self.prepended = 0;
+
+        let start = self.pos_xs.len();
+        let new_commits = &commits[start..];
+        self.pos_xs
+            .extend(new_commits.iter().map(|c| commit_pos_map[&c.commit_hash].0));
+        if color_set.mode == GraphColorMode::Branch {
+            self.branch_colors
+                .extend(new_commits.iter().map(|c| commit_colors[&c.commit_hash]));
+        }
+        self.commit_hashes
+            .extend(new_commits.iter().map(|c| c.commit_hash.clone()));
+    }
+
+    /// Moves the rows laid out before below the commits prepended by refreshing,
+    /// so that only the new commits and the lines from them are laid out in the next graph.
+    /// Returns `false` if the layout must be calculated again, such as when the commits laid out before are changed.
+    pub fn prepend(&mut self, repository: &Repository) -> bool {
+        let commits = repository.all_commits();
+        let Some(count) = commits.len().checked_sub(self.commit_hashes.len()) else {
+            return false;
+        };
+        // the lines down to the bottom cannot be crossed by the lines from the new commits
+        if self.commit_hashes.is_empty()
+            || !self.open_rows.is_empty()
+            || !self.missing_merge_rows.is_empty()
+            || repository.is_revision_limited()
+            || !commits[count..]
+                .iter()
+                .map(|c| &c.commit_hash)
+                .eq(&self.commit_hashes)
+        {
+            return false;
+        }
+
+        self.prepended = count;
+        self.commit_hashes
+            .splice(0..0, commits[..count].iter().map(|c| c.commit_hash.clone()));
+        // the lanes and the colors of the new rows are set when they are laid out
+        self.pos_xs.splice(0..0, vec![0; count]);
+        if !self.branch_colors.is_empty() {
+            self.branch_colors.splice(0..0, vec![0; count]);
+        }
+        self.edges.splice(0..0, vec![Vec::new(); count]);
+        self.sorted_edges.splice(0..0, vec![Vec::new(); count]);
+        self.sorted_edge_counts
+            .splice(0..0, vec![usize::MAX; count]);
+        for pos_y in self.commit_line_state.iter_mut().flatten() {
+            *pos_y += count;
+        }
+        self.missing_merge_pos_y += count;
+        true
+    }
+
+    // Returns the first row each lane is used in below the prepended rows, where the lines from them must end.
+    fn lane_tops(&self) -> Vec<usize> {
+        let rows = self.pos_xs.len();
+        let mut tops = vec![rows; self.max_pos_x + 1];
+        for pos_y in (self.prepended..rows).rev() {
+            let lanes = self.edges[pos_y]
+                .iter()
+                .filter(|e| e.edge.edge_type != EdgeType::Horizontal)
+                .map(|e| e.edge.pos_x)
+                .chain([self.pos_xs[pos_y]]);
+            for pos_x in lanes {
+                tops[pos_x] = pos_y;
+            }
+        }
+        tops
+    }
+
+    /// Updates the layout for the side branch folded or unfolded since it was calculated,
+    /// so that only the rows from the row it is folded into down to the commit it forks from are changed.
+    /// Returns `false` if the layout must be calculated again, such as when the branch is not drawn in a single lane.
+    pub fn refold(&mut self, repository: &Repository) -> bool {
+        let commits = repository.all_commits();
+        match commits.len().cmp(&self.commit_hashes.len()) {
+            Ordering::Less => self.fold(repository, &commits),
+            Ordering::Greater => self.unfold(&commits),
+            Ordering::Equal => self
+                .commit_hashes
+                .iter()
+                .eq(commits.iter().map(|c| &c.commit_hash)),
+        }
+    }
+
+    fn fold(&mut self, repository: &Repository, commits: &[&Commit]) -> bool {
+        // the commits are the ones laid out except the folded ones
+        let mut removed_rows = Vec::new();
+        let mut remaining = commits.iter().peekable();
+        for (pos_y, hash) in self.commit_hashes.iter().enumerate() {
+            if remaining.next_if(|c| c.commit_hash == *hash).is_none() {
+                removed_rows.push(pos_y);
+            }
+        }
+        let (Some(&first_removed), Some(&last_removed)) =
+            (removed_rows.first(), removed_rows.last())
+        else {
+            return false;
+        };
+        if remaining.next().is_some() {
+            return false;
+        }
+
+        // the branch is folded into the row of its newest commit
+        let first_removed_hash = &self.commit_hashes[first_removed];
+        let Some(row_pos_y) = (0..first_removed).rev().find(|pos_y| {
+            repository
+                .collapsed_commits(&self.commit_hashes[*pos_y])
+                .is_some_and(|members| members.contains(first_removed_hash))
+        }) else {
+            return false;
+        };
+        let row_hash = &self.commit_hashes[row_pos_y];
+        let Some(merge_hash) = repository.folded_merge(row_hash) else {
+            return false;
+        };
+        let members: FxHashSet<&CommitHash> = repository
+            .collapsed_commits(row_hash)
+            .unwrap_or_default()
+            .iter()
+            .collect();
+        if removed_rows
+            .iter()
+            .any(|pos_y| !members.contains(&self.commit_hashes[*pos_y]))
+        {
+            return false;
+        }
+
+        // the lines of the other commits are kept, so the merge commit must have been drawn to the row alone
+        let merged_elsewhere = repository.commit(merge_hash).is_none_or(|c| {
+            c.parent_commit_hashes
+                .iter()
+                .any(|h| h != row_hash && members.contains(h))
+        });
+        let merge_line = line_id(merge_hash, row_hash);
+        if merged_elsewhere
+            || !self.edges[row_pos_y]
+                .iter()
+                .any(|e| e.edge.line_id == merge_line)
+        {
+            return false;
+        }
+        // the lines going down from the folded commits are not drawn again
+        let mut folded_rows: FxHashSet<usize> = removed_rows.iter().copied().collect();
+        folded_rows.insert(row_pos_y);
+        if self
+            .open_rows
+            .iter()
+            .chain(&self.missing_merge_rows)
+            .chain(self.commit_line_state.iter().flatten())
+            .any(|pos_y| folded_rows.contains(pos_y))
+        {
+            return false;
+        }
+
+        let removed_ids: FxHashSet<u64> = removed_rows
+            .iter()
+            .map(|pos_y| commit_id(&self.commit_hashes[*pos_y]))
+            .collect();
+        let row_pos_x = self.pos_xs[row_pos_y];
+        let row_color = self
+            .branch_colors
+            .get(row_pos_y)
+            .copied()
+            .unwrap_or(row_pos_x);
+        let mut end = last_removed + 1;
+        // the line from the branch to the commit it forks from is extended up to the row in the same lane
+        let mut fork_line = None;
+        match repository.parents_hash(row_hash)[..] {
+            [] => {}
+            [parent_hash] => {
+                let Some(parent_pos_y) = self.commit_hashes[end..]
+                    .iter()
+                    .position(|h| h == parent_hash)
+                    .map(|i| end + i)
+                else {
+                    return false;
+                };
+                let parent_id = commit_id(parent_hash);
+                let branch_lines: FxHashMap<u64, usize> = folded_rows
+                    .iter()
+                    .map(|pos_y| (line_id(&self.commit_hashes[*pos_y], parent_hash), *pos_y))
+                    .collect();
+                let lines: FxHashSet<u64> = self.edges[row_pos_y..parent_pos_y]
+                    .iter()
+                    .flatten()
+                    .filter(|e| e.parent_id == parent_id)
+                    .map(|e| e.edge.line_id)
+                    .filter(|line| branch_lines.contains_key(line))
+                    .collect();
+                let [line] = lines.into_iter().collect::<Vec<_>>()[..] else {
+                    return false;
+                };
+                let branch_pos_y = branch_lines[&line];
+                if branch_pos_y != row_pos_y {
+                    let goes_down = self.edges[branch_pos_y].iter().any(|e| {
+                        e.edge.line_id == line
+                            && e.edge.edge_type == EdgeType::Down
+                            && e.edge.pos_x == row_pos_x
+                    });
+                    let vacant = ((row_pos_y + 1)..branch_pos_y)
+                        .filter(|pos_y| !folded_rows.contains(pos_y))
+                        .all(|pos_y| {
+                            self.pos_xs[pos_y] != row_pos_x
+                                && self.edges[pos_y].iter().all(|e| {
+                                    e.edge.pos_x != row_pos_x
+                                        || e.edge.edge_type == EdgeType::Horizontal
+                                        || removed_ids.contains(&e.parent_id)
+                                })
+                        });
+                    if !goes_down || !vacant {
+                        return false;
+                    }
+                    fork_line = Some((
+                        line,
+                        branch_pos_y,
+                        parent_id,
+                        line_id(row_hash, parent_hash),
+                    ));
+                }
+                end = end.max(parent_pos_y + 1);
+            }
+            _ => return false,
+        }
+
+        let unfolded = self.span_rows(row_pos_y..end);
+        let mut folded = SpanRows::default();
+        for pos_y in row_pos_y..end {
+            if removed_ids.contains(&commit_id(&self.commit_hashes[pos_y])) {
+                continue;
+            }
+            folded.commit_hashes.push(self.commit_hashes[pos_y].clone());
+            folded.pos_xs.push(self.pos_xs[pos_y]);
+            if let Some(color) = self.branch_colors.get(pos_y) {
+                folded.branch_colors.push(*color);
+            }
+            let mut edges: Vec<WrappedEdge> = self.edges[pos_y]
+                .iter()
+                .filter(|e| !removed_ids.contains(&e.parent_id))
+                .cloned()
+                .collect();
+            if let Some((line, branch_pos_y, parent_id, new_line)) = fork_line {
+                edges.retain(|e| e.edge.line_id != line || pos_y > branch_pos_y);
+                for e in edges.iter_mut().filter(|e| e.edge.line_id == line) {
+                    e.edge.line_id = new_line;
+                    e.edge.color_index = row_color;
+                    e.edge.dotted = false;
+                }
+                let edge_type = match pos_y {
+                    y if y == row_pos_y => Some(EdgeType::Down),
+                    y if y < branch_pos_y => Some(EdgeType::Vertical),
+                    _ => None,
+                };
+                if let Some(edge_type) = edge_type {
+                    edges.push(WrappedEdge::new(
+                        edge_type, row_pos_x, row_pos_x, parent_id, false, row_color, new_line,
+                    ));
+                }
+            }
+            folded.edges.push(edges);
+        }
+
+        let row_hash = row_hash.clone();
+        if !self.splice(row_pos_y, end - row_pos_y, folded.clone()) {
+            return false;
+        }
+        self.folded_spans
+            .insert(row_hash, FoldedSpan { unfolded, folded });
+        true
+    }
+
+    fn unfold(&mut self, commits: &[&Commit]) -> bool {
+        let rows = self.commit_hashes.len();
+        let same_prefix = self
+            .commit_hashes
+            .iter()
+            .zip(commits)
+            .take_while(|(h, c)| **h == c.commit_hash)
+            .count();
+        let same_suffix = self
+            .commit_hashes
+            .iter()
+            .rev()
+            .zip(commits.iter().rev())
+            .take(rows - same_prefix)
+            .take_while(|(h, c)| **h == c.commit_hash)
+            .count();
+
+        // the rows are restored if they are not changed since folding
+        let restored = self.folded_spans.iter().find_map(|(row_hash, span)| {
+            let start = self.commit_hashes[..same_prefix.min(rows - 1) + 1]
+                .iter()
+                .rposition(|h| h == row_hash)?;
+            let folded_end = start + span.folded.commit_hashes.len();
+            let unfolded_end = start + span.unfolded.commit_hashes.len();
+            let matches = folded_end <= rows
+                && folded_end + same_suffix >= rows
+                && commits.len() - unfolded_end == rows - folded_end
+                && commits[start..unfolded_end]
+                    .iter()
+                    .map(|c| &c.commit_hash)
+                    .eq(&span.unfolded.commit_hashes)
+                && self.span_rows(start..folded_end) == span.folded;
+            matches.then(|| (row_hash.clone(), start))
+        });
+        let Some((row_hash, start)) = restored else {
+            return false;
+        };
+        let span = self.folded_spans.remove(&row_hash).unwrap();
+        self.splice(start, span.folded.commit_hashes.len(), span.unfolded)
+    }
+
+    fn span_rows(&self, rows: Range<usize>) -> SpanRows {
+        SpanRows {
+            commit_hashes: self.commit_hashes[rows.clone()].to_vec(),
+            pos_xs: self.pos_xs[rows.clone()].to_vec(),
+            branch_colors: self
+                .branch_colors
+                .get(rows.clone())
+                .map(<[usize]>::to_vec)
+                .unwrap_or_default(),
+            edges: self.edges[rows].to_vec(),
+        }
+    }
+
+    // Replaces the rows from `start` with the given ones, moving the rows below them.
+    // Returns `false` without changing anything if the lines going down from the replaced commits are lost.
+    fn splice(&mut self, start: usize, len: usize, rows: SpanRows) -> bool {
+        let end = start + len;
+        let new_len = rows.commit_hashes.len();
+        let new_pos_ys: FxHashMap<&CommitHash, usize> = rows
+            .commit_hashes
+            .iter()
+            .enumerate()
+            .map(|(i, hash)| (hash, start + i))
+            .collect();
+        let move_row = |pos_y: usize| match pos_y {
+            y if y < start => Some(y),
+            y if y >= end => Some(y - len + new_len),
+            y => new_pos_ys.get(&self.commit_hashes[y]).copied(),
+        };
+        let commit_line_state: Option<Vec<Option<usize>>> = self
+            .commit_line_state
+            .iter()
+            .map(|line| match line {
+                Some(pos_y) => move_row(*pos_y).map(Some),
+                None => Some(None),
+            })
+            .collect();
+        let open_rows: Option<Vec<usize>> = self.open_rows.iter().map(|y| move_row(*y)).collect();
+        let missing_merge_rows: Option<Vec<usize>> = self
+            .missing_merge_rows
+            .iter()
+            .map(|y| move_row(*y))
+            .collect();
+        let (Some(commit_line_state), Some(open_rows), Some(missing_merge_rows)) =
+            (commit_line_state, open_rows, missing_merge_rows)
+        else {
+            return false;
+        };
+        self.commit_line_state = commit_line_state;
+        self.open_rows = open_rows;
+        self.missing_merge_rows = missing_merge_rows;
+        self.missing_merge_pos_y = match self.missing_merge_pos_y {
+            y if y >= end => y - len + new_len,
+            y => y.min(start),
+        };
+
+        self.commit_hashes.splice(start..end, rows.commit_hashes);
+        self.pos_xs.splice(start..end, rows.pos_xs);
+        if !self.branch_colors.is_empty() {
+            self.branch_colors.splice(start..end, rows.branch_colors);
+        }
+        self.edges.splice(start..end, rows.edges);
+        // the replaced rows are sorted again
+        self.sorted_edges
+            .splice(start..end, vec![Vec::new(); new_len]);
+        self.sorted_edge_counts
+            .splice(start..end, vec![usize::MAX; new_len]);
+        self.max_pos_x = self
+            .pos_xs
+            .iter()
+            .copied()
+            .chain(self.edges.iter().flatten().map(|e| e.edge.pos_x))
+            .max()
+            .unwrap_or_default();
+        true
+    }
+}
+
+// Colors the commits by the branches whose first-parent histories they belong to.
+// The commits shared by some branches are colored by the branch matching the earliest rule,
+// or by the local branch, or by the branch whose name comes first.
+fn calc_branch_colors<'a>(
+    commits: &[&'a Commit],
+    repository: &'a Repository,
+    color_set: &GraphColorSet,
+) -> FxHashMap<&'a CommitHash, usize> {
+    let mut branches: Vec<&Ref> = repository
+        .all_refs()
+        .into_iter()
+        .filter(|r| matches!(r, Ref::Branch { .. } | Ref::RemoteBranch { .. }))
+        .collect();
+    branches.sort_by_key(|r| {
+        (
+            color_set.rule_index(r).unwrap_or(usize::MAX),
+            matches!(r, Ref::RemoteBranch { .. }),
+            r.name(),
+        )
+    });
+
+    // the commits of no branches, such as of the deleted ones, are colored by the newest commit of the history
+    let tips = branches
+        .into_iter()
+        .map(|r| (r.target(), color_set.branch_color_index(r)))
+        .chain(
+            commits
+                .iter()
+                .map(|c| (&c.commit_hash, color_set.hash_color_index(&c.commit_hash))),
+        );
+    let mut commit_colors = FxHashMap::default();
+    for (tip, color) in tips {
+        insert_first_parent_history(&mut commit_colors, repository, tip, color);
+    }
+    commit_colors
+}
+
+// Decides the lanes of the first-parent histories of the pinned branches, and returns them with the number of the lanes.
+// The branches matching the same pattern are placed in the order of the local ones first and then of the names.
+fn calc_pinned_lanes<'a>(
+    repository: &'a Repository,
+    patterns: &[String],
+) -> (FxHashMap<&'a CommitHash, usize>, usize) {
+    let mut branches: Vec<(usize, &Ref)> = repository
+        .all_refs()
+        .into_iter()
+        .filter(|r| matches!(r, Ref::Branch { .. } | Ref::RemoteBranch { .. }))
+        .filter_map(|r| {
+            let i = patterns.iter().position(|p| r.matches_pattern(p))?;
+            Some((i, r))
+        })
+        .collect();
+    branches.sort_by_key(|(i, r)| (*i, matches!(r, Ref::RemoteBranch { .. }), r.name()));
+
+    let mut pinned_lanes = FxHashMap::default();
+    let mut lane_count = 0;
+    for (_, r) in branches {
+        // the branches whose tips are in the history of another one, such as `origin/main`, share its lane
+        if insert_first_parent_history(&mut pinned_lanes, repository, r.target(), lane_count) {
+            lane_count += 1;
+        }
+    }
+    (pinned_lanes, lane_count)
+}
+
+// Inserts the commits of the first-parent history from the tip until the commit already inserted,
+// and returns whether any commits are inserted.
+fn insert_first_parent_history<'a, T: Copy>(
+    map: &mut FxHashMap<&'a CommitHash, T>,
+    repository: &'a Repository,
+    tip: &'a CommitHash,
+    value: T,
+) -> bool {
+    let mut inserted = false;
+    let mut hash = tip;
+    while repository.commit(hash).is_some() && !map.contains_key(hash) {
+        map.insert(hash, value);
+        inserted = true;
+        match repository.parents_hash(hash).first() {
+            Some(parent_hash) => hash = parent_hash,
+            None => break,
+        }
     }
+    inserted
 }
 
+// Places the commits in the lanes, continuing from the commits in `commit_pos_map` placed before.
 fn calc_commit_positions<'a>(
     commits: &[&'a Commit],
+    commit_pos_map: &mut CommitPosMap<'a>,
+    commit_line_state: &mut Vec<Option<usize>>,
     repository: &'a Repository,
-) -> CommitPosMap<'a> {
-    let mut commit_pos_map: CommitPosMap = FxHashMap::default();
-    let mut commit_line_state: Vec<Option<&CommitHash>> = Vec::new();
+    pinned_lanes: &FxHashMap<&CommitHash, usize>,
+    pinned_lane_count: usize,
+    lane_hint: &LaneHint,
+) {
+    let start = commit_pos_map.len();
+    // the lanes of the previous graph are followed only when the commits are laid out from scratch
+    let no_hint = LaneHint::default();
+    let lane_hint = if start == 0 { lane_hint } else { &no_hint };
+    let reserved_lines = calc_reserved_lines(commits, repository, lane_hint);
+    let hinted_lane = |commit: &Commit| {
+        lane_hint
+            .lane(&commit.commit_hash)
+            .filter(|pos_x| *pos_x >= pinned_lane_count)
+    };
 
-    for (pos_y, commit) in commits.iter().enumerate() {
+    for (pos_y, commit) in commits.iter().enumerate().skip(start) {
         let filtered_children_hash = filtered_children_hash(commit, repository);
-        if filtered_children_hash.is_empty() {
-            let pos_x = get_first_vacant_line(&commit_line_state);
-            add_commit_line(commit, &mut commit_line_state, pos_x);
+        let children_pos_y: Vec<usize> = filtered_children_hash
+            .iter()
+            .filter_map(|hash| commit_pos_map.get(*hash).map(|(_, pos_y)| *pos_y))
+            .collect();
+        if let Some(&pos_x) = pinned_lanes.get(&commit.commit_hash) {
+            // the lines of the other children end here, as the commit is always placed in its lane
+            for line in commit_line_state.iter_mut() {
+                if line.is_some_and(|y| children_pos_y.contains(&y)) {
+                    *line = None;
+                }
+            }
+            commit_line_state[pos_x] = Some(pos_y);
+            commit_pos_map.insert(&commit.commit_hash, (pos_x, pos_y));
+        } else if filtered_children_hash.is_empty() {
+            // the other commits are placed to the right of the pinned lanes,
+            // where the lane of the previous graph is followed unless it leaves the lanes to its left vacant
+            let pos_x = match hinted_lane(commit) {
+                Some(pos_x)
+                    if is_vacant_line(commit_line_state, pos_x)
+                        && pos_x <= get_first_vacant_line(commit_line_state, pinned_lane_count) =>
+                {
+                    pos_x
+                }
+                _ if reserved_lines.is_empty() => {
+                    get_first_vacant_line(commit_line_state, pinned_lane_count)
+                }
+                _ => {
+                    // the line down to the parent must not cross the lines kept from the previous graph
+                    let end_pos_y = reserved_lines.parent_pos_y(commit, repository);
+                    (pinned_lane_count..)
+                        .find(|pos_x| {
+                            is_vacant_line(commit_line_state, *pos_x)
+                                && !reserved_lines.overlaps(*pos_x, pos_y..end_pos_y)
+                        })
+                        .unwrap()
+                }
+            };
+            add_commit_line(pos_y, commit_line_state, pos_x);
             commit_pos_map.insert(&commit.commit_hash, (pos_x, pos_y));
         } else {
-            let pos_x = update_commit_line(commit, &mut commit_line_state, &filtered_children_hash);
+            let pos_x = update_commit_line(
+                pos_y,
+                commit_line_state,
+                &children_pos_y,
+                hinted_lane(commit),
+            );
             commit_pos_map.insert(&commit.commit_hash, (pos_x, pos_y));
         }
     }
+}
+
+// Places the commits prepended by refreshing above the commits laid out before, which are kept in their lanes.
+// The line from a commit to its parent laid out before goes down in a lane which is vacant down to the parent.
+// Returns `false` if the lines cannot be drawn so, such as when the lane of a pinned commit is used.
+fn calc_prepended_positions<'a>(
+    commits: &[&'a Commit],
+    commit_pos_map: &mut CommitPosMap<'a>,
+    repository: &'a Repository,
+    pinned_lanes: &FxHashMap<&CommitHash, usize>,
+    pinned_lane_count: usize,
+    lane_tops: &[usize],
+) -> bool {
+    let lane_top = |pos_x: usize| lane_tops.get(pos_x).copied().unwrap_or(usize::MAX);
+    let mut commit_line_state = vec![None; pinned_lane_count];
 
-    commit_pos_map
+    for (pos_y, commit) in commits.iter().enumerate() {
+        let parent_pos = match repository.parents_hash(&commit.commit_hash).first() {
+            Some(parent_hash) => match commit_pos_map.get(*parent_hash) {
+                Some(pos) => Some(*pos),
+                // the parent is one of the prepended commits below
+                None if repository.commit(parent_hash).is_some() => None,
+                None => return false,
+            },
+            None => None,
+        };
+        // the line goes down to the parent in its lane, or turns to it in the row of the parent
+        let fits = |pos_x: usize| match parent_pos {
+            Some((parent_pos_x, parent_pos_y)) => {
+                lane_top(pos_x) > parent_pos_y
+                    || (pos_x == parent_pos_x && lane_top(pos_x) == parent_pos_y)
+            }
+            None => true,
+        };
+
+        // the lines of the children end here
+        let children_pos_y: Vec<usize> = filtered_children_hash(commit, repository)
+            .iter()
+            .filter_map(|hash| commit_pos_map.get(*hash).map(|(_, pos_y)| *pos_y))
+            .collect();
+        let mut children_lanes = Vec::new();
+        for (pos_x, line) in commit_line_state.iter_mut().enumerate() {
+            if line.is_some_and(|y| children_pos_y.contains(&y)) {
+                *line = None;
+                children_lanes.push(pos_x);
+            }
+        }
+
+        let pos_x = match pinned_lanes.get(&commit.commit_hash) {
+            Some(&pos_x) if fits(pos_x) => pos_x,
+            Some(_) => return false,
+            None => children_lanes
+                .into_iter()
+                .find(|pos_x| fits(*pos_x))
+                .unwrap_or_else(|| {
+                    (pinned_lane_count..)
+                        .find(|pos_x| is_vacant_line(&commit_line_state, *pos_x) && fits(*pos_x))
+                        .unwrap()
+                }),
+        };
+        add_commit_line(pos_y, &mut commit_line_state, pos_x);
+        commit_pos_map.insert(&commit.commit_hash, (pos_x, pos_y));
+    }
+    true
+}
+
+// The commits drawn in a lane one after another, from the first commit down to the row where the line of the last one ends.
+struct LaneRun<'a> {
+    pos_x: usize,
+    rows: Range<usize>,
+    commits: Vec<&'a CommitHash>,
+    // the commit where the line of the last commit ends
+    parent: Option<&'a CommitHash>,
+}
+
+// Moves the runs of the commits to the leftmost lanes which are vacant through all their rows.
+// A commit forked into several branches starts a new run, which can take the lane of any of the branches.
+// The lane of a branch merged but not forked from any other commit is reserved from the merge commit,
+// so that the line of the merge goes straight down to the branch instead of taking another lane for a detour.
+fn pack_lanes<'a>(
+    commits: &[&'a Commit],
+    commit_pos_map: &mut CommitPosMap<'a>,
+    repository: &'a Repository,
+    pinned_lane_count: usize,
+) {
+    let mut runs: Vec<LaneRun> = Vec::new();
+    let mut run_indexes: FxHashMap<&CommitHash, usize> = FxHashMap::default();
+    for commit in commits {
+        let hash = &commit.commit_hash;
+        let (pos_x, pos_y) = commit_pos_map[hash];
+        let children_hash = filtered_children_hash(commit, repository);
+        let is_pinned = |pos_x: usize| pos_x < pinned_lane_count;
+        let continued_run = match children_hash[..] {
+            // the commits in the pinned lanes are kept in them
+            _ if is_pinned(pos_x) => children_hash
+                .iter()
+                .find(|child_hash| commit_pos_map[**child_hash].0 == pos_x)
+                .copied(),
+            [child_hash] if !is_pinned(commit_pos_map[child_hash].0) => Some(child_hash),
+            _ => None,
+        };
+        let index = match continued_run {
+            Some(child_hash) => run_indexes[child_hash],
+            None => {
+                let start = match children_hash[..] {
+                    [] => repository
+                        .children_hash(hash)
+                        .into_iter()
+                        .map(|child_hash| commit_pos_map[child_hash].1)
+                        .min()
+                        .unwrap_or(pos_y),
+                    _ => pos_y,
+                };
+                runs.push(LaneRun {
+                    pos_x,
+                    rows: start..pos_y + 1,
+                    commits: Vec::new(),
+                    parent: None,
+                });
+                runs.len() - 1
+            }
+        };
+        // the line goes down to the row of the first parent, or to the bottom if it is not loaded
+        let run = &mut runs[index];
+        run.parent = None;
+        run.rows.end = pos_y + 1;
+        if let Some(parent_hash) = repository.parents_hash(hash).first() {
+            match commit_pos_map.get(*parent_hash) {
+                Some((_, parent_pos_y)) if repository.commit(parent_hash).is_some() => {
+                    run.rows.end = parent_pos_y + 1;
+                    run.parent = Some(parent_hash);
+                }
+                _ => run.rows.end = commits.len(),
+            }
+        }
+        run.commits.push(hash);
+        run_indexes.insert(hash, index);
+    }
+
+    // the runs are assigned in the order of their first rows, so each lane is vacant below the last run in it
+    runs.sort_by_key(|run| (run.rows.start, run.pos_x));
+    let mut lane_ends: Vec<(usize, Option<&CommitHash>)> = Vec::new();
+    for run in &runs {
+        let head = run.commits[0];
+        let pos_x = if run.pos_x < pinned_lane_count {
+            run.pos_x
+        } else {
+            (pinned_lane_count..)
+                .find(|pos_x| match lane_ends.get(*pos_x) {
+                    Some((end, parent)) => *end <= run.rows.start || *parent == Some(head),
+                    None => true,
+                })
+                .unwrap()
+        };
+        if lane_ends.len() <= pos_x {
+            lane_ends.resize(pos_x + 1, (0, None));
+        }
+        if lane_ends[pos_x].0 < run.rows.end {
+            lane_ends[pos_x] = (run.rows.end, run.parent);
+        }
+        for hash in &run.commits {
+            commit_pos_map.get_mut(*hash).unwrap().0 = pos_x;
+        }
+    }
 }
 
 fn filtered_children_hash<'a>(
@@ -99,38 +1068,113 @@ fn filtered_children_hash<'a>(
         .collect()
 }
 
-fn get_first_vacant_line(commit_line_state: &[Option<&CommitHash>]) -> usize {
+fn get_first_vacant_line(commit_line_state: &[Option<usize>], min_pos_x: usize) -> usize {
     commit_line_state
         .iter()
+        .skip(min_pos_x)
         .position(|c| c.is_none())
-        .unwrap_or(commit_line_state.len())
+        .map_or(commit_line_state.len(), |pos_x| pos_x + min_pos_x)
 }
 
-fn add_commit_line<'a>(
-    commit: &'a Commit,
-    commit_line_state: &mut Vec<Option<&'a CommitHash>>,
-    pos_x: usize,
-) {
+fn is_vacant_line(commit_line_state: &[Option<usize>], pos_x: usize) -> bool {
+    commit_line_state.get(pos_x).is_none_or(|c| c.is_none())
+}
+
+fn add_commit_line(pos_y: usize, commit_line_state: &mut Vec<Option<usize>>, pos_x: usize) {
     if commit_line_state.len() <= pos_x {
-        commit_line_state.push(Some(&commit.commit_hash));
-    } else {
-        commit_line_state[pos_x] = Some(&commit.commit_hash);
+        commit_line_state.resize(pos_x + 1, None);
     }
+    commit_line_state[pos_x] = Some(pos_y);
 }
 
-fn update_commit_line<'a>(
-    commit: &'a Commit,
-    commit_line_state: &mut [Option<&'a CommitHash>],
-    target_commit_hashes: &[&CommitHash],
+// The rows where the lines of the commits in the previous graph are drawn in each lane.
+struct ReservedLines<'a> {
+    rows: FxHashMap<usize, Vec<Range<usize>>>,
+    commit_pos_y: FxHashMap<&'a CommitHash, usize>,
+}
+
+impl ReservedLines<'_> {
+    fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    // the row where the line of the commit ends, which is the bottom if the parent is not in the graph
+    fn parent_pos_y(&self, commit: &Commit, repository: &Repository) -> usize {
+        repository
+            .parents_hash(&commit.commit_hash)
+            .first()
+            .and_then(|hash| self.commit_pos_y.get(hash).copied())
+            .unwrap_or(self.commit_pos_y.len())
+    }
+
+    fn overlaps(&self, pos_x: usize, rows: Range<usize>) -> bool {
+        self.rows.get(&pos_x).is_some_and(|reserved| {
+            let i = reserved.partition_point(|r| r.end <= rows.start);
+            reserved.get(i).is_some_and(|r| r.start < rows.end)
+        })
+    }
+}
+
+// Reserves the rows from each commit in the previous graph up to its children drawn in the same lane,
+// so that the new commits do not take the lanes which the commits are going to be placed in.
+fn calc_reserved_lines<'a>(
+    commits: &[&'a Commit],
+    repository: &Repository,
+    lane_hint: &LaneHint,
+) -> ReservedLines<'a> {
+    let mut rows: FxHashMap<usize, Vec<Range<usize>>> = FxHashMap::default();
+    if lane_hint.lanes.is_empty() {
+        let commit_pos_y = FxHashMap::default();
+        return ReservedLines { rows, commit_pos_y };
+    }
+    let commit_pos_y: FxHashMap<&CommitHash, usize> = commits
+        .iter()
+        .enumerate()
+        .map(|(pos_y, c)| (&c.commit_hash, pos_y))
+        .collect();
+    for (pos_y, commit) in commits.iter().enumerate() {
+        let Some(pos_x) = lane_hint.lane(&commit.commit_hash) else {
+            continue;
+        };
+        let start_pos_y = filtered_children_hash(commit, repository)
+            .into_iter()
+            .filter(|hash| lane_hint.lane(hash) == Some(pos_x))
+            .filter_map(|hash| commit_pos_y.get(hash).copied())
+            .min()
+            .unwrap_or(pos_y);
+        rows.entry(pos_x)
+            .or_default()
+            .push(start_pos_y..(pos_y + 1));
+    }
+    // the rows of each lane are merged into the sorted disjoint ranges to be searched
+    for reserved in rows.values_mut() {
+        reserved.sort_by_key(|r| r.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(reserved.len());
+        for r in reserved.drain(..) {
+            match merged.last_mut() {
+                Some(last) if r.start <= last.end => last.end = last.end.max(r.end),
+                _ => merged.push(r),
+            }
+        }
+        *reserved = merged;
+    }
+    ReservedLines { rows, commit_pos_y }
+}
+
+fn update_commit_line(
+    pos_y: usize,
+    commit_line_state: &mut [Option<usize>],
+    target_pos_ys: &[usize],
+    hinted_pos_x: Option<usize>,
 ) -> usize {
     if commit_line_state.is_empty() {
         return 0;
     }
     let mut min_pos_x = commit_line_state.len().saturating_sub(1);
-    for target_hash in target_commit_hashes {
-        for (pos_x, commit_hash) in commit_line_state.iter().enumerate() {
-            if let Some(hash) = commit_hash {
-                if hash == target_hash {
+    for target_pos_y in target_pos_ys {
+        for (pos_x, line_pos_y) in commit_line_state.iter().enumerate() {
+            if let Some(y) = line_pos_y {
+                if y == target_pos_y {
                     commit_line_state[pos_x] = None;
                     if min_pos_x > pos_x {
                         min_pos_x = pos_x;
@@ -140,54 +1184,170 @@ fn update_commit_line<'a>(
             }
         }
     }
-    commit_line_state[min_pos_x] = Some(&commit.commit_hash);
-    min_pos_x
+    // the commit stays in the lane of the previous graph if the lane is vacant after the lines of its children end
+    let pos_x = match hinted_pos_x {
+        Some(pos_x) if pos_x < commit_line_state.len() && commit_line_state[pos_x].is_none() => {
+            pos_x
+        }
+        _ => min_pos_x,
+    };
+    commit_line_state[pos_x] = Some(pos_y);
+    pos_x
 }
 
-#[derive(Debug, Clone)]
-struct WrappedEdge<'a> {
+#[derive(Debug, Clone, PartialEq)]
+struct WrappedEdge {
     edge: Edge,
-    edge_parent_hash: &'a CommitHash,
+    // the parent of the line which the edge is a part of, see `commit_id`
+    parent_id: u64,
 }
 
-impl<'a> WrappedEdge<'a> {
+impl WrappedEdge {
     fn new(
         edge_type: EdgeType,
         pos_x: usize,
         line_pos_x: usize,
-        edge_parent_hash: &'a CommitHash,
+        parent_id: u64,
+        dotted: bool,
+        color_index: usize,
+        line_id: u64,
     ) -> Self {
         Self {
-            edge: Edge::new(edge_type, pos_x, line_pos_x),
-            edge_parent_hash,
+            edge: Edge {
+                dotted,
+                color_index,
+                line_id,
+                ..Edge::new(edge_type, pos_x, line_pos_x)
+            },
+            parent_id,
         }
     }
 }
 
+// Identifies the commit in the edges kept across laying out the appended commits, whether or not it is loaded.
+fn commit_id(commit_hash: &CommitHash) -> u64 {
+    let mut hasher = rustc_hash::FxHasher::default();
+    commit_hash.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Draws the edges of the commits appended since the layout was calculated, and returns the edges of all the rows.
 fn calc_edges(
     commit_pos_map: &CommitPosMap,
+    commit_colors: &FxHashMap<&CommitHash, usize>,
     commits: &[&Commit],
     repository: &Repository,
+    color_set: &GraphColorSet,
+    compact_lanes: bool,
+    incremental: &mut IncrementalLayout,
 ) -> (Vec<Vec<Edge>>, usize) {
-    let mut max_pos_x = 0;
-    let mut edges: Vec<Vec<WrappedEdge>> = vec![vec![]; commits.len()];
+    let IncrementalLayout {
+        edges,
+        max_pos_x,
+        open_rows,
+        missing_merge_rows,
+        sorted_edges,
+        sorted_edge_counts,
+        missing_merge_pos_y,
+        prepended,
+        ..
+    } = incremental;
+    let start = edges.len();
+    edges.resize(commits.len(), Vec::new());
+    let prepended = *prepended;
 
-    for commit in commits {
+    // the commits laid out before are drawn again only for the lines from the prepended commits
+    let mut old_parent_rows: Vec<usize> = commits[..prepended]
+        .iter()
+        .flat_map(|c| repository.parents_hash(&c.commit_hash))
+        .filter_map(|hash| commit_pos_map.get(hash).map(|(_, pos_y)| *pos_y))
+        .filter(|pos_y| (prepended..start).contains(pos_y))
+        .collect();
+    old_parent_rows.sort_unstable();
+    old_parent_rows.dedup();
+    let new_commits: Vec<(&Commit, Vec<&CommitHash>)> = (0..prepended)
+        .chain(old_parent_rows)
+        .chain(start..commits.len())
+        .map(|pos_y| {
+            let commit = commits[pos_y];
+            let mut children_hash = repository.children_hash(&commit.commit_hash);
+            if (prepended..start).contains(&pos_y) {
+                children_hash.retain(|hash| commit_pos_map[hash].1 < prepended);
+            }
+            (commit, children_hash)
+        })
+        .collect();
+
+    // the lines to the first parents which are still not loaded are extended to the new rows
+    open_rows.retain(|&pos_y| {
+        let hash = &commits[pos_y].commit_hash;
+        let parent_hash = repository.parents_hash(hash)[0];
+        if repository.commit(parent_hash).is_some() {
+            return false;
+        }
+        let pos_x = commit_pos_map[hash].0;
+        let color = commit_colors[hash];
+        let parent_id = commit_id(parent_hash);
+        let line = line_id(hash, parent_hash);
+        for es in &mut edges[start..] {
+            es.push(WrappedEdge::new(
+                EdgeType::Vertical,
+                pos_x,
+                pos_x,
+                parent_id,
+                false,
+                color,
+                line,
+            ));
+        }
+        true
+    });
+
+    for (commit, children_hash) in &new_commits {
         let (pos_x, pos_y) = commit_pos_map[&commit.commit_hash];
         let hash = &commit.commit_hash;
+        let color = commit_colors[hash];
+        let parent_id = commit_id(hash);
 
-        for child_hash in repository.children_hash(hash) {
+        for child_hash in children_hash {
             let (child_pos_x, child_pos_y) = commit_pos_map[child_hash];
+            let dotted = repository.has_hidden_commits_between(child_hash, hash);
+            let child_color = commit_colors[child_hash];
+            let line = line_id(child_hash, hash);
 
             if pos_x == child_pos_x {
                 // commit
-                edges[pos_y].push(WrappedEdge::new(EdgeType::Up, pos_x, pos_x, hash));
+                edges[pos_y].push(WrappedEdge::new(
+                    EdgeType::Up,
+                    pos_x,
+                    pos_x,
+                    parent_id,
+                    dotted,
+                    child_color,
+                    line,
+                ));
                 for y in ((child_pos_y + 1)..pos_y).rev() {
-                    edges[y].push(WrappedEdge::new(EdgeType::Vertical, pos_x, pos_x, hash));
+                    edges[y].push(WrappedEdge::new(
+                        EdgeType::Vertical,
+                        pos_x,
+                        pos_x,
+                        parent_id,
+                        dotted,
+                        child_color,
+                        line,
+                    ));
                 }
-                edges[child_pos_y].push(WrappedEdge::new(EdgeType::Down, pos_x, pos_x, hash));
+                edges[child_pos_y].push(WrappedEdge::new(
+                    EdgeType::Down,
+                    pos_x,
+                    pos_x,
+                    parent_id,
+                    dotted,
+                    child_color,
+                    line,
+                ));
             } else {
-                let child_first_parent_hash = &commits[child_pos_y].parent_commit_hashes[0];
+                let child_first_parent_hash = repository.parents_hash(child_hash)[0];
                 if *child_first_parent_hash == *hash {
                     // branch
                     if pos_x < child_pos_x {
@@ -195,42 +1355,60 @@ fn calc_edges(
                             EdgeType::Right,
                             pos_x,
                             child_pos_x,
-                            hash,
+                            parent_id,
+                            dotted,
+                            child_color,
+                            line,
                         ));
                         for x in (pos_x + 1)..child_pos_x {
                             edges[pos_y].push(WrappedEdge::new(
                                 EdgeType::Horizontal,
                                 x,
                                 child_pos_x,
-                                hash,
+                                parent_id,
+                                dotted,
+                                child_color,
+                                line,
                             ));
                         }
                         edges[pos_y].push(WrappedEdge::new(
                             EdgeType::RightBottom,
                             child_pos_x,
                             child_pos_x,
-                            hash,
+                            parent_id,
+                            dotted,
+                            child_color,
+                            line,
                         ));
                     } else {
                         edges[pos_y].push(WrappedEdge::new(
                             EdgeType::Left,
                             pos_x,
                             child_pos_x,
-                            hash,
+                            parent_id,
+                            dotted,
+                            child_color,
+                            line,
                         ));
                         for x in (child_pos_x + 1)..pos_x {
                             edges[pos_y].push(WrappedEdge::new(
                                 EdgeType::Horizontal,
                                 x,
                                 child_pos_x,
-                                hash,
+                                parent_id,
+                                dotted,
+                                child_color,
+                                line,
                             ));
                         }
                         edges[pos_y].push(WrappedEdge::new(
                             EdgeType::LeftBottom,
                             child_pos_x,
                             child_pos_x,
-                            hash,
+                            parent_id,
+                            dotted,
+                            child_color,
+                            line,
                         ));
                     }
                     for y in ((child_pos_y + 1)..pos_y).rev() {
@@ -238,14 +1416,20 @@ fn calc_edges(
                             EdgeType::Vertical,
                             child_pos_x,
                             child_pos_x,
-                            hash,
+                            parent_id,
+                            dotted,
+                            child_color,
+                            line,
                         ));
                     }
                     edges[child_pos_y].push(WrappedEdge::new(
                         EdgeType::Down,
                         child_pos_x,
                         child_pos_x,
-                        hash,
+                        parent_id,
+                        dotted,
+                        child_color,
+                        line,
                     ));
                 } else {
                     // merge
@@ -254,33 +1438,55 @@ fn calc_edges(
             }
         }
 
-        if max_pos_x < pos_x {
-            max_pos_x = pos_x;
+        if *max_pos_x < pos_x {
+            *max_pos_x = pos_x;
         }
 
-        // draw down edge if has parent but parent not in the graph (when max_count is set)
-        if !commit.parent_commit_hashes.is_empty()
-            && repository.commit(&commit.parent_commit_hashes[0]).is_none()
-        {
-            edges[pos_y].push(WrappedEdge::new(EdgeType::Down, pos_x, pos_x, hash));
+        // draw down edge if has parent but parent not in the graph (when max_count is set or still loading)
+        let parent_hashes = repository.parents_hash(hash);
+        if !parent_hashes.is_empty() && repository.commit(parent_hashes[0]).is_none() {
+            let parent_id = commit_id(parent_hashes[0]);
+            let line = line_id(hash, parent_hashes[0]);
+            edges[pos_y].push(WrappedEdge::new(
+                EdgeType::Down,
+                pos_x,
+                pos_x,
+                parent_id,
+                false,
+                color,
+                line,
+            ));
             ((pos_y + 1)..commits.len()).for_each(|y| {
-                edges[y].push(WrappedEdge::new(EdgeType::Vertical, pos_x, pos_x, hash));
+                edges[y].push(WrappedEdge::new(
+                    EdgeType::Vertical,
+                    pos_x,
+                    pos_x,
+                    parent_id,
+                    false,
+                    color,
+                    line,
+                ));
             });
+            open_rows.push(pos_y);
         }
     }
 
-    for commit in commits {
+    for (commit, children_hash) in &new_commits {
         let (pos_x, pos_y) = commit_pos_map[&commit.commit_hash];
         let hash = &commit.commit_hash;
+        let color = commit_colors[hash];
+        let parent_id = commit_id(hash);
 
-        for child_hash in repository.children_hash(hash) {
+        for child_hash in children_hash {
             let (child_pos_x, child_pos_y) = commit_pos_map[child_hash];
+            let dotted = repository.has_hidden_commits_between(child_hash, hash);
+            let line = line_id(child_hash, hash);
 
             if pos_x == child_pos_x {
                 // commit
                 // skip
             } else {
-                let child_first_parent_hash = &commits[child_pos_y].parent_commit_hashes[0];
+                let child_first_parent_hash = repository.parents_hash(child_hash)[0];
                 if *child_first_parent_hash == *hash {
                     // branch
                     // skip
@@ -301,7 +1507,7 @@ fn calc_edges(
                             .iter()
                             .filter(|e| e.edge.pos_x == pos_x)
                             .filter(|e| matches!(e.edge.edge_type, EdgeType::Vertical))
-                            .any(|e| e.edge_parent_hash != hash)
+                            .any(|e| e.parent_id != parent_id)
                         {
                             skip_judge_overlap = false;
                             break;
@@ -320,7 +1526,7 @@ fn calc_edges(
                             }
                             for edge in &edges[y] {
                                 if edge.edge.pos_x >= new_pos_x
-                                    && edge.edge_parent_hash != hash
+                                    && edge.parent_id != parent_id
                                     && matches!(edge.edge.edge_type, EdgeType::Vertical)
                                 {
                                     overlap = true;
@@ -332,101 +1538,172 @@ fn calc_edges(
                         }
                     }
 
+                    if overlap && compact_lanes {
+                        new_pos_x = find_vacant_lane(
+                            edges,
+                            commit_pos_map,
+                            commits,
+                            pos_x.max(child_pos_x) + 1,
+                            &[child_pos_y, pos_y],
+                            (child_pos_y + 1)..pos_y,
+                        );
+                    }
+
                     if overlap {
                         // detour
-                        edges[pos_y].push(WrappedEdge::new(EdgeType::Right, pos_x, pos_x, hash));
+                        edges[pos_y].push(WrappedEdge::new(
+                            EdgeType::Right,
+                            pos_x,
+                            pos_x,
+                            parent_id,
+                            dotted,
+                            color,
+                            line,
+                        ));
                         for x in (pos_x + 1)..new_pos_x {
                             edges[pos_y].push(WrappedEdge::new(
                                 EdgeType::Horizontal,
                                 x,
                                 pos_x,
-                                hash,
+                                parent_id,
+                                dotted,
+                                color,
+                                line,
                             ));
                         }
                         edges[pos_y].push(WrappedEdge::new(
                             EdgeType::RightBottom,
                             new_pos_x,
                             pos_x,
-                            hash,
+                            parent_id,
+                            dotted,
+                            color,
+                            line,
                         ));
                         for y in ((child_pos_y + 1)..pos_y).rev() {
                             edges[y].push(WrappedEdge::new(
                                 EdgeType::Vertical,
                                 new_pos_x,
                                 pos_x,
-                                hash,
+                                parent_id,
+                                dotted,
+                                color,
+                                line,
                             ));
                         }
                         edges[child_pos_y].push(WrappedEdge::new(
                             EdgeType::RightTop,
                             new_pos_x,
                             pos_x,
-                            hash,
+                            parent_id,
+                            dotted,
+                            color,
+                            line,
                         ));
                         for x in (child_pos_x + 1)..new_pos_x {
                             edges[child_pos_y].push(WrappedEdge::new(
                                 EdgeType::Horizontal,
                                 x,
                                 pos_x,
-                                hash,
+                                parent_id,
+                                dotted,
+                                color,
+                                line,
                             ));
                         }
                         edges[child_pos_y].push(WrappedEdge::new(
                             EdgeType::Right,
                             child_pos_x,
                             pos_x,
-                            hash,
+                            parent_id,
+                            dotted,
+                            color,
+                            line,
                         ));
 
-                        if max_pos_x < new_pos_x {
-                            max_pos_x = new_pos_x;
+                        if *max_pos_x < new_pos_x {
+                            *max_pos_x = new_pos_x;
                         }
                     } else {
-                        edges[pos_y].push(WrappedEdge::new(EdgeType::Up, pos_x, pos_x, hash));
+                        edges[pos_y].push(WrappedEdge::new(
+                            EdgeType::Up,
+                            pos_x,
+                            pos_x,
+                            parent_id,
+                            dotted,
+                            color,
+                            line,
+                        ));
                         for y in ((child_pos_y + 1)..pos_y).rev() {
-                            edges[y].push(WrappedEdge::new(EdgeType::Vertical, pos_x, pos_x, hash));
+                            edges[y].push(WrappedEdge::new(
+                                EdgeType::Vertical,
+                                pos_x,
+                                pos_x,
+                                parent_id,
+                                dotted,
+                                color,
+                                line,
+                            ));
                         }
                         if pos_x < child_pos_x {
                             edges[child_pos_y].push(WrappedEdge::new(
                                 EdgeType::LeftTop,
                                 pos_x,
                                 pos_x,
-                                hash,
+                                parent_id,
+                                dotted,
+                                color,
+                                line,
                             ));
                             for x in (pos_x + 1)..child_pos_x {
                                 edges[child_pos_y].push(WrappedEdge::new(
                                     EdgeType::Horizontal,
                                     x,
                                     pos_x,
-                                    hash,
+                                    parent_id,
+                                    dotted,
+                                    color,
+                                    line,
                                 ));
                             }
                             edges[child_pos_y].push(WrappedEdge::new(
                                 EdgeType::Left,
                                 child_pos_x,
                                 pos_x,
-                                hash,
+                                parent_id,
+                                dotted,
+                                color,
+                                line,
                             ));
                         } else {
                             edges[child_pos_y].push(WrappedEdge::new(
                                 EdgeType::RightTop,
                                 pos_x,
                                 pos_x,
-                                hash,
+                                parent_id,
+                                dotted,
+                                color,
+                                line,
                             ));
                             for x in (child_pos_x + 1)..pos_x {
                                 edges[child_pos_y].push(WrappedEdge::new(
                                     EdgeType::Horizontal,
                                     x,
                                     pos_x,
-                                    hash,
+                                    parent_id,
+                                    dotted,
+                                    color,
+                                    line,
                                 ));
                             }
                             edges[child_pos_y].push(WrappedEdge::new(
                                 EdgeType::Right,
                                 child_pos_x,
                                 pos_x,
-                                hash,
+                                parent_id,
+                                dotted,
+                                color,
+                                line,
                             ));
                         }
                     }
@@ -434,20 +1711,221 @@ fn calc_edges(
             }
         }
 
-        if max_pos_x < pos_x {
-            max_pos_x = pos_x;
+        if *max_pos_x < pos_x {
+            *max_pos_x = pos_x;
+        }
+
+        // the merge parents cut off only by `max_count` are not drawn, as the history below them is cut off as well
+        if repository.is_revision_limited()
+            // the index and untracked commits of the stash are never loaded
+            && !is_stash_commit(commit, repository)
+        {
+            missing_merge_rows.push(pos_y);
         }
     }
 
-    let edges: Vec<Vec<Edge>> = edges
-        .into_iter()
-        .map(|es| {
-            let mut es: Vec<Edge> = es.into_iter().map(|e| e.edge).collect();
-            es.sort_by_key(|e| (e.associated_line_pos_x, e.pos_x, e.edge_type));
-            es.dedup();
-            es
+    // the lines to the missing merge parents avoid all the other lines down to the bottom,
+    // so they are drawn again in all the rows below them each time
+    missing_merge_rows.retain(|&pos_y| {
+        let parents_hash = repository.parents_hash(&commits[pos_y].commit_hash);
+        parents_hash
+            .into_iter()
+            .skip(1)
+            .any(|hash| repository.commit(hash).is_none())
+    });
+    let missing_from_pos_y = missing_merge_rows.first().copied().unwrap_or(commits.len());
+    let settled_edge_counts: Vec<usize> =
+        edges[missing_from_pos_y..].iter().map(Vec::len).collect();
+    let mut graph_max_pos_x = *max_pos_x;
+    for &pos_y in missing_merge_rows.iter() {
+        let hash = &commits[pos_y].commit_hash;
+        let pos_x = commit_pos_map[hash].0;
+
+        // draw edge to the bottom if merge parent not in the graph (when revisions are limited)
+        for parent_hash in repository.parents_hash(hash).into_iter().skip(1) {
+            if repository.commit(parent_hash).is_some() {
+                continue;
+            }
+
+            let new_pos_x = if compact_lanes {
+                find_vacant_lane(
+                    edges,
+                    commit_pos_map,
+                    commits,
+                    pos_x + 1,
+                    &[pos_y],
+                    (pos_y + 1)..commits.len(),
+                )
+            } else {
+                get_vacant_pos_x_to_bottom(edges, commit_pos_map, commits, pos_x, pos_y)
+            };
+            let missing_color = color_set.lane_color_index(new_pos_x);
+            let parent_id = commit_id(parent_hash);
+            let line = line_id(hash, parent_hash);
+
+            edges[pos_y].push(WrappedEdge::new(
+                EdgeType::Right,
+                pos_x,
+                new_pos_x,
+                parent_id,
+                false,
+                missing_color,
+                line,
+            ));
+            for x in (pos_x + 1)..new_pos_x {
+                edges[pos_y].push(WrappedEdge::new(
+                    EdgeType::Horizontal,
+                    x,
+                    new_pos_x,
+                    parent_id,
+                    false,
+                    missing_color,
+                    line,
+                ));
+            }
+            edges[pos_y].push(WrappedEdge::new(
+                EdgeType::RightTop,
+                new_pos_x,
+                new_pos_x,
+                parent_id,
+                false,
+                missing_color,
+                line,
+            ));
+            ((pos_y + 1)..commits.len()).for_each(|y| {
+                edges[y].push(WrappedEdge::new(
+                    EdgeType::Vertical,
+                    new_pos_x,
+                    new_pos_x,
+                    parent_id,
+                    false,
+                    missing_color,
+                    line,
+                ));
+            });
+
+            if graph_max_pos_x < new_pos_x {
+                graph_max_pos_x = new_pos_x;
+            }
+        }
+    }
+
+    // only the rows with the new edges are sorted again
+    let redraw_from_pos_y = (*missing_merge_pos_y).min(missing_from_pos_y);
+    sorted_edges.resize(commits.len(), Vec::new());
+    sorted_edge_counts.resize(commits.len(), usize::MAX);
+    for (pos_y, es) in edges.iter().enumerate() {
+        if pos_y < redraw_from_pos_y && sorted_edge_counts[pos_y] == es.len() {
+            continue;
+        }
+        let mut sorted: Vec<Edge> = es.iter().map(|e| e.edge).collect();
+        sorted.sort_by_key(|e| (e.associated_line_pos_x, e.pos_x, e.edge_type));
+        sorted.dedup();
+        sorted_edges[pos_y] = sorted;
+        sorted_edge_counts[pos_y] = es.len();
+    }
+    for (pos_y, count) in (missing_from_pos_y..).zip(settled_edge_counts) {
+        edges[pos_y].truncate(count);
+        sorted_edge_counts[pos_y] = count;
+    }
+    *missing_merge_pos_y = missing_from_pos_y;
+
+    (sorted_edges.clone(), graph_max_pos_x)
+}
+
+fn is_stash_commit(commit: &Commit, repository: &Repository) -> bool {
+    repository
+        .refs(&commit.commit_hash)
+        .iter()
+        .any(|r| matches!(r, Ref::Stash { .. }))
+}
+
+fn get_vacant_pos_x_to_bottom(
+    edges: &[Vec<WrappedEdge>],
+    commit_pos_map: &CommitPosMap,
+    commits: &[&Commit],
+    pos_x: usize,
+    pos_y: usize,
+) -> usize {
+    let mut new_pos_x = pos_x + 1;
+    for y in pos_y..commits.len() {
+        let commit_pos_x = commit_pos_map[&commits[y].commit_hash].0;
+        let edge_max_pos_x = edges[y].iter().map(|e| e.edge.pos_x).max();
+        let row_max_pos_x = edge_max_pos_x.map_or(commit_pos_x, |x| x.max(commit_pos_x));
+        if row_max_pos_x >= new_pos_x {
+            new_pos_x = row_max_pos_x + 1;
+        }
+    }
+    new_pos_x
+}
+
+// Finds the leftmost lane from `min_pos_x` where a line can turn at the corner rows and go through the rows,
+// unlike `get_vacant_pos_x_to_bottom` which finds the lane to the right of all the lines.
+// The line can only cross the horizontal edges, and no commits or other edges can be in the lane at the corners.
+fn find_vacant_lane(
+    edges: &[Vec<WrappedEdge>],
+    commit_pos_map: &CommitPosMap,
+    commits: &[&Commit],
+    min_pos_x: usize,
+    corner_rows: &[usize],
+    rows: Range<usize>,
+) -> usize {
+    let is_vacant = |pos_x: usize, y: usize, crossable: fn(EdgeType) -> bool| {
+        commit_pos_map[&commits[y].commit_hash].0 != pos_x
+            && edges[y]
+                .iter()
+                .all(|e| e.edge.pos_x != pos_x || crossable(e.edge.edge_type))
+    };
+    (min_pos_x..)
+        .find(|pos_x| {
+            corner_rows.iter().all(|y| is_vacant(*pos_x, *y, |_| false))
+                && rows
+                    .clone()
+                    .all(|y| is_vacant(*pos_x, y, |t| t == EdgeType::Horizontal))
+        })
+        .unwrap()
+}
+
+// Moves the commits and the edges beyond `max_lanes` into the overflow column,
+// where the rows with any of them are connected by a dotted line.
+fn elide_overflow_lanes(
+    commit_pos_map: &mut CommitPosMap,
+    edges: &mut [Vec<Edge>],
+    commits: &[&Commit],
+    max_lanes: usize,
+    color_index: usize,
+) {
+    let overflow_rows: Vec<bool> = commits
+        .iter()
+        .zip(edges.iter())
+        .map(|(commit, es)| {
+            commit_pos_map[&commit.commit_hash].0 >= max_lanes
+                || es.iter().any(|e| e.pos_x >= max_lanes)
         })
         .collect();
+    let overflow_edge = |edge_type| Edge {
+        dotted: true,
+        color_index,
+        ..Edge::new(edge_type, max_lanes, max_lanes)
+    };
 
-    (edges, max_pos_x)
+    for (pos_y, commit) in commits.iter().enumerate() {
+        edges[pos_y].retain(|e| e.pos_x < max_lanes);
+        if !overflow_rows[pos_y] {
+            continue;
+        }
+        let (pos_x, _) = commit_pos_map.get_mut(&commit.commit_hash).unwrap();
+        if *pos_x < max_lanes {
+            edges[pos_y].push(overflow_edge(EdgeType::Vertical));
+            continue;
+        }
+        // the commit is drawn in the overflow column, between the lines of the adjacent rows
+        *pos_x = max_lanes;
+        if pos_y > 0 && overflow_rows[pos_y - 1] {
+            edges[pos_y].push(overflow_edge(EdgeType::Up));
+        }
+        if overflow_rows.get(pos_y + 1) == Some(&true) {
+            edges[pos_y].push(overflow_edge(EdgeType::Down));
+        }
+    }
 }
@@ -0,0 +1,148 @@
+use std::ops::Range;
+
+use rustc_hash::FxHashSet;
+
+use crate::{
+    git::{CommitHash, Repository},
+    graph::{line_id, Edge, Graph},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightMode {
+    Ancestors,
+    FirstParent,
+    Descendants,
+}
+
+impl HighlightMode {
+    /// Returns the mode switched to by toggling, where none turns off the highlight.
+    pub fn toggle(mode: Option<Self>) -> Option<Self> {
+        match mode {
+            None => Some(HighlightMode::Ancestors),
+            Some(HighlightMode::Ancestors) => Some(HighlightMode::FirstParent),
+            Some(HighlightMode::FirstParent) => Some(HighlightMode::Descendants),
+            Some(HighlightMode::Descendants) => None,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            HighlightMode::Ancestors => "ancestors",
+            HighlightMode::FirstParent => "first-parent ancestors",
+            HighlightMode::Descendants => "descendants",
+        }
+    }
+}
+
+/// The commits related to the selected commit and the lines between them,
+/// which are drawn as they are while the rest of the graph is dimmed.
+///
+/// The related commits are searched only in the rows passed to `search_rows`,
+/// so that selecting another commit in a long history does not walk all of it.
+#[derive(Debug)]
+pub struct GraphHighlight<'a> {
+    repository: &'a Repository,
+    graph: &'a Graph<'a>,
+    commit_hash: &'a CommitHash,
+    mode: HighlightMode,
+    commits: FxHashSet<&'a CommitHash>,
+    // the ids of the lines, see `line_id`
+    lines: FxHashSet<u64>,
+    // the related commits out of the rows searched, whose relations are followed when the rows reach them
+    pending: Vec<&'a CommitHash>,
+    // the rows searched from the row of the commit, below it for the ancestors and above it for the descendants
+    searched_rows: Range<usize>,
+}
+
+impl<'a> GraphHighlight<'a> {
+    pub fn new(
+        repository: &'a Repository,
+        graph: &'a Graph<'a>,
+        commit_hash: &'a CommitHash,
+        mode: HighlightMode,
+    ) -> Self {
+        let (_, pos_y) = graph.commit_pos_map[commit_hash];
+        GraphHighlight {
+            repository,
+            graph,
+            commit_hash,
+            mode,
+            commits: FxHashSet::default(),
+            lines: FxHashSet::default(),
+            pending: vec![commit_hash],
+            searched_rows: pos_y..pos_y,
+        }
+    }
+
+    /// Extends the rows where the related commits are searched, so that the commits and the lines in them are highlighted.
+    pub fn search_rows(&mut self, rows: Range<usize>) {
+        let (_, pos_y) = self.graph.commit_pos_map[self.commit_hash];
+        let searched_rows = match self.mode {
+            HighlightMode::Descendants => rows.start.min(self.searched_rows.start)..pos_y + 1,
+            HighlightMode::Ancestors | HighlightMode::FirstParent => {
+                pos_y..rows.end.max(self.searched_rows.end)
+            }
+        };
+        if searched_rows == self.searched_rows {
+            return;
+        }
+        self.searched_rows = searched_rows;
+
+        let graph = self.graph;
+        let pos_y = |hash: &CommitHash| graph.commit_pos_map[hash].1;
+        let (mut stack, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|hash| self.searched_rows.contains(&pos_y(hash)));
+        self.pending = pending;
+        while let Some(hash) = stack.pop() {
+            if !self.commits.insert(hash) {
+                continue;
+            }
+            let related = match self.mode {
+                HighlightMode::Ancestors => self.repository.parents_hash(hash),
+                HighlightMode::FirstParent => self
+                    .repository
+                    .parents_hash(hash)
+                    .into_iter()
+                    .take(1)
+                    .collect(),
+                HighlightMode::Descendants => self.repository.children_hash(hash),
+            };
+            for related_hash in related {
+                if self.mode == HighlightMode::Descendants {
+                    self.lines.insert(line_id(related_hash, hash));
+                } else {
+                    self.lines.insert(line_id(hash, related_hash));
+                }
+                // the lines to the parents that are not loaded are highlighted, but the parents cannot be followed
+                if self.repository.commit(related_hash).is_none()
+                    || self.commits.contains(related_hash)
+                {
+                    continue;
+                }
+                if self.searched_rows.contains(&pos_y(related_hash)) {
+                    stack.push(related_hash);
+                } else {
+                    self.pending.push(related_hash);
+                }
+            }
+        }
+    }
+
+    pub fn commit_hash(&self) -> &'a CommitHash {
+        self.commit_hash
+    }
+
+    pub fn mode(&self) -> HighlightMode {
+        self.mode
+    }
+
+    /// Returns whether the commit is related, which is known only in the rows searched.
+    pub fn contains_commit(&self, commit_hash: &CommitHash) -> bool {
+        self.commits.contains(commit_hash)
+    }
+
+    pub fn contains_edge(&self, edge: &Edge) -> bool {
+        self.lines.contains(&edge.line_id)
+    }
+}
@@ -1,7 +1,7 @@
 use std::{
     fmt::{self, Debug, Formatter},
     hash::{Hash, Hasher},
-    io::Cursor,
+    ops::Range,
     process,
     time::{SystemTime, UNIX_EPOCH},
 };
@@ -12,10 +12,11 @@ use crate::{
     color::GraphColorSet,
     git::CommitHash,
     graph::{
+        calc_graph_row_text,
         geometry::{bounding_box_u32, Point},
-        Edge, EdgeType, Graph,
+        Edge, EdgeType, Graph, GraphHighlight, TextParams,
     },
-    protocol::{ImageProtocol, PreparedImage},
+    protocol::{CellSize, ImageEncoder, ImageProtocol, PreparedImage},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -31,20 +32,157 @@ pub enum GraphImageWidthMode {
     Fixed,
 }
 
+/// The lanes drawn in the row images, which are narrowed to the width of the graph column
+/// and moved by scrolling the graph horizontally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaneRange {
+    pub offset: usize,
+    pub count: usize,
+}
+
+impl Default for LaneRange {
+    fn default() -> Self {
+        LaneRange {
+            offset: 0,
+            count: usize::MAX,
+        }
+    }
+}
+
+/// The prepared images of the graph rows, which are kept across rebuilding the graph
+/// so that the images of the unchanged rows are not built and uploaded again.
 #[derive(Debug)]
-pub struct GraphImageManager<'a> {
-    prepared_image_map: FxHashMap<CommitHash, PreparedImage>,
+pub struct GraphImageCache {
+    images: FxHashMap<CommitHash, CachedImages>,
     image_ids: FxHashSet<u32>,
+    session_nonce: u32,
+    // kept as well so that the graph stays scrolled after rebuilding
+    lanes: LaneRange,
+}
+
+// the images of a row drawn as usual and with the highlight, either of which may not be built yet
+#[derive(Debug, Default)]
+struct CachedImages {
+    normal: Option<CachedImage>,
+    highlighted: Option<CachedImage>,
+}
+
+impl CachedImages {
+    fn get(&self, highlighted: bool) -> Option<&CachedImage> {
+        if highlighted {
+            self.highlighted.as_ref()
+        } else {
+            self.normal.as_ref()
+        }
+    }
+
+    fn get_mut(&mut self, highlighted: bool) -> &mut Option<CachedImage> {
+        if highlighted {
+            &mut self.highlighted
+        } else {
+            &mut self.normal
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedImage {
+    source: GraphRowSource,
+    image: PreparedImage,
+    image_id: u32,
+}
+
+/// The parts of a graph row that are not related to the highlighted commit, which are drawn dimmed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RowDimming {
+    pub commit: bool,
+    // in the same order as the edges of the row
+    pub edges: Vec<bool>,
+}
+
+// everything the image of a graph row is built from, except for the settings that never change
+#[derive(Debug, PartialEq, Eq)]
+struct GraphRowSource {
+    cell_width_type: CellWidthType,
+    // none if the commit is out of the drawn lanes
+    pos_x: Option<usize>,
+    color_index: usize,
+    collapsed_merge: bool,
+    cell_count: usize,
+    edges: Vec<Edge>,
+    // none unless the graph is highlighted
+    dimming: Option<RowDimming>,
+}
+
+impl Default for GraphImageCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GraphImageCache {
+    pub fn new() -> Self {
+        GraphImageCache {
+            images: FxHashMap::default(),
+            image_ids: FxHashSet::default(),
+            session_nonce: create_session_nonce(),
+            lanes: LaneRange::default(),
+        }
+    }
+
+    /// Removes the images of the rows that are changed or removed in the graph,
+    /// and returns the sorted image ids of them.
+    pub fn retain_unchanged(
+        &mut self,
+        graph: &Graph<'_>,
+        cell_width_type: CellWidthType,
+        image_width_mode: GraphImageWidthMode,
+    ) -> Vec<u32> {
+        let mut removed_image_ids = Vec::new();
+        self.images.retain(|commit_hash, cached| {
+            // the highlight is computed again for the new graph, so the highlighted images are built again
+            if let Some(highlighted) = cached.highlighted.take() {
+                removed_image_ids.push(highlighted.image_id);
+            }
+            let unchanged = cached.normal.as_ref().is_some_and(|normal| {
+                graph.commit_pos_map.contains_key(commit_hash)
+                    && normal.source
+                        == graph_row_source(
+                            graph,
+                            cell_width_type,
+                            image_width_mode,
+                            self.lanes,
+                            None,
+                            commit_hash,
+                        )
+            });
+            if let (false, Some(normal)) = (unchanged, &cached.normal) {
+                removed_image_ids.push(normal.image_id);
+            }
+            unchanged
+        });
+        for image_id in &removed_image_ids {
+            self.image_ids.remove(image_id);
+        }
+        removed_image_ids.sort_unstable();
+        removed_image_ids
+    }
+}
+
+#[derive(Debug)]
+pub struct GraphImageManager<'a> {
+    cache: GraphImageCache,
     pending_uploads: Vec<String>,
 
     graph: &'a Graph<'a>,
+    highlight: Option<GraphHighlight<'a>>,
     cell_width_type: CellWidthType,
     graph_style: GraphStyle,
     image_width_mode: GraphImageWidthMode,
     image_params: ImageParams,
     drawing_pixels: DrawingPixels,
+    text_params: TextParams,
     image_protocol: ImageProtocol,
-    session_nonce: u32,
 }
 
 impl<'a> GraphImageManager<'a> {
@@ -55,62 +193,148 @@ impl<'a> GraphImageManager<'a> {
         graph_style: GraphStyle,
         image_width_mode: GraphImageWidthMode,
         image_protocol: ImageProtocol,
+        cell_size: Option<CellSize>,
+        cache: GraphImageCache,
     ) -> Self {
-        let image_params = ImageParams::new(graph_color_set, cell_width_type);
+        let image_params = ImageParams::new(graph_color_set, cell_width_type, cell_size);
         let drawing_pixels = DrawingPixels::new(&image_params);
+        let text_params = TextParams::new(graph_color_set);
 
         GraphImageManager {
-            prepared_image_map: FxHashMap::default(),
-            image_ids: FxHashSet::default(),
+            cache,
             pending_uploads: Vec::default(),
             graph,
+            highlight: None,
             cell_width_type,
             graph_style,
             image_width_mode,
             image_params,
             drawing_pixels,
+            text_params,
             image_protocol,
-            session_nonce: create_session_nonce(),
         }
     }
 
+    pub fn into_cache(self) -> GraphImageCache {
+        self.cache
+    }
+
     pub fn prepared_image(&self, commit_hash: &CommitHash) -> &PreparedImage {
-        self.prepared_image_map.get(commit_hash).unwrap()
+        let cached = &self.cache.images[commit_hash];
+        &cached.get(self.highlight.is_some()).unwrap().image
     }
 
     pub fn image_ids(&self) -> &FxHashSet<u32> {
-        &self.image_ids
+        &self.cache.image_ids
     }
 
     pub fn drain_pending_uploads(&mut self) -> Vec<String> {
         std::mem::take(&mut self.pending_uploads)
     }
 
-    pub fn ensure_uploaded(&mut self, commit_hash: &CommitHash) {
-        if self.prepared_image_map.contains_key(commit_hash) {
-            return;
+    pub fn lane_count(&self) -> usize {
+        self.graph.max_pos_x + 1
+    }
+
+    pub fn lane_cell_width(&self) -> usize {
+        match self.cell_width_type {
+            CellWidthType::Double => 2,
+            CellWidthType::Single => 1,
+        }
+    }
+
+    pub fn lanes(&self) -> LaneRange {
+        self.cache.lanes
+    }
+
+    /// Changes the lanes to draw. The images already uploaded are built again when they are shown.
+    pub fn set_lanes(&mut self, lanes: LaneRange) {
+        self.cache.lanes = lanes;
+    }
+
+    pub fn graph(&self) -> &'a Graph<'a> {
+        self.graph
+    }
+
+    pub fn highlight(&self) -> Option<&GraphHighlight<'a>> {
+        self.highlight.as_ref()
+    }
+
+    /// Searches the commits related to the highlighted one in the rows, which are to be shown.
+    pub fn search_highlight(&mut self, rows: Range<usize>) {
+        if let Some(highlight) = &mut self.highlight {
+            highlight.search_rows(rows);
         }
-        let image_id = graph_image_id(self.session_nonce, commit_hash);
-        let graph_row_image = build_single_graph_row_image(
+    }
+
+    /// Changes the highlight of the graph. The images are drawn with it when they are shown,
+    /// and the images drawn as usual are kept to be shown again after the highlight is turned off.
+    pub fn set_highlight(&mut self, highlight: Option<GraphHighlight<'a>>) {
+        self.highlight = highlight;
+    }
+
+    pub fn ensure_uploaded(&mut self, commit_hash: &CommitHash) {
+        let source = graph_row_source(
             self.graph,
-            &self.image_params,
-            &self.drawing_pixels,
-            self.graph_style,
+            self.cell_width_type,
             self.image_width_mode,
+            self.cache.lanes,
+            self.highlight.as_ref(),
             commit_hash,
         );
-        let mut image =
-            graph_row_image.prepare(self.cell_width_type, self.image_protocol, image_id);
+        let highlighted = source.dimming.is_some();
+        let cached_images = self.cache.images.get(commit_hash);
+        if let Some(cached) = cached_images.and_then(|images| images.get(highlighted)) {
+            if cached.source == source {
+                return;
+            }
+        }
+        // the image is replaced with the same id when the lanes or the highlight are changed
+        let image_id = graph_image_id(self.cache.session_nonce, commit_hash, highlighted);
+        let mut image = match self.image_protocol.image_encoder() {
+            None => calc_graph_row_text(
+                source.pos_x,
+                source.color_index,
+                source.collapsed_merge,
+                source.cell_count,
+                &source.edges,
+                source.dimming.as_ref(),
+                &self.text_params,
+                self.cell_width_type,
+                self.graph_style,
+            ),
+            Some(image_encoder) => {
+                let graph_row_image = calc_graph_row_image(
+                    source.pos_x,
+                    source.color_index,
+                    source.collapsed_merge,
+                    source.cell_count,
+                    &source.edges,
+                    source.dimming.as_ref(),
+                    &self.image_params,
+                    &self.drawing_pixels,
+                    self.graph_style,
+                );
+                graph_row_image.prepare(self.cell_width_type, image_encoder, image_id)
+            }
+        };
         if let Some(upload_data) = image.take_upload_data() {
             self.pending_uploads.push(upload_data);
         }
-        self.prepared_image_map.insert(commit_hash.clone(), image);
-        self.image_ids.insert(image_id);
+        let cached = CachedImage {
+            source,
+            image,
+            image_id,
+        };
+        let cached_images = self.cache.images.entry(commit_hash.clone()).or_default();
+        *cached_images.get_mut(highlighted) = Some(cached);
+        self.cache.image_ids.insert(image_id);
     }
 }
 
 pub struct GraphRowImage {
-    pub bytes: Vec<u8>,
+    // the pixels drawn, which are encoded for each image protocol
+    pub buffer: image::RgbaImage,
     pub cell_count: usize,
 }
 
@@ -118,8 +342,9 @@ impl Debug for GraphRowImage {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "GraphRowImage {{ bytes: [{} bytes], cell_count: {} }}",
-            self.bytes.len(),
+            "GraphRowImage {{ buffer: [{}x{} pixels], cell_count: {} }}",
+            self.buffer.width(),
+            self.buffer.height(),
             self.cell_count
         )
     }
@@ -129,14 +354,14 @@ impl GraphRowImage {
     fn prepare(
         &self,
         cell_width_type: CellWidthType,
-        image_protocol: ImageProtocol,
+        image_encoder: ImageEncoder,
         image_id: u32,
     ) -> PreparedImage {
         let image_cell_width = match cell_width_type {
             CellWidthType::Double => self.cell_count * 2,
             CellWidthType::Single => self.cell_count,
         };
-        image_protocol.prepare_image(&self.bytes, image_cell_width, image_id)
+        image_encoder.prepare_image(&self.buffer, image_cell_width, image_id)
     }
 }
 
@@ -151,10 +376,11 @@ fn create_session_nonce() -> u32 {
     hasher.finish() as u32
 }
 
-fn graph_image_id(session_nonce: u32, commit_hash: &CommitHash) -> u32 {
+fn graph_image_id(session_nonce: u32, commit_hash: &CommitHash, highlighted: bool) -> u32 {
     let mut hasher = rustc_hash::FxHasher::default();
     session_nonce.hash(&mut hasher);
     commit_hash.hash(&mut hasher);
+    highlighted.hash(&mut hasher);
     hasher.finish() as u32
 }
 
@@ -177,12 +403,45 @@ pub enum CellWidthType {
 }
 
 impl ImageParams {
-    pub fn new(graph_color_set: &GraphColorSet, cell_width_type: CellWidthType) -> Self {
-        let (width, height, line_width, circle_inner_radius, circle_outer_radius) =
-            match cell_width_type {
-                CellWidthType::Double => (50, 50, 5, 10, 13),
-                CellWidthType::Single => (25, 50, 3, 7, 10),
-            };
+    /// Creates the params to draw the images in the pixel size of the cells, so that they are not scaled by the terminal.
+    /// The images are drawn in the default size if the cell size is unknown or too small.
+    pub fn new(
+        graph_color_set: &GraphColorSet,
+        cell_width_type: CellWidthType,
+        cell_size: Option<CellSize>,
+    ) -> Self {
+        const MIN_CELL_WIDTH: u16 = 5;
+        const MIN_CELL_HEIGHT: u16 = 10;
+
+        let (cells, default_sizes) = match cell_width_type {
+            CellWidthType::Double => (2, (50, 50, 5, 10, 13)),
+            CellWidthType::Single => (1, (25, 50, 3, 7, 10)),
+        };
+        let (width, height, line_width, circle_inner_radius, circle_outer_radius) = match cell_size
+        {
+            Some(cell_size)
+                if cell_size.width >= MIN_CELL_WIDTH && cell_size.height >= MIN_CELL_HEIGHT =>
+            {
+                let (default_width, default_height, line_width, inner_radius, outer_radius) =
+                    default_sizes;
+                let width = cell_size.width * cells;
+                let height = cell_size.height;
+                // the lines and the circles keep the proportion to the default size
+                let ratio = (width as f32 / default_width as f32)
+                    .min(height as f32 / default_height as f32);
+                let scale = |size: u16| ((size as f32 * ratio).round() as u16).max(1);
+                let circle_inner_radius = scale(inner_radius);
+                let circle_outer_radius = scale(outer_radius).max(circle_inner_radius + 1);
+                (
+                    width,
+                    height,
+                    scale(line_width),
+                    circle_inner_radius,
+                    circle_outer_radius,
+                )
+            }
+            _ => default_sizes,
+        };
         let edge_colors = graph_color_set
             .colors
             .iter()
@@ -206,6 +465,21 @@ impl ImageParams {
         self.edge_colors[index % self.edge_colors.len()]
     }
 
+    // the dimmed colors are blended with the background, or made translucent if the background is transparent
+    fn dimmed_color(&self, color: image::Rgba<u8>, dimmed: bool) -> image::Rgba<u8> {
+        const RATIO: f32 = 0.3;
+        if !dimmed {
+            return color;
+        }
+        let [r, g, b, a] = color.0;
+        let bg = self.background_color;
+        if bg[3] == 0 {
+            return image::Rgba([r, g, b, (a as f32 * RATIO) as u8]);
+        }
+        let blend = |c: u8, bg: u8| (c as f32 * RATIO + bg as f32 * (1.0 - RATIO)) as u8;
+        image::Rgba([blend(r, bg[0]), blend(g, bg[1]), blend(b, bg[2]), a])
+    }
+
     fn corner_radius(&self) -> u16 {
         if self.width < self.height {
             self.width / 2
@@ -213,34 +487,61 @@ impl ImageParams {
             self.height / 2
         }
     }
+
+    // dotted edges are drawn as dashes whose length divides the cell height evenly,
+    // so that the dashes continue across the rows
+    fn is_dot_gap(&self, pos: u32) -> bool {
+        (pos / (self.height as u32 / 10)) % 2 == 1
+    }
 }
 
-fn build_single_graph_row_image(
+fn graph_row_source(
     graph: &Graph<'_>,
-    image_params: &ImageParams,
-    drawing_pixels: &DrawingPixels,
-    graph_style: GraphStyle,
+    cell_width_type: CellWidthType,
     image_width_mode: GraphImageWidthMode,
+    lanes: LaneRange,
+    highlight: Option<&GraphHighlight>,
     commit_hash: &CommitHash,
-) -> GraphRowImage {
+) -> GraphRowSource {
     let (pos_x, pos_y) = graph.commit_pos_map[&commit_hash];
-    let edges = &graph.edges[pos_y];
+    let lanes_end = lanes
+        .offset
+        .saturating_add(lanes.count)
+        .min(graph.max_pos_x + 1);
+    let in_lanes = |x: usize| (lanes.offset..lanes_end).contains(&x);
+
+    let pos_x = in_lanes(pos_x).then(|| pos_x - lanes.offset);
+    let edges: Vec<Edge> = graph.edges[pos_y]
+        .iter()
+        .filter(|e| in_lanes(e.pos_x))
+        .map(|e| Edge {
+            pos_x: e.pos_x - lanes.offset,
+            ..*e
+        })
+        .collect();
 
     let max_pos_x = match image_width_mode {
-        GraphImageWidthMode::Compact => edges.iter().map(|e| e.pos_x).fold(pos_x, usize::max),
-        GraphImageWidthMode::Fixed => graph.max_pos_x,
+        GraphImageWidthMode::Compact => edges
+            .iter()
+            .map(|e| e.pos_x)
+            .fold(pos_x.unwrap_or_default(), usize::max),
+        GraphImageWidthMode::Fixed => lanes_end.saturating_sub(lanes.offset + 1),
     };
 
-    let cell_count = max_pos_x + 1;
+    let dimming = highlight.map(|highlight| RowDimming {
+        commit: !highlight.contains_commit(commit_hash),
+        edges: edges.iter().map(|e| !highlight.contains_edge(e)).collect(),
+    });
 
-    calc_graph_row_image(
+    GraphRowSource {
+        cell_width_type,
         pos_x,
-        cell_count,
+        color_index: graph.commit_colors[commit_hash],
+        collapsed_merge: graph.collapsed_merges.contains(commit_hash),
+        cell_count: max_pos_x + 1,
         edges,
-        image_params,
-        drawing_pixels,
-        graph_style,
-    )
+        dimming,
+    }
 }
 
 type Pixels = FxHashSet<(i32, i32)>;
@@ -248,6 +549,7 @@ type Pixels = FxHashSet<(i32, i32)>;
 #[derive(Debug)]
 pub struct DrawingPixels {
     circle: Pixels,
+    circle_hole: Pixels,
     circle_edge: Pixels,
     vertical_edge: Pixels,
     horizontal_edge: Pixels,
@@ -264,6 +566,7 @@ pub struct DrawingPixels {
 impl DrawingPixels {
     pub fn new(image_params: &ImageParams) -> Self {
         let circle = calc_commit_circle_drawing_pixels(image_params);
+        let circle_hole = calc_circle_hole_drawing_pixels(image_params);
         let circle_edge = calc_circle_edge_drawing_pixels(image_params);
         let vertical_edge = calc_vertical_edge_drawing_pixels(image_params);
         let horizontal_edge = calc_horizontal_edge_drawing_pixels(image_params);
@@ -278,6 +581,7 @@ impl DrawingPixels {
 
         Self {
             circle,
+            circle_hole,
             circle_edge,
             vertical_edge,
             horizontal_edge,
@@ -297,6 +601,10 @@ fn calc_commit_circle_drawing_pixels(image_params: &ImageParams) -> Pixels {
     calc_circle_drawing_pixels(image_params, image_params.circle_inner_radius as i32)
 }
 
+fn calc_circle_hole_drawing_pixels(image_params: &ImageParams) -> Pixels {
+    calc_circle_drawing_pixels(image_params, image_params.circle_inner_radius as i32 / 2)
+}
+
 fn calc_circle_edge_drawing_pixels(image_params: &ImageParams) -> Pixels {
     let inner = calc_circle_drawing_pixels(image_params, image_params.circle_inner_radius as i32);
     let outer = calc_circle_drawing_pixels(image_params, image_params.circle_outer_radius as i32);
@@ -600,9 +908,12 @@ fn calc_corner_edge_drawing_pixels(
 }
 
 pub fn calc_graph_row_image(
-    commit_pos_x: usize,
+    commit_pos_x: Option<usize>,
+    commit_color_index: usize,
+    collapsed_merge: bool,
     cell_count: usize,
     edges: &[Edge],
+    dimming: Option<&RowDimming>,
     image_params: &ImageParams,
     drawing_pixels: &DrawingPixels,
     graph_style: GraphStyle,
@@ -613,37 +924,59 @@ pub fn calc_graph_row_image(
     let mut img_buf = image::ImageBuffer::new(image_width, image_height);
 
     draw_background(&mut img_buf, image_params);
-    draw_commit_circle(&mut img_buf, commit_pos_x, image_params, drawing_pixels);
+    if let Some(commit_pos_x) = commit_pos_x {
+        draw_commit_circle(
+            &mut img_buf,
+            commit_pos_x,
+            commit_color_index,
+            collapsed_merge,
+            dimming.is_some_and(|d| d.commit),
+            image_params,
+            drawing_pixels,
+        );
+    }
+
+    let mut edges: Vec<(&Edge, bool)> = edges
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e, dimming.is_some_and(|d| d.edges[i])))
+        .collect();
+    // the dimmed edges are drawn first, so that they do not cover the highlighted edges where they cross
+    edges.sort_by_key(|(_, dimmed)| !dimmed);
 
     match graph_style {
         GraphStyle::Rounded => {
-            for edge in edges {
-                draw_edge(&mut img_buf, edge, image_params, drawing_pixels)
+            for (edge, dimmed) in edges {
+                draw_edge(&mut img_buf, edge, dimmed, image_params, drawing_pixels)
             }
         }
         GraphStyle::Angular => {
-            let (vertial_edges, horizontal_edges): (Vec<&Edge>, Vec<&Edge>) = edges
-                .iter()
-                .partition(|e| e.edge_type.is_vertically_related());
-            for edge in vertial_edges {
-                draw_edge(&mut img_buf, edge, image_params, drawing_pixels)
+            let (vertial_edges, horizontal_edges): (Vec<_>, Vec<_>) = edges
+                .into_iter()
+                .partition(|(e, _)| e.edge_type.is_vertically_related());
+            for (edge, dimmed) in vertial_edges {
+                draw_edge(&mut img_buf, edge, dimmed, image_params, drawing_pixels)
             }
-            let mut horizontal_edges_map: FxHashMap<usize, Vec<&Edge>> = FxHashMap::default();
-            for edge in horizontal_edges {
+            let mut horizontal_edges_map: FxHashMap<usize, Vec<(&Edge, bool)>> =
+                FxHashMap::default();
+            for (edge, dimmed) in horizontal_edges {
                 horizontal_edges_map
                     .entry(edge.associated_line_pos_x)
                     .or_default()
-                    .push(edge);
+                    .push((edge, dimmed));
             }
-            for edges in horizontal_edges_map.values() {
-                draw_diagonal_connected_edge(&mut img_buf, edges, image_params);
+            let mut horizontal_edges: Vec<_> = horizontal_edges_map.into_values().collect();
+            horizontal_edges.sort_by_key(|edges| !edges.iter().any(|(_, dimmed)| *dimmed));
+            for edges in horizontal_edges {
+                draw_diagonal_connected_edge(&mut img_buf, &edges, image_params);
             }
         }
     }
 
-    let bytes = build_image(&img_buf, image_width, image_height);
-
-    GraphRowImage { bytes, cell_count }
+    GraphRowImage {
+        buffer: img_buf,
+        cell_count,
+    }
 }
 
 fn draw_background(
@@ -662,11 +995,14 @@ fn draw_background(
 fn draw_commit_circle(
     img_buf: &mut image::ImageBuffer<image::Rgba<u8>, Vec<u8>>,
     circle_pos_x: usize,
+    color_index: usize,
+    collapsed_merge: bool,
+    dimmed: bool,
     image_params: &ImageParams,
     drawing_pixels: &DrawingPixels,
 ) {
     let x_offset = (circle_pos_x * image_params.width as usize) as i32;
-    let color = image_params.edge_color(circle_pos_x);
+    let color = image_params.dimmed_color(image_params.edge_color(color_index), dimmed);
 
     for (x, y) in &drawing_pixels.circle {
         let x = (*x + x_offset) as u32;
@@ -676,6 +1012,17 @@ fn draw_commit_circle(
         *pixel = color;
     }
 
+    if collapsed_merge {
+        // the collapsed merges are drawn as rings to tell that they have more parents
+        for (x, y) in &drawing_pixels.circle_hole {
+            let x = (*x + x_offset) as u32;
+            let y = *y as u32;
+
+            let pixel = img_buf.get_pixel_mut(x, y);
+            *pixel = image_params.background_color;
+        }
+    }
+
     if image_params.circle_edge_color[3] == 0 {
         // If the alpha value is 0, the circle edge is transparent, so we don't need to draw it.
         return;
@@ -686,13 +1033,14 @@ fn draw_commit_circle(
         let y = *y as u32;
 
         let pixel = img_buf.get_pixel_mut(x, y);
-        *pixel = image_params.circle_edge_color;
+        *pixel = image_params.dimmed_color(image_params.circle_edge_color, dimmed);
     }
 }
 
 fn draw_edge(
     img_buf: &mut image::ImageBuffer<image::Rgba<u8>, Vec<u8>>,
     edge: &Edge,
+    dimmed: bool,
     image_params: &ImageParams,
     drawing_pixels: &DrawingPixels,
 ) {
@@ -710,12 +1058,23 @@ fn draw_edge(
     };
 
     let x_offset = (edge.pos_x * image_params.width as usize) as i32;
-    let color = image_params.edge_color(edge.associated_line_pos_x);
+    let color = image_params.dimmed_color(image_params.edge_color(edge.color_index), dimmed);
 
     for (x, y) in pixels {
         let x = (*x + x_offset) as u32;
         let y = *y as u32;
 
+        if edge.dotted {
+            let pos = match edge.edge_type {
+                EdgeType::Vertical | EdgeType::Up | EdgeType::Down => y,
+                EdgeType::Horizontal | EdgeType::Left | EdgeType::Right => x,
+                _ => x + y,
+            };
+            if image_params.is_dot_gap(pos) {
+                continue;
+            }
+        }
+
         let pixel = img_buf.get_pixel_mut(x, y);
         *pixel = color;
     }
@@ -724,10 +1083,10 @@ fn draw_edge(
 // fixme: cache edge drawing range calculations
 fn draw_diagonal_connected_edge(
     img_buf: &mut image::ImageBuffer<image::Rgba<u8>, Vec<u8>>,
-    edges: &[&Edge],
+    edges: &[(&Edge, bool)],
     image_params: &ImageParams,
 ) {
-    let corner_edges = edges.iter().filter(|e| {
+    let corner_edges = edges.iter().map(|(e, _)| e).filter(|e| {
         matches!(
             e.edge_type,
             EdgeType::RightBottom | EdgeType::LeftBottom | EdgeType::RightTop | EdgeType::LeftTop
@@ -742,9 +1101,12 @@ fn draw_diagonal_connected_edge(
         };
         let side_edge_opt = edges
             .iter()
-            .find(|e| e.edge_type == expected_side_edge_type);
+            .find(|(e, _)| e.edge_type == expected_side_edge_type);
         // No side edge found, nothing to draw (should not happen)
-        if let Some(side_edge) = side_edge_opt {
+        if let Some((side_edge, dimmed)) = side_edge_opt {
+            let dotted = side_edge.dotted || corner_edge.dotted;
+            let color =
+                image_params.dimmed_color(image_params.edge_color(side_edge.color_index), *dimmed);
             let line_width_f64 = image_params.line_width as f64;
             let line_width_i32 = image_params.line_width as i32;
 
@@ -802,10 +1164,10 @@ fn draw_diagonal_connected_edge(
                             if x < img_buf.width() && y < img_buf.height() {
                                 let p = Point::new(x as f64 + 0.5, y as f64 + 0.5);
 
-                                if p.is_inside_polygon(&vertices) {
+                                if p.is_inside_polygon(&vertices)
+                                    && !(dotted && image_params.is_dot_gap(x))
+                                {
                                     let pixel = img_buf.get_pixel_mut(x, y);
-                                    let color =
-                                        image_params.edge_color(side_edge.associated_line_pos_x);
                                     *pixel = color;
                                 }
                             }
@@ -818,10 +1180,11 @@ fn draw_diagonal_connected_edge(
                     for y in 0..y_end {
                         for i in 0..line_width_i32 {
                             let x = (x_start + i) as u32;
-                            if x < img_buf.width() && y < img_buf.height() {
+                            if x < img_buf.width()
+                                && y < img_buf.height()
+                                && !(dotted && image_params.is_dot_gap(y))
+                            {
                                 let pixel = img_buf.get_pixel_mut(x, y);
-                                let color =
-                                    image_params.edge_color(side_edge.associated_line_pos_x);
                                 *pixel = color;
                             }
                         }
@@ -871,10 +1234,10 @@ fn draw_diagonal_connected_edge(
                             if x < img_buf.width() && y < img_buf.height() {
                                 let p = Point::new(x as f64 + 0.5, y as f64 + 0.5);
 
-                                if p.is_inside_polygon(&vertices) {
+                                if p.is_inside_polygon(&vertices)
+                                    && !(dotted && image_params.is_dot_gap(x))
+                                {
                                     let pixel = img_buf.get_pixel_mut(x, y);
-                                    let color =
-                                        image_params.edge_color(side_edge.associated_line_pos_x);
                                     *pixel = color;
                                 }
                             }
@@ -887,10 +1250,11 @@ fn draw_diagonal_connected_edge(
                     for y in (y_start + 1)..image_params.height as u32 {
                         for i in 0..line_width_i32 {
                             let x = (x_start + i) as u32;
-                            if x < img_buf.width() && y < img_buf.height() {
+                            if x < img_buf.width()
+                                && y < img_buf.height()
+                                && !(dotted && image_params.is_dot_gap(y))
+                            {
                                 let pixel = img_buf.get_pixel_mut(x, y);
-                                let color =
-                                    image_params.edge_color(side_edge.associated_line_pos_x);
                                 *pixel = color;
                             }
                         }
@@ -902,20 +1266,6 @@ fn draw_diagonal_connected_edge(
     }
 }
 
-fn build_image(img_buf: &[u8], image_width: u32, image_height: u32) -> Vec<u8> {
-    let mut bytes = Cursor::new(Vec::new());
-    image::write_buffer_with_format(
-        &mut bytes,
-        img_buf,
-        image_width,
-        image_height,
-        image::ColorType::Rgba8,
-        image::ImageFormat::Png,
-    )
-    .unwrap();
-    bytes.into_inner()
-}
-
 #[cfg(test)]
 mod tests {
     use std::path::Path;
@@ -946,7 +1296,7 @@ mod tests {
         let graph_color_config = GraphColorConfig::default();
         let graph_color_set = GraphColorSet::new(&graph_color_config);
         let cell_width_type = CellWidthType::Double;
-        let image_params = ImageParams::new(&graph_color_set, cell_width_type);
+        let image_params = ImageParams::new(&graph_color_set, cell_width_type, None);
         let drawing_pixels = DrawingPixels::new(&image_params);
 
         test_calc_graph_row_image(
@@ -971,7 +1321,7 @@ mod tests {
         let graph_color_config = GraphColorConfig::default();
         let graph_color_set = GraphColorSet::new(&graph_color_config);
         let cell_width_type = CellWidthType::Double;
-        let mut image_params = ImageParams::new(&graph_color_set, cell_width_type);
+        let mut image_params = ImageParams::new(&graph_color_set, cell_width_type, None);
         image_params.width = 100;
         let drawing_pixels = DrawingPixels::new(&image_params);
 
@@ -997,7 +1347,7 @@ mod tests {
         let graph_color_config = GraphColorConfig::default();
         let graph_color_set = GraphColorSet::new(&graph_color_config);
         let cell_width_type = CellWidthType::Double;
-        let mut image_params = ImageParams::new(&graph_color_set, cell_width_type);
+        let mut image_params = ImageParams::new(&graph_color_set, cell_width_type, None);
         image_params.height = 100;
         let drawing_pixels = DrawingPixels::new(&image_params);
 
@@ -1023,7 +1373,7 @@ mod tests {
         let graph_color_config = GraphColorConfig::default();
         let graph_color_set = GraphColorSet::new(&graph_color_config);
         let cell_width_type = CellWidthType::Single;
-        let image_params = ImageParams::new(&graph_color_set, cell_width_type);
+        let image_params = ImageParams::new(&graph_color_set, cell_width_type, None);
         let drawing_pixels = DrawingPixels::new(&image_params);
 
         test_calc_graph_row_image(
@@ -1048,7 +1398,7 @@ mod tests {
         let graph_color_config = GraphColorConfig::default();
         let graph_color_set = GraphColorSet::new(&graph_color_config);
         let cell_width_type = CellWidthType::Double;
-        let mut image_params = ImageParams::new(&graph_color_set, cell_width_type);
+        let mut image_params = ImageParams::new(&graph_color_set, cell_width_type, None);
         image_params.circle_inner_radius = 5;
         image_params.circle_outer_radius = 12;
         let drawing_pixels = DrawingPixels::new(&image_params);
@@ -1075,7 +1425,7 @@ mod tests {
         let graph_color_config = GraphColorConfig::default();
         let graph_color_set = GraphColorSet::new(&graph_color_config);
         let cell_width_type = CellWidthType::Double;
-        let mut image_params = ImageParams::new(&graph_color_set, cell_width_type);
+        let mut image_params = ImageParams::new(&graph_color_set, cell_width_type, None);
         image_params.line_width = 1;
         let drawing_pixels = DrawingPixels::new(&image_params);
 
@@ -1104,10 +1454,68 @@ mod tests {
             ],
             edge: "#ffffff".into(),
             background: "#00ff0070".into(),
+            ..Default::default()
         };
         let graph_color_set = GraphColorSet::new(&graph_color_config);
         let cell_width_type = CellWidthType::Double;
-        let image_params = ImageParams::new(&graph_color_set, cell_width_type);
+        let image_params = ImageParams::new(&graph_color_set, cell_width_type, None);
+        let drawing_pixels = DrawingPixels::new(&image_params);
+
+        test_calc_graph_row_image(
+            params,
+            cell_count,
+            image_params,
+            drawing_pixels,
+            graph_style,
+            file_name,
+        );
+    }
+
+    #[rstest]
+    #[case("highlight_rounded", GraphStyle::Rounded)]
+    #[case("highlight_angular", GraphStyle::Angular)]
+    fn test_calc_graph_row_image_highlight(
+        #[case] file_name: &str,
+        #[case] graph_style: GraphStyle,
+    ) {
+        let params = branches_test_params();
+        let cell_count = 7;
+        let graph_color_config = GraphColorConfig::default();
+        let graph_color_set = GraphColorSet::new(&graph_color_config);
+        let cell_width_type = CellWidthType::Double;
+        let image_params = ImageParams::new(&graph_color_set, cell_width_type, None);
+        let drawing_pixels = DrawingPixels::new(&image_params);
+
+        test_calc_graph_row_image_with_dimming(
+            params,
+            cell_count,
+            image_params,
+            drawing_pixels,
+            graph_style,
+            Some(&[1, 3, 4]),
+            file_name,
+        );
+    }
+
+    #[rstest]
+    #[case("cell_size_double_rounded", CellWidthType::Double, GraphStyle::Rounded)]
+    #[case("cell_size_double_angular", CellWidthType::Double, GraphStyle::Angular)]
+    #[case("cell_size_single_rounded", CellWidthType::Single, GraphStyle::Rounded)]
+    #[case("cell_size_single_angular", CellWidthType::Single, GraphStyle::Angular)]
+    fn test_calc_graph_row_image_cell_size(
+        #[case] file_name: &str,
+        #[case] cell_width_type: CellWidthType,
+        #[case] graph_style: GraphStyle,
+    ) {
+        let params = branches_test_params();
+        let cell_count = 7;
+        let graph_color_config = GraphColorConfig::default();
+        let graph_color_set = GraphColorSet::new(&graph_color_config);
+        let cell_size = CellSize {
+            width: 9,
+            height: 19,
+        };
+        let image_params = ImageParams::new(&graph_color_set, cell_width_type, Some(cell_size));
         let drawing_pixels = DrawingPixels::new(&image_params);
 
         test_calc_graph_row_image(
@@ -1120,6 +1528,40 @@ mod tests {
         );
     }
 
+    #[rustfmt::skip]
+    #[rstest]
+    #[case(CellWidthType::Double, None,           (50, 50, 5, 10, 13))] // unknown cell size
+    #[case(CellWidthType::Single, None,           (25, 50, 3, 7, 10))]
+    #[case(CellWidthType::Double, Some((5, 10)),  (10, 10, 1, 2, 3))]   // smallest cell size
+    #[case(CellWidthType::Single, Some((5, 10)),  (5, 10, 1, 1, 2))]
+    #[case(CellWidthType::Double, Some((10, 20)), (20, 20, 2, 4, 5))]   // small cell size
+    #[case(CellWidthType::Single, Some((10, 20)), (10, 20, 1, 3, 4))]
+    #[case(CellWidthType::Double, Some((18, 36)), (36, 36, 4, 7, 9))]
+    #[case(CellWidthType::Double, Some((32, 64)), (64, 64, 6, 13, 17))] // HiDPI cell size
+    #[case(CellWidthType::Single, Some((32, 64)), (32, 64, 4, 9, 13))]
+    #[case(CellWidthType::Double, Some((4, 8)),   (50, 50, 5, 10, 13))] // below the minimum cell size
+    #[case(CellWidthType::Double, Some((4, 20)),  (50, 50, 5, 10, 13))]
+    #[case(CellWidthType::Single, Some((10, 9)),  (25, 50, 3, 7, 10))]
+    fn test_image_params_with_cell_size(
+        #[case] cell_width_type: CellWidthType,
+        #[case] cell_size: Option<(u16, u16)>,
+        #[case] expected: (u16, u16, u16, u16, u16),
+    ) {
+        let graph_color_set = GraphColorSet::new(&GraphColorConfig::default());
+        let cell_size = cell_size.map(|(width, height)| CellSize { width, height });
+        let image_params = ImageParams::new(&graph_color_set, cell_width_type, cell_size);
+
+        let actual = (
+            image_params.width,
+            image_params.height,
+            image_params.line_width,
+            image_params.circle_inner_radius,
+            image_params.circle_outer_radius,
+        );
+        assert_eq!(actual, expected);
+        assert!(image_params.circle_outer_radius > image_params.circle_inner_radius);
+    }
+
     #[rustfmt::skip]
     fn simple_test_params() -> Vec<TestParam> {
         vec![
@@ -1160,6 +1602,27 @@ mod tests {
         drawing_pixels: DrawingPixels,
         graph_style: GraphStyle,
         file_name: &str,
+    ) {
+        test_calc_graph_row_image_with_dimming(
+            params,
+            cell_count,
+            image_params,
+            drawing_pixels,
+            graph_style,
+            None,
+            file_name,
+        );
+    }
+
+    // the commits and the edges of the lines at the positions are dimmed
+    fn test_calc_graph_row_image_with_dimming(
+        params: Vec<TestParam>,
+        cell_count: usize,
+        image_params: ImageParams,
+        drawing_pixels: DrawingPixels,
+        graph_style: GraphStyle,
+        dimmed_lines: Option<&[usize]>,
+        file_name: &str,
     ) {
         let graph_row_images: Vec<GraphRowImage> = params
             .into_iter()
@@ -1168,10 +1631,20 @@ mod tests {
                     .into_iter()
                     .map(|t| Edge::new(t.0, t.1, t.2))
                     .collect();
+                let dimming = dimmed_lines.map(|lines| RowDimming {
+                    commit: lines.contains(&commit_pos_x),
+                    edges: edges
+                        .iter()
+                        .map(|e| lines.contains(&e.associated_line_pos_x))
+                        .collect(),
+                });
                 calc_graph_row_image(
+                    Some(commit_pos_x),
                     commit_pos_x,
+                    false,
                     cell_count,
                     &edges,
+                    dimming.as_ref(),
                     &image_params,
                     &drawing_pixels,
                     graph_style,
@@ -1196,9 +1669,9 @@ mod tests {
             image::ImageBuffer::new(image_width, image_height);
 
         for (i, graph_row_image) in graph_row_images.iter().enumerate() {
-            let image = image::load_from_memory(&graph_row_image.bytes).unwrap();
+            let image = &graph_row_image.buffer;
             let y = image_params.height as u32 * (rows_len - (i as u32) - 1);
-            img_buf.copy_from(&image, 0, y).unwrap();
+            img_buf.copy_from(image, 0, y).unwrap();
 
             for x in 0..cell_count {
                 let x_offset = x as u32 * image_params.width as u32;
@@ -0,0 +1,203 @@
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::{
+    color::GraphColorSet,
+    graph::{CellWidthType, Edge, EdgeType, GraphStyle, RowDimming},
+    protocol::{PreparedImage, PreparedImageCell},
+};
+
+const COMMIT_SYMBOL: &str = "●";
+const COLLAPSED_MERGE_SYMBOL: &str = "○";
+
+#[derive(Debug)]
+pub struct TextParams {
+    edge_colors: Vec<Color>,
+}
+
+impl TextParams {
+    pub fn new(graph_color_set: &GraphColorSet) -> Self {
+        let edge_colors = graph_color_set
+            .colors
+            .iter()
+            .map(|c| c.to_ratatui_color())
+            .collect();
+        Self { edge_colors }
+    }
+
+    fn edge_color(&self, index: usize) -> Color {
+        self.edge_colors[index % self.edge_colors.len()]
+    }
+}
+
+// the directions from the center of a lane that the lines in it extend to
+#[derive(Debug, Default, Clone, Copy)]
+struct Directions {
+    up: bool,
+    down: bool,
+    left: bool,
+    right: bool,
+}
+
+impl Directions {
+    fn of(edge_type: EdgeType) -> Self {
+        let (up, down, left, right) = match edge_type {
+            EdgeType::Vertical => (true, true, false, false),
+            EdgeType::Horizontal => (false, false, true, true),
+            EdgeType::Up => (true, false, false, false),
+            EdgeType::Down => (false, true, false, false),
+            EdgeType::Left => (false, false, true, false),
+            EdgeType::Right => (false, false, false, true),
+            EdgeType::RightTop => (false, true, true, false),
+            EdgeType::RightBottom => (true, false, true, false),
+            EdgeType::LeftTop => (false, true, false, true),
+            EdgeType::LeftBottom => (true, false, false, true),
+        };
+        Directions {
+            up,
+            down,
+            left,
+            right,
+        }
+    }
+
+    fn is_vertical(&self) -> bool {
+        self.up || self.down
+    }
+
+    fn merge(&mut self, other: Directions) {
+        self.up |= other.up;
+        self.down |= other.down;
+        self.left |= other.left;
+        self.right |= other.right;
+    }
+
+    fn symbol(&self, dotted: bool, graph_style: GraphStyle) -> &'static str {
+        let rounded = graph_style == GraphStyle::Rounded;
+        match (self.up, self.down, self.left, self.right) {
+            (true, true, true, true) => "┼",
+            (true, true, true, false) => "┤",
+            (true, true, false, true) => "├",
+            (true, true, false, false) if dotted => "┆",
+            (true, true, false, false) => "│",
+            (false, false, true, true) if dotted => "┄",
+            (false, false, true, true) => "─",
+            (false, true, true, true) => "┬",
+            (true, false, true, true) => "┴",
+            (true, false, true, false) if rounded => "╯",
+            (true, false, true, false) => "┘",
+            (true, false, false, true) if rounded => "╰",
+            (true, false, false, true) => "└",
+            (false, true, true, false) if rounded => "╮",
+            (false, true, true, false) => "┐",
+            (false, true, false, true) if rounded => "╭",
+            (false, true, false, true) => "┌",
+            (true, false, false, false) => "╵",
+            (false, true, false, false) => "╷",
+            (false, false, true, false) => "╴",
+            (false, false, false, true) => "╶",
+            (false, false, false, false) => " ",
+        }
+    }
+}
+
+// the lines drawn in a lane, or between the lane and the next one
+#[derive(Debug, Default)]
+struct LaneCell {
+    directions: Directions,
+    // the edge which the color is taken from, which is the vertical one if any
+    // so that the lines passing across the lane do not change the color of the lines along it
+    color_index: Option<usize>,
+    vertical_colored: bool,
+    dotted: bool,
+    dimmed: bool,
+}
+
+impl LaneCell {
+    fn add(&mut self, directions: Directions, edge: &Edge, dimmed: bool) {
+        let first = self.color_index.is_none();
+        let vertical = directions.is_vertical();
+        if first || (vertical && !self.vertical_colored) {
+            self.color_index = Some(edge.color_index);
+            self.vertical_colored = vertical;
+        }
+        // the cell is drawn dotted or dimmed only if all the lines in it are
+        self.dotted = (first || self.dotted) && edge.dotted;
+        self.dimmed = (first || self.dimmed) && dimmed;
+        self.directions.merge(directions);
+    }
+
+    fn style(&self, text_params: &TextParams) -> Style {
+        let mut style = Style::default();
+        if let Some(color_index) = self.color_index {
+            style = style.fg(text_params.edge_color(color_index));
+        }
+        if self.dimmed {
+            style = style.add_modifier(Modifier::DIM);
+        }
+        style
+    }
+}
+
+/// Draws a graph row with the box-drawing characters, for the terminals that do not support any image protocols.
+#[allow(clippy::too_many_arguments)]
+pub fn calc_graph_row_text(
+    commit_pos_x: Option<usize>,
+    commit_color_index: usize,
+    collapsed_merge: bool,
+    cell_count: usize,
+    edges: &[Edge],
+    dimming: Option<&RowDimming>,
+    text_params: &TextParams,
+    cell_width_type: CellWidthType,
+    graph_style: GraphStyle,
+) -> PreparedImage {
+    let mut lanes: Vec<LaneCell> = (0..cell_count).map(|_| LaneCell::default()).collect();
+    // the connectors to the next lanes, which are used only if the lanes are 2 cells wide
+    let mut connectors: Vec<LaneCell> = (0..cell_count).map(|_| LaneCell::default()).collect();
+
+    for (i, edge) in edges.iter().enumerate() {
+        if edge.pos_x >= cell_count {
+            continue;
+        }
+        let dimmed = dimming.is_some_and(|d| d.edges[i]);
+        let directions = Directions::of(edge.edge_type);
+        lanes[edge.pos_x].add(directions, edge, dimmed);
+        if directions.right {
+            let horizontal = Directions {
+                left: true,
+                right: true,
+                ..Default::default()
+            };
+            connectors[edge.pos_x].add(horizontal, edge, dimmed);
+        }
+    }
+
+    let mut cells = Vec::new();
+    for (x, (lane, connector)) in lanes.iter().zip(connectors.iter()).enumerate() {
+        if commit_pos_x == Some(x) {
+            let symbol = if collapsed_merge {
+                COLLAPSED_MERGE_SYMBOL
+            } else {
+                COMMIT_SYMBOL
+            };
+            let mut style = Style::default().fg(text_params.edge_color(commit_color_index));
+            if dimming.is_some_and(|d| d.commit) {
+                style = style.add_modifier(Modifier::DIM);
+            }
+            cells.push(PreparedImageCell::text(symbol, style));
+        } else {
+            let symbol = lane.directions.symbol(lane.dotted, graph_style);
+            cells.push(PreparedImageCell::text(symbol, lane.style(text_params)));
+        }
+
+        if cell_width_type == CellWidthType::Double {
+            let symbol = connector.directions.symbol(connector.dotted, graph_style);
+            cells.push(PreparedImageCell::text(
+                symbol,
+                connector.style(text_params),
+            ));
+        }
+    }
+
+    PreparedImage::text(cells)
+}
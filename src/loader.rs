@@ -0,0 +1,115 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crate::{
+    event::{AppEvent, Sender},
+    git::{Commit, CommitDiffReader, CommitStream, GitError},
+};
+
+const COMMIT_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Default)]
+struct LoadState {
+    // commits that have been loaded but not yet taken
+    commits: Vec<Commit>,
+    loaded: usize,
+    finished: bool,
+    failed: bool,
+    error: Option<GitError>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LoadStatus {
+    pub loaded: usize,
+    pub pending: usize,
+    pub finished: bool,
+    // whether loading has stopped with an error, even after the error is taken
+    pub failed: bool,
+}
+
+/// Loads commits on a background thread and notifies the progress with `AppEvent::CommitsLoaded`.
+///
+/// Loading is cancelled when the loader is dropped.
+#[derive(Debug)]
+pub struct CommitLoader {
+    state: Arc<Mutex<LoadState>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl CommitLoader {
+    pub fn start(stream: CommitStream, tx: Sender) -> Self {
+        let state = Arc::new(Mutex::new(LoadState::default()));
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        {
+            let state = state.clone();
+            let cancel = cancel.clone();
+            thread::spawn(move || {
+                let result = stream.read(COMMIT_BATCH_SIZE, |commits| {
+                    if cancel.load(Ordering::Relaxed) {
+                        return false;
+                    }
+                    {
+                        let mut state = state.lock().unwrap();
+                        state.loaded += commits.len();
+                        state.commits.extend(commits);
+                    }
+                    tx.try_send(AppEvent::CommitsLoaded);
+                    true
+                });
+
+                {
+                    let mut state = state.lock().unwrap();
+                    state.finished = true;
+                    state.failed = result.is_err();
+                    state.error = result.err();
+                }
+                if !cancel.load(Ordering::Relaxed) {
+                    tx.try_send(AppEvent::CommitsLoaded);
+                }
+            });
+        }
+
+        Self { state, cancel }
+    }
+
+    pub fn status(&self) -> LoadStatus {
+        let state = self.state.lock().unwrap();
+        LoadStatus {
+            loaded: state.loaded,
+            pending: state.commits.len(),
+            finished: state.finished,
+            failed: state.failed,
+        }
+    }
+
+    pub fn take_commits(&self) -> Vec<Commit> {
+        std::mem::take(&mut self.state.lock().unwrap().commits)
+    }
+
+    pub fn take_error(&self) -> Option<GitError> {
+        self.state.lock().unwrap().error.take()
+    }
+}
+
+impl Drop for CommitLoader {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Reads the patch of the commit on a background thread and notifies it with `AppEvent::CommitDiffLoaded`.
+pub fn load_commit_diff(reader: CommitDiffReader, tx: Sender) {
+    thread::spawn(move || {
+        let result = reader.read();
+        tx.try_send(AppEvent::CommitDiffLoaded(
+            reader.commit_hash().clone(),
+            result,
+        ));
+    });
+}
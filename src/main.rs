@@ -7,29 +7,124 @@ mod external;
 mod git;
 mod graph;
 mod keybind;
+mod loader;
 mod protocol;
+mod syntax;
 mod view;
+mod watcher;
 mod widget;
 
+#[cfg(test)]
+#[path = "tests/blame.rs"]
+mod blame_tests;
+
+#[cfg(test)]
+#[path = "tests/branch_color.rs"]
+mod branch_color_tests;
+
+#[cfg(test)]
+#[path = "tests/commit_filter.rs"]
+mod commit_filter_tests;
+
+#[cfg(test)]
+#[path = "tests/diff.rs"]
+mod diff_tests;
+
+// the file modes are set with the unix permissions
+#[cfg(all(test, unix))]
+#[path = "tests/file_tree.rs"]
+mod file_tree_tests;
+
+#[cfg(test)]
+#[path = "tests/first_parent.rs"]
+mod first_parent_tests;
+
+#[cfg(test)]
+#[path = "tests/fold_merges.rs"]
+mod fold_merges_tests;
+
+// the fake git commands are shell scripts
+#[cfg(all(test, unix))]
+#[path = "tests/git_error.rs"]
+mod git_error_tests;
+
 #[cfg(test)]
 #[path = "tests/graph.rs"]
 mod graph_tests;
 
+#[cfg(test)]
+#[path = "tests/highlight.rs"]
+mod highlight_tests;
+
+#[cfg(test)]
+#[path = "tests/image_protocol.rs"]
+mod image_protocol_tests;
+
+#[cfg(test)]
+#[path = "tests/lane_layout.rs"]
+mod lane_layout_tests;
+
+#[cfg(test)]
+#[path = "tests/load.rs"]
+mod load_tests;
+
 #[cfg(test)]
 #[path = "tests/mailmap.rs"]
 mod mailmap_tests;
 
-use std::{path::Path, rc::Rc};
+#[cfg(test)]
+#[path = "tests/path_filter.rs"]
+mod path_filter_tests;
+
+#[cfg(test)]
+#[path = "tests/pinned_branches.rs"]
+mod pinned_branches_tests;
+
+#[cfg(test)]
+#[path = "tests/refresh.rs"]
+mod refresh_tests;
+
+#[cfg(test)]
+#[path = "tests/repository_path.rs"]
+mod repository_path_tests;
+
+#[cfg(test)]
+#[path = "tests/simplify_by_decoration.rs"]
+mod simplify_by_decoration_tests;
+
+#[cfg(test)]
+#[path = "tests/stable_layout.rs"]
+mod stable_layout_tests;
+
+#[cfg(test)]
+#[path = "tests/text_graph.rs"]
+mod text_graph_tests;
+
+#[cfg(test)]
+#[path = "tests/util.rs"]
+mod test_util;
+
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use app::{App, Ret};
 use clap::{Parser, ValueEnum};
-use graph::GraphImageManager;
+use graph::{GraphImageCache, GraphImageManager};
+use loader::CommitLoader;
+use rustc_hash::FxHashSet;
 use serde::Deserialize;
+use watcher::RepositoryWatcher;
 
 /// Serie - A rich git commit graph in your terminal, like magic 📚
 #[derive(Parser)]
 #[command(version)]
 struct Args {
+    /// Path to the git repository [default: current directory]
+    #[arg(short = 'C', long = "repo", value_name = "PATH")]
+    repo: Option<PathBuf>,
+
     /// Maximum number of commits to render
     #[arg(short = 'n', long, value_name = "NUMBER")]
     max_count: Option<usize>,
@@ -53,6 +148,50 @@ struct Args {
     /// Initial selection of commit [default: latest]
     #[arg(short, long, value_name = "TYPE")]
     initial_selection: Option<InitialSelection>,
+
+    /// Refresh automatically when the refs or HEAD of the repository change
+    #[arg(long)]
+    watch: bool,
+
+    /// Follow the history of the file beyond renames, requires exactly one path
+    #[arg(long)]
+    follow: bool,
+
+    /// Only show the first parents of merge commits
+    #[arg(long)]
+    first_parent: bool,
+
+    /// Only show commits that have refs, and the merge and fork points that connect them
+    #[arg(long)]
+    simplify_by_decoration: bool,
+
+    /// Only show commits whose author matches the pattern (regex)
+    #[arg(long, value_name = "PATTERN")]
+    author: Option<String>,
+
+    /// Only show commits whose committer matches the pattern (regex)
+    #[arg(long, value_name = "PATTERN")]
+    committer: Option<String>,
+
+    /// Only show commits more recent than the date
+    #[arg(long, value_name = "DATE")]
+    since: Option<String>,
+
+    /// Only show commits older than the date
+    #[arg(long, value_name = "DATE")]
+    until: Option<String>,
+
+    /// Only show commits whose message matches the pattern (regex)
+    #[arg(long, value_name = "PATTERN")]
+    grep: Option<String>,
+
+    /// Revision ranges to render [default: all refs]
+    #[arg(value_name = "REVISION-RANGE")]
+    revisions: Vec<String>,
+
+    /// Only render commits that modify the given paths
+    #[arg(last = true, value_name = "PATH")]
+    paths: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
@@ -62,18 +201,44 @@ enum ImageProtocolType {
     Iterm,
     Kitty,
     KittyUnicode,
+    Sixel,
+    Text,
 }
 
-impl From<Option<ImageProtocolType>> for protocol::ImageProtocol {
-    fn from(protocol: Option<ImageProtocolType>) -> Self {
+impl ImageProtocolType {
+    // The terminal is queried only if the protocol is detected or sixel is used, whose images are encoded in the cell size.
+    // The images of the other protocols are drawn in the pixel size of the cells as well,
+    // so the cell size is read from the window size for them.
+    fn terminal_capabilities(
+        protocol: Option<ImageProtocolType>,
+        query: impl FnOnce() -> protocol::TerminalCapabilities,
+        window_cell_size: impl FnOnce() -> Option<protocol::CellSize>,
+    ) -> protocol::TerminalCapabilities {
+        match protocol {
+            None | Some(ImageProtocolType::Auto | ImageProtocolType::Sixel) => query(),
+            Some(_) => protocol::TerminalCapabilities {
+                cell_size: window_cell_size(),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn into_protocol(
+        protocol: Option<ImageProtocolType>,
+        capabilities: &protocol::TerminalCapabilities,
+    ) -> protocol::ImageProtocol {
         match protocol {
-            Some(ImageProtocolType::Auto) => protocol::auto_detect(),
+            Some(ImageProtocolType::Auto) => protocol::auto_detect(capabilities),
             Some(ImageProtocolType::Iterm) => protocol::ImageProtocol::Iterm2,
             Some(ImageProtocolType::Kitty) => protocol::ImageProtocol::Kitty,
             Some(ImageProtocolType::KittyUnicode) => protocol::ImageProtocol::KittyUnicode {
                 tmux: protocol::detect_tmux(),
             },
-            None => protocol::auto_detect(),
+            Some(ImageProtocolType::Sixel) => protocol::ImageProtocol::Sixel {
+                cell_size: capabilities.cell_size.unwrap_or_default(),
+            },
+            Some(ImageProtocolType::Text) => protocol::ImageProtocol::Text,
+            None => protocol::auto_detect(capabilities),
         }
     }
 }
@@ -139,13 +304,34 @@ impl From<Option<InitialSelection>> for app::InitialSelection {
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-fn main() -> Result<()> {
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
     let args = Args::parse();
     let (core_config, ui_config, graph_config, color_theme, keybind_patch) = config::load()?;
     let keybind = keybind::KeyBind::new(keybind_patch);
 
+    if ui_config.syntax.enabled {
+        syntax::preload();
+    }
+
+    let repo_path = args.repo.as_deref().unwrap_or(Path::new("."));
+    let repo_path = git::resolve_repository_path(repo_path)?;
+
     let max_count = args.max_count;
-    let image_protocol = args.protocol.or(core_config.option.protocol).into();
+    let image_protocol_type = args.protocol.or(core_config.option.protocol);
+    let terminal_capabilities = ImageProtocolType::terminal_capabilities(
+        image_protocol_type,
+        protocol::TerminalCapabilities::query,
+        protocol::window_cell_size,
+    );
+    let image_protocol =
+        ImageProtocolType::into_protocol(image_protocol_type, &terminal_capabilities);
     let order = args.order.or(core_config.option.order).into();
     let graph_width = args.graph_width.or(core_config.option.graph_width);
     let graph_style = args.graph_style.or(core_config.option.graph_style).into();
@@ -155,8 +341,36 @@ fn main() -> Result<()> {
         .or(core_config.option.initial_selection)
         .into();
     let mailmap = core_config.git.mailmap;
+    let backend = core_config.git.backend;
+    let watch = args.watch || core_config.git.watch;
+    if args.follow && args.paths.len() != 1 {
+        return Err("--follow requires exactly one path".into());
+    }
+    // the paths are given relative to the current directory, but git commands are executed at the top
+    let paths = repo_path.resolve_pathspecs(args.paths);
+    let mut revision_filter =
+        git::RevisionFilter::new(args.revisions, paths).with_follow(args.follow);
+    let commit_filter_query = git::CommitFilterQuery {
+        author: args.author,
+        committer: args.committer,
+        since: args.since,
+        until: args.until,
+        grep: args.grep,
+    };
+    let mut view_filter = ViewFilter {
+        first_parent: args.first_parent,
+        commit_filter: git::CommitFilter::new(&repo_path, &commit_filter_query)?,
+        simplify_by_decoration: args.simplify_by_decoration,
+        expanded_commits: FxHashSet::default(),
+        folded_merges: FxHashSet::default(),
+    };
 
     let graph_color_set = color::GraphColorSet::new(&graph_config.color);
+    let graph_layout = graph::GraphLayout {
+        pinned_branches: graph_config.pinned_branches,
+        compact_lanes: graph_config.compact_lanes,
+        max_lanes: graph_config.max_lanes,
+    };
 
     let ctx = Rc::new(app::AppContext {
         keybind,
@@ -164,18 +378,97 @@ fn main() -> Result<()> {
         ui_config,
         color_theme,
         image_protocol,
+        repo_path: repo_path.clone(),
     });
 
     let ec = event::EventController::init();
+
+    let load_repository = |revision_filter: &git::RevisionFilter| {
+        let (repository, stream) = git::Repository::load_without_commits(
+            &repo_path,
+            backend,
+            order,
+            max_count,
+            mailmap,
+            revision_filter,
+        )?;
+        start_loading(repository, stream, &ec)
+    };
+
+    let (mut repository, mut loader) = load_repository(&revision_filter)?;
+
+    let _watcher = if watch {
+        let (git_dir, common_dir) = git::resolve_git_dirs(&repo_path)?;
+        let watcher = RepositoryWatcher::start(&git_dir, &common_dir, ec.sender())
+            .map_err(|e| format!("failed to watch the repository: {e}"))?;
+        Some(watcher)
+    } else {
+        None
+    };
+
     let mut refresh_view_context = None;
+    let mut refresh_error = None;
+    let mut graph_image_cache = GraphImageCache::new();
+    // the lanes of the previous graph, which are followed only while the same commits are shown
+    let mut lane_hint = graph::LaneHint::default();
+    let mut incremental_layout = graph::IncrementalLayout::default();
+    let mut appended = false;
+    let mut refreshed = false;
+    let mut fold_toggled = false;
+    // the commits filtered when the filter is changed, which are shown without filtering again
+    let mut prefiltered = None;
     let mut terminal = None;
 
     let ret = loop {
-        let repository = git::Repository::load(Path::new("."), order, max_count, mailmap)?;
-
-        let graph = graph::calc_graph(&repository);
-
-        let cell_width_type = check::decide_cell_width_type(&graph, graph_width)?;
+        let result = match prefiltered.take() {
+            Some(filtered) => Ok(filtered),
+            None => filter_repository(&mut repository, &loader, &view_filter, &ec),
+        };
+        let filtered = match result {
+            Ok(filtered) => filtered,
+            // the filter given by the options must match before the first screen
+            Err(e) if terminal.is_none() => break Err(e),
+            Err(e) => {
+                view_filter.commit_filter = git::CommitFilter::default();
+                lane_hint = graph::LaneHint::default();
+                refresh_error = Some(format!("Failed to filter commits: {e}"));
+                view_filter.apply(&repository)
+            }
+        };
+        let shown_repository = filtered.as_ref().unwrap_or(&repository);
+
+        // only the appended or prepended commits are laid out if the commits shown before are not changed,
+        // and only the rows of the branch are laid out again if it is folded or unfolded
+        let continued = std::mem::take(&mut appended) && filtered.is_none();
+        let prepended = std::mem::take(&mut refreshed)
+            && filtered.is_none()
+            && incremental_layout.prepend(shown_repository);
+        let refolded =
+            std::mem::take(&mut fold_toggled) && incremental_layout.refold(shown_repository);
+        if !continued && !prepended && !refolded {
+            incremental_layout.clear();
+        }
+        // the commits are kept in the same lanes as the previous graph
+        let graph = graph::calc_graph(
+            shown_repository,
+            &graph_color_set,
+            &graph_layout,
+            &lane_hint,
+            &mut incremental_layout,
+        );
+        lane_hint = graph.lane_hint();
+
+        let cell_width_type = match check::decide_cell_width_type(&graph, graph_width) {
+            Ok(cell_width_type) => cell_width_type,
+            Err(e) => break Err(e),
+        };
+
+        // the images of the rows that are not changed by refreshing are reused
+        let stale_image_ids =
+            graph_image_cache.retain_unchanged(&graph, cell_width_type, graph_image_width_mode);
+        if let Err(e) = image_protocol.delete_images(&stale_image_ids) {
+            break Err(e.into());
+        }
 
         let graph_image_manager = GraphImageManager::new(
             &graph,
@@ -184,6 +477,8 @@ fn main() -> Result<()> {
             graph_style,
             graph_image_width_mode,
             image_protocol,
+            terminal_capabilities.cell_size,
+            graph_image_cache,
         );
 
         if terminal.is_none() {
@@ -191,31 +486,267 @@ fn main() -> Result<()> {
         }
 
         let mut app = App::new(
-            &repository,
+            shown_repository,
+            &loader,
             graph_image_manager,
             &graph,
             &graph_color_set,
-            cell_width_type,
             initial_selection,
             ctx.clone(),
             &ec,
             refresh_view_context,
         );
 
+        if let Some(e) = refresh_error.take() {
+            ec.send(event::AppEvent::NotifyError(e));
+        }
+
         match app.run(terminal.as_mut().unwrap()) {
             Ok(Ret::Quit) => {
                 break Ok(());
             }
             Ok(Ret::Refresh(request)) => {
                 refresh_view_context = Some(request.context);
-                continue;
+                graph_image_cache = request.graph_image_cache;
+
+                // only the new commits are loaded and laid out, and the images of the unchanged rows are reused
+                // keep showing the current repository if reloading fails
+                let result = refresh_repository(&mut repository, &mut loader, || {
+                    load_repository(&revision_filter)
+                });
+                match result {
+                    Ok(prepended) => refreshed = prepended,
+                    Err(e) => refresh_error = Some(format!("Failed to refresh: {e}")),
+                }
+            }
+            Ok(Ret::FilterPaths { request, paths }) => {
+                // the paths are relative to the top-level directory, as the views resolve the ones typed in the prompt
+                // like `log.follow` of git, the renames are followed if the only path is a file,
+                // which is looked up in the selected commit as the working tree may not have it
+                // the paths are filtered without following if the lookup fails
+                let selected: git::CommitHash =
+                    request.context.list_context().commit_hash.as_str().into();
+                let follow =
+                    paths.len() == 1 && repository.is_file(&selected, &paths[0]).unwrap_or(false);
+
+                refresh_view_context = Some(request.context);
+                graph_image_cache = request.graph_image_cache;
+
+                let filter = revision_filter.with_paths(paths).with_follow(follow);
+                // keep showing the current repository if no commits match the paths
+                match load_repository(&filter) {
+                    Ok(loaded) => {
+                        (repository, loader) = loaded;
+                        revision_filter = filter;
+                        lane_hint = graph::LaneHint::default();
+                    }
+                    Err(e) => refresh_error = Some(format!("Failed to filter by paths: {e}")),
+                }
+            }
+            Ok(Ret::FilterCommits { request, query }) => {
+                refresh_view_context = Some(request.context);
+                graph_image_cache = request.graph_image_cache;
+
+                // keep showing the current commits if the query is invalid or no commits match it
+                let result = parse_commit_filter(&repo_path, &query).and_then(|commit_filter| {
+                    let filter = ViewFilter {
+                        commit_filter,
+                        ..view_filter.clone()
+                    };
+                    let filtered = filter_repository(&mut repository, &loader, &filter, &ec)?;
+                    Ok((filter, filtered))
+                });
+                match result {
+                    Ok((filter, filtered)) => {
+                        view_filter = filter;
+                        prefiltered = Some(filtered);
+                        lane_hint = graph::LaneHint::default();
+                    }
+                    Err(e) => refresh_error = Some(format!("Failed to filter commits: {e}")),
+                }
+            }
+            Ok(Ret::ToggleFirstParent(request)) => {
+                refresh_view_context = Some(request.context);
+                graph_image_cache = request.graph_image_cache;
+                view_filter.first_parent = !view_filter.first_parent;
+                lane_hint = graph::LaneHint::default();
+            }
+            Ok(Ret::ToggleSimplifyByDecoration(request)) => {
+                refresh_view_context = Some(request.context);
+                graph_image_cache = request.graph_image_cache;
+                view_filter.simplify_by_decoration = !view_filter.simplify_by_decoration;
+                lane_hint = graph::LaneHint::default();
+            }
+            Ok(Ret::ExpandCommits {
+                request,
+                commit_hash,
+            }) => {
+                refresh_view_context = Some(request.context);
+                graph_image_cache = request.graph_image_cache;
+                let Some(filtered) = &filtered else {
+                    continue;
+                };
+                lane_hint = graph::LaneHint::default();
+                if let Some(merge_hash) = filtered.folded_merge(&commit_hash) {
+                    view_filter.folded_merges.remove(merge_hash);
+                    fold_toggled = true;
+                } else if let Some(hidden) = filtered.collapsed_commits(&commit_hash) {
+                    view_filter.expanded_commits.extend(hidden.iter().cloned());
+                }
+            }
+            Ok(Ret::ToggleFold {
+                request,
+                commit_hash,
+            }) => {
+                refresh_view_context = Some(request.context);
+                graph_image_cache = request.graph_image_cache;
+                // the merge commit is unfolded from either itself or the row it is folded into
+                let merge_hash = filtered
+                    .as_ref()
+                    .and_then(|filtered| filtered.folded_merge(&commit_hash))
+                    .unwrap_or(&commit_hash);
+                if !view_filter.folded_merges.remove(merge_hash) {
+                    view_filter.folded_merges.insert(commit_hash);
+                }
+                lane_hint = graph::LaneHint::default();
+                fold_toggled = true;
+            }
+            Ok(Ret::Append(request)) => {
+                refresh_view_context = Some(request.context);
+                graph_image_cache = request.graph_image_cache;
+                repository.append_commits(loader.take_commits());
+                appended = true;
             }
             Err(e) => {
-                break Err(e);
+                break Err(e.into());
             }
         }
     };
 
-    ratatui::restore();
-    ret.map_err(Into::into)
+    if terminal.is_some() {
+        ratatui::restore();
+    }
+    ret
+}
+
+// Loads only the new commits if possible, otherwise loads the whole repository again.
+// Returns `true` if the new commits are prepended to the loaded ones.
+fn refresh_repository(
+    repository: &mut git::Repository,
+    loader: &mut CommitLoader,
+    load_repository: impl Fn() -> Result<(git::Repository, CommitLoader)>,
+) -> Result<bool> {
+    // the new commits are decided from the loaded ones, so all of them must have been loaded
+    let status = loader.status();
+    if status.finished && !status.failed {
+        repository.append_commits(loader.take_commits());
+        if repository.refresh()? {
+            return Ok(true);
+        }
+    }
+    (*repository, *loader) = load_repository()?;
+    Ok(false)
+}
+
+fn parse_commit_filter(repo_path: &git::RepositoryPath, query: &str) -> Result<git::CommitFilter> {
+    let query = git::CommitFilterQuery::parse(query)?;
+    Ok(git::CommitFilter::new(repo_path, &query)?)
+}
+
+// How the loaded commits are shown, which are narrowed down in the order of the fields.
+#[derive(Clone)]
+struct ViewFilter {
+    first_parent: bool,
+    commit_filter: git::CommitFilter,
+    simplify_by_decoration: bool,
+    // the hidden commits of the collapsed rows that have been expanded
+    expanded_commits: FxHashSet<git::CommitHash>,
+    // the merge commits whose side branches are folded
+    folded_merges: FxHashSet<git::CommitHash>,
+}
+
+impl ViewFilter {
+    // Returns `None` if all the commits are shown as they are.
+    fn apply(&self, repository: &git::Repository) -> Option<git::Repository> {
+        let mut filtered = self.first_parent.then(|| repository.first_parent_only());
+        if !self.commit_filter.is_empty() {
+            let base = filtered.as_ref().unwrap_or(repository);
+            filtered = Some(base.filter_commits(&self.commit_filter));
+        }
+        if self.simplify_by_decoration {
+            let base = filtered.as_ref().unwrap_or(repository);
+            filtered = Some(base.simplify_by_decoration(&self.expanded_commits));
+        }
+        if !self.folded_merges.is_empty() {
+            let base = filtered.as_ref().unwrap_or(repository);
+            filtered = Some(base.fold_merges(&self.folded_merges));
+        }
+        filtered
+    }
+}
+
+// Applies the filter, waiting for more commits until some match.
+fn filter_repository(
+    repository: &mut git::Repository,
+    loader: &CommitLoader,
+    filter: &ViewFilter,
+    ec: &event::EventController,
+) -> Result<Option<git::Repository>> {
+    loop {
+        // the status is checked first so that no commits are loaded after the check
+        let finished = loader.status().finished;
+        repository.append_commits(loader.take_commits());
+        let Some(filtered) = filter.apply(repository) else {
+            return Ok(None);
+        };
+        if !filtered.all_commits().is_empty() {
+            return Ok(Some(filtered));
+        }
+        if finished {
+            return Err("no commits match the filter".into());
+        }
+        wait_loading(loader, ec, |_| true)?;
+    }
+}
+
+// Starts loading the commits in the background and waits until the first screen can be filled.
+fn start_loading(
+    mut repository: git::Repository,
+    stream: git::CommitStream,
+    ec: &event::EventController,
+) -> Result<(git::Repository, CommitLoader)> {
+    let loader = CommitLoader::start(stream, ec.sender());
+
+    let (_, height) = ratatui::crossterm::terminal::size().unwrap_or_default();
+    wait_loading(&loader, ec, |status| status.loaded >= height as usize)?;
+
+    repository.append_commits(loader.take_commits());
+    Ok((repository, loader))
+}
+
+// Waits until the loading finishes or the loaded commits satisfy the condition.
+fn wait_loading(
+    loader: &CommitLoader,
+    ec: &event::EventController,
+    is_enough: impl Fn(&loader::LoadStatus) -> bool,
+) -> Result<()> {
+    let mut deferred_events = Vec::new();
+    let result = loop {
+        match ec.recv() {
+            event::AppEvent::CommitsLoaded => {
+                if let Some(e) = loader.take_error() {
+                    break Err(e);
+                }
+                let status = loader.status();
+                if status.finished || is_enough(&status) {
+                    break Ok(());
+                }
+            }
+            // events such as key inputs are handled after the loading
+            event => deferred_events.push(event),
+        }
+    };
+    deferred_events.into_iter().for_each(|event| ec.send(event));
+    result?;
+    Ok(())
 }
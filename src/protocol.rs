@@ -1,22 +1,31 @@
 use std::{
     env,
-    io::{self, Write},
+    io::{self, Cursor, Write},
+    time::Duration,
 };
 
 use base64::Engine;
+use image::imageops::FilterType;
 use ratatui::style::{Color, Style};
+use rustc_hash::FxHashMap;
 
-// By default assume the Iterm2 is the best protocol to use for all terminals *unless* an env
-// variable is set that suggests the terminal is probably Kitty.
-pub fn auto_detect() -> ImageProtocol {
-    if detect_kitty_graphics_protocol() {
+// Use the image protocol the terminal reports or is known to support from the env variables,
+// and fall back to drawing the graph with text if none of them is found.
+pub fn auto_detect(capabilities: &TerminalCapabilities) -> ImageProtocol {
+    if capabilities.kitty_graphics || detect_kitty_graphics_protocol() {
         if detect_tmux() {
             ImageProtocol::KittyUnicode { tmux: true }
         } else {
             ImageProtocol::Kitty
         }
-    } else {
+    } else if detect_iterm2_inline_images_protocol() {
         ImageProtocol::Iterm2
+    } else if capabilities.sixel || detect_sixel() {
+        ImageProtocol::Sixel {
+            cell_size: capabilities.cell_size.unwrap_or_default(),
+        }
+    } else {
+        ImageProtocol::Text
     }
 }
 
@@ -30,6 +39,224 @@ fn detect_kitty_graphics_protocol() -> bool {
     || env::var("GHOSTTY_RESOURCES_DIR").is_ok()
 }
 
+fn detect_iterm2_inline_images_protocol() -> bool {
+    // iTerm2, WezTerm, Rio and VSCode integrated terminal
+    env::var("TERM_PROGRAM").is_ok_and(|term_program| {
+        matches!(
+            term_program.as_str(),
+            "iTerm.app" | "WezTerm" | "rio" | "vscode"
+        )
+    })
+    // iTerm2 sets it even over ssh
+    // https://iterm2.com/documentation-variables.html
+    || env::var("LC_TERMINAL").is_ok_and(|t| t == "iTerm2")
+}
+
+fn detect_sixel() -> bool {
+    // foot
+    // https://codeberg.org/dnkl/foot/wiki#supported-terminfo-names
+    env::var("TERM").is_ok_and(|t| t.starts_with("foot") || t == "mlterm")
+    // mlterm
+    || env::var("MLTERM").is_ok()
+    // Konsole (22.04+)
+    || env::var("KONSOLE_VERSION").is_ok()
+}
+
+// the pixel size of the window reported by the ioctl, which is zero in some terminals
+pub(crate) fn window_cell_size() -> Option<CellSize> {
+    ratatui::crossterm::terminal::window_size()
+        .ok()
+        .and_then(|size| {
+            cell_size_of_window(size.width, size.height, Some((size.columns, size.rows)))
+        })
+}
+
+fn cell_size_of_window(
+    width: u16,
+    height: u16,
+    terminal_size: Option<(u16, u16)>,
+) -> Option<CellSize> {
+    let (columns, rows) = terminal_size?;
+    if width == 0 || height == 0 || columns == 0 || rows == 0 {
+        return None;
+    }
+    Some(CellSize {
+        width: (width / columns).max(1),
+        height: (height / rows).max(1),
+    })
+}
+
+const QUERY_TIMEOUT: Duration = Duration::from_millis(500);
+// how long the input is quiet until the late responses are regarded as all read
+const DRAIN_QUIET_TIME: Duration = Duration::from_millis(50);
+
+/// The capabilities the terminal reports for the queries, which are all unknown if it cannot be queried.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TerminalCapabilities {
+    pub kitty_graphics: bool,
+    pub sixel: bool,
+    pub cell_size: Option<CellSize>,
+}
+
+impl TerminalCapabilities {
+    /// Queries the terminal, which must be done before the terminal events start to be read.
+    pub fn query() -> Self {
+        let terminal_size = ratatui::crossterm::terminal::size().ok();
+        let mut capabilities = query_terminal(detect_tmux())
+            .map(|response| parse_query_response(&response, terminal_size))
+            .unwrap_or_default();
+        capabilities.cell_size = capabilities.cell_size.or_else(window_cell_size);
+        capabilities
+    }
+}
+
+#[cfg(unix)]
+fn query_terminal(tmux: bool) -> Option<String> {
+    use ratatui::crossterm::terminal;
+
+    // the Linux console cannot show images, and prints the kitty query as it is
+    if env::var("TERM").is_ok_and(|t| t == "linux") {
+        return None;
+    }
+
+    let mut tty = std::fs::File::options()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+
+    // https://sw.kovidgoyal.net/kitty/graphics-protocol/#querying-support-and-available-transmission-mediums
+    // the cell size (CSI 16 t), the window size (CSI 14 t) and the primary device attributes (DA1) follow,
+    // and the last is answered by almost all terminals, which tells the end of the responses
+    let (start, esc, end) = passthrough_escapes(tmux);
+    let query =
+        format!("{start}{esc}_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA{esc}\\{end}\x1b[16t\x1b[14t\x1b[c");
+
+    let raw_mode = terminal::is_raw_mode_enabled().unwrap_or(false);
+    if !raw_mode {
+        terminal::enable_raw_mode().ok()?;
+    }
+    let response = read_query_response(&mut tty, &query);
+    if !raw_mode {
+        let _ = terminal::disable_raw_mode();
+    }
+    response.ok()
+}
+
+#[cfg(not(unix))]
+fn query_terminal(_tmux: bool) -> Option<String> {
+    None
+}
+
+#[cfg(unix)]
+fn read_query_response(tty: &mut std::fs::File, query: &str) -> io::Result<String> {
+    use std::{io::Read, time::Instant};
+
+    tty.write_all(query.as_bytes())?;
+    tty.flush()?;
+
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    let mut response = Vec::new();
+    let mut buf = [0; 1024];
+    while !has_primary_device_attributes(&String::from_utf8_lossy(&response)) {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        if timeout.is_zero() || !wait_readable(tty, timeout)? {
+            // the responses arriving late are discarded, so that they are not read as the key inputs
+            drain_input(tty)?;
+            break;
+        }
+        let n = tty.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        response.extend_from_slice(&buf[..n]);
+    }
+    Ok(String::from_utf8_lossy(&response).into_owned())
+}
+
+#[cfg(unix)]
+fn drain_input(tty: &mut std::fs::File) -> io::Result<()> {
+    use std::{io::Read, time::Instant};
+
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    let mut buf = [0; 1024];
+    while Instant::now() < deadline && wait_readable(tty, DRAIN_QUIET_TIME)? {
+        if tty.read(&mut buf)? == 0 {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// Waits until the tty has input to read, and returns `false` if it times out.
+// The events of crossterm cannot be used here, as they do not pass the unknown sequences of the responses through.
+#[cfg(unix)]
+fn wait_readable(tty: &std::fs::File, timeout: Duration) -> io::Result<bool> {
+    use std::os::fd::AsRawFd;
+
+    let mut fds = libc::pollfd {
+        fd: tty.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout = timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+    // SAFETY: `fds` is a single initialized `pollfd` for the file descriptor of `tty`, which is open while borrowed,
+    // and the count passed is 1, so `poll` reads and writes only this struct during the call.
+    let ret = unsafe { libc::poll(&mut fds, 1, timeout) };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(ret > 0)
+}
+
+// the parameters and the final characters of the CSI sequences in the response
+fn csi_sequences(response: &str) -> impl Iterator<Item = (&str, char)> {
+    response.split("\x1b[").skip(1).filter_map(|s| {
+        let end = s.find(|c: char| ('\x40'..='\x7e').contains(&c))?;
+        Some((&s[..end], s[end..].chars().next()?))
+    })
+}
+
+fn has_primary_device_attributes(response: &str) -> bool {
+    csi_sequences(response).any(|(params, c)| c == 'c' && params.starts_with('?'))
+}
+
+fn parse_query_response(response: &str, terminal_size: Option<(u16, u16)>) -> TerminalCapabilities {
+    let kitty_graphics = response.contains("\x1b_Gi=31;OK");
+
+    let mut sixel = false;
+    let mut cell_size = None;
+    let mut window_size = None;
+    for (params, c) in csi_sequences(response) {
+        let numbers = || params.split(';').map(|p| p.parse::<u16>().ok());
+        match c {
+            // https://vt100.net/docs/vt510-rm/DA1.html
+            'c' if params.starts_with('?') => {
+                sixel = params[1..].split(';').any(|p| p == "4");
+            }
+            't' => match numbers().collect::<Vec<_>>()[..] {
+                [Some(6), Some(height), Some(width)] if width > 0 && height > 0 => {
+                    cell_size = Some(CellSize { width, height });
+                }
+                [Some(4), Some(height), Some(width)] => {
+                    window_size = Some((width, height));
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    let cell_size = cell_size.or_else(|| {
+        window_size.and_then(|(width, height)| cell_size_of_window(width, height, terminal_size))
+    });
+
+    TerminalCapabilities {
+        kitty_graphics,
+        sixel,
+        cell_size,
+    }
+}
+
 pub fn detect_tmux() -> bool {
     env::var("TMUX").is_ok_and(|tmux| !tmux.is_empty())
         || env::var("TERM").is_ok_and(|term| term.starts_with("tmux"))
@@ -41,6 +268,25 @@ pub enum ImageProtocol {
     Iterm2,
     Kitty,
     KittyUnicode { tmux: bool },
+    Sixel { cell_size: CellSize },
+    // not an image protocol, but draws the graph with the box-drawing characters
+    Text,
+}
+
+/// The size of a terminal cell in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellSize {
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Default for CellSize {
+    fn default() -> Self {
+        CellSize {
+            width: 10,
+            height: 20,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -51,6 +297,14 @@ pub struct PreparedImageCell {
 }
 
 impl PreparedImageCell {
+    pub fn text(symbol: &str, style: Style) -> Self {
+        PreparedImageCell {
+            symbol: symbol.to_string(),
+            style,
+            skip: false,
+        }
+    }
+
     pub fn symbol(&self) -> &str {
         &self.symbol
     }
@@ -71,6 +325,13 @@ pub struct PreparedImage {
 }
 
 impl PreparedImage {
+    pub fn text(cells: Vec<PreparedImageCell>) -> Self {
+        PreparedImage {
+            cells,
+            upload_data: None,
+        }
+    }
+
     pub fn cells(&self) -> &[PreparedImageCell] {
         &self.cells
     }
@@ -80,14 +341,30 @@ impl PreparedImage {
     }
 }
 
-impl ImageProtocol {
-    pub fn prepare_image(&self, bytes: &[u8], cell_width: usize, image_id: u32) -> PreparedImage {
+/// The image protocols the graph rows are encoded in, which are all of `ImageProtocol` except the text.
+#[derive(Debug, Clone, Copy)]
+pub enum ImageEncoder {
+    Iterm2,
+    Kitty,
+    KittyUnicode { tmux: bool },
+    Sixel { cell_size: CellSize },
+}
+
+impl ImageEncoder {
+    pub fn prepare_image(
+        &self,
+        image: &image::RgbaImage,
+        cell_width: usize,
+        image_id: u32,
+    ) -> PreparedImage {
         let symbol = match self {
-            ImageProtocol::Iterm2 => iterm2_encode(bytes, cell_width, 1),
-            ImageProtocol::Kitty => kitty_encode(bytes, cell_width, 1),
-            ImageProtocol::KittyUnicode { tmux } => {
-                return kitty_unicode_prepare(bytes, cell_width, image_id, *tmux);
+            ImageEncoder::Iterm2 => iterm2_encode(&png_encode(image), cell_width, 1),
+            ImageEncoder::Kitty => kitty_encode(&png_encode(image), cell_width, 1),
+            ImageEncoder::KittyUnicode { tmux } => {
+                return kitty_unicode_prepare(&png_encode(image), cell_width, image_id, *tmux);
             }
+            // the pixels are encoded as they are, without going through the png
+            ImageEncoder::Sixel { cell_size } => sixel_encode(image, cell_width, 1, *cell_size),
         };
         let mut cells = Vec::with_capacity(cell_width);
         cells.push(PreparedImageCell {
@@ -107,26 +384,42 @@ impl ImageProtocol {
             upload_data: None,
         }
     }
+}
+
+impl ImageProtocol {
+    /// Returns the encoder of the graph images, or none if the graph is drawn with text.
+    pub fn image_encoder(&self) -> Option<ImageEncoder> {
+        match *self {
+            ImageProtocol::Iterm2 => Some(ImageEncoder::Iterm2),
+            ImageProtocol::Kitty => Some(ImageEncoder::Kitty),
+            ImageProtocol::KittyUnicode { tmux } => Some(ImageEncoder::KittyUnicode { tmux }),
+            ImageProtocol::Sixel { cell_size } => Some(ImageEncoder::Sixel { cell_size }),
+            ImageProtocol::Text => None,
+        }
+    }
 
     pub fn clear_line(&self, y: u16) {
         match self {
-            ImageProtocol::Iterm2 => {}
+            ImageProtocol::Iterm2 | ImageProtocol::Sixel { .. } => {}
             ImageProtocol::Kitty => kitty_clear_line(y),
-            ImageProtocol::KittyUnicode { .. } => {}
+            ImageProtocol::KittyUnicode { .. } | ImageProtocol::Text => {}
         }
     }
 
     pub fn clear(&self) {
         match self {
-            ImageProtocol::Iterm2 => {}
+            ImageProtocol::Iterm2 | ImageProtocol::Sixel { .. } => {}
             ImageProtocol::Kitty => kitty_clear(),
-            ImageProtocol::KittyUnicode { .. } => {}
+            ImageProtocol::KittyUnicode { .. } | ImageProtocol::Text => {}
         }
     }
 
     pub fn delete_images(&self, image_ids: &[u32]) -> Result<(), std::io::Error> {
         match self {
-            ImageProtocol::Iterm2 | ImageProtocol::Kitty => Ok(()),
+            ImageProtocol::Iterm2
+            | ImageProtocol::Kitty
+            | ImageProtocol::Sixel { .. }
+            | ImageProtocol::Text => Ok(()),
             ImageProtocol::KittyUnicode { tmux } => kitty_unicode_delete_images(image_ids, *tmux),
         }
     }
@@ -438,6 +731,12 @@ fn to_base64_str(bytes: &[u8]) -> String {
 }
 
 // https://iterm2.com/documentation-images.html
+fn png_encode(image: &image::RgbaImage) -> Vec<u8> {
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, image::ImageFormat::Png).unwrap();
+    bytes.into_inner()
+}
+
 fn iterm2_encode(bytes: &[u8], cell_width: usize, cell_height: usize) -> String {
     format!(
         "\x1b]1337;File=size={};width={};height={};preserveAspectRatio=0;inline=1:{}\u{0007}",
@@ -476,6 +775,106 @@ fn kitty_encode(bytes: &[u8], cell_width: usize, cell_height: usize) -> String {
     s
 }
 
+// https://vt100.net/docs/vt3xx-gp/chapter14.html
+fn sixel_encode(
+    image: &image::RgbaImage,
+    cell_width: usize,
+    cell_height: usize,
+    cell_size: CellSize,
+) -> String {
+    let width = (cell_width * cell_size.width as usize) as u32;
+    let height = (cell_height * cell_size.height as usize) as u32;
+    let img = image::imageops::resize(image, width, height, FilterType::Nearest);
+
+    let pixels: Vec<Option<[u8; 3]>> = img.pixels().map(|p| sixel_color(p.0)).collect();
+    let (palette, shift) = sixel_palette(&pixels);
+    let register = |color: [u8; 3]| palette[&color.map(|c| c >> shift)];
+
+    let mut s = String::new();
+
+    // erase the cells first, because the pixels left transparent do not overwrite the previous image
+    s.push_str(&format!("\x1b[{cell_width}X"));
+    s.push_str(&format!("\x1bP0;1;0q\"1;1;{width};{height}"));
+
+    let mut registers: Vec<([u8; 3], usize)> = palette.iter().map(|(c, i)| (*c, *i)).collect();
+    registers.sort_by_key(|(_, i)| *i);
+    for (color, i) in &registers {
+        let [r, g, b] = color.map(|c| (((c << shift) as u32) * 100 / 255) as u8);
+        s.push_str(&format!("#{i};2;{r};{g};{b}"));
+    }
+
+    let (width, height) = (width as usize, height as usize);
+    for band_y in (0..height).step_by(6) {
+        // the sixels of each color in the band, where each bit is a pixel from the top
+        let mut band: Vec<Vec<u8>> = vec![vec![0; width]; registers.len()];
+        for dy in 0..6.min(height - band_y) {
+            for x in 0..width {
+                if let Some(color) = pixels[(band_y + dy) * width + x] {
+                    band[register(color)][x] |= 1 << dy;
+                }
+            }
+        }
+
+        let mut first = true;
+        for (i, sixels) in band.iter().enumerate() {
+            let Some(end) = sixels.iter().rposition(|&b| b != 0) else {
+                continue;
+            };
+            if !first {
+                s.push('$'); // back to the start of the band to draw the next color over it
+            }
+            first = false;
+            s.push_str(&format!("#{i}"));
+            push_sixel_runs(&mut s, &sixels[..=end]);
+        }
+        s.push('-');
+    }
+
+    s.push_str("\x1b\\");
+    s
+}
+
+// the transparent pixels are not drawn, and the translucent ones are blended with black
+// because the background color of the terminal is unknown
+fn sixel_color([r, g, b, a]: [u8; 4]) -> Option<[u8; 3]> {
+    if a == 0 {
+        return None;
+    }
+    let blend = |c: u8| (c as u32 * a as u32 / 255) as u8;
+    Some([blend(r), blend(g), blend(b)])
+}
+
+// Returns the color registers and the bits dropped from the colors, which are reduced to fit the 256 registers.
+fn sixel_palette(pixels: &[Option<[u8; 3]>]) -> (FxHashMap<[u8; 3], usize>, u8) {
+    const MAX_REGISTERS: usize = 256;
+    let mut shift = 0;
+    loop {
+        let mut palette = FxHashMap::default();
+        for color in pixels.iter().flatten() {
+            let len = palette.len();
+            palette.entry(color.map(|c| c >> shift)).or_insert(len);
+        }
+        if palette.len() <= MAX_REGISTERS || shift == 7 {
+            return (palette, shift);
+        }
+        shift += 1;
+    }
+}
+
+fn push_sixel_runs(s: &mut String, sixels: &[u8]) {
+    let mut i = 0;
+    while i < sixels.len() {
+        let n = sixels[i..].iter().take_while(|&&b| b == sixels[i]).count();
+        let c = (sixels[i] + 0x3f) as char;
+        if n > 3 {
+            s.push_str(&format!("!{n}{c}"));
+        } else {
+            s.extend(std::iter::repeat_n(c, n));
+        }
+        i += n;
+    }
+}
+
 fn kitty_unicode_prepare(
     bytes: &[u8],
     cell_width: usize,
@@ -595,3 +994,116 @@ fn passthrough_escapes(tmux: bool) -> (&'static str, &'static str, &'static str)
         ("", "\x1b", "")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba_image(
+        width: u32,
+        height: u32,
+        pixel: impl Fn(u32, u32) -> [u8; 4],
+    ) -> image::RgbaImage {
+        image::RgbaImage::from_fn(width, height, |x, y| image::Rgba(pixel(x, y)))
+    }
+
+    #[test]
+    fn test_sixel_encode() {
+        // red on the left half, blue on the bottom of the right half, and transparent on the top of it
+        let image = rgba_image(8, 12, |x, y| match (x, y) {
+            (0..4, _) => [255, 0, 0, 255],
+            (_, 6..) => [0, 0, 255, 255],
+            _ => [0, 0, 0, 0],
+        });
+        let cell_size = CellSize {
+            width: 8,
+            height: 12,
+        };
+
+        let actual = sixel_encode(&image, 1, 1, cell_size);
+        let expected = [
+            "\x1b[1X",
+            "\x1bP0;1;0q\"1;1;8;12",
+            "#0;2;100;0;0#1;2;0;0;100",
+            "#0!4~-",
+            "#0!4~$#1!4?!4~-",
+            "\x1b\\",
+        ]
+        .concat();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_sixel_encode_scaled_to_cells() {
+        let image = rgba_image(50, 50, |_, _| [0, 255, 0, 255]);
+        let cell_size = CellSize {
+            width: 3,
+            height: 7,
+        };
+
+        let actual = sixel_encode(&image, 2, 1, cell_size);
+        let expected = [
+            "\x1b[2X",
+            "\x1bP0;1;0q\"1;1;6;7",
+            "#0;2;0;100;0",
+            "#0!6~-",
+            "#0!6@-",
+            "\x1b\\",
+        ]
+        .concat();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_query_response_kitty() {
+        let response = "\x1b_Gi=31;OK\x1b\\\x1b[6;36;18t\x1b[4;1080;1920t\x1b[?62;22;52c";
+        let actual = parse_query_response(response, Some((100, 30)));
+        let expected = TerminalCapabilities {
+            kitty_graphics: true,
+            sixel: false,
+            cell_size: Some(CellSize {
+                width: 18,
+                height: 36,
+            }),
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_query_response_sixel() {
+        // the cell size is computed from the window size if it is not reported
+        let response = "\x1b[4;600;800t\x1b[?63;1;2;4;6;9;15;22c";
+        let actual = parse_query_response(response, Some((80, 30)));
+        let expected = TerminalCapabilities {
+            kitty_graphics: false,
+            sixel: true,
+            cell_size: Some(CellSize {
+                width: 10,
+                height: 20,
+            }),
+        };
+        assert_eq!(actual, expected);
+        assert!(has_primary_device_attributes(response));
+    }
+
+    #[test]
+    fn test_parse_query_response_unsupported() {
+        let response = "\x1b_Gi=31;ENOTSUPPORTED:graphics\x1b\\\x1b[?1;2c";
+        let actual = parse_query_response(response, Some((80, 30)));
+        assert_eq!(actual, TerminalCapabilities::default());
+        assert!(has_primary_device_attributes(response));
+    }
+
+    #[test]
+    fn test_parse_query_response_incomplete() {
+        let response = "\x1b_Gi=31;OK\x1b\\\x1b[6;36;18t\x1b[?62;2";
+        assert!(!has_primary_device_attributes(response));
+    }
+
+    #[test]
+    fn test_sixel_color() {
+        assert_eq!(sixel_color([10, 20, 30, 0]), None);
+        assert_eq!(sixel_color([10, 20, 30, 255]), Some([10, 20, 30]));
+        assert_eq!(sixel_color([200, 100, 50, 51]), Some([40, 20, 10]));
+    }
+}
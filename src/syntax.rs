@@ -0,0 +1,156 @@
+use std::{ops::Range, path::Path};
+
+use once_cell::sync::Lazy;
+use ratatui::style::Color;
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Theme, ThemeSet},
+    parsing::{SyntaxReference, SyntaxSet},
+};
+
+use crate::config::UiSyntaxConfig;
+
+static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
+
+// the files larger than this are shown without syntax highlighting, as it takes a while
+const MAX_HIGHLIGHT_LINES: usize = 10_000;
+
+// The foreground colors of the byte ranges in a line
+pub type LineHighlights = Vec<(Range<usize>, Color)>;
+
+/// Loads the bundled syntaxes in the background, so that the first diff is shown without waiting for them.
+pub fn preload() {
+    std::thread::spawn(|| Lazy::force(&SYNTAX_SET));
+}
+
+pub fn theme_names() -> Vec<&'static str> {
+    THEME_SET.themes.keys().map(String::as_str).collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SyntaxHighlighter {
+    theme: &'static Theme,
+}
+
+impl SyntaxHighlighter {
+    pub fn new(config: &UiSyntaxConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        let theme = THEME_SET.themes.get(&config.theme)?;
+        Some(Self { theme })
+    }
+
+    /// Highlights the consecutive lines of the file.
+    /// Returns `None` if the file has too many lines or the language of the file cannot be detected.
+    pub fn highlight(&self, path: &str, lines: &[&str]) -> Option<Vec<LineHighlights>> {
+        if lines.len() > MAX_HIGHLIGHT_LINES {
+            return None;
+        }
+        let syntax = find_syntax(path, lines.first().copied())?;
+        let mut highlighter = HighlightLines::new(syntax, self.theme);
+        let mut highlights = Vec::with_capacity(lines.len());
+        for line in lines {
+            // the syntaxes are loaded to expect the lines to end with a newline
+            let line = format!("{line}\n");
+            let Ok(ranges) = highlighter.highlight_line(&line, &SYNTAX_SET) else {
+                // do not continue with the broken parse state
+                break;
+            };
+            let mut pos = 0;
+            let mut line_highlights = Vec::new();
+            for (style, s) in ranges {
+                let start = pos;
+                pos += s.len();
+                let end = pos.min(line.len() - 1);
+                if start < end {
+                    let fg = style.foreground;
+                    line_highlights.push((start..end, Color::Rgb(fg.r, fg.g, fg.b)));
+                }
+            }
+            highlights.push(line_highlights);
+        }
+        highlights.resize_with(lines.len(), Vec::new);
+        Some(highlights)
+    }
+}
+
+fn find_syntax(path: &str, first_line: Option<&str>) -> Option<&'static SyntaxReference> {
+    let path = Path::new(path);
+    let extension = path.extension().and_then(|s| s.to_str());
+    let file_name = path.file_name().and_then(|s| s.to_str());
+    let syntax = extension
+        .and_then(|ext| SYNTAX_SET.find_syntax_by_extension(ext))
+        // some syntaxes list the whole file names, e.g. `Makefile`
+        .or_else(|| file_name.and_then(|name| SYNTAX_SET.find_syntax_by_extension(name)))
+        .or_else(|| first_line.and_then(|line| SYNTAX_SET.find_syntax_by_first_line(line)))?;
+    if syntax.name == SYNTAX_SET.find_syntax_plain_text().name {
+        return None;
+    }
+    Some(syntax)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("src/main.rs", None, Some("Rust"))]
+    #[case("a/b/Makefile", None, Some("Makefile"))]
+    #[case("bin/run", Some("#!/bin/bash"), Some("Bourne Again Shell (bash)"))]
+    #[case("notes.txt", None, None)]
+    #[case("unknown.xyz", None, None)]
+    fn test_find_syntax(
+        #[case] path: &str,
+        #[case] first_line: Option<&str>,
+        #[case] expected: Option<&str>,
+    ) {
+        let actual = find_syntax(path, first_line).map(|s| s.name.as_str());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_highlight() {
+        let highlighter = SyntaxHighlighter::new(&UiSyntaxConfig::default()).unwrap();
+
+        let lines = ["fn main() {", "    let s = \"こんにちは\";", "", "}"];
+        let highlights = highlighter.highlight("main.rs", &lines).unwrap();
+        assert_eq!(highlights.len(), lines.len());
+        for (line, ranges) in lines.iter().zip(&highlights) {
+            // the ranges cover the whole line without the trailing newline
+            let mut pos = 0;
+            for (range, _) in ranges {
+                assert_eq!(range.start, pos);
+                assert!(line.is_char_boundary(range.end));
+                pos = range.end;
+            }
+            assert_eq!(pos, line.len());
+        }
+        // the keyword and the string are colored differently
+        let color_at = |line: usize, pos: usize| {
+            let (_, color) = highlights[line]
+                .iter()
+                .find(|(r, _)| r.contains(&pos))
+                .unwrap();
+            *color
+        };
+        assert_ne!(color_at(0, 0), color_at(1, 12));
+
+        assert!(highlighter.highlight("notes.txt", &lines).is_none());
+
+        let lines = vec!["let x = 1;"; MAX_HIGHLIGHT_LINES + 1];
+        assert!(highlighter.highlight("main.rs", &lines).is_none());
+    }
+
+    #[test]
+    fn test_highlighter_disabled() {
+        let config = UiSyntaxConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        assert!(SyntaxHighlighter::new(&config).is_none());
+    }
+}
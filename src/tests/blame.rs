@@ -0,0 +1,81 @@
+use std::fs;
+
+use chrono::DateTime;
+
+use crate::{
+    git,
+    test_util::{load_with_backend, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn blame() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    git.init();
+    fs::write(repo_path.join("a.txt"), "a\nb\n")?;
+    git.run(&["add", "."]);
+    git.commit("001", "2024-01-01");
+    fs::write(repo_path.join("a.txt"), "a\nB\nc\n")?;
+    git.run(&["add", "."]);
+    git.commit("002", "2024-01-02");
+    git.run(&["mv", "a.txt", "b.txt"]);
+    fs::write(repo_path.join("b.txt"), "a\nB\nC\n")?;
+    git.run(&["add", "."]);
+    git.run_with_date(&["commit", "-m", "003 rename"], "2024-01-03T00:00:00+09:30");
+
+    for backend in [git::GitBackendType::Subprocess, git::GitBackendType::Gix] {
+        let repository = load_with_backend(repo_path, backend)?;
+        let commits = repository.all_commits();
+        let hashes: Vec<_> = commits.iter().map(|c| c.commit_hash.clone()).collect();
+
+        let blame = repository.blame(&hashes[0], "b.txt")?;
+        let actual: Vec<_> = blame
+            .lines
+            .iter()
+            .map(|l| (&l.commit_hash, l.original_line_number, l.content.as_str()))
+            .collect();
+        assert_eq!(
+            actual,
+            vec![
+                (&hashes[2], 1, "a"),
+                (&hashes[1], 2, "B"),
+                (&hashes[0], 3, "C")
+            ]
+        );
+
+        let commit = &blame.commits[&hashes[0]];
+        assert_eq!(commit.author_name, "Author Name");
+        assert_eq!(commit.summary, "003 rename");
+        assert_eq!(
+            commit.author_date,
+            DateTime::parse_from_rfc3339("2024-01-03T00:00:00+09:30")?
+        );
+        // the file was renamed in the commit
+        assert_eq!(commit.previous, Some((hashes[1].clone(), "a.txt".into())));
+        assert_eq!(
+            blame.commits[&hashes[1]].previous,
+            Some((hashes[2].clone(), "a.txt".into()))
+        );
+        assert_eq!(blame.commits[&hashes[2]].previous, None);
+
+        // blame again at the parent of the commit
+        let (parent, path) = commit.previous.clone().unwrap();
+        let blame = repository.blame(&parent, &path)?;
+        let actual: Vec<_> = blame
+            .lines
+            .iter()
+            .map(|l| (&l.commit_hash, l.content.as_str()))
+            .collect();
+        assert_eq!(
+            actual,
+            vec![(&hashes[2], "a"), (&hashes[1], "B"), (&hashes[1], "c")]
+        );
+
+        assert!(repository.blame(&hashes[0], "a.txt").is_err());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use crate::{
+    color::{GraphColorMode, GraphColorSet},
+    config::{GraphColorConfig, GraphColorRule},
+    git::{self, Ref, Repository},
+    graph::{self, EdgeType},
+    test_util::{load, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn branch_colors_follow_first_parent_history() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.commit("002", "2024-01-02");
+    git.run(&["checkout", "-b", "release/1.0", "HEAD~1"]);
+    git.commit("003", "2024-01-03");
+    git.run(&["checkout", "-b", "feature", "master"]);
+    git.commit("004", "2024-01-04");
+    git.run(&["checkout", "master"]);
+    git.commit("005", "2024-01-05");
+    git.run(&["checkout", "feature"]);
+    git.commit("006", "2024-01-06");
+
+    let mut config = GraphColorConfig {
+        mode: GraphColorMode::Branch,
+        ..Default::default()
+    };
+    config.rules.push(GraphColorRule {
+        pattern: "release/*".into(),
+        color: "#FFA500".into(),
+    });
+    let color_set = GraphColorSet::new(&config);
+    let palette_len = config.branches.len();
+    let master_color = palette_len + 1;
+    let release_color = palette_len + 2;
+    let feature_color = color_set.branch_color_index(&Ref::Branch {
+        name: "feature".into(),
+        target: Default::default(),
+    });
+
+    let repository = load(repo_path)?;
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &graph::GraphLayout::default(),
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        commit_colors(&repository, &graph),
+        vec![
+            ("006", feature_color),
+            ("005", master_color),
+            ("004", feature_color),
+            ("003", release_color),
+            ("002", master_color),
+            ("001", master_color),
+        ]
+    );
+
+    // the edges from the fork point are drawn in the colors of the branches
+    let (_, pos_y) = graph.commit_pos_map[&find(&repository, "002")];
+    for (subject, color) in [("004", feature_color), ("005", master_color)] {
+        let (pos_x, _) = graph.commit_pos_map[&find(&repository, subject)];
+        let edges: Vec<_> = graph.edges[pos_y]
+            .iter()
+            .filter(|e| e.associated_line_pos_x == pos_x && e.edge_type != EdgeType::Down)
+            .collect();
+        assert!(!edges.is_empty());
+        assert!(edges.iter().all(|e| e.color_index == color));
+    }
+
+    // the colors do not depend on the lanes
+    git.run(&["checkout", "master"]);
+    git.commit("007", "2024-01-07");
+    let repository = load(repo_path)?;
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &graph::GraphLayout::default(),
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(commit_colors(&repository, &graph)[0], ("007", master_color));
+    assert_eq!(
+        commit_colors(&repository, &graph)[1],
+        ("006", feature_color)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn branch_colors_of_remote_branches() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["update-ref", "refs/remotes/origin/main", "HEAD"]);
+    git.commit("002", "2024-01-02");
+    git.run(&["branch", "-m", "topic"]);
+
+    let config = GraphColorConfig {
+        mode: GraphColorMode::Branch,
+        ..Default::default()
+    };
+    let color_set = GraphColorSet::new(&config);
+    let main_color = config.branches.len();
+    let topic_color = color_set.branch_color_index(&Ref::Branch {
+        name: "topic".into(),
+        target: Default::default(),
+    });
+
+    // `origin/main` matches the rule of `main`, so it takes the commits shared with `topic`
+    let repository = load(repo_path)?;
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &graph::GraphLayout::default(),
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        commit_colors(&repository, &graph),
+        vec![("002", topic_color), ("001", main_color)]
+    );
+
+    Ok(())
+}
+
+fn find(repository: &Repository, subject: &str) -> git::CommitHash {
+    repository
+        .all_commits()
+        .into_iter()
+        .find(|c| c.subject == subject)
+        .map(|c| c.commit_hash.clone())
+        .unwrap()
+}
+
+fn commit_colors<'a>(repository: &'a Repository, graph: &graph::Graph) -> Vec<(&'a str, usize)> {
+    repository
+        .all_commits()
+        .into_iter()
+        .map(|c| (c.subject.as_str(), graph.commit_colors[&c.commit_hash]))
+        .collect()
+}
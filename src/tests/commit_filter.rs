@@ -0,0 +1,162 @@
+use std::path::Path;
+
+use crate::{
+    git::{self, CommitFilter, CommitFilterQuery, GitError, RepositoryPath},
+    test_util::{load, load_repository, parent_subjects, subjects, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn filter_commits_rewrites_parents() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001 init", "2024-01-01");
+    git.run(&["checkout", "-b", "feature"]);
+    git.commit("002 fix typo", "2024-01-02");
+    git.commit("003 update docs", "2024-01-03");
+    git.run(&["checkout", "master"]);
+    git.commit("004 fix bug", "2024-01-04");
+    git.run_with_date(
+        &["merge", "--no-ff", "feature", "-m", "005 merge"],
+        "2024-01-05T00:00:00+00:00",
+    );
+    git.commit("006 fix crash", "2024-01-06");
+    git.commit("007 fix again", "2024-01-07");
+
+    let repository = load(repo_path)?;
+    let query = CommitFilterQuery {
+        grep: Some("fix".into()),
+        ..Default::default()
+    };
+    let filter = CommitFilter::new(&RepositoryPath::new(repo_path), &query)?;
+    let filtered = repository.filter_commits(&filter);
+
+    let commits = filtered.all_commits();
+    assert_eq!(
+        subjects(&commits),
+        vec![
+            "007 fix again",
+            "006 fix crash",
+            "004 fix bug",
+            "002 fix typo"
+        ]
+    );
+    let parents: Vec<Vec<&str>> = commits
+        .iter()
+        .map(|c| parent_subjects(&filtered, c))
+        .collect();
+    assert_eq!(
+        parents,
+        vec![
+            vec!["006 fix crash"],
+            vec!["004 fix bug", "002 fix typo"],
+            Vec::<&str>::new(),
+            Vec::<&str>::new(),
+        ]
+    );
+
+    let hash = |i: usize| &commits[i].commit_hash;
+    assert!(!filtered.has_hidden_commits_between(hash(0), hash(1)));
+    assert!(filtered.has_hidden_commits_between(hash(1), hash(2)));
+    assert!(filtered.has_hidden_commits_between(hash(1), hash(3)));
+    // the commits keep their original parents
+    assert_eq!(commits[1].parent_commit_hashes.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn filter_commits_drops_missing_parent_reachable_from_other_parent() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001 init", "2024-01-01");
+    git.run(&["tag", "base"]);
+    git.run(&["checkout", "-b", "feature"]);
+    git.commit("002 update docs", "2024-01-02");
+    git.run(&["checkout", "master"]);
+    git.commit("003 fix bug", "2024-01-03");
+    git.run_with_date(
+        &["merge", "--no-ff", "feature", "-m", "004 fix merge"],
+        "2024-01-04T00:00:00+00:00",
+    );
+
+    // 001 is not loaded, and it is the parent of 003 as well as the nearest matching ancestor of 002
+    let revisions = vec!["master".into(), "^base".into()];
+    let repository = load_repository(
+        &RepositoryPath::new(repo_path),
+        git::GitBackendType::Subprocess,
+        git::SortCommit::Chronological,
+        None,
+        false,
+        &git::RevisionFilter::new(revisions, Vec::new()),
+    )?;
+    let query = CommitFilterQuery {
+        grep: Some("fix".into()),
+        ..Default::default()
+    };
+    let filter = CommitFilter::new(&RepositoryPath::new(repo_path), &query)?;
+    let filtered = repository.filter_commits(&filter);
+
+    let commits = filtered.all_commits();
+    assert_eq!(subjects(&commits), vec!["004 fix merge", "003 fix bug"]);
+    assert_eq!(
+        filtered.parents_hash(&commits[0].commit_hash),
+        vec![&commits[1].commit_hash]
+    );
+    assert_eq!(filtered.parents_hash(&commits[1].commit_hash).len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn filter_commits_by_dates_and_author() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    for (i, date) in ["2024-01-01", "2024-01-02", "2024-01-03", "2024-01-04"]
+        .iter()
+        .enumerate()
+    {
+        git.commit(&format!("00{}", i + 1), date);
+    }
+
+    let repository = load(repo_path)?;
+
+    let query = CommitFilterQuery {
+        author: Some("^Author Name <author@".into()),
+        since: Some("2024-01-02 01:02:03 +0000".into()),
+        until: Some("2024-01-03 01:02:03 +0000".into()),
+        ..Default::default()
+    };
+    let filter = CommitFilter::new(&RepositoryPath::new(repo_path), &query)?;
+    let filtered = repository.filter_commits(&filter);
+    assert_eq!(subjects(&filtered.all_commits()), vec!["003", "002"]);
+
+    let query = CommitFilterQuery {
+        committer: Some("Author Name".into()),
+        ..Default::default()
+    };
+    let filter = CommitFilter::new(&RepositoryPath::new(repo_path), &query)?;
+    assert!(repository.filter_commits(&filter).all_commits().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn invalid_pattern() {
+    let query = CommitFilterQuery {
+        grep: Some("fix(".into()),
+        ..Default::default()
+    };
+    let actual = CommitFilter::new(&RepositoryPath::new(Path::new(".")), &query);
+    assert!(matches!(actual, Err(GitError::InvalidPattern { pattern, .. }) if pattern == "fix("));
+}
@@ -0,0 +1,335 @@
+use std::fs;
+
+use crate::{
+    git::{self, parse_unified_diff, DiffLineKind},
+    test_util::{load_repository, load_with_backend, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn parse_hunks() {
+    let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1234567..89abcde 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,4 +1,4 @@ fn main() {
+ fn a() {}
+-fn b() {}
++fn c() {}
+ fn d() {}
+ 
+@@ -10 +10,2 @@
+ x
++y
+";
+    let files = parse_unified_diff(diff);
+    assert_eq!(files.len(), 1);
+    let file = &files[0];
+    assert_eq!(file.path(), "src/lib.rs");
+    assert_eq!(file.stats(), (2, 1));
+    assert_eq!(file.headers, Vec::<String>::new());
+    assert_eq!(file.hunks.len(), 2);
+
+    let hunk = &file.hunks[0];
+    assert_eq!(hunk.header, "@@ -1,4 +1,4 @@ fn main() {");
+    assert_eq!((hunk.old_start, hunk.new_start), (1, 1));
+    let lines: Vec<_> = hunk
+        .lines
+        .iter()
+        .map(|l| (l.kind, l.content.as_str(), l.old_lineno, l.new_lineno))
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            (DiffLineKind::Context, "fn a() {}", Some(1), Some(1)),
+            (DiffLineKind::Removed, "fn b() {}", Some(2), None),
+            (DiffLineKind::Added, "fn c() {}", None, Some(2)),
+            (DiffLineKind::Context, "fn d() {}", Some(3), Some(3)),
+            (DiffLineKind::Context, "", Some(4), Some(4)),
+        ]
+    );
+
+    let hunk = &file.hunks[1];
+    let lines: Vec<_> = hunk
+        .lines
+        .iter()
+        .map(|l| (l.kind, l.old_lineno, l.new_lineno))
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            (DiffLineKind::Context, Some(10), Some(10)),
+            (DiffLineKind::Added, None, Some(11)),
+        ]
+    );
+}
+
+#[test]
+fn parse_content_looking_like_headers() {
+    // the removed and added lines starting with `--` and `++` are not the file headers
+    let diff = "\
+diff --git a/a.md b/a.md
+--- a/a.md
++++ b/a.md
+@@ -1 +1 @@
+--- a/b
++++ b/c
+diff --git a/b.md b/b.md
+new file mode 100644
+--- /dev/null
++++ b/b.md
+@@ -0,0 +1 @@
++b
+\\ No newline at end of file
+";
+    let files = parse_unified_diff(diff);
+    assert_eq!(files.len(), 2);
+
+    let lines: Vec<_> = files[0].hunks[0]
+        .lines
+        .iter()
+        .map(|l| (l.kind, l.content.as_str()))
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            (DiffLineKind::Removed, "-- a/b"),
+            (DiffLineKind::Added, "++ b/c"),
+        ]
+    );
+
+    assert_eq!(files[1].path(), "b.md");
+    assert_eq!(files[1].headers, vec!["new file mode 100644"]);
+    let kinds: Vec<_> = files[1].hunks[0].lines.iter().map(|l| l.kind).collect();
+    assert_eq!(kinds, vec![DiffLineKind::Added, DiffLineKind::NoNewline]);
+}
+
+#[test]
+fn parse_paths() {
+    let diff = "\
+diff --git a/dir name/x y.txt b/dir name/x y.txt
+Binary files a/dir name/x y.txt and b/dir name/x y.txt differ
+diff --git a/old.txt b/new.txt
+similarity index 90%
+rename from old.txt
+rename to new.txt
+diff --git \"a/tab\\there.txt\" \"b/tab\\there.txt\"
+deleted file mode 100644
+";
+    let files = parse_unified_diff(diff);
+    let paths: Vec<_> = files.iter().map(|f| f.path()).collect();
+    assert_eq!(
+        paths,
+        vec!["dir name/x y.txt", "old.txt -> new.txt", "tab\there.txt"]
+    );
+    assert_eq!(
+        files[0].headers,
+        vec!["Binary files a/dir name/x y.txt and b/dir name/x y.txt differ"]
+    );
+}
+
+#[test]
+fn highlight_changed_words() {
+    let diff = "\
+diff --git a/a.rs b/a.rs
+--- a/a.rs
++++ b/a.rs
+@@ -1,3 +1,3 @@
+-let value = foo(1);
+-abc
++let value = bar(1, 2);
++xyz
+ end
+";
+    let files = parse_unified_diff(diff);
+    let lines = &files[0].hunks[0].lines;
+    let emphasized = |i: usize| -> Vec<&str> {
+        let line = &lines[i];
+        line.emphasized
+            .iter()
+            .map(|r| &line.content[r.clone()])
+            .collect()
+    };
+    assert_eq!(emphasized(0), vec!["foo"]);
+    assert_eq!(emphasized(2), vec!["bar", ", 2"]);
+    // the lines with nothing in common are not highlighted by words
+    assert_eq!(emphasized(1), Vec::<&str>::new());
+    assert_eq!(emphasized(3), Vec::<&str>::new());
+    assert_eq!(emphasized(4), Vec::<&str>::new());
+}
+
+#[test]
+fn commit_diff() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    git.init();
+    fs::write(repo_path.join("a.txt"), "1\n2\n3\n")?;
+    git.run(&["add", "."]);
+    git.commit("001", "2024-01-01");
+    fs::write(repo_path.join("a.txt"), "1\ntwo\n3\n")?;
+    git.run(&["mv", "a.txt", "b.txt"]);
+    git.run(&["add", "."]);
+    git.commit("002", "2024-01-02");
+
+    for backend in [git::GitBackendType::Subprocess, git::GitBackendType::Gix] {
+        let repository = load_with_backend(repo_path, backend)?;
+        let commits = repository.all_commits();
+
+        // the initial commit is compared with the empty tree
+        let diffs = repository
+            .commit_diff_reader(&commits[1].commit_hash)?
+            .read()?;
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path(), "a.txt");
+        assert_eq!(diffs[0].stats(), (3, 0));
+
+        let diffs = repository
+            .commit_diff_reader(&commits[0].commit_hash)?
+            .read()?;
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path(), "a.txt -> b.txt");
+        assert_eq!(diffs[0].stats(), (1, 1));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn commit_diff_against_real_parent() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    git.init();
+    fs::write(repo_path.join("a.txt"), "a\n")?;
+    git.run(&["add", "."]);
+    git.commit("001", "2024-01-01");
+    git.run(&["checkout", "-b", "feature"]);
+    fs::write(repo_path.join("b.txt"), "b\n")?;
+    git.run(&["add", "."]);
+    git.commit("002", "2024-01-02");
+    git.run(&["checkout", "master"]);
+    git.commit("003", "2024-01-03");
+    // c.txt is added only in the merge
+    git.run(&["merge", "--no-ff", "--no-commit", "feature"]);
+    fs::write(repo_path.join("c.txt"), "c\n")?;
+    git.run(&["add", "."]);
+    git.commit("004 merge", "2024-01-04");
+
+    for backend in [git::GitBackendType::Subprocess, git::GitBackendType::Gix] {
+        let repository = load_repository(
+            &git::RepositoryPath::new(repo_path),
+            backend,
+            git::SortCommit::Chronological,
+            None,
+            false,
+            &git::RevisionFilter::new(Vec::new(), vec!["c.txt".into()]),
+        )?;
+        let commits = repository.all_commits();
+
+        // the parents are rewritten away by the filter, but the merge is still compared with its first parent
+        assert_eq!(commits.len(), 1);
+        assert!(commits[0].parent_commit_hashes.is_empty());
+        let diffs = repository
+            .commit_diff_reader(&commits[0].commit_hash)?
+            .read()?;
+        let mut paths: Vec<_> = diffs.iter().map(|d| d.path()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["b.txt", "c.txt"]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn file_change_of_diff() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    git.init();
+    fs::write(repo_path.join("a.txt"), "1\n2\n3\n")?;
+    fs::write(repo_path.join("b.txt"), "b\n")?;
+    fs::write(repo_path.join("c.txt"), "c1\nc2\nc3\nc4\n")?;
+    git.run(&["add", "."]);
+    git.commit("001", "2024-01-01");
+    fs::write(repo_path.join("a.txt"), "1\ntwo\n3\n")?;
+    fs::write(repo_path.join("d.txt"), "d\n")?;
+    git.run(&["rm", "-q", "b.txt"]);
+    git.run(&["mv", "c.txt", "e.txt"]);
+    git.run(&["add", "."]);
+    git.commit("002", "2024-01-02");
+
+    for backend in [git::GitBackendType::Subprocess, git::GitBackendType::Gix] {
+        let repository = load_with_backend(repo_path, backend)?;
+        let hash = &repository.all_commits()[0].commit_hash;
+        let (_, changes) = repository.commit_detail(hash)?;
+        let diffs = repository.commit_diff_reader(hash)?.read()?;
+
+        let mut paths: Vec<_> = changes
+            .iter()
+            .map(|change| {
+                let matched: Vec<_> = diffs.iter().filter(|d| change.is_change_of(d)).collect();
+                assert_eq!(matched.len(), 1, "{change:?}");
+                (change.path(), matched[0].path())
+            })
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                ("a.txt", "a.txt".into()),
+                ("b.txt", "b.txt".into()),
+                ("d.txt", "d.txt".into()),
+                ("e.txt", "c.txt -> e.txt".into()),
+            ]
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn file_change_of_diff_with_non_ascii_paths() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    git.init();
+    fs::write(repo_path.join("é.txt"), "1\n2\n3\n")?;
+    fs::write(repo_path.join("ü.txt"), "u1\nu2\nu3\nu4\n")?;
+    git.run(&["add", "."]);
+    git.commit("001", "2024-01-01");
+    fs::write(repo_path.join("é.txt"), "1\ntwo\n3\n")?;
+    git.run(&["mv", "ü.txt", "日本語.txt"]);
+    git.run(&["add", "."]);
+    git.commit("002", "2024-01-02");
+
+    for backend in [git::GitBackendType::Subprocess, git::GitBackendType::Gix] {
+        let repository = load_with_backend(repo_path, backend)?;
+        let commits = repository.all_commits();
+
+        let mut actual = Vec::new();
+        for commit in commits {
+            let (_, changes) = repository.commit_detail(&commit.commit_hash)?;
+            let diffs = repository.commit_diff_reader(&commit.commit_hash)?.read()?;
+            for change in &changes {
+                let matched: Vec<_> = diffs.iter().filter(|d| change.is_change_of(d)).collect();
+                assert_eq!(matched.len(), 1, "{change:?}");
+                actual.push((change.path().to_string(), matched[0].path()));
+            }
+        }
+        actual.sort();
+        assert_eq!(
+            actual,
+            vec![
+                ("é.txt".into(), "é.txt".into()),
+                ("é.txt".into(), "é.txt".into()),
+                ("ü.txt".into(), "ü.txt".into()),
+                ("日本語.txt".into(), "ü.txt -> 日本語.txt".into()),
+            ]
+        );
+    }
+
+    Ok(())
+}
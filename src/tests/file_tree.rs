@@ -0,0 +1,86 @@
+use std::{fs, os::unix::fs::PermissionsExt};
+
+use crate::{
+    git,
+    test_util::{load_with_backend, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn tree_entries() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    git.init();
+    fs::create_dir_all(repo_path.join("src/bin"))?;
+    fs::write(repo_path.join("README.md"), "# readme\n")?;
+    fs::write(repo_path.join("src/lib.rs"), "pub fn a() {}\n")?;
+    fs::write(repo_path.join("src/bin/run.sh"), "#!/bin/sh\n")?;
+    fs::set_permissions(
+        repo_path.join("src/bin/run.sh"),
+        fs::Permissions::from_mode(0o755),
+    )?;
+    git.run(&["add", "."]);
+    git.commit("001", "2024-01-01");
+    fs::write(
+        repo_path.join("src/lib.rs"),
+        "pub fn b() {}\npub fn c() {}\n",
+    )?;
+    git.run(&["add", "."]);
+    git.commit("002", "2024-01-02");
+
+    for backend in [git::GitBackendType::Subprocess, git::GitBackendType::Gix] {
+        let repository = load_with_backend(repo_path, backend)?;
+        let commits = repository.all_commits();
+
+        let mut entries = repository.tree_entries(&commits[1].commit_hash)?;
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        let actual: Vec<_> = entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.mode.as_str(), e.size))
+            .collect();
+        assert_eq!(
+            actual,
+            vec![
+                ("README.md", "100644", Some(9)),
+                ("src/bin/run.sh", "100755", Some(10)),
+                ("src/lib.rs", "100644", Some(14)),
+            ]
+        );
+
+        let lib = entries.iter().find(|e| e.path == "src/lib.rs").unwrap();
+        assert_eq!(repository.blob_content(lib, 1024)?, b"pub fn a() {}\n");
+        // only the beginning of the blob is read
+        assert_eq!(repository.blob_content(lib, 6)?, b"pub fn");
+
+        let entries = repository.tree_entries(&commits[0].commit_hash)?;
+        let lib = entries.iter().find(|e| e.path == "src/lib.rs").unwrap();
+        assert_eq!(
+            repository.blob_content(lib, 1024)?,
+            b"pub fn b() {}\npub fn c() {}\n"
+        );
+
+        // the last commit that changed the file in the history of the given commit
+        let last_modified = |commit: usize, path: &str| {
+            repository
+                .last_modified_commit(&commits[commit].commit_hash, path)
+                .unwrap()
+        };
+        assert_eq!(
+            last_modified(0, "src/lib.rs"),
+            Some(commits[0].commit_hash.clone())
+        );
+        assert_eq!(
+            last_modified(0, "README.md"),
+            Some(commits[1].commit_hash.clone())
+        );
+        assert_eq!(
+            last_modified(1, "src/lib.rs"),
+            Some(commits[1].commit_hash.clone())
+        );
+        assert_eq!(last_modified(0, "src/*.rs"), None);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use crate::{
+    color, config, graph,
+    test_util::{load, parent_subjects, subjects, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn first_parent_only() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["checkout", "-b", "feature"]);
+    git.commit("002", "2024-01-02");
+    git.run(&["checkout", "-b", "topic"]);
+    git.commit("003", "2024-01-03");
+    git.run(&["checkout", "feature"]);
+    git.run_with_date(
+        &["merge", "--no-ff", "topic", "-m", "004"],
+        "2024-01-04T00:00:00+00:00",
+    );
+    git.run(&["branch", "-D", "topic"]);
+    git.run(&["checkout", "master"]);
+    git.commit("005", "2024-01-05");
+    git.run_with_date(
+        &["merge", "--no-ff", "feature", "-m", "006"],
+        "2024-01-06T00:00:00+00:00",
+    );
+
+    let repository = load(repo_path)?;
+    let first_parent = repository.first_parent_only();
+
+    // the feature branch is still shown from its tip, but the deleted topic branch is not
+    let commits = first_parent.all_commits();
+    assert_eq!(subjects(&commits), vec!["006", "005", "004", "002", "001"]);
+    let parents: Vec<Vec<&str>> = commits
+        .iter()
+        .map(|c| parent_subjects(&first_parent, c))
+        .collect();
+    assert_eq!(
+        parents,
+        vec![
+            vec!["005"],
+            vec!["001"],
+            vec!["002"],
+            vec!["001"],
+            Vec::<&str>::new()
+        ]
+    );
+
+    let graph_color_set = color::GraphColorSet::new(&config::GraphColorConfig::default());
+    let graph = graph::calc_graph(
+        &first_parent,
+        &graph_color_set,
+        &graph::GraphLayout::default(),
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    let mut collapsed_merges: Vec<&str> = graph
+        .collapsed_merges
+        .iter()
+        .map(|hash| first_parent.commit(hash).unwrap().subject.as_str())
+        .collect();
+    collapsed_merges.sort();
+    assert_eq!(collapsed_merges, vec!["004", "006"]);
+
+    Ok(())
+}
@@ -0,0 +1,272 @@
+use rustc_hash::FxHashSet;
+
+use crate::{
+    color::GraphColorSet,
+    config::GraphColorConfig,
+    git::{Commit, Repository},
+    graph::{self, GraphLayout},
+    test_util::{load, parent_subjects, subjects, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn fold_merges_folds_side_branch() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["checkout", "-b", "feature"]);
+    git.commit("002", "2024-01-02");
+    git.commit("003", "2024-01-03");
+    git.run(&["checkout", "master"]);
+    git.commit("004", "2024-01-04");
+    git.run_with_date(
+        &["merge", "--no-ff", "feature", "-m", "005"],
+        "2024-01-05T00:00:00+00:00",
+    );
+    git.commit("006", "2024-01-06");
+
+    let repository = load(repo_path)?;
+    let merge_hash = find(&repository, "005").commit_hash.clone();
+    let folded = repository.fold_merges(&FxHashSet::from_iter([merge_hash.clone()]));
+
+    let commits = folded.all_commits();
+    assert_eq!(
+        subjects(&commits),
+        vec!["006", "005", "004", "2 commits", "001"]
+    );
+    let parents: Vec<Vec<&str>> = commits
+        .iter()
+        .map(|c| parent_subjects(&folded, c))
+        .collect();
+    assert_eq!(
+        parents,
+        vec![
+            vec!["005"],
+            vec!["004", "2 commits"],
+            vec!["001"],
+            vec!["001"],
+            Vec::<&str>::new(),
+        ]
+    );
+    let row = commits[3];
+    assert_eq!(
+        collapsed_subjects(&repository, &folded, row),
+        vec!["003", "002"]
+    );
+    assert_eq!(folded.folded_merge(&row.commit_hash), Some(&merge_hash));
+    assert!(folded.folded_merge(&merge_hash).is_none());
+    assert!(!folded.has_hidden_commits_between(&merge_hash, &row.commit_hash));
+
+    Ok(())
+}
+
+#[test]
+fn fold_merges_keeps_commits_merged_elsewhere() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["checkout", "-b", "feature"]);
+    git.commit("002", "2024-01-02");
+    git.run(&["checkout", "master"]);
+    git.run_with_date(
+        &["merge", "--no-ff", "feature", "-m", "003"],
+        "2024-01-03T00:00:00+00:00",
+    );
+    git.run(&["checkout", "feature"]);
+    git.commit("004", "2024-01-04");
+    git.run(&["checkout", "master"]);
+    git.run_with_date(
+        &["merge", "--no-ff", "feature", "-m", "005"],
+        "2024-01-05T00:00:00+00:00",
+    );
+    git.run(&["branch", "-d", "feature"]);
+
+    let repository = load(repo_path)?;
+    let merges = FxHashSet::from_iter([
+        find(&repository, "003").commit_hash.clone(),
+        find(&repository, "005").commit_hash.clone(),
+    ]);
+    let folded = repository.fold_merges(&merges);
+
+    // 002 is merged by both of the merge commits, so only 004 is folded into the row of 005
+    let commits = folded.all_commits();
+    assert_eq!(
+        subjects(&commits),
+        vec!["005", "1 commit", "003", "002", "001"]
+    );
+    let parents: Vec<Vec<&str>> = commits
+        .iter()
+        .map(|c| parent_subjects(&folded, c))
+        .collect();
+    assert_eq!(
+        parents,
+        vec![
+            vec!["003", "1 commit"],
+            vec!["002"],
+            vec!["001", "002"],
+            vec!["001"],
+            Vec::<&str>::new(),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn fold_merges_ignores_first_parent_history() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["checkout", "-b", "feature"]);
+    git.commit("002", "2024-01-02");
+    git.commit("003", "2024-01-03");
+    git.run(&["checkout", "master"]);
+    git.run_with_date(
+        &["merge", "--no-ff", "feature", "-m", "004"],
+        "2024-01-04T00:00:00+00:00",
+    );
+
+    let repository = load(repo_path)?;
+    let merge_hash = find(&repository, "004").commit_hash.clone();
+    let folded = repository.fold_merges(&FxHashSet::from_iter([merge_hash]));
+
+    // the first parent 001 is also reachable from the merged branch, but is not folded
+    assert_eq!(
+        subjects(&folded.all_commits()),
+        vec!["004", "2 commits", "001"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn fold_merges_counts_collapsed_commits() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["tag", "v1"]);
+    git.run(&["checkout", "-b", "feature"]);
+    git.commit("002", "2024-01-02");
+    git.commit("003", "2024-01-03");
+    git.commit("004", "2024-01-04");
+    git.run(&["checkout", "master"]);
+    git.run_with_date(
+        &["merge", "--no-ff", "feature", "-m", "005"],
+        "2024-01-05T00:00:00+00:00",
+    );
+    git.run(&["branch", "-d", "feature"]);
+
+    let repository = load(repo_path)?;
+    let simplified = repository.simplify_by_decoration(&FxHashSet::default());
+    assert_eq!(
+        subjects(&simplified.all_commits()),
+        vec!["005", "3 commits", "001"]
+    );
+
+    // the commits collapsed into the row are counted in the folded row
+    let merge_hash = find(&repository, "005").commit_hash.clone();
+    let folded = simplified.fold_merges(&FxHashSet::from_iter([merge_hash]));
+    let commits = folded.all_commits();
+    assert_eq!(subjects(&commits), vec!["005", "3 commits", "001"]);
+    assert_eq!(
+        collapsed_subjects(&repository, &folded, commits[1]),
+        vec!["004", "003", "002"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn refold_lays_out_only_folded_rows() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["checkout", "-b", "feature"]);
+    git.commit("002", "2024-01-02");
+    git.commit("004", "2024-01-04");
+    git.run(&["checkout", "-b", "other", "master"]);
+    git.commit("003", "2024-01-03");
+    git.run(&["checkout", "master"]);
+    git.commit("005", "2024-01-05");
+    git.run_with_date(
+        &["merge", "--no-ff", "feature", "-m", "006"],
+        "2024-01-06T00:00:00+00:00",
+    );
+    git.commit("007", "2024-01-07");
+
+    let repository = load(repo_path)?;
+    let merge_hash = find(&repository, "006").commit_hash.clone();
+    let folded = repository.fold_merges(&FxHashSet::from_iter([merge_hash]));
+
+    let mut incremental = graph::IncrementalLayout::default();
+    let unfolded_graph = calc_graph(&repository, &mut incremental);
+
+    // the line of the row is drawn down in its lane past 003
+    assert!(incremental.refold(&folded));
+    let actual = calc_graph(&folded, &mut incremental);
+    let expected = calc_graph(&folded, &mut graph::IncrementalLayout::default());
+    assert_eq!(actual.commit_pos_map, expected.commit_pos_map);
+    assert_eq!(actual.edges, expected.edges);
+    assert_eq!(actual.max_pos_x, expected.max_pos_x);
+
+    // the rows are restored as they were before folding
+    assert!(incremental.refold(&repository));
+    let actual = calc_graph(&repository, &mut incremental);
+    assert_eq!(actual.commit_pos_map, unfolded_graph.commit_pos_map);
+    assert_eq!(actual.edges, unfolded_graph.edges);
+    assert_eq!(actual.max_pos_x, unfolded_graph.max_pos_x);
+
+    Ok(())
+}
+
+fn find<'a>(repository: &'a Repository, subject: &str) -> &'a Commit {
+    repository
+        .all_commits()
+        .into_iter()
+        .find(|c| c.subject == subject)
+        .unwrap()
+}
+
+fn collapsed_subjects<'a>(
+    repository: &'a Repository,
+    folded: &Repository,
+    commit: &Commit,
+) -> Vec<&'a str> {
+    folded
+        .collapsed_commits(&commit.commit_hash)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|hash| repository.commit(hash))
+        .map(|c| c.subject.as_str())
+        .collect()
+}
+
+fn calc_graph<'a>(
+    repository: &'a Repository,
+    incremental: &mut graph::IncrementalLayout,
+) -> graph::Graph<'a> {
+    let color_set = GraphColorSet::new(&GraphColorConfig::default());
+    graph::calc_graph(
+        repository,
+        &color_set,
+        &GraphLayout::default(),
+        &graph::LaneHint::default(),
+        incremental,
+    )
+}
@@ -0,0 +1,263 @@
+use std::{
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use rstest::rstest;
+
+use crate::{
+    git::{self, GitError, Repository},
+    test_util::{load_repository, load_with_backend, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn git_not_found() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path().join("repo");
+    let bin_path = dir.path().join("bin");
+    fs::create_dir_all(&repo_path)?;
+    fs::create_dir_all(&bin_path)?;
+
+    let git = GitRepository::new(&repo_path);
+    git.init();
+    git.commit("001", "2024-01-01");
+
+    let actual = load_with_fake_git(&repo_path, &bin_path);
+
+    assert!(matches!(actual, Err(GitError::Spawn { .. })), "{actual:?}");
+
+    Ok(())
+}
+
+#[test]
+fn git_log_failed() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path().join("repo");
+    fs::create_dir_all(&repo_path)?;
+
+    let git = GitRepository::new(&repo_path);
+    git.init();
+    git.commit("001", "2024-01-01");
+
+    let bin_path = write_fake_git(
+        dir.path(),
+        "log",
+        "echo 'fatal: bad object HEAD' >&2; exit 128",
+    );
+
+    let actual = load_with_fake_git(&repo_path, &bin_path);
+
+    match actual {
+        Err(GitError::CommandFailed {
+            command,
+            status,
+            stderr,
+        }) => {
+            assert_eq!(command, "git log");
+            assert_eq!(status.code(), Some(128));
+            assert_eq!(stderr, "fatal: bad object HEAD");
+        }
+        _ => panic!("unexpected result: {actual:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn git_log_failed_with_large_stderr() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path().join("repo");
+    fs::create_dir_all(&repo_path)?;
+
+    let git = GitRepository::new(&repo_path);
+    git.init();
+    git.commit("001", "2024-01-01");
+
+    // more than the pipe buffer is written to stderr before stdout is closed
+    let bin_path = write_fake_git(
+        dir.path(),
+        "log",
+        "i=0; while [ $i -lt 2000 ]; do printf '%0100d' 0 >&2; i=$((i + 1)); done; exit 128",
+    );
+
+    let actual = load_with_fake_git(&repo_path, &bin_path);
+
+    match actual {
+        Err(GitError::CommandFailed { stderr, .. }) => {
+            assert_eq!(stderr.len(), 200000);
+        }
+        _ => panic!("unexpected result: {actual:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn git_log_unexpected_output() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path().join("repo");
+    fs::create_dir_all(&repo_path)?;
+
+    let git = GitRepository::new(&repo_path);
+    git.init();
+    git.commit("001", "2024-01-01");
+
+    let bin_path = write_fake_git(dir.path(), "log", "printf 'broken output'");
+
+    let actual = load_with_fake_git(&repo_path, &bin_path);
+
+    match actual {
+        Err(GitError::UnexpectedOutput { command, output }) => {
+            assert_eq!(command, "git log");
+            assert_eq!(output, "broken output");
+        }
+        _ => panic!("unexpected result: {actual:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn git_log_invalid_date() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path().join("repo");
+    fs::create_dir_all(&repo_path)?;
+
+    let git = GitRepository::new(&repo_path);
+    git.init();
+    git.commit("001", "2024-01-01");
+
+    let bin_path = write_fake_git(
+        dir.path(),
+        "log",
+        r"printf '%s\037a\037a@example.com\037yesterday\037c\037c@example.com\037today\037subject\037\037' 0123456789012345678901234567890123456789",
+    );
+
+    let actual = load_with_fake_git(&repo_path, &bin_path);
+
+    assert!(
+        matches!(actual, Err(GitError::UnexpectedOutput { .. })),
+        "{actual:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn git_diff_failed() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path().join("repo");
+    fs::create_dir_all(&repo_path)?;
+
+    let git = GitRepository::new(&repo_path);
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.commit("002", "2024-01-01");
+
+    let bin_path = write_fake_git(
+        dir.path(),
+        "diff",
+        "echo 'fatal: unable to read tree' >&2; exit 128",
+    );
+
+    let repository = load_with_fake_git(&repo_path, &bin_path)?;
+    let commit_hash = repository.all_commits()[0].commit_hash.clone();
+
+    let actual = repository.commit_detail(&commit_hash);
+
+    match actual {
+        Err(GitError::CommandFailed {
+            command, stderr, ..
+        }) => {
+            assert_eq!(command, "git diff");
+            assert_eq!(stderr, "fatal: unable to read tree");
+        }
+        _ => panic!("unexpected result: {actual:?}"),
+    }
+
+    Ok(())
+}
+
+#[rstest]
+#[case::subprocess(git::GitBackendType::Subprocess)]
+#[case::gix(git::GitBackendType::Gix)]
+fn not_a_repository(#[case] backend: git::GitBackendType) -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    // prevent the discovery from reaching a repository that contains the temporary directory
+    fs::write(repo_path.join(".git"), "gitdir: /nonexistent")?;
+
+    let actual = load_with_backend(repo_path, backend);
+
+    assert!(matches!(actual, Err(GitError::NotRepository)), "{actual:?}");
+
+    Ok(())
+}
+
+#[test]
+fn gix_open_failed() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+
+    let git = GitRepository::new(repo_path);
+    git.init();
+    git.commit("001", "2024-01-01");
+    // the object format is not supported
+    fs::write(
+        repo_path.join(".git").join("config"),
+        "[core]\n\trepositoryformatversion = 1\n[extensions]\n\tobjectformat = unknown\n",
+    )?;
+
+    let actual = load_with_backend(repo_path, git::GitBackendType::Gix);
+
+    // the repository is found but cannot be opened
+    assert!(
+        matches!(actual, Err(GitError::Gix { operation, .. }) if operation == "open repository"),
+        "{actual:?}"
+    );
+
+    Ok(())
+}
+
+// Loads the repository with the git processes looked up in the bin directory.
+fn load_with_fake_git(repo_path: &Path, bin_path: &Path) -> git::Result<Repository> {
+    let path = git::RepositoryPath::new(repo_path).with_env("PATH", bin_path.as_os_str());
+    load_repository(
+        &path,
+        git::GitBackendType::Subprocess,
+        git::SortCommit::Chronological,
+        None,
+        false,
+        &git::RevisionFilter::default(),
+    )
+}
+
+// Writes a fake git binary that runs the script for the subcommand and delegates others to the real git.
+fn write_fake_git(dir: &Path, subcommand: &str, script: &str) -> PathBuf {
+    let output = Command::new("sh")
+        .args(["-c", "command -v git"])
+        .output()
+        .unwrap();
+    let real_git = String::from_utf8(output.stdout).unwrap().trim().to_string();
+
+    let bin_path = dir.join("bin");
+    fs::create_dir_all(&bin_path).unwrap();
+
+    let git_path = bin_path.join("git");
+    let content = format!(
+        "#!/bin/sh\n\
+         if [ \"$1\" = \"{subcommand}\" ]; then\n\
+         {script}\n\
+         exit\n\
+         fi\n\
+         exec {real_git} \"$@\"\n"
+    );
+    fs::write(&git_path, content).unwrap();
+    fs::set_permissions(&git_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+    bin_path
+}
@@ -1,12 +1,13 @@
-use std::{path::Path, process::Command};
+use std::path::Path;
 
-use chrono::{DateTime, Days, NaiveDate, TimeZone, Utc};
+use chrono::{Days, TimeZone, Utc};
 use image::{GenericImage, GenericImageView};
 use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
     color, config, git,
     graph::{self, Edge, GraphRowImage},
+    test_util::{load_repository, GitRepository},
 };
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
@@ -14,6 +15,10 @@ type TestResult = Result<(), Box<dyn std::error::Error>>;
 const OUTPUT_DIR: &str = "./out/graph";
 const SNAPSHOT_DIR: &str = "./tests/graph";
 
+// the graphs of all backends are compared with the same snapshots
+const BACKENDS: [git::GitBackendType; 2] =
+    [git::GitBackendType::Subprocess, git::GitBackendType::Gix];
+
 #[test]
 fn straight_001() -> TestResult {
     let dir = tempfile::tempdir()?;
@@ -1271,90 +1276,69 @@ fn complex_001() -> TestResult {
     Ok(())
 }
 
-struct GitRepository<'a> {
-    path: &'a Path,
-}
-
-impl GitRepository<'_> {
-    fn new(path: &'_ Path) -> GitRepository<'_> {
-        GitRepository { path }
-    }
+#[test]
+fn range_001() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
 
-    fn init(&self) {
-        self.run(&["init", "-b", "master"], "");
-    }
+    let git = &GitRepository::new(repo_path);
 
-    fn commit(&self, message: &str, date: &str) {
-        let datetime_str = parse_date(date).to_rfc3339();
-        self.run(&["commit", "--allow-empty", "-m", message], &datetime_str);
-    }
+    git.init();
 
-    fn checkout(&self, branch_name: &str) {
-        self.run(&["checkout", branch_name], "");
-    }
+    git.commit("001", "2024-01-01");
+    git.commit("002", "2024-01-02");
 
-    fn checkout_b(&self, branch_name: &str) {
-        self.run(&["checkout", "-b", branch_name], "");
-    }
+    git.checkout_b("10");
+    git.commit("011", "2024-02-01");
+    git.commit("012", "2024-02-02");
 
-    fn checkout_orphan(&self, branch_name: &str) {
-        self.run(&["checkout", "--orphan", branch_name], "");
-    }
+    git.checkout("master");
+    git.commit("003", "2024-02-03");
 
-    fn merge(&self, branch_names: &[&str], date: &str) {
-        let datetime_str = parse_date(date).to_rfc3339();
-        let mut args = vec!["merge", "--no-ff", "--no-log"];
-        args.extend_from_slice(branch_names);
-        self.run(&args, &datetime_str);
-    }
+    git.checkout_b("20");
+    git.commit("021", "2024-02-04");
 
-    fn branch_d(&self, branch_name: &str) {
-        self.run(&["branch", "-D", branch_name], "");
-    }
+    git.checkout("10");
+    git.merge(&["20"], "2024-02-05");
+    git.commit("013", "2024-02-06");
 
-    fn stash(&self, date: &str) {
-        let dummy_file_path = self.path.join("stash.txt");
-        std::fs::File::create(dummy_file_path).unwrap();
+    git.checkout("master");
+    git.commit("004", "2024-02-07");
 
-        let datetime_str = parse_date(date).to_rfc3339();
-        self.run(&["stash", "--include-untracked"], &datetime_str);
-    }
+    git.log();
 
-    fn rev_parse_head(&self) -> String {
-        let output = self.run(&["rev-parse", "HEAD"], "");
-        String::from_utf8(output.stdout).unwrap().trim().to_string()
-    }
+    let options = &[
+        GenerateGraphOption::new(
+            "range_001_chrono",
+            git::SortCommit::Chronological,
+            graph::GraphStyle::Rounded,
+        ),
+        GenerateGraphOption::new(
+            "range_001_range",
+            git::SortCommit::Chronological,
+            graph::GraphStyle::Rounded,
+        )
+        .with_revisions(&["master..10"]),
+        GenerateGraphOption::new(
+            "range_001_merge",
+            git::SortCommit::Chronological,
+            graph::GraphStyle::Rounded,
+        )
+        .with_revisions(&["20..10"]),
+        GenerateGraphOption::new(
+            "range_001_multiple",
+            git::SortCommit::Chronological,
+            graph::GraphStyle::Rounded,
+        )
+        .with_revisions(&["10", "20", "^master~2"]),
+    ];
 
-    fn log(&self) {
-        let output = self.run(&["log", "--pretty=format:%h %s", "--graph", "--all"], "");
-        println!("{}", String::from_utf8(output.stdout).unwrap())
-    }
+    copy_git_dir(repo_path, "range_001");
 
-    fn run(&self, args: &[&str], datetime_str: &str) -> std::process::Output {
-        let out = Command::new("git")
-            .args(args)
-            .current_dir(self.path)
-            .env("GIT_AUTHOR_NAME", "Author Name")
-            .env("GIT_AUTHOR_EMAIL", "author@example.com")
-            .env("GIT_AUTHOR_DATE", datetime_str)
-            .env("GIT_COMMITTER_NAME", "Committer Name")
-            .env("GIT_COMMITTER_EMAIL", "committer@example.com")
-            .env("GIT_COMMITTER_DATE", datetime_str)
-            .env("GIT_CONFIG_NOSYSTEM", "true")
-            .env("HOME", "/dev/null")
-            .output()
-            .unwrap_or_else(|_| panic!("failed to execute git {}", args.join(" ")));
-        println!("git {}: returned {}", args.join(" "), out.status,);
-        out
-    }
-}
+    generate_and_output_graph_images(repo_path, options);
+    assert_graph_images(options);
 
-fn parse_date(date: &str) -> DateTime<Utc> {
-    let dt = NaiveDate::parse_from_str(date, "%Y-%m-%d")
-        .unwrap()
-        .and_hms_opt(1, 2, 3)
-        .unwrap();
-    Utc.from_utc_datetime(&dt)
+    Ok(())
 }
 
 struct GenerateGraphOption {
@@ -1362,6 +1346,7 @@ struct GenerateGraphOption {
     sort: git::SortCommit,
     style: graph::GraphStyle,
     max_count: Option<usize>,
+    revisions: Vec<&'static str>,
 }
 
 impl GenerateGraphOption {
@@ -1375,6 +1360,7 @@ impl GenerateGraphOption {
             sort,
             style,
             max_count: None,
+            revisions: Vec::new(),
         }
     }
 
@@ -1382,23 +1368,57 @@ impl GenerateGraphOption {
         self.max_count = Some(max_count);
         self
     }
+
+    fn with_revisions(mut self, revisions: &[&'static str]) -> GenerateGraphOption {
+        self.revisions = revisions.to_vec();
+        self
+    }
 }
 
 fn generate_and_output_graph_images(repo_path: &Path, options: &[GenerateGraphOption]) {
-    for option in options {
-        generate_and_output_graph_image(repo_path, option);
+    for backend in BACKENDS {
+        for option in options {
+            generate_and_output_graph_image(repo_path, option, backend);
+        }
     }
 }
 
-fn generate_and_output_graph_image<P: AsRef<Path>>(path: P, option: &GenerateGraphOption) {
+fn output_name(option: &GenerateGraphOption, backend: git::GitBackendType) -> String {
+    match backend {
+        git::GitBackendType::Subprocess => option.output_name.to_string(),
+        git::GitBackendType::Gix => format!("{}_gix", option.output_name),
+    }
+}
+
+fn generate_and_output_graph_image<P: AsRef<Path>>(
+    path: P,
+    option: &GenerateGraphOption,
+    backend: git::GitBackendType,
+) {
     // Build graphs in the same way as application
     let max_count = option.max_count;
     let graph_color_config = config::GraphColorConfig::default();
     let graph_color_set = color::GraphColorSet::new(&graph_color_config);
     let cell_width_type = graph::CellWidthType::Double;
-    let repository = git::Repository::load(path.as_ref(), option.sort, max_count, true).unwrap();
-    let graph = graph::calc_graph(&repository);
-    let image_params = graph::ImageParams::new(&graph_color_set, cell_width_type);
+    let revisions = option.revisions.iter().map(|r| r.to_string()).collect();
+    let filter = git::RevisionFilter::new(revisions, Vec::new());
+    let repository = load_repository(
+        &git::RepositoryPath::new(path.as_ref()),
+        backend,
+        option.sort,
+        max_count,
+        true,
+        &filter,
+    )
+    .unwrap();
+    let graph = graph::calc_graph(
+        &repository,
+        &graph_color_set,
+        &graph::GraphLayout::default(),
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    let image_params = graph::ImageParams::new(&graph_color_set, cell_width_type, None);
     let drawing_pixels = graph::DrawingPixels::new(&image_params);
     let graph_image = build_graph_image(&graph, &image_params, &drawing_pixels, option.style);
 
@@ -1417,8 +1437,7 @@ fn generate_and_output_graph_image<P: AsRef<Path>>(path: P, option: &GenerateGra
 
         // write graph
         let graph_row_image = &graph_image.images[edges];
-        let image = image::load_from_memory(&graph_row_image.bytes).unwrap();
-        img_buf.copy_from(&image, 0, y).unwrap();
+        img_buf.copy_from(&graph_row_image.buffer, 0, y).unwrap();
 
         // write hash and date
         let commit = &graph.commits[i];
@@ -1448,7 +1467,7 @@ fn generate_and_output_graph_image<P: AsRef<Path>>(path: P, option: &GenerateGra
 
     // Save
     create_output_dirs(OUTPUT_DIR);
-    let file_name = format!("{}/{}.png", OUTPUT_DIR, option.output_name);
+    let file_name = format!("{}/{}.png", OUTPUT_DIR, output_name(option, backend));
     image::save_buffer(
         file_name,
         &img_buf,
@@ -1486,9 +1505,12 @@ fn build_graph_image(
         .into_iter()
         .map(|(pos_x, edges)| {
             let graph_row_image = graph::calc_graph_row_image(
+                Some(pos_x),
                 pos_x,
+                false,
                 cell_count,
                 edges,
+                None,
                 image_params,
                 drawing_pixels,
                 graph_style,
@@ -1515,9 +1537,10 @@ fn copy_git_dir(path: &Path, name: &str) {
 }
 
 fn assert_graph_images(options: &[GenerateGraphOption]) {
-    let errors: Vec<_> = options
+    let errors: Vec<_> = BACKENDS
         .iter()
-        .map(compare_graph_image)
+        .flat_map(|backend| options.iter().map(|option| (option, *backend)))
+        .map(|(option, backend)| compare_graph_image(option, backend))
         .filter_map(Result::err)
         .collect();
     if !errors.is_empty() {
@@ -1525,16 +1548,20 @@ fn assert_graph_images(options: &[GenerateGraphOption]) {
     }
 }
 
-fn compare_graph_image(option: &GenerateGraphOption) -> Result<(), String> {
+fn compare_graph_image(
+    option: &GenerateGraphOption,
+    backend: git::GitBackendType,
+) -> Result<(), String> {
     let expected_file = format!("{}/{}.png", SNAPSHOT_DIR, option.output_name);
     let expected_img = image::open(expected_file).unwrap();
 
-    let actual_file = format!("{}/{}.png", OUTPUT_DIR, option.output_name);
+    let output_name = output_name(option, backend);
+    let actual_file = format!("{OUTPUT_DIR}/{output_name}.png");
     let actual_img = image::open(actual_file).unwrap();
 
     if actual_img.dimensions() != expected_img.dimensions() {
         return Err(format!(
-            "Image dimensions are different. expected: {:?}, actual: {:?}",
+            "Image dimensions are different ({output_name}). expected: {:?}, actual: {:?}",
             expected_img.dimensions(),
             actual_img.dimensions()
         ));
@@ -1559,7 +1586,7 @@ fn compare_graph_image(option: &GenerateGraphOption) -> Result<(), String> {
     }
 
     if diff {
-        let diff_file = format!("{}/{}_diff.png", OUTPUT_DIR, option.output_name);
+        let diff_file = format!("{OUTPUT_DIR}/{output_name}_diff.png");
         image::save_buffer(
             diff_file.clone(),
             &img_buf,
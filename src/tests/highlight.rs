@@ -0,0 +1,227 @@
+use crate::{
+    color::GraphColorSet,
+    config::GraphColorConfig,
+    git::Repository,
+    graph::{self, EdgeType, GraphHighlight, GraphLayout, HighlightMode},
+    test_util::{load, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn highlight_ancestors() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    create_merge(&git);
+
+    let repository = load(repo_path)?;
+    let rows = highlighted_rows(&repository, "005 merge", HighlightMode::Ancestors);
+    assert_eq!(
+        rows,
+        vec![
+            ("006", false, vec![]),
+            (
+                "005 merge",
+                true,
+                vec![EdgeType::Down, EdgeType::Right, EdgeType::RightTop]
+            ),
+            (
+                "004",
+                true,
+                vec![EdgeType::Up, EdgeType::Down, EdgeType::Vertical]
+            ),
+            (
+                "003",
+                true,
+                vec![EdgeType::Vertical, EdgeType::Up, EdgeType::Down]
+            ),
+            (
+                "002",
+                true,
+                vec![EdgeType::Vertical, EdgeType::Up, EdgeType::Down]
+            ),
+            (
+                "001",
+                true,
+                vec![EdgeType::Up, EdgeType::Right, EdgeType::RightBottom]
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn highlight_first_parent() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    create_merge(&git);
+
+    let repository = load(repo_path)?;
+    let rows = highlighted_rows(&repository, "005 merge", HighlightMode::FirstParent);
+    // the side branch crossing the rows is dimmed
+    assert_eq!(
+        rows,
+        vec![
+            ("006", false, vec![]),
+            ("005 merge", true, vec![EdgeType::Down]),
+            ("004", true, vec![EdgeType::Up, EdgeType::Down]),
+            ("003", false, vec![EdgeType::Vertical]),
+            ("002", false, vec![EdgeType::Vertical]),
+            ("001", true, vec![EdgeType::Up]),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn highlight_descendants() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    create_merge(&git);
+
+    let repository = load(repo_path)?;
+    let rows = highlighted_rows(&repository, "003", HighlightMode::Descendants);
+    assert_eq!(
+        rows,
+        vec![
+            ("006", true, vec![EdgeType::Down]),
+            (
+                "005 merge",
+                true,
+                vec![EdgeType::Up, EdgeType::Right, EdgeType::RightTop]
+            ),
+            ("004", false, vec![EdgeType::Vertical]),
+            ("003", true, vec![EdgeType::Up]),
+            ("002", false, vec![]),
+            ("001", false, vec![]),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn highlight_toggle_cycles_modes() {
+    let mut mode = None;
+    let mut modes = Vec::new();
+    for _ in 0..4 {
+        mode = HighlightMode::toggle(mode);
+        modes.push(mode);
+    }
+    assert_eq!(
+        modes,
+        vec![
+            Some(HighlightMode::Ancestors),
+            Some(HighlightMode::FirstParent),
+            Some(HighlightMode::Descendants),
+            None,
+        ]
+    );
+}
+
+#[test]
+fn highlight_searches_rows_shown() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    create_merge(&git);
+
+    let repository = load(repo_path)?;
+    let color_set = GraphColorSet::new(&GraphColorConfig::default());
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &GraphLayout::default(),
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    let hash = |subject: &str| {
+        &repository
+            .all_commits()
+            .into_iter()
+            .find(|c| c.subject == subject)
+            .unwrap()
+            .commit_hash
+    };
+    let mut highlight = GraphHighlight::new(
+        &repository,
+        &graph,
+        hash("005 merge"),
+        HighlightMode::Ancestors,
+    );
+
+    // the ancestors below the rows are not searched yet
+    highlight.search_rows(0..3);
+    assert!(highlight.contains_commit(hash("004")));
+    assert!(!highlight.contains_commit(hash("003")));
+
+    highlight.search_rows(3..6);
+    for subject in ["005 merge", "004", "003", "002", "001"] {
+        assert!(highlight.contains_commit(hash(subject)), "{subject}");
+    }
+    assert!(!highlight.contains_commit(hash("006")));
+
+    Ok(())
+}
+
+// 003 is merged into master by 005, after 004 is committed.
+fn create_merge(git: &GitRepository) {
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["checkout", "-b", "side"]);
+    git.commit("002", "2024-01-02");
+    git.commit("003", "2024-01-03");
+    git.run(&["checkout", "master"]);
+    git.commit("004", "2024-01-04");
+    git.run_with_date(
+        &["merge", "--no-ff", "side", "-m", "005 merge"],
+        "2024-01-05T00:00:00+00:00",
+    );
+    git.commit("006", "2024-01-06");
+}
+
+// Returns whether the commit of each row is highlighted, and the types of the highlighted edges in the row.
+fn highlighted_rows<'a>(
+    repository: &'a Repository,
+    subject: &str,
+    mode: HighlightMode,
+) -> Vec<(&'a str, bool, Vec<EdgeType>)> {
+    let color_set = GraphColorSet::new(&GraphColorConfig::default());
+    let graph = graph::calc_graph(
+        repository,
+        &color_set,
+        &GraphLayout::default(),
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    let commit = repository
+        .all_commits()
+        .into_iter()
+        .find(|c| c.subject == subject)
+        .unwrap();
+    let mut highlight = GraphHighlight::new(repository, &graph, &commit.commit_hash, mode);
+    highlight.search_rows(0..graph.commits.len());
+
+    repository
+        .all_commits()
+        .into_iter()
+        .map(|c| {
+            let (_, pos_y) = graph.commit_pos_map[&c.commit_hash];
+            let edges = graph.edges[pos_y]
+                .iter()
+                .filter(|e| highlight.contains_edge(e))
+                .map(|e| e.edge_type)
+                .collect();
+            (
+                c.subject.as_str(),
+                highlight.contains_commit(&c.commit_hash),
+                edges,
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,61 @@
+use rstest::rstest;
+
+use crate::{
+    protocol::{CellSize, ImageProtocol, TerminalCapabilities},
+    ImageProtocolType,
+};
+
+const CELL_SIZE: CellSize = CellSize {
+    width: 9,
+    height: 18,
+};
+
+fn queried() -> TerminalCapabilities {
+    TerminalCapabilities {
+        kitty_graphics: false,
+        sixel: true,
+        cell_size: Some(CELL_SIZE),
+    }
+}
+
+// the images are drawn in the pixel size of the cells with all the protocols, even if the terminal is not queried
+#[rstest]
+#[case::iterm(ImageProtocolType::Iterm)]
+#[case::kitty(ImageProtocolType::Kitty)]
+#[case::kitty_unicode(ImageProtocolType::KittyUnicode)]
+fn fixed_protocol_reads_cell_size_from_window(#[case] protocol: ImageProtocolType) {
+    let capabilities = ImageProtocolType::terminal_capabilities(
+        Some(protocol),
+        || panic!("the terminal must not be queried"),
+        || Some(CELL_SIZE),
+    );
+    assert_eq!(capabilities.cell_size, Some(CELL_SIZE));
+
+    let image_protocol = ImageProtocolType::into_protocol(Some(protocol), &capabilities);
+    assert!(!matches!(
+        image_protocol,
+        ImageProtocol::Sixel { .. } | ImageProtocol::Text
+    ));
+}
+
+#[rstest]
+#[case::auto(Some(ImageProtocolType::Auto))]
+#[case::sixel(Some(ImageProtocolType::Sixel))]
+#[case::none(None)]
+fn detected_protocol_queries_terminal(#[case] protocol: Option<ImageProtocolType>) {
+    let capabilities =
+        ImageProtocolType::terminal_capabilities(protocol, queried, || unreachable!());
+    assert_eq!(capabilities, queried());
+}
+
+#[test]
+fn sixel_encodes_in_queried_cell_size() {
+    let capabilities =
+        ImageProtocolType::terminal_capabilities(Some(ImageProtocolType::Sixel), queried, || None);
+    let image_protocol =
+        ImageProtocolType::into_protocol(Some(ImageProtocolType::Sixel), &capabilities);
+    assert!(matches!(
+        image_protocol,
+        ImageProtocol::Sixel { cell_size } if cell_size == CELL_SIZE
+    ));
+}
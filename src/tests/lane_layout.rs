@@ -0,0 +1,380 @@
+use std::path::Path;
+
+use rstest::rstest;
+
+use crate::{
+    color::GraphColorSet,
+    config::GraphColorConfig,
+    git::{self, Repository},
+    graph::{self, EdgeType, GraphLayout},
+    test_util::{commit_lanes, load_repository, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn compact_lanes_use_vacant_lane_for_missing_parent() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    create_branches(&git);
+
+    // the merged parent of 005 is not loaded
+    let repository = load(repo_path)?;
+    let color_set = GraphColorSet::new(&GraphColorConfig::default());
+
+    // the line to the missing parent is drawn to the right of the line of 006
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &GraphLayout::default(),
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        missing_parent_lane(&repository, &graph, "005 merge"),
+        Some(3)
+    );
+    assert_eq!(graph.max_pos_x, 3);
+
+    // the lane of 007 is vacant below 004
+    let layout = GraphLayout {
+        compact_lanes: true,
+        ..Default::default()
+    };
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &layout,
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        missing_parent_lane(&repository, &graph, "005 merge"),
+        Some(1)
+    );
+    assert_eq!(graph.max_pos_x, 2);
+
+    Ok(())
+}
+
+#[test]
+fn compact_lanes_reserve_lane_of_merged_branch() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["checkout", "-b", "feature"]);
+    git.commit("002", "2024-01-02");
+    git.run(&["checkout", "master"]);
+    git.commit("003", "2024-01-03");
+    git.run(&["checkout", "-b", "other"]);
+    git.commit("004", "2024-01-04");
+    git.run(&["checkout", "master"]);
+    git.commit("005", "2024-01-05");
+    git.run_with_date(
+        &["merge", "--no-ff", "feature", "-m", "006 merge"],
+        "2024-01-06T00:00:00+00:00",
+    );
+
+    let repository = load_repository(
+        &git::RepositoryPath::new(repo_path),
+        git::GitBackendType::Subprocess,
+        git::SortCommit::Chronological,
+        None,
+        false,
+        &git::RevisionFilter::default(),
+    )?;
+    let color_set = GraphColorSet::new(&GraphColorConfig::default());
+
+    // the merge is drawn in a detour around 004, which takes the lane of 002 above it
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &GraphLayout::default(),
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        commit_lanes(&repository, &graph),
+        vec![
+            ("006 merge", 0),
+            ("005", 0),
+            ("004", 1),
+            ("003", 0),
+            ("002", 1),
+            ("001", 0)
+        ]
+    );
+    assert!(graph.edges[0].iter().any(|e| e.pos_x == 2));
+
+    // the lane of 002 is reserved from the merge, so the line goes straight down
+    let layout = GraphLayout {
+        compact_lanes: true,
+        ..Default::default()
+    };
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &layout,
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        commit_lanes(&repository, &graph),
+        vec![
+            ("006 merge", 0),
+            ("005", 0),
+            ("004", 2),
+            ("003", 0),
+            ("002", 1),
+            ("001", 0)
+        ]
+    );
+    assert!(graph.edges[0].iter().all(|e| e.pos_x <= 1));
+    assert_eq!(graph.max_pos_x, 2);
+
+    Ok(())
+}
+
+#[test]
+fn compact_lanes_pack_branches_kept_in_lanes_by_hint() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.commit("002", "2024-01-02");
+    git.run(&["checkout", "-b", "feature", "HEAD~1"]);
+    git.commit("003", "2024-01-03");
+    let repository = load_repository(
+        &git::RepositoryPath::new(repo_path),
+        git::GitBackendType::Subprocess,
+        git::SortCommit::Chronological,
+        None,
+        false,
+        &git::RevisionFilter::default(),
+    )?;
+    let color_set = GraphColorSet::new(&GraphColorConfig::default());
+    let lane_hint = graph::calc_graph(
+        &repository,
+        &color_set,
+        &GraphLayout::default(),
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    )
+    .lane_hint();
+
+    git.run(&["checkout", "-b", "hotfix", "master~1"]);
+    git.commit("004", "2024-01-04");
+    git.run(&["checkout", "master"]);
+    git.commit("005", "2024-01-05");
+    let repository = load_repository(
+        &git::RepositoryPath::new(repo_path),
+        git::GitBackendType::Subprocess,
+        git::SortCommit::Chronological,
+        None,
+        false,
+        &git::RevisionFilter::default(),
+    )?;
+
+    // the commits are not kept to the right of the vacant lanes
+    let layout = GraphLayout {
+        compact_lanes: true,
+        ..Default::default()
+    };
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &layout,
+        &lane_hint,
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        commit_lanes(&repository, &graph),
+        vec![("005", 0), ("004", 1), ("003", 2), ("002", 0), ("001", 0)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn max_lanes_elide_lanes_into_overflow_column() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    create_branches(&git);
+
+    let repository = load(repo_path)?;
+    let color_set = GraphColorSet::new(&GraphColorConfig::default());
+    let layout = GraphLayout {
+        max_lanes: Some(2),
+        ..Default::default()
+    };
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &layout,
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+
+    assert_eq!(graph.max_pos_x, 2);
+    let rows: Vec<(&str, usize, Vec<EdgeType>)> = repository
+        .all_commits()
+        .into_iter()
+        .map(|c| {
+            let (pos_x, pos_y) = graph.commit_pos_map[&c.commit_hash];
+            let edges = &graph.edges[pos_y];
+            assert!(edges.iter().all(|e| e.pos_x <= 2));
+            let overflow_edges = edges
+                .iter()
+                .filter(|e| e.pos_x == 2)
+                .inspect(|e| assert!(e.dotted))
+                .map(|e| e.edge_type)
+                .collect();
+            (c.subject.as_str(), pos_x, overflow_edges)
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            ("008", 0, vec![]),
+            ("007", 1, vec![]),
+            ("006", 2, vec![EdgeType::Down]),
+            ("004", 0, vec![EdgeType::Vertical]),
+            ("005 merge", 0, vec![EdgeType::Vertical]),
+            ("003", 0, vec![EdgeType::Vertical]),
+        ]
+    );
+
+    // the lanes fitting in the limit are not changed
+    let layout = GraphLayout {
+        max_lanes: Some(4),
+        ..Default::default()
+    };
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &layout,
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(graph.max_pos_x, 3);
+    assert!(graph.edges.iter().flatten().all(|e| !e.dotted));
+
+    Ok(())
+}
+
+#[rstest]
+#[case::all_refs(&[], false)]
+#[case::limited_revisions(&["master", "long", "short", "^side"], false)]
+#[case::limited_revisions_compact_lanes(&["master", "long", "short", "^side"], true)]
+fn incremental_layout_matches_full_layout(
+    #[case] revisions: &[&str],
+    #[case] compact_lanes: bool,
+) -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    create_branches(&git);
+
+    let revisions = revisions.iter().map(|r| r.to_string()).collect();
+    let (mut repository, stream) = Repository::load_without_commits(
+        &git::RepositoryPath::new(repo_path),
+        git::GitBackendType::Subprocess,
+        git::SortCommit::Chronological,
+        None,
+        false,
+        &git::RevisionFilter::new(revisions, Vec::new()),
+    )?;
+    let color_set = GraphColorSet::new(&GraphColorConfig::default());
+    let layout = GraphLayout {
+        compact_lanes,
+        ..Default::default()
+    };
+    let lane_hint = graph::LaneHint::default();
+    let mut incremental = graph::IncrementalLayout::default();
+
+    // the parents of the commits in each batch are loaded in the later batches
+    stream.read(1, |commits| {
+        repository.append_commits(commits);
+        let actual = graph::calc_graph(
+            &repository,
+            &color_set,
+            &layout,
+            &lane_hint,
+            &mut incremental,
+        );
+        let expected = graph::calc_graph(
+            &repository,
+            &color_set,
+            &layout,
+            &lane_hint,
+            &mut graph::IncrementalLayout::default(),
+        );
+        assert_eq!(actual.commit_pos_map, expected.commit_pos_map);
+        assert_eq!(actual.edges, expected.edges);
+        assert_eq!(actual.max_pos_x, expected.max_pos_x);
+        true
+    })?;
+
+    Ok(())
+}
+
+// The line of 007 ends at 004 above the merge 005, while the line of 006 continues to 003 below it.
+fn create_branches(git: &GitRepository) {
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["checkout", "-b", "side"]);
+    git.commit("002", "2024-01-02");
+    git.run(&["checkout", "master"]);
+    git.commit("003", "2024-01-03");
+    git.run_with_date(
+        &["merge", "--no-ff", "side", "-m", "005 merge"],
+        "2024-01-04T00:00:00+00:00",
+    );
+    git.commit("004", "2024-01-05");
+    git.run(&["checkout", "-b", "long", "HEAD~2"]);
+    git.commit("006", "2024-01-06");
+    git.run(&["checkout", "-b", "short", "master"]);
+    git.commit("007", "2024-01-07");
+    git.run(&["checkout", "master"]);
+    git.commit("008", "2024-01-08");
+}
+
+// Loads the commits down to 003, excluding the side branch merged into 005.
+fn load(path: &Path) -> git::Result<Repository> {
+    let revisions = ["master", "long", "short", "^side"]
+        .iter()
+        .map(|r| r.to_string())
+        .collect();
+    load_repository(
+        &git::RepositoryPath::new(path),
+        git::GitBackendType::Subprocess,
+        git::SortCommit::Chronological,
+        None,
+        false,
+        &git::RevisionFilter::new(revisions, Vec::new()),
+    )
+}
+
+// Returns the lane of the line from the commit to its merged parent which is not loaded.
+fn missing_parent_lane(
+    repository: &Repository,
+    graph: &graph::Graph,
+    subject: &str,
+) -> Option<usize> {
+    let commit = repository
+        .all_commits()
+        .into_iter()
+        .find(|c| c.subject == subject)?;
+    let (_, pos_y) = graph.commit_pos_map[&commit.commit_hash];
+    graph.edges[pos_y]
+        .iter()
+        .find(|e| e.edge_type == EdgeType::RightTop)
+        .map(|e| e.pos_x)
+}
@@ -0,0 +1,128 @@
+use std::fs;
+
+use crate::{
+    git::{self, CommitHash, Repository},
+    test_util::{load_repository, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn load_in_batches() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.commit("002", "2024-01-01");
+    fs::write(repo_path.join("file"), "stash")?;
+    git.run(&["add", "file"]);
+    git.run(&["stash"]);
+    git.commit("003", "2024-01-01");
+    git.run(&["checkout", "-b", "feature", "HEAD~2"]);
+    git.commit("004", "2024-01-01");
+    git.run(&["checkout", "master"]);
+    git.run(&["merge", "--no-ff", "feature", "-m", "005"]);
+
+    let expected = load_repository(
+        &git::RepositoryPath::new(repo_path),
+        git::GitBackendType::Subprocess,
+        git::SortCommit::Chronological,
+        None,
+        false,
+        &git::RevisionFilter::default(),
+    )?;
+
+    let (mut actual, stream) = Repository::load_without_commits(
+        &git::RepositoryPath::new(repo_path),
+        git::GitBackendType::Subprocess,
+        git::SortCommit::Chronological,
+        None,
+        false,
+        &git::RevisionFilter::default(),
+    )?;
+    let mut batches = 0;
+    stream.read(1, |commits| {
+        assert_eq!(commits.len(), 1);
+        batches += 1;
+        actual.append_commits(commits);
+        true
+    })?;
+
+    assert_eq!(batches, 5);
+    assert_eq!(commit_hashes(&actual), commit_hashes(&expected));
+    // the stash is merged after its base commit has been loaded
+    assert_eq!(actual.all_commits().len(), 6);
+    for commit in expected.all_commits() {
+        let hash = &commit.commit_hash;
+        assert_eq!(actual.parents_hash(hash), expected.parents_hash(hash));
+        assert_eq!(actual.children_hash(hash), expected.children_hash(hash));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn load_cancelled() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    for i in 0..10 {
+        git.commit(&format!("{i:03}"), "2024-01-01");
+    }
+
+    let (_, stream) = Repository::load_without_commits(
+        &git::RepositoryPath::new(repo_path),
+        git::GitBackendType::Subprocess,
+        git::SortCommit::Chronological,
+        None,
+        false,
+        &git::RevisionFilter::default(),
+    )?;
+    let mut loaded = 0;
+    stream.read(3, |commits| {
+        loaded += commits.len();
+        false
+    })?;
+
+    assert_eq!(loaded, 3);
+
+    Ok(())
+}
+
+#[test]
+fn load_no_commits() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+
+    let (_, stream) = Repository::load_without_commits(
+        &git::RepositoryPath::new(repo_path),
+        git::GitBackendType::Subprocess,
+        git::SortCommit::Chronological,
+        None,
+        false,
+        &git::RevisionFilter::default(),
+    )?;
+    let actual = stream.read(1, |_| true);
+
+    assert!(
+        matches!(actual, Err(git::GitError::NoCommits)),
+        "{actual:?}"
+    );
+
+    Ok(())
+}
+
+fn commit_hashes(repository: &Repository) -> Vec<CommitHash> {
+    repository
+        .all_commits()
+        .iter()
+        .map(|c| c.commit_hash.clone())
+        .collect()
+}
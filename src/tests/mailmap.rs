@@ -1,6 +1,8 @@
 use std::{fs, path::Path, process::Command};
 
-use crate::git::{self, Repository};
+use rstest::rstest;
+
+use crate::{git, test_util::load_repository};
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
 
@@ -16,8 +18,12 @@ const MAPPED_AUTHOR_EMAIL: &str = "new-author@example.com";
 const MAPPED_COMMITTER_NAME: &str = "New Committer";
 const MAPPED_COMMITTER_EMAIL: &str = "new-committer@example.com";
 
-#[test]
-fn mailmap_enabled_rewrites_author_and_committer() -> TestResult {
+#[rstest]
+#[case::subprocess(git::GitBackendType::Subprocess)]
+#[case::gix(git::GitBackendType::Gix)]
+fn mailmap_enabled_rewrites_author_and_committer(
+    #[case] backend: git::GitBackendType,
+) -> TestResult {
     let dir = tempfile::tempdir()?;
     let repo_path = dir.path();
     let git = TestGit::new(repo_path);
@@ -26,7 +32,15 @@ fn mailmap_enabled_rewrites_author_and_committer() -> TestResult {
     git.commit("commit");
     write_mailmap(repo_path);
 
-    let repository = Repository::load(repo_path, git::SortCommit::Chronological, None, true)?;
+    let filter = git::RevisionFilter::default();
+    let repository = load_repository(
+        &git::RepositoryPath::new(repo_path),
+        backend,
+        git::SortCommit::Chronological,
+        None,
+        true,
+        &filter,
+    )?;
     let commits = repository.all_commits();
     let commit = commits.first().unwrap();
 
@@ -38,8 +52,10 @@ fn mailmap_enabled_rewrites_author_and_committer() -> TestResult {
     Ok(())
 }
 
-#[test]
-fn mailmap_disabled_keeps_raw_identity() -> TestResult {
+#[rstest]
+#[case::subprocess(git::GitBackendType::Subprocess)]
+#[case::gix(git::GitBackendType::Gix)]
+fn mailmap_disabled_keeps_raw_identity(#[case] backend: git::GitBackendType) -> TestResult {
     let dir = tempfile::tempdir()?;
     let repo_path = dir.path();
     let git = TestGit::new(repo_path);
@@ -48,7 +64,15 @@ fn mailmap_disabled_keeps_raw_identity() -> TestResult {
     git.commit("commit");
     write_mailmap(repo_path);
 
-    let repository = Repository::load(repo_path, git::SortCommit::Chronological, None, false)?;
+    let filter = git::RevisionFilter::default();
+    let repository = load_repository(
+        &git::RepositoryPath::new(repo_path),
+        backend,
+        git::SortCommit::Chronological,
+        None,
+        false,
+        &filter,
+    )?;
     let commits = repository.all_commits();
     let commit = commits.first().unwrap();
 
@@ -60,8 +84,12 @@ fn mailmap_disabled_keeps_raw_identity() -> TestResult {
     Ok(())
 }
 
-#[test]
-fn mailmap_enabled_without_mailmap_file_is_a_no_op() -> TestResult {
+#[rstest]
+#[case::subprocess(git::GitBackendType::Subprocess)]
+#[case::gix(git::GitBackendType::Gix)]
+fn mailmap_enabled_without_mailmap_file_is_a_no_op(
+    #[case] backend: git::GitBackendType,
+) -> TestResult {
     let dir = tempfile::tempdir()?;
     let repo_path = dir.path();
     let git = TestGit::new(repo_path);
@@ -69,7 +97,15 @@ fn mailmap_enabled_without_mailmap_file_is_a_no_op() -> TestResult {
     git.init();
     git.commit("commit");
 
-    let repository = Repository::load(repo_path, git::SortCommit::Chronological, None, true)?;
+    let filter = git::RevisionFilter::default();
+    let repository = load_repository(
+        &git::RepositoryPath::new(repo_path),
+        backend,
+        git::SortCommit::Chronological,
+        None,
+        true,
+        &filter,
+    )?;
     let commits = repository.all_commits();
     let commit = commits.first().unwrap();
 
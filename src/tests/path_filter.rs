@@ -0,0 +1,191 @@
+use std::{fs, path::Path};
+
+use rstest::rstest;
+
+use crate::{
+    git::{self, FileChange, Repository},
+    test_util::{load_repository, parent_subjects, subjects, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[rstest]
+#[case::subprocess(git::GitBackendType::Subprocess)]
+#[case::gix(git::GitBackendType::Gix)]
+fn follow_renames_and_rewrite_parents(#[case] backend: git::GitBackendType) -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    fs::write(repo_path.join("a.txt"), "1\n2\n3\n4\n5\n")?;
+    git.commit_all("001", "2024-01-01");
+    fs::write(repo_path.join("other.txt"), "other")?;
+    git.commit_all("002", "2024-01-02");
+    git.run(&["mv", "a.txt", "b.txt"]);
+    git.commit_all("003", "2024-01-03");
+    git.run(&["checkout", "-b", "feature"]);
+    fs::write(repo_path.join("b.txt"), "1\n2\n3\n4\n5\n6\n")?;
+    git.commit_all("004", "2024-01-04");
+    git.run(&["checkout", "master"]);
+    fs::write(repo_path.join("other.txt"), "other changed")?;
+    git.commit_all("005", "2024-01-05");
+    git.run_with_date(
+        &["merge", "--no-ff", "feature", "-m", "006"],
+        "2024-01-06T00:00:00+00:00",
+    );
+    fs::write(repo_path.join("b.txt"), "1\n2\n3\n4\n5\n6\n7\n")?;
+    git.commit_all("007", "2024-01-07");
+
+    let filter = git::RevisionFilter::new(Vec::new(), vec!["b.txt".into()]).with_follow(true);
+    let repository = load(repo_path, backend, &filter)?;
+
+    let commits = repository.all_commits();
+    assert_eq!(subjects(&commits), vec!["007", "004", "003", "001"]);
+    // the parents skip the commits that do not touch the file, and 003 is not a parent of 007
+    // because it is also an ancestor of 004
+    let parents: Vec<Vec<&str>> = commits
+        .iter()
+        .map(|c| parent_subjects(&repository, c))
+        .collect();
+    assert_eq!(
+        parents,
+        vec![vec!["004"], vec!["003"], vec!["001"], Vec::<&str>::new()]
+    );
+    let followed_paths: Vec<Option<&str>> =
+        commits.iter().map(|c| c.followed_path.as_deref()).collect();
+    assert_eq!(
+        followed_paths,
+        vec![Some("b.txt"), Some("b.txt"), Some("b.txt"), Some("a.txt")]
+    );
+
+    // the renamed file is highlighted in the commit that renamed it
+    let (commit, changes) = repository.commit_detail(&commits[2].commit_hash)?;
+    assert!(matches!(
+        changes.as_slice(),
+        [change @ FileChange::Move { from, to }]
+            if from == "a.txt" && to == "b.txt" && repository.is_filtered_change(&commit, change)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn filtered_changes_match_directories() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    fs::create_dir_all(repo_path.join("config").join("nested"))?;
+    fs::write(repo_path.join("config").join("nested").join("a.toml"), "a")?;
+    fs::write(repo_path.join("config.toml"), "b")?;
+    fs::write(repo_path.join("readme"), "c")?;
+    git.commit_all("001", "2024-01-01");
+    fs::write(repo_path.join("readme"), "d")?;
+    git.commit_all("002", "2024-01-02");
+
+    let filter = git::RevisionFilter::new(Vec::new(), vec!["./config/".into()]);
+    let repository = load(repo_path, git::GitBackendType::Subprocess, &filter)?;
+
+    let commits = repository.all_commits();
+    assert_eq!(subjects(&commits), vec!["001"]);
+
+    let (commit, changes) = repository.commit_detail(&commits[0].commit_hash)?;
+    let filtered: Vec<(&str, bool)> = changes
+        .iter()
+        .map(|change| match change {
+            FileChange::Add { path } => (
+                path.as_str(),
+                repository.is_filtered_change(&commit, change),
+            ),
+            _ => panic!("unexpected change: {change:?}"),
+        })
+        .collect();
+    assert_eq!(
+        filtered,
+        vec![
+            ("config.toml", false),
+            ("config/nested/a.toml", true),
+            ("readme", false),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn filtered_changes_match_wildcards() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    fs::create_dir_all(repo_path.join("config"))?;
+    fs::write(repo_path.join("config").join("a.toml"), "a")?;
+    fs::write(repo_path.join("b.toml"), "b")?;
+    fs::write(repo_path.join("readme"), "c")?;
+    git.commit_all("001", "2024-01-01");
+
+    let filter = git::RevisionFilter::new(Vec::new(), vec!["*.toml".into()]);
+    let repository = load(repo_path, git::GitBackendType::Subprocess, &filter)?;
+
+    let commits = repository.all_commits();
+    let (commit, changes) = repository.commit_detail(&commits[0].commit_hash)?;
+    let filtered: Vec<bool> = changes
+        .iter()
+        .map(|change| repository.is_filtered_change(&commit, change))
+        .collect();
+    // b.toml, config/a.toml, readme
+    assert_eq!(filtered, vec![true, true, false]);
+
+    Ok(())
+}
+
+#[rstest]
+#[case::subprocess(git::GitBackendType::Subprocess)]
+#[case::gix(git::GitBackendType::Gix)]
+fn is_file_looks_up_tree_of_commit(#[case] backend: git::GitBackendType) -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path().join("repo");
+    fs::create_dir(&repo_path)?;
+    let git = GitRepository::new(&repo_path);
+
+    git.init();
+    fs::create_dir_all(repo_path.join("dir"))?;
+    fs::write(repo_path.join("dir").join("a.txt"), "a")?;
+    fs::write(repo_path.join("b.txt"), "b")?;
+    git.commit_all("001", "2024-01-01");
+    git.run(&["rm", "b.txt"]);
+    git.commit_all("002", "2024-01-02");
+
+    // the working tree is not read, so the files are found in a bare repository too
+    let bare_path = dir.path().join("bare.git");
+    git.run(&["clone", "--bare", ".", bare_path.to_str().unwrap()]);
+    let repository = load(&bare_path, backend, &git::RevisionFilter::default())?;
+
+    let commits = repository.all_commits();
+    assert_eq!(subjects(&commits), vec!["002", "001"]);
+    let (latest, initial) = (&commits[0].commit_hash, &commits[1].commit_hash);
+    assert!(repository.is_file(latest, "dir/a.txt")?);
+    assert!(!repository.is_file(latest, "dir")?);
+    assert!(!repository.is_file(latest, "b.txt")?);
+    assert!(repository.is_file(initial, "b.txt")?);
+
+    Ok(())
+}
+
+fn load(
+    path: &Path,
+    backend: git::GitBackendType,
+    filter: &git::RevisionFilter,
+) -> git::Result<Repository> {
+    load_repository(
+        &git::RepositoryPath::new(path),
+        backend,
+        git::SortCommit::Chronological,
+        None,
+        false,
+        filter,
+    )
+}
@@ -0,0 +1,83 @@
+use crate::{
+    color::GraphColorSet,
+    config::GraphColorConfig,
+    graph::{self, GraphLayout},
+    test_util::{commit_lanes, load, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn pinned_branches_occupy_leftmost_lanes() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["update-ref", "refs/remotes/origin/master", "HEAD"]);
+    git.run(&["checkout", "-b", "develop"]);
+    git.commit("002", "2024-01-02");
+    git.run(&["checkout", "-b", "feature"]);
+    git.commit("003", "2024-01-03");
+    git.run(&["checkout", "master"]);
+    git.commit("004", "2024-01-04");
+    git.run(&["checkout", "develop"]);
+    git.commit("005", "2024-01-05");
+    git.run(&["checkout", "-b", "release/1.0", "master"]);
+    git.commit("006", "2024-01-06");
+    git.run(&["checkout", "feature"]);
+    git.commit("007", "2024-01-07");
+
+    let repository = load(repo_path)?;
+    let color_set = GraphColorSet::new(&GraphColorConfig::default());
+
+    // `master` is moved by the newer commits without pinning
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &GraphLayout::default(),
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        commit_lanes(&repository, &graph),
+        vec![
+            ("007", 0),
+            ("006", 1),
+            ("005", 2),
+            ("004", 1),
+            ("003", 0),
+            ("002", 0),
+            ("001", 0),
+        ]
+    );
+
+    // `origin/master` shares the lane of `master`
+    let layout = GraphLayout {
+        pinned_branches: vec!["*master".into(), "develop".into(), "release/*".into()],
+        ..Default::default()
+    };
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &layout,
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        commit_lanes(&repository, &graph),
+        vec![
+            ("007", 3),
+            ("006", 2),
+            ("005", 1),
+            ("004", 0),
+            ("003", 3),
+            ("002", 1),
+            ("001", 0),
+        ]
+    );
+    assert_eq!(graph.max_pos_x, 3);
+
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use std::{fs, path::Path};
+
+use rstest::rstest;
+
+use crate::{
+    git::{self, CommitHash, Head, Repository},
+    test_util::{load_repository, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[rstest]
+#[case::subprocess(git::GitBackendType::Subprocess)]
+#[case::gix(git::GitBackendType::Gix)]
+fn refresh_loads_new_commits(#[case] backend: git::GitBackendType) -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.commit("002", "2024-01-02");
+    git.run(&["checkout", "-b", "feature"]);
+    git.commit("003", "2024-01-03");
+
+    let mut actual = load(repo_path, backend, None)?;
+
+    git.commit("004", "2024-01-04");
+    git.run(&["checkout", "master"]);
+    git.commit("005", "2024-01-05");
+    git.run_with_date(
+        &["merge", "--no-ff", "feature", "-m", "006"],
+        "2024-01-06T00:00:00+00:00",
+    );
+    git.run(&["checkout", "-b", "other", "HEAD~1"]);
+    git.commit("007", "2024-01-07");
+    git.run(&["tag", "v1"]);
+
+    assert!(actual.refresh()?);
+
+    let expected = load(repo_path, backend, None)?;
+    assert_eq!(commit_hashes(&actual), commit_hashes(&expected));
+    for commit in expected.all_commits() {
+        let hash = &commit.commit_hash;
+        assert_eq!(actual.parents_hash(hash), expected.parents_hash(hash));
+        assert_eq!(actual.children_hash(hash), expected.children_hash(hash));
+        assert_eq!(actual.refs(hash), expected.refs(hash));
+    }
+    assert!(matches!(actual.head(), Head::Branch { name } if name == "other"));
+
+    Ok(())
+}
+
+#[rstest]
+#[case::subprocess(git::GitBackendType::Subprocess)]
+#[case::gix(git::GitBackendType::Gix)]
+fn refresh_updates_refs_only(#[case] backend: git::GitBackendType) -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.commit("002", "2024-01-02");
+
+    let mut actual = load(repo_path, backend, None)?;
+    let before = commit_hashes(&actual);
+
+    git.run(&["checkout", "--detach", "HEAD~1"]);
+    git.run(&["branch", "feature"]);
+
+    assert!(actual.refresh()?);
+
+    assert_eq!(commit_hashes(&actual), before);
+    let first = &before[1];
+    assert!(matches!(actual.head(), Head::Detached { target } if target == first));
+    let ref_names: Vec<&str> = actual.refs(first).iter().map(|r| r.name()).collect();
+    assert_eq!(ref_names, vec!["feature"]);
+
+    Ok(())
+}
+
+#[rstest]
+#[case::subprocess(git::GitBackendType::Subprocess)]
+#[case::gix(git::GitBackendType::Gix)]
+fn refresh_requires_reload_when_commits_become_unreachable(
+    #[case] backend: git::GitBackendType,
+) -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["checkout", "-b", "feature"]);
+    git.commit("002", "2024-01-02");
+    git.run(&["checkout", "master"]);
+
+    let mut actual = load(repo_path, backend, None)?;
+    let before = commit_hashes(&actual);
+
+    git.run(&["branch", "-D", "feature"]);
+
+    assert!(!actual.refresh()?);
+    assert_eq!(commit_hashes(&actual), before);
+
+    Ok(())
+}
+
+#[rstest]
+#[case::subprocess(git::GitBackendType::Subprocess)]
+#[case::gix(git::GitBackendType::Gix)]
+fn refresh_requires_reload_when_new_commits_are_older(
+    #[case] backend: git::GitBackendType,
+) -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.commit("002", "2024-01-03");
+
+    let mut actual = load(repo_path, backend, None)?;
+
+    git.run(&["checkout", "-b", "feature", "HEAD~1"]);
+    git.commit("003", "2024-01-02");
+
+    assert!(!actual.refresh()?);
+
+    Ok(())
+}
+
+#[rstest]
+#[case::subprocess(git::GitBackendType::Subprocess)]
+#[case::gix(git::GitBackendType::Gix)]
+fn refresh_requires_reload_when_stashes_change(#[case] backend: git::GitBackendType) -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+
+    let mut actual = load(repo_path, backend, None)?;
+
+    fs::write(repo_path.join("file"), "stash")?;
+    git.run(&["add", "file"]);
+    git.run(&["stash"]);
+
+    assert!(!actual.refresh()?);
+
+    Ok(())
+}
+
+#[test]
+fn refresh_requires_reload_with_max_count() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+
+    let mut actual = load(repo_path, git::GitBackendType::Subprocess, Some(1))?;
+
+    git.commit("002", "2024-01-02");
+
+    assert!(!actual.refresh()?);
+
+    Ok(())
+}
+
+fn load(
+    path: &Path,
+    backend: git::GitBackendType,
+    max_count: Option<usize>,
+) -> git::Result<Repository> {
+    load_repository(
+        &git::RepositoryPath::new(path),
+        backend,
+        git::SortCommit::Chronological,
+        max_count,
+        false,
+        &git::RevisionFilter::default(),
+    )
+}
+
+fn commit_hashes(repository: &Repository) -> Vec<CommitHash> {
+    repository
+        .all_commits()
+        .iter()
+        .map(|c| c.commit_hash.clone())
+        .collect()
+}
@@ -0,0 +1,207 @@
+use std::{fs, path::Path, process::Command};
+
+use crate::{
+    git::{self, Head},
+    test_util::{load, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn resolve_subdirectory_to_toplevel() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path().canonicalize()?;
+    let git = GitRepository::new(&repo_path);
+
+    git.init();
+    git.commit("commit", "2024-01-01");
+
+    let sub_path = repo_path.join("a").join("b");
+    fs::create_dir_all(&sub_path)?;
+
+    let actual = git::resolve_repository_path(&sub_path)?;
+    assert_eq!(actual.path(), repo_path);
+
+    Ok(())
+}
+
+#[test]
+fn resolve_linked_worktree() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let root_path = dir.path().canonicalize()?;
+    let repo_path = root_path.join("main");
+    let worktree_path = root_path.join("worktree");
+    fs::create_dir_all(&repo_path)?;
+    let git = GitRepository::new(&repo_path);
+
+    git.init();
+    git.commit("commit", "2024-01-01");
+    git.run(&["worktree", "add", "-b", "feature", "../worktree"]);
+
+    let actual = git::resolve_repository_path(&worktree_path)?;
+    assert_eq!(actual.path(), worktree_path);
+
+    let repository = load(actual.path())?;
+    assert!(matches!(repository.head(), Head::Branch { name } if name == "feature"));
+
+    Ok(())
+}
+
+#[test]
+fn resolve_submodule_checkout() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let root_path = dir.path().canonicalize()?;
+    let sub_origin_path = root_path.join("sub");
+    let repo_path = root_path.join("super");
+    fs::create_dir_all(&sub_origin_path)?;
+    fs::create_dir_all(&repo_path)?;
+
+    let sub_git = GitRepository::new(&sub_origin_path);
+    sub_git.init();
+    sub_git.commit("sub commit", "2024-01-01");
+
+    let git = GitRepository::new(&repo_path);
+    git.init();
+    git.commit("super commit", "2024-01-01");
+    git.run(&[
+        "-c",
+        "protocol.file.allow=always",
+        "submodule",
+        "add",
+        "../sub",
+        "modules/sub",
+    ]);
+
+    let submodule_path = repo_path.join("modules").join("sub");
+    let actual = git::resolve_repository_path(&submodule_path)?;
+    assert_eq!(actual.path(), submodule_path);
+
+    let repository = load(actual.path())?;
+    let subjects: Vec<&str> = repository
+        .all_commits()
+        .iter()
+        .map(|c| c.subject.as_str())
+        .collect();
+    assert_eq!(subjects, vec!["sub commit"]);
+
+    Ok(())
+}
+
+#[test]
+fn resolve_bare_repository_to_git_dir() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path().canonicalize()?;
+    let git = GitRepository::new(&repo_path);
+
+    git.run(&["init", "--bare", "-b", "master"]);
+
+    let actual = git::resolve_repository_path(&repo_path)?;
+    assert_eq!(actual.path(), repo_path);
+
+    Ok(())
+}
+
+#[test]
+fn resolve_not_a_repository() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    // prevent the discovery from reaching a repository that contains the temporary directory
+    let repo_path = dir.path().canonicalize()?;
+    fs::write(repo_path.join(".git"), "gitdir: /nonexistent")?;
+
+    let actual = git::resolve_repository_path(&repo_path);
+    assert!(actual.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn resolve_relative_git_path_env() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let root_path = dir.path().canonicalize()?;
+    let work_tree_path = root_path.join("work");
+    fs::create_dir_all(&work_tree_path)?;
+    let git = GitRepository::new(&work_tree_path);
+
+    git.init();
+    git.commit("commit", "2024-01-01");
+    fs::rename(work_tree_path.join(".git"), root_path.join("repo.git"))?;
+
+    let env = git::absolute_git_path_env(&work_tree_path, |key| match key {
+        "GIT_DIR" => Some("../repo.git".into()),
+        "GIT_WORK_TREE" => Some(".".into()),
+        _ => None,
+    });
+    assert_eq!(
+        env,
+        vec![
+            ("GIT_DIR", work_tree_path.join("../repo.git")),
+            ("GIT_WORK_TREE", work_tree_path.join(".")),
+        ]
+    );
+
+    // git processes are executed in a directory other than the one the paths are relative to
+    let output = Command::new("git")
+        .args(["rev-parse", "--absolute-git-dir", "--show-toplevel"])
+        .current_dir(&root_path)
+        .envs(env)
+        .output()?;
+    assert!(output.status.success());
+    let expected = format!(
+        "{}\n{}\n",
+        root_path.join("repo.git").display(),
+        work_tree_path.display()
+    );
+    assert_eq!(String::from_utf8(output.stdout)?, expected);
+
+    Ok(())
+}
+
+#[test]
+fn resolve_nonexistent_path() {
+    let actual = git::resolve_repository_path(Path::new("/nonexistent/serie/repository"));
+    assert!(actual.is_err());
+}
+
+#[test]
+fn resolve_pathspecs_from_subdirectory() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path().canonicalize()?;
+    let git = GitRepository::new(&repo_path);
+
+    git.init();
+    git.commit("commit", "2024-01-01");
+
+    let sub_path = repo_path.join("a").join("b");
+    fs::create_dir_all(&sub_path)?;
+
+    let actual = git::resolve_repository_path(&sub_path)?;
+    let pathspecs = vec![
+        "file".into(),
+        "./c/".into(),
+        "../file".into(),
+        ".".into(),
+        "../..".into(),
+        ":!file".into(),
+        ":(glob)*.rs".into(),
+        ":/file".into(),
+        ":(top)file".into(),
+        sub_path.join("file").to_string_lossy().into_owned(),
+    ];
+    assert_eq!(
+        actual.resolve_pathspecs(pathspecs),
+        vec![
+            "a/b/file",
+            "a/b/c/",
+            "a/file",
+            "a/b",
+            ".",
+            ":!a/b/file",
+            ":(glob)a/b/*.rs",
+            ":/file",
+            ":(top)file",
+            "a/b/file",
+        ]
+    );
+
+    Ok(())
+}
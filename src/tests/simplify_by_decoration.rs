@@ -0,0 +1,145 @@
+use rustc_hash::FxHashSet;
+
+use crate::{
+    git::{Commit, GitError, Repository},
+    test_util::{load, parent_subjects, subjects, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn simplify_by_decoration_collapses_commits() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["tag", "v1"]);
+    git.commit("002", "2024-01-02");
+    git.commit("003", "2024-01-03");
+    git.commit("004", "2024-01-04");
+    git.run(&["checkout", "-b", "feature"]);
+    git.commit("005", "2024-01-05");
+    git.commit("006", "2024-01-06");
+    git.commit("007", "2024-01-07");
+    git.run(&["checkout", "master"]);
+    git.commit("008", "2024-01-08");
+    git.commit("009", "2024-01-09");
+    git.commit("010", "2024-01-10");
+
+    let repository = load(repo_path)?;
+    let simplified = repository.simplify_by_decoration(&FxHashSet::default());
+
+    // 004 is kept as the fork point of the branches
+    let commits = simplified.all_commits();
+    assert_eq!(
+        subjects(&commits),
+        vec![
+            "010",
+            "2 commits",
+            "007",
+            "2 commits",
+            "004",
+            "2 commits",
+            "001"
+        ]
+    );
+    let parents: Vec<Vec<&str>> = commits
+        .iter()
+        .map(|c| parent_subjects(&simplified, c))
+        .collect();
+    assert_eq!(
+        parents,
+        vec![
+            vec!["2 commits"],
+            vec!["004"],
+            vec!["2 commits"],
+            vec!["004"],
+            vec!["2 commits"],
+            vec!["001"],
+            Vec::<&str>::new(),
+        ]
+    );
+    let collapsed = collapsed_subjects(&repository, &simplified, commits[1]);
+    assert_eq!(collapsed, vec!["009", "008"]);
+    assert!(
+        !simplified.has_hidden_commits_between(&commits[0].commit_hash, &commits[1].commit_hash)
+    );
+    // the collapsed row is not the newest hidden commit whose hash it has
+    assert_eq!(commits[1].author_name, "");
+    assert!(matches!(
+        simplified.commit_detail(&commits[1].commit_hash),
+        Err(GitError::CollapsedCommits)
+    ));
+    assert!(simplified.commit_detail(&commits[0].commit_hash).is_ok());
+
+    let expanded = simplified
+        .collapsed_commits(&commits[1].commit_hash)
+        .unwrap()
+        .iter()
+        .cloned()
+        .collect();
+    let simplified = repository.simplify_by_decoration(&expanded);
+    assert_eq!(
+        subjects(&simplified.all_commits()),
+        vec![
+            "010",
+            "009",
+            "008",
+            "007",
+            "2 commits",
+            "004",
+            "2 commits",
+            "001"
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn simplify_by_decoration_hides_merged_branches() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["tag", "v1"]);
+    git.run(&["checkout", "-b", "feature"]);
+    git.commit("002", "2024-01-02");
+    git.commit("003", "2024-01-03");
+    git.run(&["checkout", "master"]);
+    git.commit("004", "2024-01-04");
+    git.run_with_date(
+        &["merge", "--no-ff", "feature", "-m", "005"],
+        "2024-01-05T00:00:00+00:00",
+    );
+    git.run(&["branch", "-D", "feature"]);
+    git.commit("006", "2024-01-06");
+
+    let repository = load(repo_path)?;
+    let simplified = repository.simplify_by_decoration(&FxHashSet::default());
+
+    let commits = simplified.all_commits();
+    assert_eq!(subjects(&commits), vec!["006", "4 commits", "001"]);
+    let collapsed = collapsed_subjects(&repository, &simplified, commits[1]);
+    assert_eq!(collapsed, vec!["005", "004", "003", "002"]);
+
+    Ok(())
+}
+
+fn collapsed_subjects<'a>(
+    repository: &'a Repository,
+    simplified: &Repository,
+    commit: &Commit,
+) -> Vec<&'a str> {
+    simplified
+        .collapsed_commits(&commit.commit_hash)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|hash| repository.commit(hash))
+        .map(|c| c.subject.as_str())
+        .collect()
+}
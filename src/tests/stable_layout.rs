@@ -0,0 +1,190 @@
+use crate::{
+    color::GraphColorSet,
+    config::GraphColorConfig,
+    graph::{self, GraphLayout, LaneHint},
+    test_util::{commit_lanes, load, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn lane_hint_keeps_commits_in_lanes() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.commit("002", "2024-01-02");
+    git.run(&["checkout", "-b", "feature", "HEAD~1"]);
+    git.commit("003", "2024-01-03");
+
+    let color_set = GraphColorSet::new(&GraphColorConfig::default());
+    let layout = GraphLayout::default();
+
+    let repository = load(repo_path)?;
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &layout,
+        &LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        commit_lanes(&repository, &graph),
+        vec![("003", 0), ("002", 1), ("001", 0)]
+    );
+    let lane_hint = graph.lane_hint();
+
+    git.run(&["checkout", "-b", "hotfix", "master~1"]);
+    git.commit("004", "2024-01-04");
+    git.run(&["checkout", "master"]);
+    git.commit("005", "2024-01-05");
+    let repository = load(repo_path)?;
+
+    // the newer commits take the left lanes without the hint
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &layout,
+        &LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        commit_lanes(&repository, &graph),
+        vec![("005", 0), ("004", 1), ("003", 2), ("002", 0), ("001", 0)]
+    );
+
+    // the new commits are placed around the lanes of the previous graph
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &layout,
+        &lane_hint,
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        commit_lanes(&repository, &graph),
+        vec![("005", 1), ("004", 2), ("003", 0), ("002", 1), ("001", 0)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn lane_hint_leaves_no_vacant_lanes() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["checkout", "-b", "feature1"]);
+    git.commit("002", "2024-01-02");
+    git.run(&["checkout", "-b", "feature2", "master"]);
+    git.commit("003", "2024-01-03");
+    git.run(&["checkout", "-b", "feature3", "master"]);
+    git.commit("004", "2024-01-04");
+
+    let color_set = GraphColorSet::new(&GraphColorConfig::default());
+    let layout = GraphLayout::default();
+
+    let repository = load(repo_path)?;
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &layout,
+        &LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        commit_lanes(&repository, &graph),
+        vec![("004", 0), ("003", 1), ("002", 2), ("001", 0)]
+    );
+    let lane_hint = graph.lane_hint();
+
+    git.run(&["checkout", "master"]);
+    git.run(&["branch", "-D", "feature2", "feature3"]);
+    let repository = load(repo_path)?;
+
+    // 002 is not kept to the right of the lanes vacated by the deleted branches
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &layout,
+        &lane_hint,
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        commit_lanes(&repository, &graph),
+        vec![("002", 0), ("001", 0)]
+    );
+    assert_eq!(graph.max_pos_x, 0);
+
+    Ok(())
+}
+
+#[test]
+fn refresh_lays_out_only_new_commits() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.commit("002", "2024-01-02");
+    git.run(&["checkout", "-b", "feature", "HEAD~1"]);
+    git.commit("003", "2024-01-03");
+
+    let color_set = GraphColorSet::new(&GraphColorConfig::default());
+    let layout = GraphLayout::default();
+    let mut incremental = graph::IncrementalLayout::default();
+
+    let mut repository = load(repo_path)?;
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &layout,
+        &LaneHint::default(),
+        &mut incremental,
+    );
+    assert_eq!(
+        commit_lanes(&repository, &graph),
+        vec![("003", 0), ("002", 1), ("001", 0)]
+    );
+    let last_row_edges = graph.edges.last().cloned();
+    drop(graph);
+
+    git.run(&["checkout", "master"]);
+    git.commit("004", "2024-01-04");
+    assert!(repository.refresh()?);
+    assert!(incremental.prepend(&repository));
+
+    // the rows below the new commit keep their lanes and edges, which a new layout would change
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &layout,
+        &LaneHint::default(),
+        &mut incremental,
+    );
+    assert_eq!(
+        commit_lanes(&repository, &graph),
+        vec![("004", 1), ("003", 0), ("002", 1), ("001", 0)]
+    );
+    assert_eq!(graph.edges.last().cloned(), last_row_edges);
+
+    let graph = graph::calc_graph(
+        &repository,
+        &color_set,
+        &layout,
+        &LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    assert_eq!(
+        commit_lanes(&repository, &graph),
+        vec![("004", 0), ("003", 1), ("002", 0), ("001", 0)]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use ratatui::style::Modifier;
+
+use crate::{
+    color::GraphColorSet,
+    config::GraphColorConfig,
+    git::Repository,
+    graph::{
+        self, CellWidthType, GraphHighlight, GraphImageCache, GraphImageManager,
+        GraphImageWidthMode, GraphLayout, GraphStyle, HighlightMode,
+    },
+    protocol::ImageProtocol,
+    test_util::{load, GitRepository},
+};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn text_graph_double_width() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    create_merge(&git);
+
+    let repository = load(repo_path)?;
+    let rows = render_rows(
+        &repository,
+        CellWidthType::Double,
+        GraphStyle::Rounded,
+        None,
+    );
+    assert_eq!(
+        symbols(&rows),
+        vec!["●   ", "●─╮ ", "● │ ", "│ ● ", "│ ● ", "●─╯ "]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn text_graph_single_width() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    create_merge(&git);
+
+    let repository = load(repo_path)?;
+    let rows = render_rows(
+        &repository,
+        CellWidthType::Single,
+        GraphStyle::Rounded,
+        None,
+    );
+    assert_eq!(symbols(&rows), vec!["● ", "●╮", "●│", "│●", "│●", "●╯"]);
+
+    Ok(())
+}
+
+#[test]
+fn text_graph_angular() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    create_merge(&git);
+
+    let repository = load(repo_path)?;
+    let rows = render_rows(
+        &repository,
+        CellWidthType::Double,
+        GraphStyle::Angular,
+        None,
+    );
+    assert_eq!(
+        symbols(&rows),
+        vec!["●   ", "●─┐ ", "● │ ", "│ ● ", "│ ● ", "●─┘ "]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn text_graph_highlight() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let repo_path = dir.path();
+    let git = GitRepository::new(repo_path);
+    create_merge(&git);
+
+    let repository = load(repo_path)?;
+    let rows = render_rows(
+        &repository,
+        CellWidthType::Double,
+        GraphStyle::Rounded,
+        Some(("004", HighlightMode::Ancestors)),
+    );
+    // the cells of the lines not related to 004 are dimmed
+    assert_eq!(
+        rows,
+        vec![
+            ("●   ".into(), "d...".into()),
+            ("●─╮ ".into(), "ddd.".into()),
+            ("● │ ".into(), "..d.".into()),
+            ("│ ● ".into(), "..d.".into()),
+            ("│ ● ".into(), "..d.".into()),
+            ("●─╯ ".into(), ".dd.".into()),
+        ]
+    );
+
+    Ok(())
+}
+
+// 003 is merged into master by 005, after 004 is committed.
+fn create_merge(git: &GitRepository) {
+    git.init();
+    git.commit("001", "2024-01-01");
+    git.run(&["checkout", "-b", "side"]);
+    git.commit("002", "2024-01-02");
+    git.commit("003", "2024-01-03");
+    git.run(&["checkout", "master"]);
+    git.commit("004", "2024-01-04");
+    git.run_with_date(
+        &["merge", "--no-ff", "side", "-m", "005 merge"],
+        "2024-01-05T00:00:00+00:00",
+    );
+    git.commit("006", "2024-01-06");
+}
+
+// Returns the symbols of the rows, and the marks of whether each cell of them is dimmed ('d') or not ('.').
+fn render_rows(
+    repository: &Repository,
+    cell_width_type: CellWidthType,
+    graph_style: GraphStyle,
+    highlight: Option<(&str, HighlightMode)>,
+) -> Vec<(String, String)> {
+    let color_set = GraphColorSet::new(&GraphColorConfig::default());
+    let graph = graph::calc_graph(
+        repository,
+        &color_set,
+        &GraphLayout::default(),
+        &graph::LaneHint::default(),
+        &mut graph::IncrementalLayout::default(),
+    );
+    let mut manager = GraphImageManager::new(
+        &graph,
+        &color_set,
+        cell_width_type,
+        graph_style,
+        GraphImageWidthMode::Fixed,
+        ImageProtocol::Text,
+        None,
+        GraphImageCache::new(),
+    );
+    let commits = repository.all_commits();
+    if let Some((subject, mode)) = highlight {
+        let commit = commits.iter().find(|c| c.subject == subject).unwrap();
+        let highlight = GraphHighlight::new(repository, &graph, &commit.commit_hash, mode);
+        manager.set_highlight(Some(highlight));
+        manager.search_highlight(0..commits.len());
+    }
+
+    commits
+        .iter()
+        .map(|c| {
+            manager.ensure_uploaded(&c.commit_hash);
+            let cells = manager.prepared_image(&c.commit_hash).cells();
+            let symbols = cells.iter().map(|cell| cell.symbol()).collect();
+            let dimmed = cells
+                .iter()
+                .map(|cell| {
+                    if cell.style().add_modifier.contains(Modifier::DIM) {
+                        'd'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            (symbols, dimmed)
+        })
+        .collect()
+}
+
+fn symbols(rows: &[(String, String)]) -> Vec<&str> {
+    rows.iter().map(|(symbols, _)| symbols.as_str()).collect()
+}
@@ -0,0 +1,162 @@
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+use crate::{
+    git::{self, Commit, Repository},
+    graph::Graph,
+};
+
+pub fn load(path: &Path) -> git::Result<Repository> {
+    load_with_backend(path, git::GitBackendType::Subprocess)
+}
+
+pub fn load_with_backend(path: &Path, backend: git::GitBackendType) -> git::Result<Repository> {
+    load_repository(
+        &git::RepositoryPath::new(path),
+        backend,
+        git::SortCommit::Chronological,
+        None,
+        false,
+        &git::RevisionFilter::default(),
+    )
+}
+
+// Loads the repository with all the commits read at once.
+pub fn load_repository(
+    path: &git::RepositoryPath,
+    backend: git::GitBackendType,
+    sort: git::SortCommit,
+    max_count: Option<usize>,
+    mailmap: bool,
+    filter: &git::RevisionFilter,
+) -> git::Result<Repository> {
+    let (mut repository, stream) =
+        Repository::load_without_commits(path, backend, sort, max_count, mailmap, filter)?;
+    stream.read(usize::MAX, |commits| {
+        repository.append_commits(commits);
+        true
+    })?;
+    Ok(repository)
+}
+
+pub fn subjects<'a>(commits: &[&'a Commit]) -> Vec<&'a str> {
+    commits.iter().map(|c| c.subject.as_str()).collect()
+}
+
+pub fn parent_subjects<'a>(repository: &'a Repository, commit: &Commit) -> Vec<&'a str> {
+    repository
+        .parents_hash(&commit.commit_hash)
+        .into_iter()
+        .filter_map(|hash| repository.commit(hash))
+        .map(|c| c.subject.as_str())
+        .collect()
+}
+
+// Returns the subjects of the commits with the lanes they are placed in.
+pub fn commit_lanes<'a>(repository: &'a Repository, graph: &Graph) -> Vec<(&'a str, usize)> {
+    repository
+        .all_commits()
+        .into_iter()
+        .map(|c| (c.subject.as_str(), graph.commit_pos_map[&c.commit_hash].0))
+        .collect()
+}
+
+pub struct GitRepository<'a> {
+    path: &'a Path,
+}
+
+impl GitRepository<'_> {
+    pub fn new(path: &'_ Path) -> GitRepository<'_> {
+        GitRepository { path }
+    }
+
+    pub fn init(&self) {
+        self.run(&["init", "-b", "master"]);
+    }
+
+    pub fn commit(&self, message: &str, date: &str) {
+        let datetime_str = parse_date(date).to_rfc3339();
+        self.run_with_date(&["commit", "--allow-empty", "-m", message], &datetime_str);
+    }
+
+    pub fn commit_all(&self, message: &str, date: &str) {
+        self.run(&["add", "--all"]);
+        self.commit(message, date);
+    }
+
+    pub fn checkout(&self, branch_name: &str) {
+        self.run(&["checkout", branch_name]);
+    }
+
+    pub fn checkout_b(&self, branch_name: &str) {
+        self.run(&["checkout", "-b", branch_name]);
+    }
+
+    pub fn checkout_orphan(&self, branch_name: &str) {
+        self.run(&["checkout", "--orphan", branch_name]);
+    }
+
+    pub fn merge(&self, branch_names: &[&str], date: &str) {
+        let datetime_str = parse_date(date).to_rfc3339();
+        let mut args = vec!["merge", "--no-ff", "--no-log"];
+        args.extend_from_slice(branch_names);
+        self.run_with_date(&args, &datetime_str);
+    }
+
+    pub fn branch_d(&self, branch_name: &str) {
+        self.run(&["branch", "-D", branch_name]);
+    }
+
+    pub fn stash(&self, date: &str) {
+        let dummy_file_path = self.path.join("stash.txt");
+        std::fs::File::create(dummy_file_path).unwrap();
+
+        let datetime_str = parse_date(date).to_rfc3339();
+        self.run_with_date(&["stash", "--include-untracked"], &datetime_str);
+    }
+
+    pub fn rev_parse_head(&self) -> String {
+        let output = self.run(&["rev-parse", "HEAD"]);
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    pub fn log(&self) {
+        let output = self.run(&["log", "--pretty=format:%h %s", "--graph", "--all"]);
+        println!("{}", String::from_utf8(output.stdout).unwrap())
+    }
+
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.run_with_date(args, "2024-01-01T00:00:00+00:00")
+    }
+
+    pub fn run_with_date(&self, args: &[&str], datetime_str: &str) -> Output {
+        let out = Command::new("git")
+            .args(args)
+            .current_dir(self.path)
+            .env("GIT_AUTHOR_NAME", "Author Name")
+            .env("GIT_AUTHOR_EMAIL", "author@example.com")
+            .env("GIT_AUTHOR_DATE", datetime_str)
+            .env("GIT_COMMITTER_NAME", "Committer Name")
+            .env("GIT_COMMITTER_EMAIL", "committer@example.com")
+            .env("GIT_COMMITTER_DATE", datetime_str)
+            .env("GIT_CONFIG_NOSYSTEM", "true")
+            .env("HOME", "/dev/null")
+            .output()
+            .unwrap_or_else(|_| panic!("failed to execute git {}", args.join(" ")));
+        println!("git {}: returned {}", args.join(" "), out.status);
+        assert!(out.status.success(), "git {} failed", args.join(" "));
+        out
+    }
+}
+
+pub fn parse_date(date: &str) -> DateTime<Utc> {
+    let dt = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .unwrap()
+        .and_hms_opt(1, 2, 3)
+        .unwrap();
+    Utc.from_utc_datetime(&dt)
+}
@@ -1,6 +1,9 @@
 mod views;
 
+mod blame;
 mod detail;
+mod diff;
+mod file_tree;
 mod help;
 mod list;
 mod refs;
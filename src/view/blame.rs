@@ -0,0 +1,241 @@
+use std::rc::Rc;
+
+use ratatui::{
+    crossterm::event::KeyEvent,
+    layout::{Constraint, Layout, Rect},
+    Frame,
+};
+
+use crate::{
+    app::AppContext,
+    event::{AppEvent, Sender, UserEvent, UserEventWithCount},
+    git::{Blame, CommitHash, Repository},
+    syntax::SyntaxHighlighter,
+    view::{BlameRefreshViewContext, ListRefreshViewContext, RefreshViewContext},
+    widget::{
+        commit_list::{CommitList, CommitListState},
+        file_blame::{FileBlame, FileBlameState},
+    },
+};
+
+#[derive(Debug)]
+pub struct BlameView<'a> {
+    commit_list_state: Option<CommitListState<'a>>,
+    file_blame_state: FileBlameState,
+
+    ctx: Rc<AppContext>,
+    tx: Sender,
+}
+
+impl<'a> BlameView<'a> {
+    pub fn new(
+        commit_list_state: CommitListState<'a>,
+        commit_hash: CommitHash,
+        path: String,
+        blame: Blame,
+        ctx: Rc<AppContext>,
+        tx: Sender,
+    ) -> BlameView<'a> {
+        let highlighter = SyntaxHighlighter::new(&ctx.ui_config.syntax);
+        BlameView {
+            commit_list_state: Some(commit_list_state),
+            file_blame_state: FileBlameState::new(commit_hash, path, blame, highlighter),
+            ctx,
+            tx,
+        }
+    }
+
+    pub fn handle_event(&mut self, event_with_count: UserEventWithCount, _: KeyEvent) {
+        let event = event_with_count.event;
+        let count = event_with_count.count;
+
+        match event {
+            UserEvent::Blame | UserEvent::Cancel | UserEvent::Close => {
+                self.tx.send(AppEvent::CloseBlame);
+            }
+            UserEvent::NavigateDown => {
+                for _ in 0..count {
+                    self.file_blame_state.select_next();
+                }
+            }
+            UserEvent::NavigateUp => {
+                for _ in 0..count {
+                    self.file_blame_state.select_prev();
+                }
+            }
+            UserEvent::PageDown => {
+                for _ in 0..count {
+                    self.file_blame_state.scroll_page_down();
+                }
+            }
+            UserEvent::PageUp => {
+                for _ in 0..count {
+                    self.file_blame_state.scroll_page_up();
+                }
+            }
+            UserEvent::HalfPageDown => {
+                for _ in 0..count {
+                    self.file_blame_state.scroll_half_page_down();
+                }
+            }
+            UserEvent::HalfPageUp => {
+                for _ in 0..count {
+                    self.file_blame_state.scroll_half_page_up();
+                }
+            }
+            UserEvent::GoToTop => {
+                self.file_blame_state.select_first();
+            }
+            UserEvent::GoToBottom => {
+                self.file_blame_state.select_last();
+            }
+            UserEvent::Confirm => {
+                self.select_line_commit();
+            }
+            UserEvent::GoToParent => {
+                self.tx.send(AppEvent::BlameParent);
+            }
+            UserEvent::ShortCopy => {
+                self.copy_line_commit_short_hash();
+            }
+            UserEvent::FullCopy => {
+                self.copy_line_commit_hash();
+            }
+            UserEvent::HelpToggle => {
+                self.tx.send(AppEvent::OpenHelp);
+            }
+            UserEvent::Refresh => {
+                self.refresh();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        let [list_area, blame_area] = self.split_areas(area);
+
+        let commit_list = CommitList::new(self.ctx.clone());
+        f.render_stateful_widget(commit_list, list_area, self.as_mut_list_state());
+
+        let file_blame = FileBlame::new(self.ctx.clone());
+        f.render_stateful_widget(file_blame, blame_area, &mut self.file_blame_state);
+    }
+
+    pub fn update_layout(&mut self, area: Rect) {
+        let [list_area, _] = self.split_areas(area);
+        let list_state = self.as_mut_list_state();
+        list_state.update_height(list_area.height as usize);
+        list_state.update_graph_width(list_area.width);
+    }
+
+    pub fn prepare_graph_uploads(&mut self) {
+        self.as_mut_list_state().ensure_visible_graph_uploaded();
+    }
+}
+
+impl<'a> BlameView<'a> {
+    pub fn take_list_state(&mut self) -> CommitListState<'a> {
+        self.commit_list_state.take().unwrap()
+    }
+
+    fn as_mut_list_state(&mut self) -> &mut CommitListState<'a> {
+        self.commit_list_state.as_mut().unwrap()
+    }
+
+    fn as_list_state(&self) -> &CommitListState<'a> {
+        self.commit_list_state.as_ref().unwrap()
+    }
+
+    pub fn drain_pending_graph_uploads(&mut self) -> Vec<String> {
+        self.as_mut_list_state().drain_pending_graph_uploads()
+    }
+
+    pub fn graph_image_ids_sorted(&self) -> Vec<u32> {
+        self.as_list_state().graph_image_ids_sorted()
+    }
+
+    fn split_areas(&self, area: Rect) -> [Rect; 2] {
+        let blame_height = (area.height - 1).min(self.ctx.ui_config.blame.height);
+        Layout::vertical([Constraint::Min(0), Constraint::Length(blame_height)]).areas(area)
+    }
+
+    fn select_line_commit(&mut self) {
+        let Some(line) = self.file_blame_state.selected_line() else {
+            return;
+        };
+        let commit_hash = line.commit_hash.clone();
+        let list_state = self.as_mut_list_state();
+        if !list_state.contains_commit_hash(&commit_hash) {
+            let msg = format!("Commit {} is not in the graph", commit_hash.as_short_hash());
+            self.tx.send(AppEvent::NotifyWarn(msg));
+            return;
+        }
+        list_state.select_commit_hash(&commit_hash);
+    }
+
+    /// Blames the file again at the parent of the commit that last changed the selected line.
+    pub fn blame_parent(&mut self, repository: &Repository) {
+        let Some(line) = self.file_blame_state.selected_line() else {
+            return;
+        };
+        let line_number = line.original_line_number;
+        let Some((parent_hash, parent_path)) = self
+            .file_blame_state
+            .selected_commit()
+            .and_then(|commit| commit.previous.clone())
+        else {
+            let msg = "The file does not exist before this commit".into();
+            self.tx.send(AppEvent::NotifyWarn(msg));
+            return;
+        };
+        let blame = match repository.blame(&parent_hash, &parent_path) {
+            Ok(blame) => blame,
+            Err(err) => {
+                self.tx.send(AppEvent::NotifyError(err.to_string()));
+                return;
+            }
+        };
+        let highlighter = SyntaxHighlighter::new(&self.ctx.ui_config.syntax);
+        self.file_blame_state = FileBlameState::new(parent_hash, parent_path, blame, highlighter);
+        // the line is not always in the parent, but usually it is around the same position
+        self.file_blame_state
+            .select_line(line_number.saturating_sub(1));
+    }
+
+    fn copy_line_commit_short_hash(&self) {
+        if let Some(line) = self.file_blame_state.selected_line() {
+            let hash = line.commit_hash.as_short_hash().into();
+            self.copy_to_clipboard("Commit SHA (short)".into(), hash);
+        }
+    }
+
+    fn copy_line_commit_hash(&self) {
+        if let Some(line) = self.file_blame_state.selected_line() {
+            let hash = line.commit_hash.as_str().into();
+            self.copy_to_clipboard("Commit SHA".into(), hash);
+        }
+    }
+
+    fn copy_to_clipboard(&self, name: String, value: String) {
+        self.tx.send(AppEvent::CopyToClipboard { name, value });
+    }
+
+    pub fn refresh(&self) {
+        let list_state = self.as_list_state();
+        let list_context = ListRefreshViewContext::from(list_state);
+        let blame_context = BlameRefreshViewContext {
+            commit_hash: self.file_blame_state.commit_hash().clone(),
+            path: self.file_blame_state.path().into(),
+            selected: self.file_blame_state.selected_index(),
+        };
+        let context = RefreshViewContext::Blame {
+            list_context,
+            blame_context,
+        };
+        self.tx.send(AppEvent::Refresh(context));
+    }
+
+    pub fn reset_blame_with(&mut self, blame_context: BlameRefreshViewContext) {
+        self.file_blame_state.select_line(blame_context.selected);
+    }
+}
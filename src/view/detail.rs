@@ -9,10 +9,12 @@ use ratatui::{
 use crate::{
     app::AppContext,
     event::{AppEvent, Sender, UserEvent, UserEventWithCount},
-    git::{Commit, FileChange, Ref, Repository},
+    git::{Commit, CommitHash, FileChange, FileDiff, Ref, Repository, Result},
+    loader,
     view::{ListRefreshViewContext, RefreshViewContext},
     widget::{
         commit_detail::{CommitDetail, CommitDetailState},
+        commit_diff::{CommitDiff, CommitDiffState},
         commit_list::{CommitList, CommitListState},
     },
 };
@@ -21,10 +23,17 @@ use crate::{
 pub struct DetailView<'a> {
     commit_list_state: Option<CommitListState<'a>>,
     commit_detail_state: CommitDetailState,
+    // the patch of the selected file, shown in place of the details
+    commit_diff_state: Option<CommitDiffState>,
 
     commit: Commit,
     changes: Vec<FileChange>,
+    // whether each change is to the paths that the commits are filtered by
+    filtered_changes: Vec<bool>,
     refs: Vec<Ref>,
+    // the patches of the commit, loaded when the patch of a file is first shown
+    diffs: Option<Vec<FileDiff>>,
+    diffs_loading: bool,
 
     ctx: Rc<AppContext>,
     tx: Sender,
@@ -35,6 +44,7 @@ impl<'a> DetailView<'a> {
         commit_list_state: CommitListState<'a>,
         commit: Commit,
         changes: Vec<FileChange>,
+        filtered_changes: Vec<bool>,
         refs: Vec<Ref>,
         ctx: Rc<AppContext>,
         tx: Sender,
@@ -42,15 +52,24 @@ impl<'a> DetailView<'a> {
         DetailView {
             commit_list_state: Some(commit_list_state),
             commit_detail_state: CommitDetailState::default(),
+            commit_diff_state: None,
             commit,
             changes,
+            filtered_changes,
             refs,
+            diffs: None,
+            diffs_loading: false,
             ctx,
             tx,
         }
     }
 
-    pub fn handle_event(&mut self, event_with_count: UserEventWithCount, _: KeyEvent) {
+    pub fn handle_event(&mut self, event_with_count: UserEventWithCount, key: KeyEvent) {
+        if self.commit_diff_state.is_some() {
+            self.handle_file_diff_event(event_with_count, key);
+            return;
+        }
+
         let event = event_with_count.event;
         let count = event_with_count.count;
 
@@ -91,6 +110,24 @@ impl<'a> DetailView<'a> {
             UserEvent::GoToBottom => {
                 self.commit_detail_state.select_last();
             }
+            UserEvent::GoToNextFile => {
+                for _ in 0..count {
+                    self.commit_detail_state
+                        .select_next_change(self.changes.len());
+                }
+            }
+            UserEvent::GoToPreviousFile => {
+                for _ in 0..count {
+                    self.commit_detail_state
+                        .select_prev_change(self.changes.len());
+                }
+            }
+            UserEvent::PathFilter => {
+                self.filter_selected_file();
+            }
+            UserEvent::Blame => {
+                self.blame_selected_file();
+            }
             UserEvent::SelectDown => {
                 self.tx.send(AppEvent::SelectOlderCommit);
             }
@@ -109,9 +146,18 @@ impl<'a> DetailView<'a> {
             UserEvent::UserCommand(n) => {
                 self.tx.send(AppEvent::OpenUserCommand(n));
             }
+            UserEvent::DiffToggle => {
+                self.tx.send(AppEvent::OpenDiff);
+            }
+            UserEvent::FileTreeToggle => {
+                self.tx.send(AppEvent::OpenFileTree);
+            }
             UserEvent::HelpToggle => {
                 self.tx.send(AppEvent::OpenHelp);
             }
+            UserEvent::Confirm if self.commit_detail_state.selected_change().is_some() => {
+                self.tx.send(AppEvent::OpenFileDiff);
+            }
             UserEvent::Confirm | UserEvent::Cancel | UserEvent::Close => {
                 self.tx.send(AppEvent::CloseDetail);
             }
@@ -122,21 +168,140 @@ impl<'a> DetailView<'a> {
         }
     }
 
+    fn handle_file_diff_event(&mut self, event_with_count: UserEventWithCount, _: KeyEvent) {
+        let event = event_with_count.event;
+        let count = event_with_count.count;
+        let Some(commit_diff_state) = self.commit_diff_state.as_mut() else {
+            return;
+        };
+
+        match event {
+            UserEvent::NavigateDown => {
+                for _ in 0..count {
+                    commit_diff_state.scroll_down();
+                }
+            }
+            UserEvent::NavigateUp => {
+                for _ in 0..count {
+                    commit_diff_state.scroll_up();
+                }
+            }
+            UserEvent::PageDown => {
+                for _ in 0..count {
+                    commit_diff_state.scroll_page_down();
+                }
+            }
+            UserEvent::PageUp => {
+                for _ in 0..count {
+                    commit_diff_state.scroll_page_up();
+                }
+            }
+            UserEvent::HalfPageDown => {
+                for _ in 0..count {
+                    commit_diff_state.scroll_half_page_down();
+                }
+            }
+            UserEvent::HalfPageUp => {
+                for _ in 0..count {
+                    commit_diff_state.scroll_half_page_up();
+                }
+            }
+            UserEvent::GoToTop => {
+                commit_diff_state.select_first();
+            }
+            UserEvent::GoToBottom => {
+                commit_diff_state.select_last();
+            }
+            UserEvent::GoToNext => {
+                commit_diff_state.select_next_hunk();
+            }
+            UserEvent::GoToPrevious => {
+                commit_diff_state.select_prev_hunk();
+            }
+            UserEvent::DiffLayoutToggle => {
+                commit_diff_state.toggle_layout();
+            }
+            UserEvent::GoToNextFile => {
+                for _ in 0..count {
+                    self.commit_detail_state
+                        .select_next_change(self.changes.len());
+                }
+                self.show_selected_file_diff();
+            }
+            UserEvent::GoToPreviousFile => {
+                for _ in 0..count {
+                    self.commit_detail_state
+                        .select_prev_change(self.changes.len());
+                }
+                self.show_selected_file_diff();
+            }
+            UserEvent::PathFilter => {
+                self.filter_selected_file();
+            }
+            UserEvent::Blame => {
+                self.blame_selected_file();
+            }
+            UserEvent::SelectDown => {
+                self.tx.send(AppEvent::SelectOlderCommit);
+            }
+            UserEvent::SelectUp => {
+                self.tx.send(AppEvent::SelectNewerCommit);
+            }
+            UserEvent::GoToParent => {
+                self.tx.send(AppEvent::SelectParentCommit);
+            }
+            UserEvent::ShortCopy => {
+                self.copy_commit_short_hash();
+            }
+            UserEvent::FullCopy => {
+                self.copy_commit_hash();
+            }
+            UserEvent::UserCommand(n) => {
+                self.tx.send(AppEvent::OpenUserCommand(n));
+            }
+            UserEvent::DiffToggle => {
+                self.tx.send(AppEvent::OpenDiff);
+            }
+            UserEvent::HelpToggle => {
+                self.tx.send(AppEvent::OpenHelp);
+            }
+            UserEvent::Confirm | UserEvent::Cancel | UserEvent::Close => {
+                self.commit_diff_state = None;
+            }
+            UserEvent::Refresh => {
+                self.refresh();
+            }
+            _ => {}
+        }
+    }
+
     pub fn render(&mut self, f: &mut Frame, area: Rect) {
         let [list_area, detail_area] = self.split_areas(area);
 
         let commit_list = CommitList::new(self.ctx.clone());
         f.render_stateful_widget(commit_list, list_area, self.as_mut_list_state());
 
-        let commit_detail =
-            CommitDetail::new(&self.commit, &self.changes, &self.refs, self.ctx.clone());
+        if let Some(commit_diff_state) = self.commit_diff_state.as_mut() {
+            let commit_diff = CommitDiff::new(self.ctx.clone());
+            f.render_stateful_widget(commit_diff, detail_area, commit_diff_state);
+            return;
+        }
+
+        let commit_detail = CommitDetail::new(
+            &self.commit,
+            &self.changes,
+            &self.filtered_changes,
+            &self.refs,
+            self.ctx.clone(),
+        );
         f.render_stateful_widget(commit_detail, detail_area, &mut self.commit_detail_state);
     }
 
     pub fn update_layout(&mut self, area: Rect) {
         let [list_area, _] = self.split_areas(area);
-        self.as_mut_list_state()
-            .update_height(list_area.height as usize);
+        let list_state = self.as_mut_list_state();
+        list_state.update_height(list_area.height as usize);
+        list_state.update_graph_width(list_area.width);
     }
 
     pub fn prepare_graph_uploads(&mut self) {
@@ -189,21 +354,133 @@ impl<'a> DetailView<'a> {
         let commit_list_state = self.as_mut_list_state();
         update_commit_list_state(commit_list_state);
         let selected = commit_list_state.selected_commit_hash().clone();
-        let (commit, changes) = repository.commit_detail(&selected);
-        let refs = repository.refs(&selected).into_iter().cloned().collect();
-        self.commit = commit;
-        self.changes = changes;
-        self.refs = refs;
+        match repository.commit_detail(&selected) {
+            Ok((commit, changes)) => {
+                self.filtered_changes = changes
+                    .iter()
+                    .map(|change| repository.is_filtered_change(&commit, change))
+                    .collect();
+                self.commit = commit;
+                self.changes = changes;
+            }
+            Err(err) => {
+                self.commit = repository.commit(&selected).cloned().unwrap_or_default();
+                self.changes = Vec::new();
+                self.filtered_changes = Vec::new();
+                self.tx.send(AppEvent::NotifyError(err.to_string()));
+            }
+        }
+        self.refs = repository.refs(&selected).into_iter().cloned().collect();
+        self.diffs = None;
+        self.diffs_loading = false;
 
         self.commit_detail_state.select_first();
+        self.commit_detail_state.clear_selected_change();
+        self.commit_diff_state = None;
+    }
+
+    pub fn open_file_diff(&mut self, repository: &Repository) {
+        if self.diffs.is_none() && !self.diffs_loading {
+            match repository.commit_diff_reader(&self.commit.commit_hash) {
+                Ok(reader) => {
+                    self.diffs_loading = true;
+                    loader::load_commit_diff(reader, self.tx.clone());
+                }
+                Err(err) => {
+                    self.tx.send(AppEvent::NotifyError(err.to_string()));
+                    return;
+                }
+            }
+        }
+        self.show_selected_file_diff();
+    }
+
+    pub fn commit_diff_loaded(&mut self, commit_hash: &CommitHash, result: Result<Vec<FileDiff>>) {
+        // the patches of the commits selected before are discarded
+        if !self.diffs_loading || self.commit.commit_hash != *commit_hash {
+            return;
+        }
+        self.diffs_loading = false;
+        match result {
+            Ok(diffs) => {
+                self.diffs = Some(diffs);
+                if self.commit_diff_state.is_some() {
+                    self.show_selected_file_diff();
+                }
+            }
+            Err(err) => self.tx.send(AppEvent::NotifyError(err.to_string())),
+        }
+    }
+
+    fn show_selected_file_diff(&mut self) {
+        let Some(change) = self.selected_change() else {
+            return;
+        };
+        let diffs: Vec<FileDiff> = self
+            .diffs
+            .iter()
+            .flatten()
+            .find(|diff| change.is_change_of(diff))
+            .cloned()
+            .into_iter()
+            .collect();
+        match self.commit_diff_state.as_mut() {
+            Some(state) => state.set_diffs(diffs),
+            None => {
+                let layout = self.ctx.ui_config.diff.layout;
+                self.commit_diff_state = Some(CommitDiffState::new(diffs, layout));
+            }
+        }
+    }
+
+    fn filter_selected_file(&self) {
+        let Some(change) = self.selected_change() else {
+            self.tx
+                .send(AppEvent::NotifyWarn("No file selected".into()));
+            return;
+        };
+        let paths = vec![change.path().into()];
+        let list_context = ListRefreshViewContext::from(self.as_list_state());
+        let context = RefreshViewContext::Detail { list_context };
+        self.tx.send(AppEvent::FilterPaths(paths, context));
+    }
+
+    fn blame_selected_file(&self) {
+        let Some(change) = self.selected_change() else {
+            self.tx
+                .send(AppEvent::NotifyWarn("No file selected".into()));
+            return;
+        };
+        if let FileChange::Delete { .. } = change {
+            self.tx.send(AppEvent::NotifyWarn(
+                "The file is deleted in this commit".into(),
+            ));
+            return;
+        }
+        let commit_hash = self.commit.commit_hash.clone();
+        self.tx
+            .send(AppEvent::OpenBlame(commit_hash, change.path().into()));
+    }
+
+    fn selected_change(&self) -> Option<&FileChange> {
+        let i = self.commit_detail_state.selected_change()?;
+        self.changes.get(i)
     }
 
     fn copy_commit_short_hash(&self) {
+        if self.as_list_state().selected_commit_is_collapsed() {
+            self.warn_collapsed_commits();
+            return;
+        }
         let selected = &self.commit.commit_hash;
         self.copy_to_clipboard("Commit SHA (short)".into(), selected.as_short_hash().into());
     }
 
     fn copy_commit_hash(&self) {
+        if self.as_list_state().selected_commit_is_collapsed() {
+            self.warn_collapsed_commits();
+            return;
+        }
         let selected = &self.commit.commit_hash;
         self.copy_to_clipboard("Commit SHA".into(), selected.as_str().into());
     }
@@ -212,6 +489,11 @@ impl<'a> DetailView<'a> {
         self.tx.send(AppEvent::CopyToClipboard { name, value });
     }
 
+    fn warn_collapsed_commits(&self) {
+        let msg = "Expand the collapsed commits to copy the hash of one";
+        self.tx.send(AppEvent::NotifyWarn(msg.into()));
+    }
+
     pub fn refresh(&self) {
         let list_state = self.as_list_state();
         let list_context = ListRefreshViewContext::from(list_state);
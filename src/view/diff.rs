@@ -0,0 +1,272 @@
+use std::rc::Rc;
+
+use ratatui::{
+    crossterm::event::KeyEvent,
+    layout::{Constraint, Layout, Rect},
+    Frame,
+};
+
+use crate::{
+    app::AppContext,
+    event::{AppEvent, Sender, UserEvent, UserEventWithCount},
+    git::{CommitHash, FileDiff, Repository, Result},
+    loader,
+    view::{DiffRefreshViewContext, ListRefreshViewContext, RefreshViewContext},
+    widget::{
+        commit_diff::{CommitDiff, CommitDiffState, DiffLayout},
+        commit_list::{CommitList, CommitListState},
+    },
+};
+
+#[derive(Debug)]
+pub struct DiffView<'a> {
+    commit_list_state: Option<CommitListState<'a>>,
+    commit_diff_state: CommitDiffState,
+
+    ctx: Rc<AppContext>,
+    tx: Sender,
+}
+
+impl<'a> DiffView<'a> {
+    pub fn new(
+        commit_list_state: CommitListState<'a>,
+        layout: DiffLayout,
+        ctx: Rc<AppContext>,
+        tx: Sender,
+    ) -> DiffView<'a> {
+        // the patch is shown when it is loaded
+        DiffView {
+            commit_list_state: Some(commit_list_state),
+            commit_diff_state: CommitDiffState::new(Vec::new(), layout),
+            ctx,
+            tx,
+        }
+    }
+
+    pub fn handle_event(&mut self, event_with_count: UserEventWithCount, _: KeyEvent) {
+        let event = event_with_count.event;
+        let count = event_with_count.count;
+
+        match event {
+            UserEvent::NavigateDown => {
+                for _ in 0..count {
+                    self.commit_diff_state.scroll_down();
+                }
+            }
+            UserEvent::NavigateUp => {
+                for _ in 0..count {
+                    self.commit_diff_state.scroll_up();
+                }
+            }
+            UserEvent::PageDown => {
+                for _ in 0..count {
+                    self.commit_diff_state.scroll_page_down();
+                }
+            }
+            UserEvent::PageUp => {
+                for _ in 0..count {
+                    self.commit_diff_state.scroll_page_up();
+                }
+            }
+            UserEvent::HalfPageDown => {
+                for _ in 0..count {
+                    self.commit_diff_state.scroll_half_page_down();
+                }
+            }
+            UserEvent::HalfPageUp => {
+                for _ in 0..count {
+                    self.commit_diff_state.scroll_half_page_up();
+                }
+            }
+            UserEvent::GoToTop => {
+                self.commit_diff_state.select_first();
+            }
+            UserEvent::GoToBottom => {
+                self.commit_diff_state.select_last();
+            }
+            UserEvent::GoToNext => {
+                self.commit_diff_state.select_next_hunk();
+            }
+            UserEvent::GoToPrevious => {
+                self.commit_diff_state.select_prev_hunk();
+            }
+            UserEvent::GoToNextFile => {
+                self.commit_diff_state.select_next_file();
+            }
+            UserEvent::GoToPreviousFile => {
+                self.commit_diff_state.select_prev_file();
+            }
+            UserEvent::FoldToggle => {
+                self.commit_diff_state.toggle_fold();
+            }
+            UserEvent::DiffLayoutToggle => {
+                self.commit_diff_state.toggle_layout();
+            }
+            UserEvent::SelectDown => {
+                self.tx.send(AppEvent::SelectOlderCommit);
+            }
+            UserEvent::SelectUp => {
+                self.tx.send(AppEvent::SelectNewerCommit);
+            }
+            UserEvent::GoToParent => {
+                self.tx.send(AppEvent::SelectParentCommit);
+            }
+            UserEvent::ShortCopy => {
+                self.copy_commit_short_hash();
+            }
+            UserEvent::FullCopy => {
+                self.copy_commit_hash();
+            }
+            UserEvent::UserCommand(n) => {
+                self.tx.send(AppEvent::OpenUserCommand(n));
+            }
+            UserEvent::HelpToggle => {
+                self.tx.send(AppEvent::OpenHelp);
+            }
+            UserEvent::Confirm => {
+                self.tx.send(AppEvent::OpenDetail);
+            }
+            UserEvent::FileTreeToggle => {
+                self.tx.send(AppEvent::OpenFileTree);
+            }
+            UserEvent::DiffToggle | UserEvent::Cancel | UserEvent::Close => {
+                self.tx.send(AppEvent::CloseDiff);
+            }
+            UserEvent::Refresh => {
+                self.refresh();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        let [list_area, diff_area] = self.split_areas(area);
+
+        let commit_list = CommitList::new(self.ctx.clone());
+        f.render_stateful_widget(commit_list, list_area, self.as_mut_list_state());
+
+        let commit_diff = CommitDiff::new(self.ctx.clone());
+        f.render_stateful_widget(commit_diff, diff_area, &mut self.commit_diff_state);
+    }
+
+    pub fn update_layout(&mut self, area: Rect) {
+        let [list_area, _] = self.split_areas(area);
+        let list_state = self.as_mut_list_state();
+        list_state.update_height(list_area.height as usize);
+        list_state.update_graph_width(list_area.width);
+    }
+
+    pub fn prepare_graph_uploads(&mut self) {
+        self.as_mut_list_state().ensure_visible_graph_uploaded();
+    }
+}
+
+impl<'a> DiffView<'a> {
+    pub fn take_list_state(&mut self) -> CommitListState<'a> {
+        self.commit_list_state.take().unwrap()
+    }
+
+    fn as_mut_list_state(&mut self) -> &mut CommitListState<'a> {
+        self.commit_list_state.as_mut().unwrap()
+    }
+
+    pub fn as_list_state(&self) -> &CommitListState<'a> {
+        self.commit_list_state.as_ref().unwrap()
+    }
+
+    pub fn drain_pending_graph_uploads(&mut self) -> Vec<String> {
+        self.as_mut_list_state().drain_pending_graph_uploads()
+    }
+
+    pub fn graph_image_ids_sorted(&self) -> Vec<u32> {
+        self.as_list_state().graph_image_ids_sorted()
+    }
+
+    pub fn set_layout(&mut self, layout: DiffLayout) {
+        if self.commit_diff_state.layout() != layout {
+            self.commit_diff_state.toggle_layout();
+        }
+    }
+
+    fn split_areas(&self, area: Rect) -> [Rect; 2] {
+        let diff_height = (area.height - 1).min(self.ctx.ui_config.diff.height);
+        Layout::vertical([Constraint::Min(0), Constraint::Length(diff_height)]).areas(area)
+    }
+
+    pub fn select_older_commit(&mut self, repository: &Repository) {
+        self.update_selected_commit(repository, |state| state.select_next());
+    }
+
+    pub fn select_newer_commit(&mut self, repository: &Repository) {
+        self.update_selected_commit(repository, |state| state.select_prev());
+    }
+
+    pub fn select_parent_commit(&mut self, repository: &Repository) {
+        self.update_selected_commit(repository, |state| state.select_parent());
+    }
+
+    fn update_selected_commit<F>(&mut self, repository: &Repository, update_commit_list_state: F)
+    where
+        F: FnOnce(&mut CommitListState<'a>),
+    {
+        let commit_list_state = self.as_mut_list_state();
+        update_commit_list_state(commit_list_state);
+        let selected = commit_list_state.selected_commit_hash();
+        match repository.commit_diff_reader(selected) {
+            Ok(reader) => loader::load_commit_diff(reader, self.tx.clone()),
+            Err(err) => self.tx.send(AppEvent::NotifyError(err.to_string())),
+        }
+        self.commit_diff_state.set_diffs(Vec::new());
+    }
+
+    pub fn commit_diff_loaded(&mut self, commit_hash: &CommitHash, result: Result<Vec<FileDiff>>) {
+        // the patches of the commits selected before are discarded
+        if self.as_list_state().selected_commit_hash() != commit_hash {
+            return;
+        }
+        match result {
+            Ok(diffs) => self.commit_diff_state.set_diffs(diffs),
+            Err(err) => self.tx.send(AppEvent::NotifyError(err.to_string())),
+        }
+    }
+
+    fn copy_commit_short_hash(&self) {
+        if self.as_list_state().selected_commit_is_collapsed() {
+            self.warn_collapsed_commits();
+            return;
+        }
+        let selected = self.as_list_state().selected_commit_hash();
+        self.copy_to_clipboard("Commit SHA (short)".into(), selected.as_short_hash().into());
+    }
+
+    fn copy_commit_hash(&self) {
+        if self.as_list_state().selected_commit_is_collapsed() {
+            self.warn_collapsed_commits();
+            return;
+        }
+        let selected = self.as_list_state().selected_commit_hash();
+        self.copy_to_clipboard("Commit SHA".into(), selected.as_str().into());
+    }
+
+    fn copy_to_clipboard(&self, name: String, value: String) {
+        self.tx.send(AppEvent::CopyToClipboard { name, value });
+    }
+
+    fn warn_collapsed_commits(&self) {
+        let msg = "Expand the collapsed commits to copy the hash of one";
+        self.tx.send(AppEvent::NotifyWarn(msg.into()));
+    }
+
+    pub fn refresh(&self) {
+        let list_state = self.as_list_state();
+        let list_context = ListRefreshViewContext::from(list_state);
+        let diff_context = DiffRefreshViewContext {
+            layout: self.commit_diff_state.layout(),
+        };
+        let context = RefreshViewContext::Diff {
+            list_context,
+            diff_context,
+        };
+        self.tx.send(AppEvent::Refresh(context));
+    }
+}
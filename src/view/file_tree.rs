@@ -0,0 +1,383 @@
+use std::rc::Rc;
+
+use ratatui::{
+    crossterm::event::KeyEvent,
+    layout::{Constraint, Layout, Rect},
+    Frame,
+};
+use tui_tree_widget::TreeItem;
+
+use crate::{
+    app::AppContext,
+    event::{AppEvent, Sender, UserEvent, UserEventWithCount},
+    git::{Repository, TreeEntry},
+    syntax::SyntaxHighlighter,
+    view::{FileTreeRefreshViewContext, ListRefreshViewContext, RefreshViewContext},
+    widget::{
+        commit_list::{CommitList, CommitListState},
+        file_preview::{FilePreview, FilePreviewState, MAX_PREVIEW_BYTES},
+        file_tree::{build_file_tree_items, FileTree, FileTreeState},
+    },
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    #[default]
+    Tree,
+    Preview,
+}
+
+#[derive(Debug)]
+pub struct FileTreeView<'a> {
+    commit_list_state: Option<CommitListState<'a>>,
+    file_tree_state: FileTreeState,
+    file_preview_state: Option<FilePreviewState>,
+
+    entries: Vec<TreeEntry>,
+    tree_items: Vec<TreeItem<'static, String>>,
+    focus: Focus,
+
+    ctx: Rc<AppContext>,
+    tx: Sender,
+}
+
+impl<'a> FileTreeView<'a> {
+    pub fn new(
+        commit_list_state: CommitListState<'a>,
+        entries: Vec<TreeEntry>,
+        ctx: Rc<AppContext>,
+        tx: Sender,
+    ) -> FileTreeView<'a> {
+        let tree_items = build_file_tree_items(&entries, &ctx.color_theme);
+        FileTreeView {
+            commit_list_state: Some(commit_list_state),
+            file_tree_state: FileTreeState::new(&tree_items),
+            file_preview_state: None,
+            entries,
+            tree_items,
+            focus: Focus::Tree,
+            ctx,
+            tx,
+        }
+    }
+
+    pub fn handle_event(&mut self, event_with_count: UserEventWithCount, key: KeyEvent) {
+        match self.focus {
+            Focus::Tree => self.handle_tree_event(event_with_count, key),
+            Focus::Preview => self.handle_preview_event(event_with_count, key),
+        }
+    }
+
+    fn handle_tree_event(&mut self, event_with_count: UserEventWithCount, _: KeyEvent) {
+        let event = event_with_count.event;
+        let count = event_with_count.count;
+
+        match event {
+            UserEvent::NavigateDown => {
+                for _ in 0..count {
+                    self.file_tree_state.select_next();
+                }
+            }
+            UserEvent::NavigateUp => {
+                for _ in 0..count {
+                    self.file_tree_state.select_prev();
+                }
+            }
+            UserEvent::GoToTop => {
+                self.file_tree_state.select_first();
+            }
+            UserEvent::GoToBottom => {
+                self.file_tree_state.select_last();
+            }
+            UserEvent::NavigateRight => {
+                self.file_tree_state.open_node();
+            }
+            UserEvent::NavigateLeft => {
+                self.file_tree_state.close_node();
+            }
+            UserEvent::Confirm => {
+                if self.selected_entry().is_some() {
+                    self.tx.send(AppEvent::OpenFilePreview);
+                } else {
+                    self.file_tree_state.toggle_node();
+                }
+            }
+            UserEvent::SelectDown => {
+                self.tx.send(AppEvent::SelectOlderCommit);
+            }
+            UserEvent::SelectUp => {
+                self.tx.send(AppEvent::SelectNewerCommit);
+            }
+            UserEvent::GoToParent => {
+                self.tx.send(AppEvent::SelectParentCommit);
+            }
+            UserEvent::Blame => {
+                if let Some(entry) = self.selected_entry() {
+                    self.blame_file(entry.path.clone());
+                }
+            }
+            UserEvent::ShortCopy | UserEvent::FullCopy => {
+                self.copy_selected_path();
+            }
+            UserEvent::HelpToggle => {
+                self.tx.send(AppEvent::OpenHelp);
+            }
+            UserEvent::FileTreeToggle | UserEvent::Cancel | UserEvent::Close => {
+                self.tx.send(AppEvent::CloseFileTree);
+            }
+            UserEvent::Refresh => {
+                self.refresh();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_preview_event(&mut self, event_with_count: UserEventWithCount, _: KeyEvent) {
+        let event = event_with_count.event;
+        let count = event_with_count.count;
+        let Some(file_preview_state) = self.file_preview_state.as_mut() else {
+            self.focus = Focus::Tree;
+            return;
+        };
+
+        match event {
+            UserEvent::NavigateDown => {
+                for _ in 0..count {
+                    file_preview_state.scroll_down();
+                }
+            }
+            UserEvent::NavigateUp => {
+                for _ in 0..count {
+                    file_preview_state.scroll_up();
+                }
+            }
+            UserEvent::PageDown => {
+                for _ in 0..count {
+                    file_preview_state.scroll_page_down();
+                }
+            }
+            UserEvent::PageUp => {
+                for _ in 0..count {
+                    file_preview_state.scroll_page_up();
+                }
+            }
+            UserEvent::HalfPageDown => {
+                for _ in 0..count {
+                    file_preview_state.scroll_half_page_down();
+                }
+            }
+            UserEvent::HalfPageUp => {
+                for _ in 0..count {
+                    file_preview_state.scroll_half_page_up();
+                }
+            }
+            UserEvent::GoToTop => {
+                file_preview_state.select_first();
+            }
+            UserEvent::GoToBottom => {
+                file_preview_state.select_last();
+            }
+            UserEvent::Blame => {
+                let path = file_preview_state.path().into();
+                self.blame_file(path);
+            }
+            UserEvent::ShortCopy | UserEvent::FullCopy => {
+                let path = file_preview_state.path().into();
+                self.copy_to_clipboard("File Path".into(), path);
+            }
+            UserEvent::HelpToggle => {
+                self.tx.send(AppEvent::OpenHelp);
+            }
+            UserEvent::Cancel | UserEvent::Close | UserEvent::NavigateLeft => {
+                self.focus = Focus::Tree;
+            }
+            UserEvent::FileTreeToggle => {
+                self.tx.send(AppEvent::CloseFileTree);
+            }
+            UserEvent::Refresh => {
+                self.refresh();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        let [list_area, file_tree_area] = self.split_areas(area);
+
+        let commit_list = CommitList::new(self.ctx.clone());
+        f.render_stateful_widget(commit_list, list_area, self.as_mut_list_state());
+
+        let tree_width = (file_tree_area.width / 2).min(self.ctx.ui_config.file_tree.width);
+        let [tree_area, preview_area] =
+            Layout::horizontal([Constraint::Length(tree_width), Constraint::Min(0)])
+                .areas(file_tree_area);
+
+        let file_tree = FileTree::new(&self.tree_items, self.ctx.clone());
+        f.render_stateful_widget(file_tree, tree_area, &mut self.file_tree_state);
+
+        if let Some(file_preview_state) = self.file_preview_state.as_mut() {
+            let file_preview = FilePreview::new(self.ctx.clone());
+            f.render_stateful_widget(file_preview, preview_area, file_preview_state);
+        }
+    }
+
+    pub fn update_layout(&mut self, area: Rect) {
+        let [list_area, _] = self.split_areas(area);
+        let list_state = self.as_mut_list_state();
+        list_state.update_height(list_area.height as usize);
+        list_state.update_graph_width(list_area.width);
+    }
+
+    pub fn prepare_graph_uploads(&mut self) {
+        self.as_mut_list_state().ensure_visible_graph_uploaded();
+    }
+}
+
+impl<'a> FileTreeView<'a> {
+    pub fn take_list_state(&mut self) -> CommitListState<'a> {
+        self.commit_list_state.take().unwrap()
+    }
+
+    fn as_mut_list_state(&mut self) -> &mut CommitListState<'a> {
+        self.commit_list_state.as_mut().unwrap()
+    }
+
+    fn as_list_state(&self) -> &CommitListState<'a> {
+        self.commit_list_state.as_ref().unwrap()
+    }
+
+    pub fn drain_pending_graph_uploads(&mut self) -> Vec<String> {
+        self.as_mut_list_state().drain_pending_graph_uploads()
+    }
+
+    pub fn graph_image_ids_sorted(&self) -> Vec<u32> {
+        self.as_list_state().graph_image_ids_sorted()
+    }
+
+    fn split_areas(&self, area: Rect) -> [Rect; 2] {
+        let file_tree_height = (area.height - 1).min(self.ctx.ui_config.file_tree.height);
+        Layout::vertical([Constraint::Min(0), Constraint::Length(file_tree_height)]).areas(area)
+    }
+
+    fn selected_entry(&self) -> Option<&TreeEntry> {
+        let path = self.file_tree_state.selected_path()?;
+        self.entries.iter().find(|entry| entry.path == path)
+    }
+
+    pub fn open_file_preview(&mut self, repository: &Repository) {
+        let Some(entry) = self.selected_entry().cloned() else {
+            return;
+        };
+        let commit_hash = self.as_list_state().selected_commit_hash().clone();
+
+        let bytes = if entry.is_submodule() {
+            Vec::new()
+        } else {
+            match repository.blob_content(&entry, MAX_PREVIEW_BYTES) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    self.tx.send(AppEvent::NotifyError(err.to_string()));
+                    return;
+                }
+            }
+        };
+        let last_modified = match repository.last_modified_commit(&commit_hash, &entry.path) {
+            Ok(hash) => hash.map(|hash| {
+                let commit = repository.commit(&hash).cloned();
+                (hash, commit)
+            }),
+            Err(err) => {
+                self.tx.send(AppEvent::NotifyError(err.to_string()));
+                None
+            }
+        };
+        let highlighter = SyntaxHighlighter::new(&self.ctx.ui_config.syntax);
+
+        self.file_preview_state = Some(FilePreviewState::new(
+            entry,
+            bytes,
+            last_modified,
+            highlighter,
+        ));
+        self.focus = Focus::Preview;
+    }
+
+    pub fn select_older_commit(&mut self, repository: &Repository) {
+        self.update_selected_commit(repository, |state| state.select_next());
+    }
+
+    pub fn select_newer_commit(&mut self, repository: &Repository) {
+        self.update_selected_commit(repository, |state| state.select_prev());
+    }
+
+    pub fn select_parent_commit(&mut self, repository: &Repository) {
+        self.update_selected_commit(repository, |state| state.select_parent());
+    }
+
+    fn update_selected_commit<F>(&mut self, repository: &Repository, update_commit_list_state: F)
+    where
+        F: FnOnce(&mut CommitListState<'a>),
+    {
+        let commit_list_state = self.as_mut_list_state();
+        update_commit_list_state(commit_list_state);
+        let selected = commit_list_state.selected_commit_hash().clone();
+        let entries = repository.tree_entries(&selected).unwrap_or_else(|err| {
+            self.tx.send(AppEvent::NotifyError(err.to_string()));
+            Vec::new()
+        });
+
+        // keep the selected and opened directories as long as they exist in the new tree
+        let (selected, opened) = self.file_tree_state.current_tree_status();
+        self.tree_items = build_file_tree_items(&entries, &self.ctx.color_theme);
+        self.entries = entries;
+        self.file_tree_state = FileTreeState::new(&self.tree_items);
+        self.file_tree_state.reset_tree_status(selected, opened);
+
+        self.file_preview_state = None;
+        self.focus = Focus::Tree;
+    }
+
+    fn blame_file(&self, path: String) {
+        if self
+            .entries
+            .iter()
+            .any(|e| e.path == path && e.is_submodule())
+        {
+            self.tx
+                .send(AppEvent::NotifyWarn("Cannot blame a submodule".into()));
+            return;
+        }
+        let commit_hash = self.as_list_state().selected_commit_hash().clone();
+        self.tx.send(AppEvent::OpenBlame(commit_hash, path));
+    }
+
+    fn copy_selected_path(&self) {
+        if let Some(path) = self.file_tree_state.selected_path() {
+            self.copy_to_clipboard("File Path".into(), path);
+        }
+    }
+
+    fn copy_to_clipboard(&self, name: String, value: String) {
+        self.tx.send(AppEvent::CopyToClipboard { name, value });
+    }
+
+    pub fn refresh(&self) {
+        let list_state = self.as_list_state();
+        let list_context = ListRefreshViewContext::from(list_state);
+        let (tree_selected, tree_opened) = self.file_tree_state.current_tree_status();
+        let file_tree_context = FileTreeRefreshViewContext {
+            selected: tree_selected,
+            opened: tree_opened,
+        };
+        let context = RefreshViewContext::FileTree {
+            list_context,
+            file_tree_context,
+        };
+        self.tx.send(AppEvent::Refresh(context));
+    }
+
+    pub fn reset_file_tree_with(&mut self, file_tree_context: FileTreeRefreshViewContext) {
+        self.file_tree_state
+            .reset_tree_status(file_tree_context.selected, file_tree_context.opened);
+    }
+}
@@ -242,17 +242,27 @@ fn build_lines(
         (vec![UserEvent::HalfPageUp], "Scroll half page up".into()),
         (vec![UserEvent::ScrollDown], "Scroll down".into()),
         (vec![UserEvent::ScrollUp], "Scroll up".into()),
+        (vec![UserEvent::NavigateRight], "Scroll graph right".into()),
+        (vec![UserEvent::NavigateLeft], "Scroll graph left".into()),
         (vec![UserEvent::SelectTop], "Select top of the screen".into()),
         (vec![UserEvent::SelectMiddle], "Select middle of the screen".into()),
         (vec![UserEvent::SelectBottom], "Select bottom of the screen".into()),
         (vec![UserEvent::Confirm], "Show commit details".into()),
+        (vec![UserEvent::DiffToggle], "Show commit diff".into()),
         (vec![UserEvent::RefList], "Open refs list".into()),
+        (vec![UserEvent::FileTreeToggle], "Show file tree".into()),
         (vec![UserEvent::Search], "Start search".into()),
         (vec![UserEvent::Cancel], "Cancel search".into()),
         (vec![UserEvent::GoToNext], "Go to next search match".into()),
         (vec![UserEvent::GoToPrevious], "Go to previous search match".into()),
         (vec![UserEvent::IgnoreCaseToggle], "Toggle ignore case".into()),
         (vec![UserEvent::FuzzyToggle], "Toggle fuzzy match".into()),
+        (vec![UserEvent::PathFilter], "Filter by paths".into()),
+        (vec![UserEvent::CommitFilter], "Filter commits".into()),
+        (vec![UserEvent::FirstParentToggle], "Toggle first-parent history".into()),
+        (vec![UserEvent::DecorationToggle], "Toggle simplify by decoration".into()),
+        (vec![UserEvent::FoldToggle], "Fold/unfold merged branch".into()),
+        (vec![UserEvent::HighlightToggle], "Switch highlight of ancestry".into()),
         (vec![UserEvent::Refresh], "Refresh".into()),
         (vec![UserEvent::ShortCopy], "Copy commit short hash".into()),
         (vec![UserEvent::FullCopy], "Copy commit hash".into()),
@@ -261,7 +271,7 @@ fn build_lines(
     let (list_key_lines, list_value_lines) = build_block_lines("Commit List:", list_helps, color_theme, keybind);
     
     let mut detail_helps = vec![
-        (vec![UserEvent::Cancel, UserEvent::Close, UserEvent::Confirm], "Close commit details".into()),
+        (vec![UserEvent::Cancel, UserEvent::Close], "Close commit details / file patch".into()),
         (vec![UserEvent::NavigateDown], "Scroll down".into()),
         (vec![UserEvent::NavigateUp], "Scroll up".into()),
         (vec![UserEvent::PageDown], "Scroll page down".into()),
@@ -270,9 +280,18 @@ fn build_lines(
         (vec![UserEvent::HalfPageUp], "Scroll half page up".into()),
         (vec![UserEvent::GoToTop], "Go to top".into()),
         (vec![UserEvent::GoToBottom], "Go to bottom".into()),
+        (vec![UserEvent::GoToNextFile], "Select next changed file".into()),
+        (vec![UserEvent::GoToPreviousFile], "Select previous changed file".into()),
+        (vec![UserEvent::Confirm], "Show patch of selected file".into()),
+        (vec![UserEvent::GoToNext], "Go to next hunk in file patch".into()),
+        (vec![UserEvent::GoToPrevious], "Go to previous hunk in file patch".into()),
+        (vec![UserEvent::PathFilter], "Show history of selected file".into()),
+        (vec![UserEvent::Blame], "Blame selected file".into()),
         (vec![UserEvent::SelectDown], "Select older commit".into()),
         (vec![UserEvent::SelectUp], "Select newer commit".into()),
         (vec![UserEvent::GoToParent], "Select parent commit".into()),
+        (vec![UserEvent::DiffToggle], "Show commit diff".into()),
+        (vec![UserEvent::FileTreeToggle], "Show file tree".into()),
         (vec![UserEvent::Refresh], "Refresh".into()),
         (vec![UserEvent::ShortCopy], "Copy commit short hash".into()),
         (vec![UserEvent::FullCopy], "Copy commit hash".into()),
@@ -280,6 +299,34 @@ fn build_lines(
     detail_helps.extend(user_command_help_items.clone());
     let (detail_key_lines, detail_value_lines) = build_block_lines("Commit Detail:", detail_helps, color_theme, keybind);
 
+    let mut diff_helps = vec![
+        (vec![UserEvent::DiffToggle, UserEvent::Cancel, UserEvent::Close], "Close commit diff".into()),
+        (vec![UserEvent::NavigateDown], "Scroll down".into()),
+        (vec![UserEvent::NavigateUp], "Scroll up".into()),
+        (vec![UserEvent::PageDown], "Scroll page down".into()),
+        (vec![UserEvent::PageUp], "Scroll page up".into()),
+        (vec![UserEvent::HalfPageDown], "Scroll half page down".into()),
+        (vec![UserEvent::HalfPageUp], "Scroll half page up".into()),
+        (vec![UserEvent::GoToTop], "Go to top".into()),
+        (vec![UserEvent::GoToBottom], "Go to bottom".into()),
+        (vec![UserEvent::GoToNext], "Go to next hunk".into()),
+        (vec![UserEvent::GoToPrevious], "Go to previous hunk".into()),
+        (vec![UserEvent::GoToNextFile], "Go to next file".into()),
+        (vec![UserEvent::GoToPreviousFile], "Go to previous file".into()),
+        (vec![UserEvent::FoldToggle], "Fold/unfold file".into()),
+        (vec![UserEvent::DiffLayoutToggle], "Switch unified/side-by-side layout".into()),
+        (vec![UserEvent::SelectDown], "Select older commit".into()),
+        (vec![UserEvent::SelectUp], "Select newer commit".into()),
+        (vec![UserEvent::GoToParent], "Select parent commit".into()),
+        (vec![UserEvent::Confirm], "Show commit details".into()),
+        (vec![UserEvent::FileTreeToggle], "Show file tree".into()),
+        (vec![UserEvent::Refresh], "Refresh".into()),
+        (vec![UserEvent::ShortCopy], "Copy commit short hash".into()),
+        (vec![UserEvent::FullCopy], "Copy commit hash".into()),
+    ];
+    diff_helps.extend(user_command_help_items.clone());
+    let (diff_key_lines, diff_value_lines) = build_block_lines("Commit Diff:", diff_helps, color_theme, keybind);
+
     let refs_helps = vec![
         (vec![UserEvent::Cancel, UserEvent::Close, UserEvent::RefList], "Close refs list".into()),
         (vec![UserEvent::NavigateDown, UserEvent::SelectDown], "Move down".into()),
@@ -292,6 +339,46 @@ fn build_lines(
         (vec![UserEvent::ShortCopy], "Copy ref name".into()),
     ];
     let (refs_key_lines, refs_value_lines) = build_block_lines("Refs List:", refs_helps, color_theme, keybind);
+
+    let file_tree_helps = vec![
+        (vec![UserEvent::Cancel, UserEvent::Close, UserEvent::FileTreeToggle], "Close file tree / file preview".into()),
+        (vec![UserEvent::NavigateDown], "Move down / Scroll down".into()),
+        (vec![UserEvent::NavigateUp], "Move up / Scroll up".into()),
+        (vec![UserEvent::PageDown], "Scroll page down".into()),
+        (vec![UserEvent::PageUp], "Scroll page up".into()),
+        (vec![UserEvent::HalfPageDown], "Scroll half page down".into()),
+        (vec![UserEvent::HalfPageUp], "Scroll half page up".into()),
+        (vec![UserEvent::GoToTop], "Go to top".into()),
+        (vec![UserEvent::GoToBottom], "Go to bottom".into()),
+        (vec![UserEvent::NavigateRight], "Open node".into()),
+        (vec![UserEvent::NavigateLeft], "Close node".into()),
+        (vec![UserEvent::Confirm], "Show file preview / Toggle node".into()),
+        (vec![UserEvent::Blame], "Blame selected file".into()),
+        (vec![UserEvent::SelectDown], "Select older commit".into()),
+        (vec![UserEvent::SelectUp], "Select newer commit".into()),
+        (vec![UserEvent::GoToParent], "Select parent commit".into()),
+        (vec![UserEvent::Refresh], "Refresh".into()),
+        (vec![UserEvent::ShortCopy], "Copy file path".into()),
+    ];
+    let (file_tree_key_lines, file_tree_value_lines) = build_block_lines("File Tree:", file_tree_helps, color_theme, keybind);
+
+    let blame_helps = vec![
+        (vec![UserEvent::Cancel, UserEvent::Close, UserEvent::Blame], "Close blame".into()),
+        (vec![UserEvent::NavigateDown], "Move down".into()),
+        (vec![UserEvent::NavigateUp], "Move up".into()),
+        (vec![UserEvent::PageDown], "Scroll page down".into()),
+        (vec![UserEvent::PageUp], "Scroll page up".into()),
+        (vec![UserEvent::HalfPageDown], "Scroll half page down".into()),
+        (vec![UserEvent::HalfPageUp], "Scroll half page up".into()),
+        (vec![UserEvent::GoToTop], "Go to top".into()),
+        (vec![UserEvent::GoToBottom], "Go to bottom".into()),
+        (vec![UserEvent::Confirm], "Select commit of line in graph".into()),
+        (vec![UserEvent::GoToParent], "Blame at parent of line's commit".into()),
+        (vec![UserEvent::Refresh], "Refresh".into()),
+        (vec![UserEvent::ShortCopy], "Copy commit short hash of line".into()),
+        (vec![UserEvent::FullCopy], "Copy commit hash of line".into()),
+    ];
+    let (blame_key_lines, blame_value_lines) = build_block_lines("Blame:", blame_helps, color_theme, keybind);
     
     let mut user_command_helps = vec![
         (vec![UserEvent::Cancel, UserEvent::Close], "Close user command".into()),
@@ -308,6 +395,7 @@ fn build_lines(
         (vec![UserEvent::GoToParent], "Select parent commit".into()),
         (vec![UserEvent::Refresh], "Refresh".into()),
         (vec![UserEvent::Confirm], "Show commit details".into()),
+        (vec![UserEvent::DiffToggle], "Show commit diff".into()),
     ];
     user_command_helps.extend(user_command_help_items);
     let (user_command_key_lines, user_command_value_lines) = build_block_lines("User Command:", user_command_helps, color_theme, keybind);
@@ -317,7 +405,10 @@ fn build_lines(
         help_key_lines,
         list_key_lines,
         detail_key_lines,
+        diff_key_lines,
         refs_key_lines,
+        file_tree_key_lines,
+        blame_key_lines,
         user_command_key_lines,
     ]);
     let value_lines = join_line_groups_with_empty(vec![
@@ -325,7 +416,10 @@ fn build_lines(
         help_value_lines,
         list_value_lines,
         detail_value_lines,
+        diff_value_lines,
         refs_value_lines,
+        file_tree_value_lines,
+        blame_value_lines,
         user_command_value_lines,
     ]);
 
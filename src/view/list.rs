@@ -1,18 +1,41 @@
 use std::rc::Rc;
 
-use ratatui::{crossterm::event::KeyEvent, layout::Rect, Frame};
+use ratatui::{
+    crossterm::event::{Event, KeyEvent},
+    layout::Rect,
+    Frame,
+};
+use tui_input::{backend::crossterm::EventHandler, Input};
 
 use crate::{
     app::AppContext,
     event::{AppEvent, Sender, UserEvent, UserEventWithCount},
-    git::CommitHash,
+    git::{CommitHash, RepositoryPath},
     view::{ListRefreshViewContext, RefreshViewContext},
     widget::commit_list::{CommitList, CommitListState, SearchState},
 };
 
+#[derive(Debug, Clone, Copy)]
+enum FilterPrompt {
+    // the paths to filter the commits by
+    Path,
+    // the query to hide the commits that do not match
+    Commit,
+}
+
+impl FilterPrompt {
+    fn label(self) -> &'static str {
+        match self {
+            FilterPrompt::Path => "Path: ",
+            FilterPrompt::Commit => "Filter: ",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ListView<'a> {
     commit_list_state: Option<CommitListState<'a>>,
+    filter_input: Option<(FilterPrompt, Input)>,
 
     ctx: Rc<AppContext>,
     tx: Sender,
@@ -26,6 +49,7 @@ impl<'a> ListView<'a> {
     ) -> ListView<'a> {
         ListView {
             commit_list_state: Some(commit_list_state),
+            filter_input: None,
             ctx,
             tx,
         }
@@ -34,6 +58,22 @@ impl<'a> ListView<'a> {
     pub fn handle_event(&mut self, event_with_count: UserEventWithCount, key: KeyEvent) {
         let event = event_with_count.event;
         let count = event_with_count.count;
+        if let Some((_, input)) = &mut self.filter_input {
+            match event {
+                UserEvent::Confirm => {
+                    self.apply_filter();
+                }
+                UserEvent::Cancel => {
+                    self.filter_input = None;
+                    self.clear_search_query();
+                }
+                _ => {
+                    input.handle_event(&Event::Key(key));
+                    self.update_filter_input();
+                }
+            }
+            return;
+        }
         if let SearchState::Searching { .. } = self.as_list_state().search_state() {
             match event {
                 UserEvent::Confirm => {
@@ -78,6 +118,16 @@ impl<'a> ListView<'a> {
                         self.as_mut_list_state().select_parent();
                     }
                 }
+                UserEvent::NavigateRight => {
+                    for _ in 0..count {
+                        self.as_mut_list_state().scroll_graph_right();
+                    }
+                }
+                UserEvent::NavigateLeft => {
+                    for _ in 0..count {
+                        self.as_mut_list_state().scroll_graph_left();
+                    }
+                }
                 UserEvent::GoToTop => {
                     self.as_mut_list_state().select_first();
                 }
@@ -133,6 +183,39 @@ impl<'a> ListView<'a> {
                     self.as_mut_list_state().start_search();
                     self.update_search_query();
                 }
+                UserEvent::PathFilter => {
+                    self.filter_input = Some((FilterPrompt::Path, Input::default()));
+                    self.update_filter_input();
+                }
+                UserEvent::CommitFilter => {
+                    self.filter_input = Some((FilterPrompt::Commit, Input::default()));
+                    self.update_filter_input();
+                }
+                UserEvent::FirstParentToggle => {
+                    self.tx
+                        .send(AppEvent::ToggleFirstParent(self.refresh_context()));
+                }
+                UserEvent::DecorationToggle => {
+                    self.tx
+                        .send(AppEvent::ToggleSimplifyByDecoration(self.refresh_context()));
+                }
+                UserEvent::FoldToggle => {
+                    let commit_hash = self.as_list_state().selected_commit_hash().clone();
+                    self.tx
+                        .send(AppEvent::ToggleFold(commit_hash, self.refresh_context()));
+                }
+                UserEvent::HighlightToggle => {
+                    let msg = match self.as_mut_list_state().toggle_highlight_mode() {
+                        Some(mode) => {
+                            format!(
+                                "Highlighting the {} of the selected commit",
+                                mode.description()
+                            )
+                        }
+                        None => "Highlight turned off".into(),
+                    };
+                    self.tx.send(AppEvent::NotifyInfo(msg));
+                }
                 UserEvent::UserCommand(n) => {
                     self.tx.send(AppEvent::OpenUserCommand(n));
                 }
@@ -144,11 +227,23 @@ impl<'a> ListView<'a> {
                     self.clear_search_query();
                 }
                 UserEvent::Confirm => {
-                    self.tx.send(AppEvent::OpenDetail);
+                    if self.as_list_state().selected_commit_is_collapsed() {
+                        let commit_hash = self.as_list_state().selected_commit_hash().clone();
+                        self.tx
+                            .send(AppEvent::ExpandCommits(commit_hash, self.refresh_context()));
+                    } else {
+                        self.tx.send(AppEvent::OpenDetail);
+                    }
                 }
                 UserEvent::RefList => {
                     self.tx.send(AppEvent::OpenRefs);
                 }
+                UserEvent::DiffToggle => {
+                    self.tx.send(AppEvent::OpenDiff);
+                }
+                UserEvent::FileTreeToggle => {
+                    self.tx.send(AppEvent::OpenFileTree);
+                }
                 UserEvent::Refresh => {
                     self.refresh();
                 }
@@ -178,7 +273,9 @@ impl<'a> ListView<'a> {
     }
 
     pub fn update_layout(&mut self, area: Rect) {
-        self.as_mut_list_state().update_height(area.height as usize);
+        let list_state = self.as_mut_list_state();
+        list_state.update_height(area.height as usize);
+        list_state.update_graph_width(area.width);
     }
 
     pub fn prepare_graph_uploads(&mut self) {
@@ -226,6 +323,36 @@ impl<'a> ListView<'a> {
         self.tx.send(AppEvent::ClearStatusLine);
     }
 
+    fn update_filter_input(&self) {
+        if let Some((prompt, input)) = &self.filter_input {
+            let prompt = prompt.label();
+            let cursor_pos = (prompt.len() + input.visual_cursor()) as u16;
+            let msg = format!("{prompt}{}", input.value());
+            self.tx
+                .send(AppEvent::UpdateStatusInput(msg, Some(cursor_pos), None));
+        }
+    }
+
+    fn apply_filter(&mut self) {
+        // an empty input clears the filter
+        match self.filter_input.take() {
+            Some((FilterPrompt::Path, input)) => {
+                let paths = parse_path_input(input.value(), &self.ctx.repo_path);
+                self.clear_search_query();
+                self.tx
+                    .send(AppEvent::FilterPaths(paths, self.refresh_context()));
+            }
+            Some((FilterPrompt::Commit, input)) => {
+                self.clear_search_query();
+                self.tx.send(AppEvent::FilterCommits(
+                    input.value().into(),
+                    self.refresh_context(),
+                ));
+            }
+            None => {}
+        }
+    }
+
     fn update_matched_message(&self) {
         if let Some((msg, matched)) = self.as_list_state().matched_query_string() {
             if matched {
@@ -239,11 +366,19 @@ impl<'a> ListView<'a> {
     }
 
     fn copy_commit_short_hash(&self) {
+        if self.as_list_state().selected_commit_is_collapsed() {
+            self.warn_collapsed_commits();
+            return;
+        }
         let selected = self.as_list_state().selected_commit_hash();
         self.copy_to_clipboard("Commit SHA (short)".into(), selected.as_short_hash().into());
     }
 
     fn copy_commit_hash(&self) {
+        if self.as_list_state().selected_commit_is_collapsed() {
+            self.warn_collapsed_commits();
+            return;
+        }
         let selected = self.as_list_state().selected_commit_hash();
         self.copy_to_clipboard("Commit SHA".into(), selected.as_str().into());
     }
@@ -252,11 +387,19 @@ impl<'a> ListView<'a> {
         self.tx.send(AppEvent::CopyToClipboard { name, value });
     }
 
+    fn warn_collapsed_commits(&self) {
+        let msg = "Expand the collapsed commits to copy the hash of one";
+        self.tx.send(AppEvent::NotifyWarn(msg.into()));
+    }
+
     pub fn refresh(&self) {
+        self.tx.send(AppEvent::Refresh(self.refresh_context()));
+    }
+
+    pub fn refresh_context(&self) -> RefreshViewContext {
         let list_state = self.as_list_state();
         let list_context = ListRefreshViewContext::from(list_state);
-        let context = RefreshViewContext::List { list_context };
-        self.tx.send(AppEvent::Refresh(context));
+        RefreshViewContext::List { list_context }
     }
 
     pub fn reset_commit_list_with(&mut self, list_context: &ListRefreshViewContext) {
@@ -266,6 +409,7 @@ impl<'a> ListView<'a> {
             height,
             scroll_to_top,
             search_context,
+            highlight_mode,
         } = list_context;
         let list_state = self.as_mut_list_state();
         list_state.reset_height(*height);
@@ -280,5 +424,37 @@ impl<'a> ListView<'a> {
         if let Some(search_context) = search_context {
             list_state.restore_search(search_context);
         }
+        list_state.set_highlight_mode(*highlight_mode);
+    }
+}
+
+// The paths are given relative to the current directory like the paths given as the arguments,
+// while git commands are executed at the top-level directory.
+fn parse_path_input(input: &str, repo_path: &RepositoryPath) -> Vec<String> {
+    let paths = input.split_whitespace().map(String::from).collect();
+    repo_path.resolve_pathspecs(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{git, test_util::GitRepository};
+
+    #[test]
+    fn test_parse_path_input_from_subdirectory() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo_path = dir.path().canonicalize().unwrap();
+        let git = GitRepository::new(&repo_path);
+        git.init();
+        git.commit("commit", "2024-01-01");
+
+        let sub_path = repo_path.join("src");
+        fs::create_dir_all(&sub_path).unwrap();
+        let repo_path = git::resolve_repository_path(&sub_path).unwrap();
+
+        let actual = parse_path_input(" main.rs  ../README.md ", &repo_path);
+        assert_eq!(actual, vec!["src/main.rs", "README.md"]);
     }
 }
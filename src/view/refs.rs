@@ -108,8 +108,9 @@ impl<'a> RefsView<'a> {
 
     pub fn update_layout(&mut self, area: Rect) {
         let [list_area, _] = self.split_areas(area);
-        self.as_mut_list_state()
-            .update_height(list_area.height as usize);
+        let list_state = self.as_mut_list_state();
+        list_state.update_height(list_area.height as usize);
+        list_state.update_graph_width(list_area.width);
     }
 
     pub fn prepare_graph_uploads(&mut self) {
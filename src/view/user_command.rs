@@ -19,7 +19,8 @@ use crate::{
     },
 };
 
-type ExecCommandFn = fn(&Commit, &[Ref], usize, Rect, &AppContext) -> Result<String, String>;
+type ExecCommandFn =
+    fn(&Repository, &Commit, &[Ref], usize, Rect, &AppContext) -> Result<String, String>;
 
 #[derive(Debug)]
 pub struct UserCommandView<'a> {
@@ -121,6 +122,9 @@ impl<'a> UserCommandView<'a> {
             UserEvent::Confirm => {
                 self.tx.send(AppEvent::OpenDetail);
             }
+            UserEvent::DiffToggle => {
+                self.tx.send(AppEvent::OpenDiff);
+            }
             UserEvent::Cancel | UserEvent::Close => {
                 self.tx.send(AppEvent::CloseUserCommand);
             }
@@ -148,8 +152,9 @@ impl<'a> UserCommandView<'a> {
 
     pub fn update_layout(&mut self, area: Rect) {
         let [list_area, _] = self.split_areas(area);
-        self.as_mut_list_state()
-            .update_height(list_area.height as usize);
+        let list_state = self.as_mut_list_state();
+        list_state.update_height(list_area.height as usize);
+        list_state.update_graph_width(list_area.width);
     }
 
     pub fn prepare_graph_uploads(&mut self) {
@@ -229,20 +234,25 @@ impl<'a> UserCommandView<'a> {
         update_commit_list_state(commit_list_state);
 
         let selected = commit_list_state.selected_commit_hash().clone();
-        let (commit, _) = repository.commit_detail(&selected);
         let refs: Vec<Ref> = repository.refs(&selected).into_iter().cloned().collect();
-        self.user_command_output_lines = exec_command(
-            &commit,
-            &refs,
-            self.user_command_number,
-            view_area,
-            &self.ctx,
-        )
-        .and_then(|output| build_user_command_output_lines(output, self.ctx.clone()))
-        .unwrap_or_else(|err| {
-            self.tx.send(AppEvent::NotifyError(err));
-            vec![]
-        });
+        self.user_command_output_lines = repository
+            .commit_detail(&selected)
+            .map_err(|err| err.to_string())
+            .and_then(|(commit, _)| {
+                exec_command(
+                    repository,
+                    &commit,
+                    &refs,
+                    self.user_command_number,
+                    view_area,
+                    &self.ctx,
+                )
+            })
+            .and_then(|output| build_user_command_output_lines(output, self.ctx.clone()))
+            .unwrap_or_else(|err| {
+                self.tx.send(AppEvent::NotifyError(err));
+                vec![]
+            });
 
         self.commit_user_command_state.select_first();
     }
@@ -5,12 +5,16 @@ use ratatui::{crossterm::event::KeyEvent, layout::Rect, Frame};
 use crate::{
     app::AppContext,
     event::{Sender, UserEventWithCount},
-    git::{Commit, FileChange, Ref},
+    git::{Blame, Commit, CommitHash, FileChange, Ref, TreeEntry},
+    graph::{GraphImageCache, HighlightMode},
     view::{
-        detail::DetailView, help::HelpView, list::ListView, refs::RefsView,
-        user_command::UserCommandView,
+        blame::BlameView, detail::DetailView, diff::DiffView, file_tree::FileTreeView,
+        help::HelpView, list::ListView, refs::RefsView, user_command::UserCommandView,
+    },
+    widget::{
+        commit_diff::DiffLayout,
+        commit_list::{CommitListState, SearchRefreshContext},
     },
-    widget::commit_list::{CommitListState, SearchRefreshContext},
 };
 
 #[derive(Debug, Default)]
@@ -19,8 +23,11 @@ pub enum View<'a> {
     Default, // dummy variant to make #[default] work
     List(Box<ListView<'a>>),
     Detail(Box<DetailView<'a>>),
+    Diff(Box<DiffView<'a>>),
     UserCommand(Box<UserCommandView<'a>>),
     Refs(Box<RefsView<'a>>),
+    FileTree(Box<FileTreeView<'a>>),
+    Blame(Box<BlameView<'a>>),
     Help(Box<HelpView<'a>>),
 }
 
@@ -30,8 +37,11 @@ impl<'a> View<'a> {
             View::Default => {}
             View::List(view) => view.handle_event(event_with_count, key_event),
             View::Detail(view) => view.handle_event(event_with_count, key_event),
+            View::Diff(view) => view.handle_event(event_with_count, key_event),
             View::UserCommand(view) => view.handle_event(event_with_count, key_event),
             View::Refs(view) => view.handle_event(event_with_count, key_event),
+            View::FileTree(view) => view.handle_event(event_with_count, key_event),
+            View::Blame(view) => view.handle_event(event_with_count, key_event),
             View::Help(view) => view.handle_event(event_with_count, key_event),
         }
     }
@@ -41,8 +51,11 @@ impl<'a> View<'a> {
             View::Default => {}
             View::List(view) => view.render(f, area),
             View::Detail(view) => view.render(f, area),
+            View::Diff(view) => view.render(f, area),
             View::UserCommand(view) => view.render(f, area),
             View::Refs(view) => view.render(f, area),
+            View::FileTree(view) => view.render(f, area),
+            View::Blame(view) => view.render(f, area),
             View::Help(view) => view.render(f, area),
         }
     }
@@ -52,8 +65,11 @@ impl<'a> View<'a> {
             View::Default => {}
             View::List(view) => view.update_layout(area),
             View::Detail(view) => view.update_layout(area),
+            View::Diff(view) => view.update_layout(area),
             View::UserCommand(view) => view.update_layout(area),
             View::Refs(view) => view.update_layout(area),
+            View::FileTree(view) => view.update_layout(area),
+            View::Blame(view) => view.update_layout(area),
             View::Help(_) => {}
         }
     }
@@ -63,8 +79,11 @@ impl<'a> View<'a> {
             View::Default => {}
             View::List(view) => view.prepare_graph_uploads(),
             View::Detail(view) => view.prepare_graph_uploads(),
+            View::Diff(view) => view.prepare_graph_uploads(),
             View::UserCommand(view) => view.prepare_graph_uploads(),
             View::Refs(view) => view.prepare_graph_uploads(),
+            View::FileTree(view) => view.prepare_graph_uploads(),
+            View::Blame(view) => view.prepare_graph_uploads(),
             View::Help(_) => {}
         }
     }
@@ -74,8 +93,11 @@ impl<'a> View<'a> {
             View::Default => Vec::new(),
             View::List(view) => view.drain_pending_graph_uploads(),
             View::Detail(view) => view.drain_pending_graph_uploads(),
+            View::Diff(view) => view.drain_pending_graph_uploads(),
             View::UserCommand(view) => view.drain_pending_graph_uploads(),
             View::Refs(view) => view.drain_pending_graph_uploads(),
+            View::FileTree(view) => view.drain_pending_graph_uploads(),
+            View::Blame(view) => view.drain_pending_graph_uploads(),
             View::Help(_) => Vec::new(),
         }
     }
@@ -85,12 +107,31 @@ impl<'a> View<'a> {
             View::Default => Vec::new(),
             View::List(view) => view.graph_image_ids_sorted(),
             View::Detail(view) => view.graph_image_ids_sorted(),
+            View::Diff(view) => view.graph_image_ids_sorted(),
             View::UserCommand(view) => view.graph_image_ids_sorted(),
             View::Refs(view) => view.graph_image_ids_sorted(),
+            View::FileTree(view) => view.graph_image_ids_sorted(),
+            View::Blame(view) => view.graph_image_ids_sorted(),
             View::Help(view) => view.graph_image_ids_sorted(),
         }
     }
 
+    /// Takes the graph images out of the view to reuse them after rebuilding the graph.
+    /// The view cannot be used after this.
+    pub fn take_graph_image_cache(&mut self) -> GraphImageCache {
+        match self {
+            View::Default => GraphImageCache::new(),
+            View::List(view) => view.take_list_state().into_graph_image_cache(),
+            View::Detail(view) => view.take_list_state().into_graph_image_cache(),
+            View::Diff(view) => view.take_list_state().into_graph_image_cache(),
+            View::UserCommand(view) => view.take_list_state().into_graph_image_cache(),
+            View::Refs(view) => view.take_list_state().into_graph_image_cache(),
+            View::FileTree(view) => view.take_list_state().into_graph_image_cache(),
+            View::Blame(view) => view.take_list_state().into_graph_image_cache(),
+            View::Help(view) => view.take_before_view().take_graph_image_cache(),
+        }
+    }
+
     pub fn of_list(
         commit_list_state: CommitListState<'a>,
         ctx: Rc<AppContext>,
@@ -103,6 +144,7 @@ impl<'a> View<'a> {
         commit_list_state: CommitListState<'a>,
         commit: Commit,
         changes: Vec<FileChange>,
+        filtered_changes: Vec<bool>,
         refs: Vec<Ref>,
         ctx: Rc<AppContext>,
         tx: Sender,
@@ -111,12 +153,22 @@ impl<'a> View<'a> {
             commit_list_state,
             commit,
             changes,
+            filtered_changes,
             refs,
             ctx,
             tx,
         )))
     }
 
+    pub fn of_diff(
+        commit_list_state: CommitListState<'a>,
+        layout: DiffLayout,
+        ctx: Rc<AppContext>,
+        tx: Sender,
+    ) -> Self {
+        View::Diff(Box::new(DiffView::new(commit_list_state, layout, ctx, tx)))
+    }
+
     pub fn of_user_command(
         commit_list_state: CommitListState<'a>,
         command_output: String,
@@ -142,6 +194,38 @@ impl<'a> View<'a> {
         View::Refs(Box::new(RefsView::new(commit_list_state, refs, ctx, tx)))
     }
 
+    pub fn of_file_tree(
+        commit_list_state: CommitListState<'a>,
+        entries: Vec<TreeEntry>,
+        ctx: Rc<AppContext>,
+        tx: Sender,
+    ) -> Self {
+        View::FileTree(Box::new(FileTreeView::new(
+            commit_list_state,
+            entries,
+            ctx,
+            tx,
+        )))
+    }
+
+    pub fn of_blame(
+        commit_list_state: CommitListState<'a>,
+        commit_hash: CommitHash,
+        path: String,
+        blame: Blame,
+        ctx: Rc<AppContext>,
+        tx: Sender,
+    ) -> Self {
+        View::Blame(Box::new(BlameView::new(
+            commit_list_state,
+            commit_hash,
+            path,
+            blame,
+            ctx,
+            tx,
+        )))
+    }
+
     pub fn of_help(before: View<'a>, ctx: Rc<AppContext>, tx: Sender) -> Self {
         View::Help(Box::new(HelpView::new(before, ctx, tx)))
     }
@@ -151,8 +235,11 @@ impl<'a> View<'a> {
             View::Default => {}
             View::List(view) => view.refresh(),
             View::Detail(view) => view.refresh(),
+            View::Diff(view) => view.refresh(),
             View::UserCommand(view) => view.refresh(),
             View::Refs(view) => view.refresh(),
+            View::FileTree(view) => view.refresh(),
+            View::Blame(view) => view.refresh(),
             View::Help(_) => {}
         }
     }
@@ -166,6 +253,10 @@ pub enum RefreshViewContext {
     Detail {
         list_context: ListRefreshViewContext,
     },
+    Diff {
+        list_context: ListRefreshViewContext,
+        diff_context: DiffRefreshViewContext,
+    },
     UserCommand {
         list_context: ListRefreshViewContext,
         user_command_context: UserCommandRefreshViewContext,
@@ -174,6 +265,14 @@ pub enum RefreshViewContext {
         list_context: ListRefreshViewContext,
         refs_context: RefsRefreshViewContext,
     },
+    FileTree {
+        list_context: ListRefreshViewContext,
+        file_tree_context: FileTreeRefreshViewContext,
+    },
+    Blame {
+        list_context: ListRefreshViewContext,
+        blame_context: BlameRefreshViewContext,
+    },
 }
 
 impl RefreshViewContext {
@@ -181,8 +280,11 @@ impl RefreshViewContext {
         match self {
             RefreshViewContext::List { list_context }
             | RefreshViewContext::Detail { list_context }
+            | RefreshViewContext::Diff { list_context, .. }
             | RefreshViewContext::UserCommand { list_context, .. }
-            | RefreshViewContext::Refs { list_context, .. } => list_context,
+            | RefreshViewContext::Refs { list_context, .. }
+            | RefreshViewContext::FileTree { list_context, .. }
+            | RefreshViewContext::Blame { list_context, .. } => list_context,
         }
     }
 }
@@ -194,6 +296,7 @@ pub struct ListRefreshViewContext {
     pub height: usize,
     pub scroll_to_top: bool,
     pub search_context: Option<SearchRefreshContext>,
+    pub highlight_mode: Option<HighlightMode>,
 }
 
 impl From<&CommitListState<'_>> for ListRefreshViewContext {
@@ -204,16 +307,23 @@ impl From<&CommitListState<'_>> for ListRefreshViewContext {
         // In this case, we set scroll_to_top to true to indicate that the view should be scrolled to the top after refresh.
         let scroll_to_top = selected == 0 && offset == 0;
         let search_context = list_state.search_refresh_context();
+        let highlight_mode = list_state.highlight_mode();
         ListRefreshViewContext {
             commit_hash,
             selected,
             height,
             scroll_to_top,
             search_context,
+            highlight_mode,
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct DiffRefreshViewContext {
+    pub layout: DiffLayout,
+}
+
 #[derive(Debug, Clone)]
 pub struct UserCommandRefreshViewContext {
     pub n: usize,
@@ -224,3 +334,16 @@ pub struct RefsRefreshViewContext {
     pub selected: Vec<String>,
     pub opened: Vec<Vec<String>>,
 }
+
+#[derive(Debug, Clone)]
+pub struct FileTreeRefreshViewContext {
+    pub selected: Vec<String>,
+    pub opened: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlameRefreshViewContext {
+    pub commit_hash: CommitHash,
+    pub path: String,
+    pub selected: usize,
+}
@@ -0,0 +1,154 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use notify::{event::EventKind, Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::event::{AppEvent, Sender};
+
+const DEBOUNCE_DURATION: Duration = Duration::from_millis(500);
+
+// the files that are placed directly in the git directory and change the refs or HEAD
+const TOP_LEVEL_REF_FILES: [&str; 3] = ["HEAD", "packed-refs", "FETCH_HEAD"];
+
+/// Watches the refs and HEAD of the repository and notifies the changes with `AppEvent::RepositoryChanged`.
+///
+/// Changes in quick succession, such as during a rebase, are notified once after they settle down.
+/// Watching is stopped when the watcher is dropped.
+#[derive(Debug)]
+pub struct RepositoryWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl RepositoryWatcher {
+    pub fn start(git_dir: &Path, common_dir: &Path, tx: Sender) -> notify::Result<Self> {
+        let (change_tx, change_rx) = mpsc::channel();
+        let filter = ChangeFilter {
+            git_dir: git_dir.to_path_buf(),
+            common_dir: common_dir.to_path_buf(),
+        };
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            if let Ok(event) = event {
+                if filter.is_ref_change(&event) {
+                    let _ = change_tx.send(());
+                }
+            }
+        })?;
+
+        // refs are updated by renaming lock files, so the directories are watched instead of the files
+        watcher.watch(git_dir, RecursiveMode::NonRecursive)?;
+        if common_dir != git_dir {
+            watcher.watch(common_dir, RecursiveMode::NonRecursive)?;
+        }
+        watcher.watch(&common_dir.join("refs"), RecursiveMode::Recursive)?;
+        let logs_refs_dir = common_dir.join("logs").join("refs");
+        if logs_refs_dir.is_dir() {
+            watcher.watch(&logs_refs_dir, RecursiveMode::NonRecursive)?;
+        }
+
+        thread::spawn(move || debounce(change_rx, || tx.try_send(AppEvent::RepositoryChanged)));
+
+        Ok(Self { _watcher: watcher })
+    }
+}
+
+struct ChangeFilter {
+    git_dir: PathBuf,
+    common_dir: PathBuf,
+}
+
+impl ChangeFilter {
+    fn is_ref_change(&self, event: &Event) -> bool {
+        if matches!(event.kind, EventKind::Access(_)) {
+            return false;
+        }
+        event.paths.iter().any(|path| self.is_ref_path(path))
+    }
+
+    fn is_ref_path(&self, path: &Path) -> bool {
+        if path.extension().is_some_and(|ext| ext == "lock") {
+            return false;
+        }
+        let is_top_level_ref = |dir: &Path| {
+            path.parent() == Some(dir)
+                && path
+                    .file_name()
+                    .is_some_and(|name| TOP_LEVEL_REF_FILES.iter().any(|file| name == *file))
+        };
+        is_top_level_ref(&self.git_dir)
+            || is_top_level_ref(&self.common_dir)
+            || path.starts_with(self.common_dir.join("refs"))
+            // dropping a stash other than the latest one only rewrites the reflog
+            || path == self.common_dir.join("logs").join("refs").join("stash")
+    }
+}
+
+fn debounce(change_rx: mpsc::Receiver<()>, notify: impl Fn()) {
+    while change_rx.recv().is_ok() {
+        // wait until no changes are made for a while
+        loop {
+            match change_rx.recv_timeout(DEBOUNCE_DURATION) {
+                Ok(()) => continue,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rustfmt::skip]
+    #[rstest]
+    #[case("/repo/.git/worktrees/wt/HEAD",           true)]  // HEAD of the worktree
+    #[case("/repo/.git/HEAD",                        true)]  // HEAD of the main worktree
+    #[case("/repo/.git/packed-refs",                 true)]
+    #[case("/repo/.git/worktrees/wt/packed-refs",    true)]
+    #[case("/repo/.git/FETCH_HEAD",                  true)]
+    #[case("/repo/.git/worktrees/wt/FETCH_HEAD",     true)]
+    #[case("/repo/.git/refs/heads/master",           true)]
+    #[case("/repo/.git/refs/remotes/origin/feature", true)]
+    #[case("/repo/.git/logs/refs/stash",             true)]
+    #[case("/repo/.git/logs/refs/heads/master",      false)] // reflogs other than the stash
+    #[case("/repo/.git/logs/HEAD",                   false)]
+    #[case("/repo/.git/HEAD.lock",                   false)] // lock files
+    #[case("/repo/.git/refs/heads/master.lock",      false)]
+    #[case("/repo/.git/packed-refs.lock",            false)]
+    #[case("/repo/.git/index",                       false)]
+    #[case("/repo/.git/ORIG_HEAD",                   false)]
+    #[case("/repo/.git/objects/ab/cdef",             false)]
+    #[case("/repo/.git/worktrees/wt/refs/heads/x",   false)] // refs are shared in the common directory
+    #[case("/repo/HEAD",                             false)]
+    fn test_is_ref_path(#[case] path: &str, #[case] expected: bool) {
+        let filter = ChangeFilter {
+            git_dir: PathBuf::from("/repo/.git/worktrees/wt"),
+            common_dir: PathBuf::from("/repo/.git"),
+        };
+        assert_eq!(filter.is_ref_path(Path::new(path)), expected);
+    }
+
+    #[test]
+    fn test_debounce() {
+        let (change_tx, change_rx) = mpsc::channel();
+        let (notify_tx, notify_rx) = mpsc::channel();
+        let handle = thread::spawn(move || debounce(change_rx, || notify_tx.send(()).unwrap()));
+
+        for _ in 0..10 {
+            change_tx.send(()).unwrap();
+        }
+        assert_eq!(notify_rx.recv_timeout(DEBOUNCE_DURATION * 4), Ok(()));
+
+        drop(change_tx);
+        handle.join().unwrap();
+        // the burst of changes is notified only once
+        assert_eq!(notify_rx.iter().count(), 0);
+    }
+}
@@ -1,4 +1,8 @@
 pub mod commit_detail;
+pub mod commit_diff;
 pub mod commit_list;
 pub mod commit_user_command;
+pub mod file_blame;
+pub mod file_preview;
+pub mod file_tree;
 pub mod ref_list;
@@ -18,6 +18,10 @@ use crate::{
 pub struct CommitDetailState {
     height: usize,
     offset: usize,
+    // the index of the selected file in the changes
+    selected_change: Option<usize>,
+    // whether to scroll to the selected file in the next render
+    scroll_to_selected_change: bool,
 }
 
 impl CommitDetailState {
@@ -52,11 +56,43 @@ impl CommitDetailState {
     pub fn select_last(&mut self) {
         self.offset = usize::MAX;
     }
+
+    pub fn selected_change(&self) -> Option<usize> {
+        self.selected_change
+    }
+
+    pub fn select_next_change(&mut self, change_count: usize) {
+        if change_count == 0 {
+            return;
+        }
+        self.selected_change = Some(match self.selected_change {
+            Some(i) => (i + 1).min(change_count - 1),
+            None => 0,
+        });
+        self.scroll_to_selected_change = true;
+    }
+
+    pub fn select_prev_change(&mut self, change_count: usize) {
+        if change_count == 0 {
+            return;
+        }
+        self.selected_change = Some(match self.selected_change {
+            Some(i) => i.saturating_sub(1),
+            None => change_count - 1,
+        });
+        self.scroll_to_selected_change = true;
+    }
+
+    pub fn clear_selected_change(&mut self) {
+        self.selected_change = None;
+        self.scroll_to_selected_change = false;
+    }
 }
 
 pub struct CommitDetail<'a> {
     commit: &'a Commit,
     changes: &'a Vec<FileChange>,
+    filtered_changes: &'a [bool],
     refs: &'a Vec<Ref>,
     ctx: Rc<AppContext>,
 }
@@ -65,12 +101,14 @@ impl<'a> CommitDetail<'a> {
     pub fn new(
         commit: &'a Commit,
         changes: &'a Vec<FileChange>,
+        filtered_changes: &'a [bool],
         refs: &'a Vec<Ref>,
         ctx: Rc<AppContext>,
     ) -> Self {
         Self {
             commit,
             changes,
+            filtered_changes,
             refs,
             ctx,
         }
@@ -84,10 +122,12 @@ impl StatefulWidget for CommitDetail<'_> {
         let [labels_area, value_area] =
             Layout::horizontal([Constraint::Length(12), Constraint::Min(0)]).areas(area);
 
-        let (mut label_lines, mut value_lines) = self.contents(area);
+        let (mut label_lines, mut value_lines) = self.contents(area, state.selected_change);
 
+        // the changes are the last lines, one line for each file
+        let changes_start = value_lines.len() - self.changes.len();
         let content_area_height = area.height as usize - 1; // minus the top border
-        self.update_state(state, value_lines.len(), content_area_height);
+        self.update_state(state, value_lines.len(), changes_start, content_area_height);
 
         label_lines = label_lines.into_iter().skip(state.offset).collect();
         value_lines = value_lines.into_iter().skip(state.offset).collect();
@@ -122,7 +162,11 @@ impl CommitDetail<'_> {
         paragraph.render(area, buf);
     }
 
-    fn contents(&self, area: Rect) -> (Vec<Line<'_>>, Vec<Line<'_>>) {
+    fn contents(
+        &self,
+        area: Rect,
+        selected_change: Option<usize>,
+    ) -> (Vec<Line<'_>>, Vec<Line<'_>>) {
         let mut label_lines: Vec<Line> = Vec::new();
         let mut value_lines: Vec<Line> = Vec::new();
 
@@ -153,7 +197,7 @@ impl CommitDetail<'_> {
         value_lines.extend(self.commit_message_lines());
 
         value_lines.push(self.divider_line(area.width as usize));
-        value_lines.extend(self.changes_lines());
+        value_lines.extend(self.changes_lines(selected_change));
 
         (label_lines, value_lines)
     }
@@ -272,36 +316,60 @@ impl CommitDetail<'_> {
         lines
     }
 
-    fn changes_lines(&self) -> Vec<Line<'_>> {
+    fn changes_lines(&self, selected_change: Option<usize>) -> Vec<Line<'_>> {
         self.changes
             .iter()
-            .map(|c| match c {
-                FileChange::Add { path } => Line::from(vec![
-                    "A".fg(self.ctx.color_theme.detail_file_change_add_fg),
-                    " ".into(),
-                    path.into(),
-                ]),
-                FileChange::Modify { path } => Line::from(vec![
-                    "M".fg(self.ctx.color_theme.detail_file_change_modify_fg),
-                    " ".into(),
-                    path.into(),
-                ]),
-                FileChange::Delete { path } => Line::from(vec![
-                    "D".fg(self.ctx.color_theme.detail_file_change_delete_fg),
-                    " ".into(),
-                    path.into(),
-                ]),
-                FileChange::Move { from, to } => Line::from(vec![
-                    "R".fg(self.ctx.color_theme.detail_file_change_move_fg),
-                    " ".into(),
-                    from.into(),
-                    " -> ".into(),
-                    to.into(),
-                ]),
+            .enumerate()
+            .map(|(i, c)| {
+                let line = self.change_line(i, c);
+                if selected_change == Some(i) {
+                    line.fg(self.ctx.color_theme.detail_file_change_selected_fg)
+                        .bg(self.ctx.color_theme.detail_file_change_selected_bg)
+                } else {
+                    line
+                }
             })
             .collect()
     }
 
+    fn change_line<'b>(&'b self, i: usize, c: &'b FileChange) -> Line<'b> {
+        let filtered = self.filtered_changes.get(i).copied().unwrap_or(false);
+        let path_span = |path| self.file_path_span(path, filtered);
+        match c {
+            FileChange::Add { path } => Line::from(vec![
+                "A".fg(self.ctx.color_theme.detail_file_change_add_fg),
+                " ".into(),
+                path_span(path),
+            ]),
+            FileChange::Modify { path } => Line::from(vec![
+                "M".fg(self.ctx.color_theme.detail_file_change_modify_fg),
+                " ".into(),
+                path_span(path),
+            ]),
+            FileChange::Delete { path } => Line::from(vec![
+                "D".fg(self.ctx.color_theme.detail_file_change_delete_fg),
+                " ".into(),
+                path_span(path),
+            ]),
+            FileChange::Move { from, to } => Line::from(vec![
+                "R".fg(self.ctx.color_theme.detail_file_change_move_fg),
+                " ".into(),
+                path_span(from),
+                " -> ".into(),
+                path_span(to),
+            ]),
+        }
+    }
+
+    fn file_path_span<'b>(&self, path: &'b str, filtered: bool) -> Span<'b> {
+        if filtered {
+            path.fg(self.ctx.color_theme.detail_file_change_match_fg)
+                .bg(self.ctx.color_theme.detail_file_change_match_bg)
+        } else {
+            path.into()
+        }
+    }
+
     fn empty_line(&self) -> Line<'_> {
         Line::raw("")
     }
@@ -310,8 +378,25 @@ impl CommitDetail<'_> {
         Line::from("─".repeat(width).fg(self.ctx.color_theme.divider_fg))
     }
 
-    fn update_state(&self, state: &mut CommitDetailState, line_count: usize, area_height: usize) {
+    fn update_state(
+        &self,
+        state: &mut CommitDetailState,
+        line_count: usize,
+        changes_start: usize,
+        area_height: usize,
+    ) {
         state.height = area_height;
+        if state.scroll_to_selected_change {
+            if let Some(i) = state.selected_change {
+                let row = changes_start + i;
+                if row < state.offset {
+                    state.offset = row;
+                } else if row >= state.offset + area_height {
+                    state.offset = row + 1 - area_height;
+                }
+            }
+            state.scroll_to_selected_change = false;
+        }
         state.offset = state.offset.min(line_count.saturating_sub(area_height));
     }
 }
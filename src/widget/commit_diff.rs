@@ -0,0 +1,552 @@
+use std::rc::Rc;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Padding, StatefulWidget, Widget},
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Deserialize;
+
+use crate::{
+    app::AppContext,
+    git::{DiffLine, DiffLineKind, FileDiff},
+    syntax::{LineHighlights, SyntaxHighlighter},
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiffLayout {
+    #[default]
+    Unified,
+    SideBySide,
+}
+
+impl DiffLayout {
+    pub fn toggle(self) -> Self {
+        match self {
+            DiffLayout::Unified => DiffLayout::SideBySide,
+            DiffLayout::SideBySide => DiffLayout::Unified,
+        }
+    }
+}
+
+// The indices of the file, and the hunk and its lines in the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiffRow {
+    File(usize),
+    // the extended header lines, e.g. `new file mode 100644`
+    Header(usize, usize),
+    Hunk(usize, usize),
+    Line(usize, usize, usize),
+    // the old and new lines shown side by side
+    Pair(usize, usize, Option<usize>, Option<usize>),
+}
+
+impl DiffRow {
+    fn file_index(&self) -> usize {
+        match *self {
+            DiffRow::File(f)
+            | DiffRow::Header(f, _)
+            | DiffRow::Hunk(f, _)
+            | DiffRow::Line(f, _, _)
+            | DiffRow::Pair(f, _, _, _) => f,
+        }
+    }
+
+    fn hunk_index(&self) -> Option<usize> {
+        match *self {
+            DiffRow::File(_) | DiffRow::Header(_, _) => None,
+            DiffRow::Hunk(_, h) | DiffRow::Line(_, h, _) | DiffRow::Pair(_, h, _, _) => Some(h),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CommitDiffState {
+    diffs: Vec<FileDiff>,
+    layout: DiffLayout,
+    // the indices of the files whose hunks are hidden
+    folded: FxHashSet<usize>,
+    // the widths of the line numbers of each file
+    lineno_widths: Vec<usize>,
+    // the syntax highlights of the lines of each hunk, computed when the file is first shown
+    highlights: FxHashMap<usize, Vec<Vec<LineHighlights>>>,
+    rows: Vec<DiffRow>,
+    height: usize,
+    offset: usize,
+}
+
+impl CommitDiffState {
+    pub fn new(diffs: Vec<FileDiff>, layout: DiffLayout) -> Self {
+        let mut state = CommitDiffState {
+            lineno_widths: diffs.iter().map(lineno_width).collect(),
+            diffs,
+            layout,
+            ..Default::default()
+        };
+        state.build_rows();
+        state
+    }
+
+    pub fn layout(&self) -> DiffLayout {
+        self.layout
+    }
+
+    pub fn set_diffs(&mut self, diffs: Vec<FileDiff>) {
+        self.lineno_widths = diffs.iter().map(lineno_width).collect();
+        self.diffs = diffs;
+        self.folded.clear();
+        self.highlights.clear();
+        self.offset = 0;
+        self.build_rows();
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.offset = self.offset.saturating_add(1);
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.offset = self.offset.saturating_sub(1);
+    }
+
+    pub fn scroll_page_down(&mut self) {
+        self.offset = self.offset.saturating_add(self.height);
+    }
+
+    pub fn scroll_page_up(&mut self) {
+        self.offset = self.offset.saturating_sub(self.height);
+    }
+
+    pub fn scroll_half_page_down(&mut self) {
+        self.offset = self.offset.saturating_add(self.height / 2);
+    }
+
+    pub fn scroll_half_page_up(&mut self) {
+        self.offset = self.offset.saturating_sub(self.height / 2);
+    }
+
+    pub fn select_first(&mut self) {
+        self.offset = 0;
+    }
+
+    pub fn select_last(&mut self) {
+        self.offset = usize::MAX;
+    }
+
+    pub fn select_next_file(&mut self) {
+        self.select_next_row(|row| matches!(row, DiffRow::File(_)));
+    }
+
+    pub fn select_prev_file(&mut self) {
+        self.select_prev_row(|row| matches!(row, DiffRow::File(_)));
+    }
+
+    pub fn select_next_hunk(&mut self) {
+        self.select_next_row(|row| matches!(row, DiffRow::Hunk(_, _)));
+    }
+
+    pub fn select_prev_hunk(&mut self) {
+        self.select_prev_row(|row| matches!(row, DiffRow::Hunk(_, _)));
+    }
+
+    /// Folds or unfolds the file at the top of the area.
+    pub fn toggle_fold(&mut self) {
+        let Some(file) = self.current_row().map(|row| row.file_index()) else {
+            return;
+        };
+        if !self.folded.remove(&file) {
+            self.folded.insert(file);
+        }
+        self.build_rows();
+        self.select_row(|row| *row == DiffRow::File(file));
+    }
+
+    /// Switches the layout, keeping the hunk at the top of the area.
+    pub fn toggle_layout(&mut self) {
+        let current = self.current_row();
+        self.layout = self.layout.toggle();
+        self.build_rows();
+        if let Some(current) = current {
+            let file = current.file_index();
+            let hunk = current.hunk_index();
+            self.select_row(|row| row.file_index() == file && row.hunk_index() == hunk);
+        }
+    }
+
+    fn current_row(&self) -> Option<DiffRow> {
+        let offset = self.offset.min(self.rows.len().saturating_sub(1));
+        self.rows.get(offset).copied()
+    }
+
+    fn select_next_row(&mut self, f: impl Fn(&DiffRow) -> bool) {
+        let start = self.offset.saturating_add(1);
+        if let Some(i) = self.rows.iter().skip(start).position(f) {
+            self.offset = start + i;
+        }
+    }
+
+    fn select_prev_row(&mut self, f: impl Fn(&DiffRow) -> bool) {
+        let end = self.offset.min(self.rows.len());
+        if let Some(i) = self.rows[..end].iter().rposition(f) {
+            self.offset = i;
+        }
+    }
+
+    fn select_row(&mut self, f: impl Fn(&DiffRow) -> bool) {
+        if let Some(i) = self.rows.iter().position(f) {
+            self.offset = i;
+        }
+    }
+
+    fn ensure_highlighted(&mut self, file: usize, highlighter: &SyntaxHighlighter) {
+        if !self.highlights.contains_key(&file) {
+            let highlights = highlight_file(&self.diffs[file], highlighter);
+            self.highlights.insert(file, highlights);
+        }
+    }
+
+    fn line_highlights(&self, file: usize, hunk: usize, line: usize) -> Option<&LineHighlights> {
+        self.highlights.get(&file)?.get(hunk)?.get(line)
+    }
+
+    fn build_rows(&mut self) {
+        let mut rows = Vec::new();
+        for (f, diff) in self.diffs.iter().enumerate() {
+            rows.push(DiffRow::File(f));
+            if self.folded.contains(&f) {
+                continue;
+            }
+            rows.extend((0..diff.headers.len()).map(|i| DiffRow::Header(f, i)));
+            for (h, hunk) in diff.hunks.iter().enumerate() {
+                rows.push(DiffRow::Hunk(f, h));
+                match self.layout {
+                    DiffLayout::Unified => {
+                        rows.extend((0..hunk.lines.len()).map(|i| DiffRow::Line(f, h, i)));
+                    }
+                    DiffLayout::SideBySide => {
+                        rows.extend(
+                            pair_lines(&hunk.lines)
+                                .into_iter()
+                                .map(|(old, new)| DiffRow::Pair(f, h, old, new)),
+                        );
+                    }
+                }
+            }
+        }
+        self.rows = rows;
+    }
+}
+
+// Pairs the removed lines with the added lines that follow them, and the context lines with themselves.
+// The `\ No newline at end of file` line is put on the same side as the line before it.
+fn pair_lines(lines: &[DiffLine]) -> Vec<(Option<usize>, Option<usize>)> {
+    let mut pairs = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if !matches!(lines[i].kind, DiffLineKind::Removed | DiffLineKind::Added) {
+            pairs.push((Some(i), Some(i)));
+            i += 1;
+            continue;
+        }
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        for (kind, run) in [
+            (DiffLineKind::Removed, &mut removed),
+            (DiffLineKind::Added, &mut added),
+        ] {
+            while i < lines.len() && lines[i].kind == kind {
+                run.push(i);
+                i += 1;
+                if i < lines.len() && lines[i].kind == DiffLineKind::NoNewline {
+                    run.push(i);
+                    i += 1;
+                }
+            }
+        }
+        for n in 0..removed.len().max(added.len()) {
+            pairs.push((removed.get(n).copied(), added.get(n).copied()));
+        }
+    }
+    pairs
+}
+
+// Highlights the old side (the context and removed lines) and the new side (the context and added lines)
+// of each hunk separately, so that each side is parsed as consecutive lines of the file.
+// Returns empty highlights if the language of the file is unknown.
+fn highlight_file(diff: &FileDiff, highlighter: &SyntaxHighlighter) -> Vec<Vec<LineHighlights>> {
+    // the path is empty on the side of an added or deleted file
+    let (old_path, new_path) = match (diff.old_path.as_str(), diff.new_path.as_str()) {
+        ("", path) | (path, "") => (path, path),
+        paths => paths,
+    };
+
+    let mut file_highlights = Vec::with_capacity(diff.hunks.len());
+    for hunk in &diff.hunks {
+        let side = |path: &str, kind: DiffLineKind| {
+            let (indices, contents): (Vec<usize>, Vec<&str>) = hunk
+                .lines
+                .iter()
+                .enumerate()
+                .filter(|(_, l)| l.kind == DiffLineKind::Context || l.kind == kind)
+                .map(|(i, l)| (i, l.content.as_str()))
+                .unzip();
+            let highlights = highlighter.highlight(path, &contents)?;
+            Some(indices.into_iter().zip(highlights).collect::<Vec<_>>())
+        };
+        let (Some(old), Some(new)) = (
+            side(old_path, DiffLineKind::Removed),
+            side(new_path, DiffLineKind::Added),
+        ) else {
+            return Vec::new();
+        };
+
+        let mut hunk_highlights = vec![Vec::new(); hunk.lines.len()];
+        // the context lines are taken from the new side
+        for (i, highlights) in old.into_iter().chain(new) {
+            hunk_highlights[i] = highlights;
+        }
+        file_highlights.push(hunk_highlights);
+    }
+    file_highlights
+}
+
+pub struct CommitDiff {
+    ctx: Rc<AppContext>,
+}
+
+impl CommitDiff {
+    pub fn new(ctx: Rc<AppContext>) -> Self {
+        Self { ctx }
+    }
+}
+
+impl StatefulWidget for CommitDiff {
+    type State = CommitDiffState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let block = Block::default()
+            .borders(Borders::TOP)
+            .style(Style::default().fg(self.ctx.color_theme.divider_fg))
+            .padding(Padding::horizontal(2));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let content_area_height = area.height as usize - 1; // minus the top border
+        self.update_state(state, content_area_height);
+
+        let rows: Vec<DiffRow> = state
+            .rows
+            .iter()
+            .skip(state.offset)
+            .take(inner.height as usize)
+            .copied()
+            .collect();
+        if let Some(highlighter) = SyntaxHighlighter::new(&self.ctx.ui_config.syntax) {
+            for row in &rows {
+                state.ensure_highlighted(row.file_index(), &highlighter);
+            }
+        }
+        for (y, row) in (inner.y..).zip(rows) {
+            let row_area = Rect::new(inner.x, y, inner.width, 1);
+            self.render_row(row, row_area, buf, state);
+        }
+    }
+}
+
+impl CommitDiff {
+    fn render_row(&self, row: DiffRow, area: Rect, buf: &mut Buffer, state: &CommitDiffState) {
+        let theme = &self.ctx.color_theme;
+        match row {
+            DiffRow::File(f) => {
+                let diff = &state.diffs[f];
+                let marker = if state.folded.contains(&f) {
+                    "▸"
+                } else {
+                    "▾"
+                };
+                let (added, removed) = diff.stats();
+                let line = Line::from(vec![
+                    Span::raw(format!("{marker} {} ", diff.path()))
+                        .fg(theme.diff_file_fg)
+                        .add_modifier(Modifier::BOLD),
+                    Span::raw(format!("+{added}")).fg(theme.diff_added_fg),
+                    Span::raw(" "),
+                    Span::raw(format!("-{removed}")).fg(theme.diff_removed_fg),
+                ]);
+                buf.set_line(area.x, area.y, &line, area.width);
+            }
+            DiffRow::Header(f, i) => {
+                let header = &state.diffs[f].headers[i];
+                let line = Line::from(header.as_str()).fg(theme.diff_header_fg);
+                buf.set_line(area.x, area.y, &line, area.width);
+            }
+            DiffRow::Hunk(f, h) => {
+                let header = &state.diffs[f].hunks[h].header;
+                let line = Line::from(header.as_str()).fg(theme.diff_hunk_fg);
+                buf.set_line(area.x, area.y, &line, area.width);
+            }
+            DiffRow::Line(f, h, i) => {
+                let diff = &state.diffs[f];
+                let line = &diff.hunks[h].lines[i];
+                let width = state.lineno_widths[f];
+                self.render_line_bg(line, area, buf);
+                let mut spans = vec![
+                    self.lineno_span(line.old_lineno, width),
+                    Span::raw(" "),
+                    self.lineno_span(line.new_lineno, width),
+                    Span::raw(" "),
+                ];
+                spans.extend(self.content_spans(line, state.line_highlights(f, h, i)));
+                buf.set_line(area.x, area.y, &Line::from(spans), area.width);
+            }
+            DiffRow::Pair(f, h, old, new) => {
+                let diff = &state.diffs[f];
+                let lines = &diff.hunks[h].lines;
+                let width = state.lineno_widths[f];
+                let half_width = area.width.saturating_sub(1) / 2;
+                let old_area = Rect::new(area.x, area.y, half_width, 1);
+                let new_area = Rect::new(
+                    area.x + half_width + 1,
+                    area.y,
+                    area.width.saturating_sub(half_width + 1),
+                    1,
+                );
+                buf.set_string(
+                    area.x + half_width,
+                    area.y,
+                    "│",
+                    Style::default().fg(theme.divider_fg),
+                );
+                if let Some(i) = old {
+                    let line = &lines[i];
+                    self.render_line_bg(line, old_area, buf);
+                    let mut spans = vec![self.lineno_span(line.old_lineno, width), Span::raw(" ")];
+                    spans.extend(self.content_spans(line, state.line_highlights(f, h, i)));
+                    buf.set_line(old_area.x, old_area.y, &Line::from(spans), old_area.width);
+                }
+                if let Some(i) = new {
+                    let line = &lines[i];
+                    self.render_line_bg(line, new_area, buf);
+                    let mut spans = vec![
+                        Span::raw(" "),
+                        self.lineno_span(line.new_lineno, width),
+                        Span::raw(" "),
+                    ];
+                    spans.extend(self.content_spans(line, state.line_highlights(f, h, i)));
+                    buf.set_line(new_area.x, new_area.y, &Line::from(spans), new_area.width);
+                }
+            }
+        }
+    }
+
+    fn lineno_span(&self, lineno: Option<usize>, width: usize) -> Span<'static> {
+        let s = match lineno {
+            Some(n) => format!("{n:>width$}"),
+            None => " ".repeat(width),
+        };
+        Span::raw(s).fg(self.ctx.color_theme.diff_line_number_fg)
+    }
+
+    fn render_line_bg(&self, line: &DiffLine, area: Rect, buf: &mut Buffer) {
+        let theme = &self.ctx.color_theme;
+        let bg = match line.kind {
+            DiffLineKind::Added => theme.diff_added_bg,
+            DiffLineKind::Removed => theme.diff_removed_bg,
+            DiffLineKind::Context | DiffLineKind::NoNewline => return,
+        };
+        buf.set_style(area, Style::default().bg(bg));
+    }
+
+    fn content_spans<'a>(
+        &self,
+        line: &'a DiffLine,
+        highlights: Option<&LineHighlights>,
+    ) -> Vec<Span<'a>> {
+        let theme = &self.ctx.color_theme;
+        let (sign, style, word_style) = match line.kind {
+            DiffLineKind::Context => (" ", Style::default().fg(theme.diff_context_fg), None),
+            DiffLineKind::Added => (
+                "+",
+                Style::default()
+                    .fg(theme.diff_added_fg)
+                    .bg(theme.diff_added_bg),
+                Some(
+                    Style::default()
+                        .fg(theme.diff_added_word_fg)
+                        .bg(theme.diff_added_word_bg),
+                ),
+            ),
+            DiffLineKind::Removed => (
+                "-",
+                Style::default()
+                    .fg(theme.diff_removed_fg)
+                    .bg(theme.diff_removed_bg),
+                Some(
+                    Style::default()
+                        .fg(theme.diff_removed_word_fg)
+                        .bg(theme.diff_removed_word_bg),
+                ),
+            ),
+            DiffLineKind::NoNewline => {
+                let style = Style::default().fg(theme.diff_header_fg);
+                return vec![Span::styled(line.content.as_str(), style)];
+            }
+        };
+
+        let tab_spaces = " ".repeat(self.ctx.ui_config.diff.tab_width as usize);
+        // tab is not rendered correctly, so replace it
+        let span = |s: &str, style: Style| Span::styled(s.replace('\t', &tab_spaces), style);
+
+        let highlights = highlights.map(Vec::as_slice).unwrap_or_default();
+
+        // split the content at the boundaries of both the emphasized words and the syntax highlights
+        let mut bounds = vec![0, line.content.len()];
+        for range in line
+            .emphasized
+            .iter()
+            .chain(highlights.iter().map(|(range, _)| range))
+        {
+            bounds.extend([range.start, range.end]);
+        }
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        let mut spans = vec![Span::styled(sign, style)];
+        for w in bounds.windows(2) {
+            let (start, end) = (w[0], w[1]);
+            let emphasized = line.emphasized.iter().any(|r| r.contains(&start));
+            let segment_style = match word_style {
+                Some(word_style) if emphasized => word_style,
+                _ => highlights
+                    .iter()
+                    .find(|(r, _)| r.contains(&start))
+                    .map_or(style, |(_, color)| style.fg(*color)),
+            };
+            spans.push(span(&line.content[start..end], segment_style));
+        }
+        spans
+    }
+
+    fn update_state(&self, state: &mut CommitDiffState, area_height: usize) {
+        state.height = area_height;
+        state.offset = state
+            .offset
+            .min(state.rows.len().saturating_sub(area_height));
+    }
+}
+
+// the width of the largest line number in the file
+fn lineno_width(diff: &FileDiff) -> usize {
+    let max = diff
+        .hunks
+        .iter()
+        .flat_map(|h| &h.lines)
+        .flat_map(|l| [l.old_lineno, l.new_lineno])
+        .flatten()
+        .max()
+        .unwrap_or_default();
+    max.to_string().len()
+}
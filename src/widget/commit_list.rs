@@ -18,28 +18,37 @@ use crate::{
     app::AppContext,
     color::ColorTheme,
     config::UserListColumnType,
-    git::{Commit, CommitHash, Head, Ref},
-    graph::GraphImageManager,
+    git::{Commit, CommitHash, Head, Ref, Repository},
+    graph::{GraphHighlight, GraphImageCache, GraphImageManager, HighlightMode, LaneRange},
     protocol::PreparedImage,
 };
 
 static FUZZY_MATCHER: Lazy<SkimMatcherV2> = Lazy::new(|| SkimMatcherV2::default().respect_case());
 
 const ELLIPSIS: &str = "...";
+const COLLAPSED_MARKER: &str = "▸ ";
 
 #[derive(Debug)]
 pub struct CommitInfo<'a> {
     commit: &'a Commit,
     refs: Vec<&'a Ref>,
     graph_color: Color,
+    // the row of hidden commits, which can be expanded
+    collapsed: bool,
 }
 
 impl<'a> CommitInfo<'a> {
-    pub fn new(commit: &'a Commit, refs: Vec<&'a Ref>, graph_color: Color) -> Self {
+    pub fn new(
+        commit: &'a Commit,
+        refs: Vec<&'a Ref>,
+        graph_color: Color,
+        collapsed: bool,
+    ) -> Self {
         Self {
             commit,
             refs,
             graph_color,
+            collapsed,
         }
     }
 }
@@ -188,7 +197,7 @@ pub struct CommitListState<'a> {
     commits: Vec<CommitInfo<'a>>,
     commit_hash_set: FxHashSet<&'a CommitHash>,
     graph_image_manager: GraphImageManager<'a>,
-    graph_cell_width: u16,
+    repository: &'a Repository,
     head: &'a Head,
 
     ref_name_to_commit_index_map: FxHashMap<&'a str, usize>,
@@ -202,6 +211,11 @@ pub struct CommitListState<'a> {
     total: usize,
     height: usize,
 
+    // the graph is highlighted with the selected commit if any
+    highlight_mode: Option<HighlightMode>,
+
+    // the width kept for the subject when the graph is too wide
+    subject_min_width: u16,
     default_ignore_case: bool,
     default_fuzzy: bool,
 }
@@ -210,9 +224,9 @@ impl<'a> CommitListState<'a> {
     pub fn new(
         commits: Vec<CommitInfo<'a>>,
         graph_image_manager: GraphImageManager<'a>,
-        graph_cell_width: u16,
-        head: &'a Head,
+        repository: &'a Repository,
         ref_name_to_commit_index_map: FxHashMap<&'a str, usize>,
+        subject_min_width: u16,
         default_ignore_case: bool,
         default_fuzzy: bool,
     ) -> CommitListState<'a> {
@@ -222,8 +236,8 @@ impl<'a> CommitListState<'a> {
             commits,
             commit_hash_set,
             graph_image_manager,
-            graph_cell_width,
-            head,
+            repository,
+            head: repository.head(),
             ref_name_to_commit_index_map,
             search_state: SearchState::Inactive,
             search_input: Input::default(),
@@ -232,13 +246,49 @@ impl<'a> CommitListState<'a> {
             offset: 0,
             total,
             height: 0,
+            highlight_mode: None,
+            subject_min_width,
             default_ignore_case,
             default_fuzzy,
         }
     }
 
     pub fn graph_area_cell_width(&self) -> u16 {
-        self.graph_cell_width + 1 // right pad
+        let manager = &self.graph_image_manager;
+        let lane_count = manager.lanes().count.min(manager.lane_count());
+        (lane_count * manager.lane_cell_width()) as u16 + 1 // right pad
+    }
+
+    /// Narrows the lanes of the graph to the ones that fit in the width with the subject,
+    /// and the rest of them are shown by scrolling the graph horizontally.
+    pub fn update_graph_width(&mut self, width: u16) {
+        let manager = &mut self.graph_image_manager;
+        let lane_count = manager.lane_count();
+        // the marker column and the right pad of the graph
+        let max_graph_width = (width as usize).saturating_sub(self.subject_min_width as usize + 2);
+        let count = (max_graph_width / manager.lane_cell_width()).clamp(1, lane_count);
+        let offset = manager.lanes().offset.min(lane_count - count);
+        manager.set_lanes(LaneRange { offset, count });
+    }
+
+    pub fn scroll_graph_right(&mut self) {
+        let manager = &mut self.graph_image_manager;
+        let lanes = manager.lanes();
+        if lanes.offset.saturating_add(lanes.count) < manager.lane_count() {
+            manager.set_lanes(LaneRange {
+                offset: lanes.offset + 1,
+                ..lanes
+            });
+        }
+    }
+
+    pub fn scroll_graph_left(&mut self) {
+        let manager = &mut self.graph_image_manager;
+        let lanes = manager.lanes();
+        manager.set_lanes(LaneRange {
+            offset: lanes.offset.saturating_sub(1),
+            ..lanes
+        });
     }
 
     pub fn update_height(&mut self, height: usize) {
@@ -256,7 +306,49 @@ impl<'a> CommitListState<'a> {
         }
     }
 
+    pub fn highlight_mode(&self) -> Option<HighlightMode> {
+        self.highlight_mode
+    }
+
+    /// Switches the highlight to the next mode, which is applied when the graph is drawn next time.
+    pub fn toggle_highlight_mode(&mut self) -> Option<HighlightMode> {
+        self.highlight_mode = HighlightMode::toggle(self.highlight_mode);
+        self.highlight_mode
+    }
+
+    pub fn set_highlight_mode(&mut self, mode: Option<HighlightMode>) {
+        self.highlight_mode = mode;
+    }
+
+    // the highlight is computed again only when the selected commit or the mode is changed
+    fn update_graph_highlight(&mut self) {
+        let selected_commit_hash = &self.commits[self.current_selected_index()]
+            .commit
+            .commit_hash;
+        let target = self.highlight_mode.map(|mode| (selected_commit_hash, mode));
+        let current = self
+            .graph_image_manager
+            .highlight()
+            .map(|highlight| (highlight.commit_hash(), highlight.mode()));
+        if target == current {
+            return;
+        }
+        let graph = self.graph_image_manager.graph();
+        let highlight =
+            target.map(|(hash, mode)| GraphHighlight::new(self.repository, graph, hash, mode));
+        self.graph_image_manager.set_highlight(highlight);
+    }
+
+    fn is_dimmed(&self, commit_info: &CommitInfo) -> bool {
+        self.graph_image_manager
+            .highlight()
+            .is_some_and(|highlight| !highlight.contains_commit(&commit_info.commit.commit_hash))
+    }
+
     pub fn ensure_visible_graph_uploaded(&mut self) {
+        self.update_graph_highlight();
+        self.graph_image_manager
+            .search_highlight(self.offset..self.offset + self.height);
         self.commits
             .iter()
             .skip(self.offset)
@@ -271,6 +363,10 @@ impl<'a> CommitListState<'a> {
         self.graph_image_manager.drain_pending_uploads()
     }
 
+    pub fn into_graph_image_cache(self) -> GraphImageCache {
+        self.graph_image_manager.into_cache()
+    }
+
     pub fn graph_image_ids_sorted(&self) -> Vec<u32> {
         let mut image_ids: Vec<u32> = self
             .graph_image_manager
@@ -432,6 +528,10 @@ impl<'a> CommitListState<'a> {
             .commit_hash
     }
 
+    pub fn selected_commit_is_collapsed(&self) -> bool {
+        self.commits[self.current_selected_index()].collapsed
+    }
+
     fn current_selected_index(&self) -> usize {
         self.offset + self.selected
     }
@@ -455,6 +555,10 @@ impl<'a> CommitListState<'a> {
         }
     }
 
+    pub fn contains_commit_hash(&self, commit_hash: &CommitHash) -> bool {
+        self.commit_hash_set.contains(commit_hash)
+    }
+
     pub fn select_commit_hash(&mut self, commit_hash: &CommitHash) {
         if !self.commit_hash_set.contains(commit_hash) {
             return;
@@ -688,6 +792,10 @@ impl<'a> CommitListState<'a> {
         for (i, commit_info) in self.commits.iter().enumerate() {
             let m = &mut self.search_matches[i];
             m.set(commit_info.commit, commit_info.refs.as_slice(), &matcher);
+            if commit_info.collapsed {
+                // the hash of the collapsed row is not shown
+                m.commit_hash = None;
+            }
             if m.matched() {
                 m.match_index = match_index;
                 match_index += 1;
@@ -838,7 +946,13 @@ impl CommitList<'_> {
         }
         let items: Vec<ListItem> = self
             .rendering_commit_info_iter(state)
-            .map(|(_, commit_info)| ListItem::new("│".fg(commit_info.graph_color)))
+            .map(|(_, commit_info)| {
+                let mut marker = "│".fg(commit_info.graph_color);
+                if state.is_dimmed(commit_info) {
+                    marker = marker.add_modifier(Modifier::DIM);
+                }
+                ListItem::new(marker)
+            })
             .collect();
         Widget::render(List::new(items), area, buf)
     }
@@ -851,12 +965,16 @@ impl CommitList<'_> {
         let items: Vec<ListItem> = self
             .rendering_commit_info_iter(state)
             .map(|(i, commit_info)| {
-                let mut spans = refs_spans(
+                let mut spans = Vec::new();
+                if commit_info.collapsed {
+                    spans.push(COLLAPSED_MARKER.fg(self.ctx.color_theme.list_subject_fg));
+                }
+                spans.extend(refs_spans(
                     commit_info,
                     state.head,
                     &state.search_matches[state.offset + i].refs,
                     &self.ctx.color_theme,
-                );
+                ));
                 let ref_spans_width: usize = spans.iter().map(|s| s.width()).sum();
                 let max_width = max_width.saturating_sub(ref_spans_width);
                 let commit = commit_info.commit;
@@ -928,9 +1046,14 @@ impl CommitList<'_> {
             return;
         }
         let items: Vec<ListItem> = self
-            .rendering_commit_iter(state)
-            .map(|(i, commit)| {
-                let hash = commit.commit_hash.as_short_hash();
+            .rendering_commit_info_iter(state)
+            .map(|(i, commit_info)| {
+                // the collapsed rows have no single commit to tell the hash of
+                let hash = if commit_info.collapsed {
+                    ""
+                } else {
+                    commit_info.commit.commit_hash.as_short_hash()
+                };
                 let spans =
                     if let Some(pos) = state.search_matches[state.offset + i].commit_hash.clone() {
                         highlighted_spans(
@@ -955,10 +1078,12 @@ impl CommitList<'_> {
             return;
         }
         let items: Vec<ListItem> = self
-            .rendering_commit_iter(state)
-            .map(|(i, commit)| {
-                let date = &commit.author_date;
-                let date_str = if self.ctx.ui_config.list.date_local {
+            .rendering_commit_info_iter(state)
+            .map(|(i, commit_info)| {
+                let date = &commit_info.commit.author_date;
+                let date_str = if commit_info.collapsed {
+                    String::new()
+                } else if self.ctx.ui_config.list.date_local {
                     let local = date.with_timezone(&chrono::Local);
                     local
                         .format(&self.ctx.ui_config.list.date_format)
@@ -1007,6 +1132,9 @@ impl CommitList<'_> {
         spans.insert(0, Span::raw(" "));
         spans.push(Span::raw(" "));
         let mut line = Line::from(spans);
+        if state.is_dimmed(&state.commits[state.offset + i]) {
+            line = line.add_modifier(Modifier::DIM);
+        }
         if i == state.selected {
             line = line
                 .bg(self.ctx.color_theme.list_selected_bg)
@@ -1209,15 +1337,18 @@ fn calc_cell_widths(
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::path::Path;
 
     use ratatui::crossterm::event::KeyCode;
 
     use crate::{
         color::GraphColorSet,
         config::GraphColorConfig,
-        git::Repository,
-        graph::{calc_graph, CellWidthType, GraphImageWidthMode, GraphStyle},
+        git::{Repository, RepositoryPath},
+        graph::{
+            calc_graph, CellWidthType, GraphImageWidthMode, GraphLayout, GraphStyle,
+            IncrementalLayout, LaneHint,
+        },
         protocol::ImageProtocol,
     };
 
@@ -1226,6 +1357,14 @@ mod tests {
     fn with_commit_list_state<R>(
         subjects: &[&str],
         f: impl FnOnce(&mut CommitListState<'_>) -> R,
+    ) -> R {
+        with_commit_list_state_drawn_by(subjects, ImageProtocol::Iterm2, f)
+    }
+
+    fn with_commit_list_state_drawn_by<R>(
+        subjects: &[&str],
+        image_protocol: ImageProtocol,
+        f: impl FnOnce(&mut CommitListState<'_>) -> R,
     ) -> R {
         let commits: Vec<Commit> = subjects
             .iter()
@@ -1241,8 +1380,8 @@ mod tests {
             .into_iter()
             .map(|c| (c.commit_hash.clone(), c))
             .collect();
-        let repository = Repository::new(
-            PathBuf::new(),
+        let repository = Repository::from_commits(
+            &RepositoryPath::new(Path::new("")),
             commit_map,
             FxHashMap::default(),
             FxHashMap::default(),
@@ -1250,29 +1389,42 @@ mod tests {
             Head::None,
             commit_hashes,
         );
-        let graph = calc_graph(&repository);
         let graph_color_set = GraphColorSet::new(&GraphColorConfig::default());
+        let graph = calc_graph(
+            &repository,
+            &graph_color_set,
+            &GraphLayout::default(),
+            &LaneHint::default(),
+            &mut IncrementalLayout::default(),
+        );
         let graph_image_manager = GraphImageManager::new(
             &graph,
             &graph_color_set,
             CellWidthType::Double,
             GraphStyle::Rounded,
             GraphImageWidthMode::Compact,
-            ImageProtocol::Iterm2,
+            image_protocol,
+            None,
+            GraphImageCache::new(),
         );
         let commit_infos = graph
             .commits
             .iter()
             .map(|commit| {
-                CommitInfo::new(commit, repository.refs(&commit.commit_hash), Color::Reset)
+                CommitInfo::new(
+                    commit,
+                    repository.refs(&commit.commit_hash),
+                    Color::Reset,
+                    false,
+                )
             })
             .collect();
         let mut state = CommitListState::new(
             commit_infos,
             graph_image_manager,
-            0,
-            repository.head(),
+            &repository,
             FxHashMap::default(),
+            0,
             false,
             false,
         );
@@ -1287,6 +1439,50 @@ mod tests {
         }
     }
 
+    // the images are uploaded to the terminal only with the kitty protocol
+    #[test]
+    fn test_highlight_keeps_images_drawn_as_usual() {
+        let image_protocol = ImageProtocol::KittyUnicode { tmux: false };
+        with_commit_list_state_drawn_by(&["first", "second"], image_protocol, |state| {
+            state.ensure_visible_graph_uploaded();
+            assert_eq!(state.drain_pending_graph_uploads().len(), 2);
+
+            state.toggle_highlight_mode();
+            state.ensure_visible_graph_uploaded();
+            assert_eq!(state.drain_pending_graph_uploads().len(), 2);
+            assert_eq!(state.graph_image_ids_sorted().len(), 4);
+
+            // the other commit is not related to the selected one
+            state.select_next();
+            state.ensure_visible_graph_uploaded();
+            assert_eq!(state.drain_pending_graph_uploads().len(), 2);
+
+            state.set_highlight_mode(None);
+            state.ensure_visible_graph_uploaded();
+            assert!(state.drain_pending_graph_uploads().is_empty());
+            assert_eq!(state.graph_image_ids_sorted().len(), 4);
+        });
+    }
+
+    #[test]
+    fn test_highlight_uploads_only_changed_rows() {
+        let image_protocol = ImageProtocol::KittyUnicode { tmux: false };
+        with_commit_list_state_drawn_by(&["first", "second", "third"], image_protocol, |state| {
+            state.toggle_highlight_mode();
+            state.ensure_visible_graph_uploaded();
+            assert_eq!(state.drain_pending_graph_uploads().len(), 3);
+
+            // the third commit is dimmed with both of the selected commits
+            state.select_next();
+            state.ensure_visible_graph_uploaded();
+            assert_eq!(state.drain_pending_graph_uploads().len(), 2);
+
+            state.select_prev();
+            state.ensure_visible_graph_uploaded();
+            assert_eq!(state.drain_pending_graph_uploads().len(), 2);
+        });
+    }
+
     #[test]
     fn test_restore_search_recalculates_matches_with_applied_options() {
         let context = with_commit_list_state(&["Fix parser", "other"], |state| {
@@ -0,0 +1,310 @@
+use std::rc::Rc;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Padding, StatefulWidget, Widget},
+};
+
+use crate::{
+    app::AppContext,
+    git::{Blame, BlameCommit, BlameLine, CommitHash},
+    syntax::{LineHighlights, SyntaxHighlighter},
+};
+
+const ELLIPSIS: &str = "...";
+const MAX_NAME_WIDTH: usize = 20;
+const HEADER_HEIGHT: u16 = 2;
+
+#[derive(Debug)]
+pub struct FileBlameState {
+    commit_hash: CommitHash,
+    path: String,
+    blame: Blame,
+    highlights: Vec<LineHighlights>,
+    selected: usize,
+    offset: usize,
+    height: usize,
+    scroll_to_selected: bool,
+}
+
+impl FileBlameState {
+    pub fn new(
+        commit_hash: CommitHash,
+        path: String,
+        blame: Blame,
+        highlighter: Option<SyntaxHighlighter>,
+    ) -> Self {
+        let highlights = highlighter
+            .and_then(|highlighter| {
+                let lines: Vec<&str> = blame.lines.iter().map(|l| l.content.as_str()).collect();
+                highlighter.highlight(&path, &lines)
+            })
+            .unwrap_or_default();
+        FileBlameState {
+            commit_hash,
+            path,
+            blame,
+            highlights,
+            selected: 0,
+            offset: 0,
+            height: 0,
+            scroll_to_selected: false,
+        }
+    }
+
+    pub fn commit_hash(&self) -> &CommitHash {
+        &self.commit_hash
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_line(&self) -> Option<&BlameLine> {
+        self.blame.lines.get(self.selected)
+    }
+
+    pub fn selected_commit(&self) -> Option<&BlameCommit> {
+        self.selected_line()
+            .and_then(|line| self.blame.commits.get(&line.commit_hash))
+    }
+
+    pub fn select_line(&mut self, index: usize) {
+        self.selected = index.min(self.blame.lines.len().saturating_sub(1));
+        self.scroll_to_selected = true;
+    }
+
+    pub fn select_next(&mut self) {
+        self.move_selection(1, false);
+    }
+
+    pub fn select_prev(&mut self) {
+        self.move_selection(1, true);
+    }
+
+    pub fn scroll_page_down(&mut self) {
+        self.move_selection(self.height, false);
+    }
+
+    pub fn scroll_page_up(&mut self) {
+        self.move_selection(self.height, true);
+    }
+
+    pub fn scroll_half_page_down(&mut self) {
+        self.move_selection(self.height / 2, false);
+    }
+
+    pub fn scroll_half_page_up(&mut self) {
+        self.move_selection(self.height / 2, true);
+    }
+
+    pub fn select_first(&mut self) {
+        self.selected = 0;
+    }
+
+    pub fn select_last(&mut self) {
+        self.selected = self.blame.lines.len().saturating_sub(1);
+    }
+
+    fn move_selection(&mut self, n: usize, up: bool) {
+        let selected = if up {
+            self.selected.saturating_sub(n)
+        } else {
+            self.selected.saturating_add(n)
+        };
+        self.selected = selected.min(self.blame.lines.len().saturating_sub(1));
+    }
+
+    fn update_offset(&mut self, height: usize) {
+        self.height = height;
+        if self.scroll_to_selected {
+            // show the selected line in the middle of the area if possible
+            self.offset = self.selected.saturating_sub(height / 2);
+            self.scroll_to_selected = false;
+        } else if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if height > 0 && self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+        self.offset = self
+            .offset
+            .min(self.blame.lines.len().saturating_sub(height));
+    }
+}
+
+pub struct FileBlame {
+    ctx: Rc<AppContext>,
+}
+
+impl FileBlame {
+    pub fn new(ctx: Rc<AppContext>) -> Self {
+        Self { ctx }
+    }
+}
+
+impl StatefulWidget for FileBlame {
+    type State = FileBlameState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let block = Block::default()
+            .borders(Borders::TOP)
+            .style(Style::default().fg(self.ctx.color_theme.divider_fg))
+            .padding(Padding::horizontal(2));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let header_lines = self.header_lines(state, inner.width as usize);
+        for (y, line) in (inner.y..inner.bottom()).zip(&header_lines) {
+            buf.set_line(inner.x, y, line, inner.width);
+        }
+
+        let content_area = Rect {
+            y: inner.y + HEADER_HEIGHT.min(inner.height),
+            height: inner.height.saturating_sub(HEADER_HEIGHT),
+            ..inner
+        };
+        self.render_lines(content_area, buf, state);
+    }
+}
+
+impl FileBlame {
+    fn header_lines(&self, state: &FileBlameState, width: usize) -> Vec<Line<'static>> {
+        let theme = &self.ctx.color_theme;
+        let title_line = Line::from(vec![
+            Span::raw(state.path.clone())
+                .fg(theme.diff_file_fg)
+                .add_modifier(Modifier::BOLD),
+            " at ".fg(theme.fg),
+            Span::raw(state.commit_hash.as_short_hash().to_string()).fg(theme.list_hash_fg),
+        ]);
+        let divider_line = Line::from("─".repeat(width).fg(theme.divider_fg));
+        vec![title_line, divider_line]
+    }
+
+    fn render_lines(&self, area: Rect, buf: &mut Buffer, state: &mut FileBlameState) {
+        let theme = &self.ctx.color_theme;
+        state.update_offset(area.height as usize);
+
+        let lines = &state.blame.lines;
+        let name_width = state
+            .blame
+            .commits
+            .values()
+            .map(|c| console::measure_text_width(&c.author_name))
+            .max()
+            .unwrap_or_default()
+            .min(MAX_NAME_WIDTH);
+        let date_width = state
+            .blame
+            .commits
+            .values()
+            .map(|c| console::measure_text_width(&self.format_date(c)))
+            .max()
+            .unwrap_or_default();
+        let lineno_width = lines.len().to_string().len();
+        let tab_spaces = " ".repeat(self.ctx.ui_config.diff.tab_width as usize);
+
+        let rows = lines
+            .iter()
+            .enumerate()
+            .skip(state.offset)
+            .take(area.height as usize);
+        for (y, (i, line)) in (area.y..).zip(rows) {
+            let selected = i == state.selected;
+            // the commit is shown only on the first of the consecutive lines from it
+            let first_of_commit = i == state.offset
+                || lines
+                    .get(i.wrapping_sub(1))
+                    .is_none_or(|prev| prev.commit_hash != line.commit_hash);
+
+            let info_style = if selected {
+                Style::default().fg(theme.blame_selected_fg)
+            } else {
+                Style::default()
+            };
+
+            let mut info_spans = Vec::new();
+            match state.blame.commits.get(&line.commit_hash) {
+                Some(commit) if first_of_commit || selected => {
+                    let name = if console::measure_text_width(&commit.author_name) > name_width {
+                        console::truncate_str(&commit.author_name, name_width, ELLIPSIS).to_string()
+                    } else {
+                        console::pad_str(
+                            &commit.author_name,
+                            name_width,
+                            console::Alignment::Left,
+                            None,
+                        )
+                        .to_string()
+                    };
+                    let date = console::pad_str(
+                        &self.format_date(commit),
+                        date_width,
+                        console::Alignment::Left,
+                        None,
+                    )
+                    .to_string();
+                    info_spans.extend([
+                        Span::raw(format!("{} ", line.commit_hash.as_short_hash()))
+                            .fg(theme.list_hash_fg),
+                        Span::raw(format!("{name} ")).fg(theme.list_name_fg),
+                        Span::raw(format!("{date} ")).fg(theme.list_date_fg),
+                    ]);
+                }
+                _ => {
+                    // the short hash, name, and date with the spaces between them
+                    let width = 8 + name_width + 1 + date_width + 1;
+                    info_spans.push(Span::raw(" ".repeat(width)));
+                }
+            }
+            info_spans.push(
+                Span::raw(format!("{:>lineno_width$} ", i + 1)).fg(theme.diff_line_number_fg),
+            );
+            let mut spans: Vec<Span> = info_spans
+                .into_iter()
+                .map(|s| s.patch_style(info_style))
+                .collect();
+            match state.highlights.get(i) {
+                Some(ranges) if !ranges.is_empty() => {
+                    spans.extend(ranges.iter().map(|(range, color)| {
+                        Span::raw(line.content[range.clone()].replace('\t', &tab_spaces)).fg(*color)
+                    }));
+                }
+                _ => spans.push(Span::raw(line.content.replace('\t', &tab_spaces)).fg(theme.fg)),
+            }
+
+            buf.set_line(area.x, y, &Line::from(spans), area.width);
+            if selected {
+                // keep the colors of the content, and highlight the whole row
+                let style = Style::default().bg(theme.blame_selected_bg);
+                buf.set_style(
+                    Rect {
+                        y,
+                        height: 1,
+                        ..area
+                    },
+                    style,
+                );
+            }
+        }
+    }
+
+    fn format_date(&self, commit: &BlameCommit) -> String {
+        let date = &commit.author_date;
+        let format = &self.ctx.ui_config.list.date_format;
+        if self.ctx.ui_config.list.date_local {
+            date.with_timezone(&chrono::Local)
+                .format(format)
+                .to_string()
+        } else {
+            date.format(format).to_string()
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use std::rc::Rc;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Padding, StatefulWidget, Widget},
+};
+
+use crate::{
+    app::AppContext,
+    git::{Commit, CommitHash, TreeEntry},
+    syntax::{LineHighlights, SyntaxHighlighter},
+};
+
+// like git, a file is considered binary if a NUL byte is found in the first bytes
+const BINARY_CHECK_BYTES: usize = 8000;
+// only the beginning of a large file is read and shown
+pub const MAX_PREVIEW_BYTES: usize = 1024 * 1024;
+const HEADER_HEIGHT: u16 = 4;
+
+#[derive(Debug)]
+enum FilePreviewContent {
+    Text {
+        lines: Vec<String>,
+        highlights: Vec<LineHighlights>,
+        truncated: bool,
+    },
+    Binary,
+    Submodule,
+}
+
+#[derive(Debug)]
+pub struct FilePreviewState {
+    entry: TreeEntry,
+    // the last commit that changed the file, and the commit itself if it is loaded in the graph
+    last_modified: Option<(CommitHash, Option<Commit>)>,
+    content: FilePreviewContent,
+    height: usize,
+    offset: usize,
+}
+
+impl FilePreviewState {
+    pub fn new(
+        entry: TreeEntry,
+        bytes: Vec<u8>,
+        last_modified: Option<(CommitHash, Option<Commit>)>,
+        highlighter: Option<SyntaxHighlighter>,
+    ) -> Self {
+        let content = if entry.is_submodule() {
+            FilePreviewContent::Submodule
+        } else if bytes.iter().take(BINARY_CHECK_BYTES).any(|b| *b == 0) {
+            FilePreviewContent::Binary
+        } else {
+            let truncated = entry.size.is_some_and(|size| size > bytes.len() as u64);
+            let mut lines: Vec<String> = String::from_utf8_lossy(&bytes)
+                .lines()
+                .map(String::from)
+                .collect();
+            if truncated && lines.len() > 1 {
+                // the last line may be cut in the middle of a character
+                lines.pop();
+            }
+            let highlights = highlighter
+                .and_then(|highlighter| {
+                    let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+                    highlighter.highlight(&entry.path, &lines)
+                })
+                .unwrap_or_default();
+            FilePreviewContent::Text {
+                lines,
+                highlights,
+                truncated,
+            }
+        };
+        FilePreviewState {
+            entry,
+            last_modified,
+            content,
+            height: 0,
+            offset: 0,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.entry.path
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.offset = self.offset.saturating_add(1);
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.offset = self.offset.saturating_sub(1);
+    }
+
+    pub fn scroll_page_down(&mut self) {
+        self.offset = self.offset.saturating_add(self.height);
+    }
+
+    pub fn scroll_page_up(&mut self) {
+        self.offset = self.offset.saturating_sub(self.height);
+    }
+
+    pub fn scroll_half_page_down(&mut self) {
+        self.offset = self.offset.saturating_add(self.height / 2);
+    }
+
+    pub fn scroll_half_page_up(&mut self) {
+        self.offset = self.offset.saturating_sub(self.height / 2);
+    }
+
+    pub fn select_first(&mut self) {
+        self.offset = 0;
+    }
+
+    pub fn select_last(&mut self) {
+        self.offset = usize::MAX;
+    }
+}
+
+pub struct FilePreview {
+    ctx: Rc<AppContext>,
+}
+
+impl FilePreview {
+    pub fn new(ctx: Rc<AppContext>) -> Self {
+        Self { ctx }
+    }
+}
+
+impl StatefulWidget for FilePreview {
+    type State = FilePreviewState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let block = Block::default()
+            .borders(Borders::TOP)
+            .style(Style::default().fg(self.ctx.color_theme.divider_fg))
+            .padding(Padding::horizontal(2));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let header_lines = self.header_lines(state, inner.width as usize);
+        for (y, line) in (inner.y..inner.bottom()).zip(&header_lines) {
+            buf.set_line(inner.x, y, line, inner.width);
+        }
+
+        let content_area = Rect {
+            y: inner.y + HEADER_HEIGHT.min(inner.height),
+            height: inner.height.saturating_sub(HEADER_HEIGHT),
+            ..inner
+        };
+        self.render_content(content_area, buf, state);
+    }
+}
+
+impl FilePreview {
+    fn header_lines(&self, state: &FilePreviewState, width: usize) -> Vec<Line<'static>> {
+        let theme = &self.ctx.color_theme;
+        let entry = &state.entry;
+
+        let path_line = Line::from(
+            Span::raw(entry.path.clone())
+                .fg(theme.diff_file_fg)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        let mut spans = vec![
+            "Mode: ".fg(theme.detail_label_fg),
+            Span::raw(entry.mode.clone()).fg(theme.fg),
+        ];
+        if let Some(size) = entry.size {
+            spans.push("  Size: ".fg(theme.detail_label_fg));
+            spans.push(Span::raw(format_size(size)).fg(theme.fg));
+        }
+        if let FilePreviewContent::Text {
+            truncated: true, ..
+        } = state.content
+        {
+            spans.push("  (truncated)".fg(theme.detail_label_fg));
+        }
+        let info_line = Line::from(spans);
+
+        let mut spans = vec!["Last modified: ".fg(theme.detail_label_fg)];
+        match &state.last_modified {
+            Some((hash, commit)) => {
+                spans.push(Span::raw(hash.as_short_hash().to_string()).fg(theme.detail_hash_fg));
+                if let Some(commit) = commit {
+                    spans.push(
+                        Span::raw(format!(" {}", self.format_date(commit)))
+                            .fg(theme.detail_date_fg),
+                    );
+                    spans.push(Span::raw(format!(" {}", commit.subject)).fg(theme.fg));
+                }
+            }
+            None => spans.push("-".fg(theme.fg)),
+        }
+        let commit_line = Line::from(spans);
+
+        let divider_line = Line::from("─".repeat(width).fg(theme.divider_fg));
+
+        vec![path_line, info_line, commit_line, divider_line]
+    }
+
+    fn render_content(&self, area: Rect, buf: &mut Buffer, state: &mut FilePreviewState) {
+        let theme = &self.ctx.color_theme;
+        let (lines, highlights) = match &state.content {
+            FilePreviewContent::Text {
+                lines, highlights, ..
+            } => (lines, highlights),
+            FilePreviewContent::Binary => {
+                let line = Line::from("Binary file not shown").fg(theme.diff_header_fg);
+                buf.set_line(area.x, area.y, &line, area.width);
+                return;
+            }
+            FilePreviewContent::Submodule => {
+                let msg = format!("Submodule at commit {}", state.entry.object_id);
+                let line = Line::from(msg).fg(theme.diff_header_fg);
+                buf.set_line(area.x, area.y, &line, area.width);
+                return;
+            }
+        };
+
+        state.height = area.height as usize;
+        state.offset = state
+            .offset
+            .min(lines.len().saturating_sub(area.height as usize));
+
+        let lineno_width = lines.len().to_string().len();
+        let tab_spaces = " ".repeat(self.ctx.ui_config.diff.tab_width as usize);
+        let rows = lines
+            .iter()
+            .enumerate()
+            .skip(state.offset)
+            .take(area.height as usize);
+        for (y, (i, line)) in (area.y..).zip(rows) {
+            let mut spans =
+                vec![Span::raw(format!("{:>lineno_width$} ", i + 1)).fg(theme.diff_line_number_fg)];
+            match highlights.get(i) {
+                Some(ranges) if !ranges.is_empty() => {
+                    spans.extend(ranges.iter().map(|(range, color)| {
+                        Span::raw(line[range.clone()].replace('\t', &tab_spaces)).fg(*color)
+                    }));
+                }
+                _ => spans.push(Span::raw(line.replace('\t', &tab_spaces)).fg(theme.fg)),
+            }
+            buf.set_line(area.x, y, &Line::from(spans), area.width);
+        }
+    }
+
+    fn format_date(&self, commit: &Commit) -> String {
+        let date = &commit.author_date;
+        let format = &self.ctx.ui_config.list.date_format;
+        if self.ctx.ui_config.list.date_local {
+            date.with_timezone(&chrono::Local)
+                .format(format)
+                .to_string()
+        } else {
+            date.format(format).to_string()
+        }
+    }
+}
+
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if size < 1024 {
+        return format!("{size} B");
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for u in &UNITS[1..] {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = u;
+    }
+    format!("{value:.1} {unit} ({size} B)")
+}
@@ -0,0 +1,160 @@
+use std::{collections::BTreeMap, rc::Rc};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Style, Stylize},
+    widgets::{Block, Borders, Padding, StatefulWidget},
+};
+use tui_tree_widget::{Tree, TreeItem, TreeState};
+
+use crate::{app::AppContext, color::ColorTheme, git::TreeEntry};
+
+#[derive(Debug, Default)]
+pub struct FileTreeState {
+    tree_state: TreeState<String>,
+}
+
+impl FileTreeState {
+    pub fn new(items: &[TreeItem<'static, String>]) -> Self {
+        let mut tree_state = TreeState::default();
+        if let Some(item) = items.first() {
+            tree_state.select(vec![item.identifier().clone()]);
+        }
+        Self { tree_state }
+    }
+}
+
+impl FileTreeState {
+    pub fn select_next(&mut self) {
+        self.tree_state.key_down();
+    }
+
+    pub fn select_prev(&mut self) {
+        self.tree_state.key_up();
+    }
+
+    pub fn select_first(&mut self) {
+        self.tree_state.select_first();
+    }
+
+    pub fn select_last(&mut self) {
+        self.tree_state.select_last();
+    }
+
+    pub fn open_node(&mut self) {
+        self.tree_state.key_right();
+    }
+
+    pub fn close_node(&mut self) {
+        self.tree_state.key_left();
+    }
+
+    pub fn toggle_node(&mut self) {
+        self.tree_state.toggle_selected();
+    }
+
+    /// Returns the path of the selected file or directory.
+    pub fn selected_path(&self) -> Option<String> {
+        self.tree_state.selected().last().cloned()
+    }
+
+    pub fn current_tree_status(&self) -> (Vec<String>, Vec<Vec<String>>) {
+        let selected = self.tree_state.selected().into();
+        let opened = self.tree_state.opened().iter().cloned().collect();
+        (selected, opened)
+    }
+
+    pub fn reset_tree_status(&mut self, selected: Vec<String>, opened: Vec<Vec<String>>) {
+        if !selected.is_empty() {
+            self.tree_state.select(selected);
+        }
+        for node in opened {
+            self.tree_state.open(node);
+        }
+    }
+}
+
+pub struct FileTree<'a> {
+    items: &'a [TreeItem<'static, String>],
+    ctx: Rc<AppContext>,
+}
+
+impl<'a> FileTree<'a> {
+    pub fn new(items: &'a [TreeItem<'static, String>], ctx: Rc<AppContext>) -> FileTree<'a> {
+        FileTree { items, ctx }
+    }
+}
+
+impl StatefulWidget for FileTree<'_> {
+    type State = FileTreeState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let tree = Tree::new(self.items)
+            .unwrap()
+            .node_closed_symbol("\u{25b8} ") // ▸
+            .node_open_symbol("\u{25be} ") // ▾
+            .node_no_children_symbol("  ")
+            .highlight_style(
+                Style::default()
+                    .bg(self.ctx.color_theme.file_tree_selected_bg)
+                    .fg(self.ctx.color_theme.file_tree_selected_fg),
+            )
+            .block(
+                Block::default()
+                    .borders(Borders::TOP | Borders::RIGHT)
+                    .style(Style::default().fg(self.ctx.color_theme.divider_fg))
+                    .padding(Padding::horizontal(1)),
+            );
+        tree.render(area, buf, &mut state.tree_state);
+    }
+}
+
+#[derive(Default)]
+struct FileTreeNode {
+    // the entries are sorted by name
+    children: BTreeMap<String, FileTreeNode>,
+    is_file: bool,
+}
+
+/// Builds the tree items of the files, whose identifiers are the paths.
+/// The directories are listed before the files in each directory.
+pub fn build_file_tree_items(
+    entries: &[TreeEntry],
+    color_theme: &ColorTheme,
+) -> Vec<TreeItem<'static, String>> {
+    let mut root = FileTreeNode::default();
+    for entry in entries {
+        let mut node = &mut root;
+        for part in entry.path.split('/') {
+            node = node.children.entry(part.into()).or_default();
+        }
+        node.is_file = true;
+    }
+    file_tree_nodes_to_tree_items(root.children, "", color_theme)
+}
+
+fn file_tree_nodes_to_tree_items(
+    nodes: BTreeMap<String, FileTreeNode>,
+    parent_path: &str,
+    color_theme: &ColorTheme,
+) -> Vec<TreeItem<'static, String>> {
+    let (files, dirs): (Vec<_>, Vec<_>) = nodes.into_iter().partition(|(_, node)| node.is_file);
+    let mut items = Vec::new();
+    for (name, node) in dirs.into_iter().chain(files) {
+        let path = if parent_path.is_empty() {
+            name.clone()
+        } else {
+            format!("{parent_path}/{name}")
+        };
+        if node.is_file {
+            let text = name.fg(color_theme.fg);
+            items.push(TreeItem::new_leaf(path, text));
+        } else {
+            let children = file_tree_nodes_to_tree_items(node.children, &path, color_theme);
+            let text = format!("{name}/").fg(color_theme.file_tree_dir_fg);
+            items.push(TreeItem::new(path, text, children).unwrap());
+        }
+    }
+    items
+}